@@ -0,0 +1,94 @@
+//! Zip archive of the files a run wrote to the output directory (`--archive`).
+//!
+//! Only available when built with the `archive` feature.
+
+use anyhow::Result;
+use std::path::Path;
+
+/// File name of the archive, written to the output directory
+pub const FILE_NAME: &str = "overdoc-report.zip";
+
+#[cfg(feature = "archive")]
+mod zip_archive {
+    use anyhow::{Context, Result};
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, DateTime, ZipWriter};
+
+    pub fn write_archive(archive: &Path, files: &[(String, &Path)]) -> Result<()> {
+        let file =
+            fs::File::create(archive).context(format!("Failed to create {}", archive.display()))?;
+        let mut zip = ZipWriter::new(file);
+
+        // A fixed timestamp and mode keep the archive identical for identical contents
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default())
+            .unix_permissions(0o644);
+
+        for (name, path) in files {
+            let content = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+            zip.start_file(name.as_str(), options)
+                .context(format!("Failed to add {} to the archive", name))?;
+            zip.write_all(&content)
+                .context(format!("Failed to add {} to the archive", name))?;
+        }
+
+        zip.finish()
+            .context(format!("Failed to finish {}", archive.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "archive"))]
+fn not_enabled() -> anyhow::Error {
+    anyhow::anyhow!(
+        "OverDoc was built without archive support; rebuild with `--features archive` to use --archive"
+    )
+}
+
+/// Fail unless archives can be written, so a missing feature is reported before the analysis
+pub fn ensure_enabled() -> Result<()> {
+    #[cfg(feature = "archive")]
+    return Ok(());
+
+    #[cfg(not(feature = "archive"))]
+    Err(not_enabled())
+}
+
+/// Bundle the artifacts of this run that lie under `output_dir` into `output_dir/FILE_NAME`,
+/// in path order, returning the archive path. Files the run didn't write are left out.
+pub fn write_archive(output_dir: &Path, artifacts: &[String]) -> Result<std::path::PathBuf> {
+    let archive = output_dir.join(FILE_NAME);
+
+    let mut files: Vec<(String, &Path)> = artifacts
+        .iter()
+        .map(Path::new)
+        .filter(|path| *path != archive)
+        .filter_map(|path| {
+            let name = path.strip_prefix(output_dir).ok()?;
+            // Entry names always use forward slashes
+            let name: Vec<_> = name
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect();
+            Some((name.join("/"), path))
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files.dedup_by(|a, b| a.0 == b.0);
+
+    #[cfg(feature = "archive")]
+    {
+        zip_archive::write_archive(&archive, &files)?;
+        Ok(archive)
+    }
+
+    #[cfg(not(feature = "archive"))]
+    {
+        let _ = files;
+        Err(not_enabled())
+    }
+}
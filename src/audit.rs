@@ -0,0 +1,212 @@
+//! Per-file trace of the analysis pipeline (`--audit-pipeline`).
+//!
+//! Each phase records the files it received, the files it emitted and why it excluded
+//! the others. A file that was received but neither emitted nor excluded was lost
+//! silently, which usually means a read error or a path that failed to join.
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Files flowing through one phase of the pipeline
+#[derive(Debug)]
+pub struct PhaseTrace {
+    pub phase: &'static str,
+    received: BTreeSet<String>,
+    emitted: BTreeSet<String>,
+
+    /// Files the phase dropped on purpose, with the reason
+    excluded: BTreeMap<String, String>,
+}
+
+impl PhaseTrace {
+    /// Start tracing a phase that received the given files
+    pub fn new<I, S>(phase: &'static str, received: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        PhaseTrace {
+            phase,
+            received: received.into_iter().map(Into::into).collect(),
+            emitted: BTreeSet::new(),
+            excluded: BTreeMap::new(),
+        }
+    }
+
+    /// Record a file passed on to the next phase
+    pub fn emit(&mut self, path: &str) {
+        self.emitted.insert(path.to_string());
+    }
+
+    /// Record a file dropped on purpose
+    pub fn exclude(&mut self, path: &str, reason: impl Into<String>) {
+        self.excluded.insert(path.to_string(), reason.into());
+    }
+
+    /// Files passed on to the next phase
+    pub fn emitted(&self) -> impl Iterator<Item = &String> {
+        self.emitted.iter()
+    }
+
+    /// Files received but neither emitted nor excluded
+    fn lost(&self) -> Vec<&String> {
+        self.received
+            .iter()
+            .filter(|path| !self.emitted.contains(*path) && !self.excluded.contains_key(*path))
+            .collect()
+    }
+
+    /// Files emitted without having been received, e.g. under a differently spelled path
+    fn unexpected(&self) -> Vec<&String> {
+        self.emitted
+            .iter()
+            .filter(|path| !self.received.contains(*path))
+            .collect()
+    }
+}
+
+/// A file that vanished inside a phase without an exclusion or warning
+#[derive(Debug, Serialize)]
+pub struct Loss {
+    pub path: String,
+    pub phase: &'static str,
+
+    /// Boundary the file crossed last, e.g. "filter → exports"
+    pub boundary: String,
+}
+
+/// File counts of one phase
+#[derive(Debug, Serialize)]
+pub struct PhaseCounts {
+    pub phase: &'static str,
+    pub received: usize,
+    pub emitted: usize,
+    pub excluded: usize,
+    pub lost: usize,
+}
+
+/// Traces of all phases, in pipeline order
+#[derive(Debug, Default)]
+pub struct PipelineAudit {
+    phases: Vec<PhaseTrace>,
+}
+
+/// What the audit writes to `pipeline_audit.json`
+#[derive(Serialize)]
+struct AuditReport<'a> {
+    phases: Vec<PhaseCounts>,
+    losses: Vec<Loss>,
+
+    /// Files emitted by a phase that didn't receive them
+    unexpected: BTreeMap<&'static str, Vec<&'a String>>,
+
+    /// Excluded files per phase, with the reason
+    excluded: BTreeMap<&'static str, &'a BTreeMap<String, String>>,
+}
+
+/// File name of the audit in the output directory
+pub const FILE_NAME: &str = "pipeline_audit.json";
+
+impl PipelineAudit {
+    pub fn new() -> Self {
+        PipelineAudit::default()
+    }
+
+    /// Add the trace of the next phase
+    pub fn push(&mut self, trace: PhaseTrace) {
+        self.phases.push(trace);
+    }
+
+    /// Trace of a phase, if it was recorded
+    pub fn phase(&self, phase: &str) -> Option<&PhaseTrace> {
+        self.phases.iter().find(|trace| trace.phase == phase)
+    }
+
+    /// Received, emitted, excluded and lost files per phase
+    pub fn counts(&self) -> Vec<PhaseCounts> {
+        self.phases
+            .iter()
+            .map(|trace| PhaseCounts {
+                phase: trace.phase,
+                received: trace.received.len(),
+                emitted: trace.emitted.len(),
+                excluded: trace.excluded.len(),
+                lost: trace.lost().len(),
+            })
+            .collect()
+    }
+
+    /// Files lost silently, in pipeline order
+    pub fn losses(&self) -> Vec<Loss> {
+        let mut losses = Vec::new();
+        for (idx, trace) in self.phases.iter().enumerate() {
+            let boundary = match idx.checked_sub(1).map(|prev| self.phases[prev].phase) {
+                Some(previous) => format!("{} → {}", previous, trace.phase),
+                None => format!("start → {}", trace.phase),
+            };
+            for path in trace.lost() {
+                losses.push(Loss {
+                    path: path.clone(),
+                    phase: trace.phase,
+                    boundary: boundary.clone(),
+                });
+            }
+        }
+        losses
+    }
+
+    /// Files emitted by a phase that didn't receive them, per phase
+    pub fn unexpected(&self) -> BTreeMap<&'static str, Vec<&String>> {
+        self.phases
+            .iter()
+            .map(|trace| (trace.phase, trace.unexpected()))
+            .filter(|(_, paths)| !paths.is_empty())
+            .collect()
+    }
+
+    /// Log the file counts of every phase and warn about each lost or unexpected file
+    pub fn log(&self) {
+        for counts in self.counts() {
+            info!(
+                "Pipeline audit, {}: {} received, {} emitted, {} excluded, {} lost",
+                counts.phase, counts.received, counts.emitted, counts.excluded, counts.lost
+            );
+        }
+
+        let losses = self.losses();
+        for loss in &losses {
+            warn!(
+                "{} was lost in the {} phase ({}) without an exclusion or warning",
+                loss.path, loss.phase, loss.boundary
+            );
+        }
+        for (phase, paths) in self.unexpected() {
+            for path in paths {
+                warn!(
+                    "{} was emitted by the {} phase without entering it",
+                    path, phase
+                );
+            }
+        }
+        if losses.is_empty() {
+            info!("Pipeline audit: no files were lost between phases");
+        }
+    }
+
+    /// Render the audit as JSON
+    pub fn render_json(&self) -> Result<String> {
+        let report = AuditReport {
+            phases: self.counts(),
+            losses: self.losses(),
+            unexpected: self.unexpected(),
+            excluded: self
+                .phases
+                .iter()
+                .map(|trace| (trace.phase, &trace.excluded))
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+}
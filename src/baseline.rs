@@ -0,0 +1,248 @@
+//! Metric values recorded for every file (`overdoc baseline`), so the quality gates only fail
+//! for files that are new or got worse since (`--baseline`).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::metrics::RepositoryMetrics;
+use crate::traversal::relative_path;
+
+/// Default location of the baseline file
+pub const DEFAULT_PATH: &str = "overdoc-baseline.json";
+
+/// Recorded metric values of one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub cyclomatic_complexity: f64,
+    pub cognitive_complexity: f64,
+
+    /// Missing when the complexity analysis of the file ran out of time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintainability_index: Option<f64>,
+}
+
+/// Direction in which a metric gets worse
+#[derive(Debug, Clone, Copy)]
+pub enum Worse {
+    Higher,
+    Lower,
+}
+
+/// Recorded metric values, keyed by path relative to the repository
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub files: BTreeMap<String, BaselineEntry>,
+}
+
+impl Baseline {
+    /// Record the values of every file with complexity metrics
+    pub fn from_metrics(metrics: &RepositoryMetrics, repo_root: &str) -> Self {
+        let files = metrics
+            .file_metrics
+            .iter()
+            .filter_map(|(path, file_metrics)| {
+                let complexity = file_metrics.complexity_metrics.as_ref()?;
+                Some((
+                    relative_path(path, repo_root).replace('\\', "/"),
+                    BaselineEntry {
+                        cyclomatic_complexity: complexity.cyclomatic_complexity,
+                        cognitive_complexity: complexity.cognitive_complexity,
+                        maintainability_index: (!complexity.partial)
+                            .then_some(complexity.maintainability_index),
+                    },
+                ))
+            })
+            .collect();
+        Baseline { files }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).context(format!("Failed to read baseline {}", path))?;
+        serde_json::from_str(&content).context(format!(
+            "Failed to parse baseline {}; write one with `overdoc baseline`",
+            path
+        ))
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(self).context("Failed to serialize the baseline")?;
+        fs::write(path, content).context(format!("Failed to write baseline {}", path))
+    }
+
+    /// Whether `path` is recorded and `value` is no worse than its recorded value plus
+    /// `tolerance`
+    pub fn accepts(
+        &self,
+        path: &str,
+        recorded: impl Fn(&BaselineEntry) -> Option<f64>,
+        value: f64,
+        worse: Worse,
+        tolerance: f64,
+    ) -> bool {
+        let Some(recorded) = self.files.get(path).and_then(recorded) else {
+            return false;
+        };
+        match worse {
+            Worse::Higher => value <= recorded + tolerance,
+            Worse::Lower => value >= recorded - tolerance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThresholdsConfig;
+    use crate::filter::PathOverrides;
+    use crate::thresholds::{check, Violations};
+
+    fn file_limit(limit: f64) -> ThresholdsConfig {
+        ThresholdsConfig {
+            file_cyclomatic_above: Some(limit),
+            ..ThresholdsConfig::default()
+        }
+    }
+
+    /// Paths named by the violations of a check, or none when it passes
+    fn offending(
+        metrics: &RepositoryMetrics,
+        thresholds: &ThresholdsConfig,
+        baseline: Option<&Baseline>,
+    ) -> Vec<String> {
+        match check(metrics, thresholds, &PathOverrides::default(), "", baseline) {
+            Ok(()) => Vec::new(),
+            Err(Violations(violations)) => violations
+                .into_iter()
+                .flat_map(|violation| violation.files)
+                .map(|(path, _)| path)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn worsened_file_fails_against_the_baseline() {
+        let thresholds = file_limit(10.0);
+        let before = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 20.0, 60.0),
+            ("src/lib.rs", 4.0, 80.0),
+        ]);
+        let baseline = Baseline::from_metrics(&before, "");
+
+        // Recorded as it is, the file over the limit is accepted
+        assert!(offending(&before, &thresholds, Some(&baseline)).is_empty());
+        assert_eq!(offending(&before, &thresholds, None), ["src/parse.rs"]);
+
+        let after = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 24.0, 60.0),
+            ("src/lib.rs", 4.0, 80.0),
+        ]);
+        let err = check(
+            &after,
+            &thresholds,
+            &PathOverrides::default(),
+            "",
+            Some(&baseline),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Quality thresholds violated:\n  - highest file cyclomatic complexity 24.0 is above 10\n      src/parse.rs (24.0)"
+        );
+    }
+
+    #[test]
+    fn improved_file_passes_and_tightens_the_baseline() {
+        let thresholds = file_limit(10.0);
+        let before = RepositoryMetrics::from_complexities(&[("src/parse.rs", 20.0, 60.0)]);
+        let baseline = Baseline::from_metrics(&before, "");
+
+        let improved = RepositoryMetrics::from_complexities(&[("src/parse.rs", 15.0, 60.0)]);
+        assert!(offending(&improved, &thresholds, Some(&baseline)).is_empty());
+
+        // Recording the improved run (--update-baseline) lowers the value the file is held to
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let path = path.to_str().unwrap();
+        Baseline::from_metrics(&improved, "").save(path).unwrap();
+        let tightened = Baseline::load(path).unwrap();
+        assert_eq!(tightened.files["src/parse.rs"].cyclomatic_complexity, 15.0);
+
+        let regressed = RepositoryMetrics::from_complexities(&[("src/parse.rs", 20.0, 60.0)]);
+        assert!(offending(&regressed, &thresholds, Some(&baseline)).is_empty());
+        assert_eq!(
+            offending(&regressed, &thresholds, Some(&tightened)),
+            ["src/parse.rs"]
+        );
+    }
+
+    #[test]
+    fn tolerance_allows_small_regressions_only() {
+        let thresholds = ThresholdsConfig {
+            baseline_tolerance: 2.0,
+            ..file_limit(10.0)
+        };
+        let baseline = Baseline::from_metrics(
+            &RepositoryMetrics::from_complexities(&[("src/parse.rs", 20.0, 60.0)]),
+            "",
+        );
+
+        let within = RepositoryMetrics::from_complexities(&[("src/parse.rs", 22.0, 60.0)]);
+        assert!(offending(&within, &thresholds, Some(&baseline)).is_empty());
+        let beyond = RepositoryMetrics::from_complexities(&[("src/parse.rs", 22.5, 60.0)]);
+        assert_eq!(
+            offending(&beyond, &thresholds, Some(&baseline)),
+            ["src/parse.rs"]
+        );
+    }
+
+    #[test]
+    fn files_missing_from_the_baseline_fail() {
+        let thresholds = file_limit(10.0);
+        let baseline = Baseline::from_metrics(
+            &RepositoryMetrics::from_complexities(&[("src/parse.rs", 20.0, 60.0)]),
+            "",
+        );
+
+        let grown = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 20.0, 60.0),
+            ("src/render.rs", 12.0, 60.0),
+        ]);
+        assert_eq!(
+            offending(&grown, &thresholds, Some(&baseline)),
+            ["src/render.rs"]
+        );
+    }
+
+    #[test]
+    fn lower_maintainability_than_recorded_fails() {
+        let thresholds = ThresholdsConfig {
+            maintainability_below: Some(50.0),
+            ..ThresholdsConfig::default()
+        };
+        let before = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 5.0, 40.0),
+            ("src/lib.rs", 5.0, 55.0),
+        ]);
+        let baseline = Baseline::from_metrics(&before, "");
+        assert!(offending(&before, &thresholds, Some(&baseline)).is_empty());
+
+        // Lower is worse for the maintainability index
+        let improved = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 5.0, 45.0),
+            ("src/lib.rs", 5.0, 52.0),
+        ]);
+        assert!(offending(&improved, &thresholds, Some(&baseline)).is_empty());
+        let worsened = RepositoryMetrics::from_complexities(&[
+            ("src/parse.rs", 5.0, 35.0),
+            ("src/lib.rs", 5.0, 55.0),
+        ]);
+        assert_eq!(
+            offending(&worsened, &thresholds, Some(&baseline)),
+            ["src/parse.rs"]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Classification of files into categories (code, test, config, docs, generated, vendored).
+//!
+//! Rules are tried in precedence order: a higher `priority` first, then the order of the
+//! configuration. The first matching rule decides the category, but every matching rule is
+//! recorded so files claimed by several rules can be reported.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::config::CategoryRule;
+use crate::filter::PatternSet;
+use crate::traversal::relative_path;
+
+/// Category of files no rule matches
+pub const DEFAULT_CATEGORY: &str = "code";
+
+/// The category of one file
+#[derive(Debug, Clone, Serialize)]
+pub struct Classification {
+    /// Category the file is counted under
+    pub category: String,
+
+    /// Every category whose rule matched, in precedence order
+    pub matches: Vec<String>,
+}
+
+impl Classification {
+    /// Whether more than one category claimed the file
+    pub fn is_ambiguous(&self) -> bool {
+        self.matches.len() > 1
+    }
+}
+
+/// A file matched by the rules of several categories
+#[derive(Debug, Serialize)]
+pub struct AmbiguousFile {
+    pub path: String,
+
+    #[serde(flatten)]
+    pub classification: Classification,
+}
+
+/// Category totals of a run
+#[derive(Debug, Default, Serialize)]
+pub struct CategorySummary {
+    /// Number of files per category
+    pub counts: BTreeMap<String, usize>,
+
+    /// Number of files matched by more than one category
+    pub ambiguous_count: usize,
+
+    /// Files matched by more than one category, by path (only collected in verbose mode)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ambiguous: Vec<AmbiguousFile>,
+}
+
+/// The category rules of the configuration in precedence order, their patterns compiled once.
+/// Patterns are matched like `ignore_patterns`: globs against paths relative to the repository
+/// root or one of their directories, and names without glob metacharacters anywhere in the path.
+#[derive(Debug, Default)]
+pub struct CategoryRules {
+    rules: Vec<(String, PatternSet)>,
+}
+
+impl CategoryRules {
+    /// Compile the patterns of every rule and order the rules by precedence
+    pub fn new(rules: &[CategoryRule]) -> Result<Self> {
+        let mut ordered: Vec<&CategoryRule> = rules.iter().collect();
+        // Stable, so equal priorities keep the configuration order
+        ordered.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+
+        let rules = ordered
+            .into_iter()
+            .map(|rule| {
+                let what = format!("categories.{}.patterns", rule.name);
+                Ok((rule.name.clone(), PatternSet::new(&rule.patterns, &what)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(CategoryRules { rules })
+    }
+
+    /// Classify a path relative to the repository root
+    pub fn classify(&self, relative_path: &str) -> Classification {
+        let path = relative_path.replace('\\', "/");
+        let mut matches: Vec<String> = Vec::new();
+
+        for (name, patterns) in &self.rules {
+            if !matches.contains(name) && patterns.first_match(&path).is_some() {
+                matches.push(name.clone());
+            }
+        }
+
+        Classification {
+            category: matches
+                .first()
+                .cloned()
+                .unwrap_or_else(|| DEFAULT_CATEGORY.to_string()),
+            matches,
+        }
+    }
+}
+
+/// Count the files per category; with `list_ambiguous` also list the files several categories claim
+pub fn summarize(
+    file_paths: &[String],
+    repo_root: &str,
+    rules: &CategoryRules,
+    list_ambiguous: bool,
+) -> CategorySummary {
+    let mut summary = CategorySummary::default();
+
+    for path in file_paths {
+        let classification = rules.classify(&relative_path(path, repo_root));
+        *summary
+            .counts
+            .entry(classification.category.clone())
+            .or_default() += 1;
+
+        if classification.is_ambiguous() {
+            summary.ambiguous_count += 1;
+            if list_ambiguous {
+                summary.ambiguous.push(AmbiguousFile {
+                    path: relative_path(path, repo_root),
+                    classification,
+                });
+            }
+        }
+    }
+
+    summary.ambiguous.sort_by(|a, b| a.path.cmp(&b.path));
+    summary
+}
+
+/// Describe a file claimed by several categories, e.g. "counted as test (also matched generated)"
+pub fn describe_ambiguity(classification: &Classification) -> String {
+    format!(
+        "counted as {} (also matched {})",
+        classification.category,
+        classification.matches[1..].join(", ")
+    )
+}
+
+/// Render the category totals and ambiguous files (for `--check-config --paths`)
+pub fn render_category_report(summary: &CategorySummary) -> String {
+    let mut output = String::from("categories\n");
+    for (category, count) in &summary.counts {
+        output.push_str(&format!("  {}: {} files\n", category, count));
+    }
+
+    output.push_str(&format!(
+        "  files matching several categories: {}\n",
+        summary.ambiguous_count
+    ));
+    for file in &summary.ambiguous {
+        output.push_str(&format!(
+            "    - {}: {}\n",
+            file.path,
+            describe_ambiguity(&file.classification)
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, patterns: &[&str], priority: i32) -> CategoryRule {
+        CategoryRule {
+            name: name.to_string(),
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            priority,
+        }
+    }
+
+    fn classify(path: &str, rules: &[CategoryRule]) -> Classification {
+        CategoryRules::new(rules).unwrap().classify(path)
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let rules = [
+            rule("generated", &["*_generated.*"], 0),
+            rule("test", &["tests/*"], 0),
+        ];
+        let classification = classify("tests/fixtures_generated.rs", &rules);
+
+        assert_eq!(classification.category, "generated");
+        assert_eq!(classification.matches, ["generated", "test"]);
+        assert!(classification.is_ambiguous());
+        assert_eq!(
+            describe_ambiguity(&classification),
+            "counted as generated (also matched test)"
+        );
+
+        let reordered = [
+            rule("test", &["tests/*"], 0),
+            rule("generated", &["*_generated.*"], 0),
+        ];
+        assert_eq!(
+            classify("tests/fixtures_generated.rs", &reordered).category,
+            "test"
+        );
+    }
+
+    #[test]
+    fn priority_overrides_the_configuration_order() {
+        let rules = [
+            rule("generated", &["*_generated.*"], 0),
+            rule("test", &["tests/*"], 10),
+            rule("docs", &["*.md"], 10),
+        ];
+        let classification = classify("tests/fixtures_generated.rs", &rules);
+
+        assert_eq!(classification.category, "test");
+        assert_eq!(classification.matches, ["test", "generated"]);
+        assert_eq!(
+            describe_ambiguity(&classification),
+            "counted as test (also matched generated)"
+        );
+
+        // Equal priorities keep the configuration order
+        let both = classify("tests/README_generated.md", &rules);
+        assert_eq!(both.matches, ["test", "docs", "generated"]);
+    }
+
+    #[test]
+    fn default_rules_put_generated_before_test() {
+        let rules = crate::config::Config::default().categories;
+
+        let classification = classify("tests/generated/fixtures.rs", &rules);
+        assert_eq!(classification.category, "generated");
+        assert_eq!(classification.matches, ["generated", "test"]);
+
+        let plain = classify("src/lib.rs", &rules);
+        assert_eq!(plain.category, DEFAULT_CATEGORY);
+        assert!(!plain.is_ambiguous());
+    }
+
+    #[test]
+    fn patterns_match_like_ignore_patterns() {
+        let rules = [
+            rule("vendored", &["vendor"], 0),
+            rule("generated", &["src/gen/**", "*.pb.go"], 0),
+            rule("docs", &["docs/*.md"], 0),
+        ];
+
+        let cases = [
+            // A bare name matches a directory anywhere in the path
+            ("vendor/lib.rs", "vendored"),
+            ("crates/api/vendor/lib.rs", "vendored"),
+            ("src/vendored.rs", DEFAULT_CATEGORY),
+            // A glob with a `/` is anchored at the repository root
+            ("src/gen/api/types.rs", "generated"),
+            ("lib/src/gen/types.rs", DEFAULT_CATEGORY),
+            // One without matches the file name anywhere
+            ("api/v1/service.pb.go", "generated"),
+            // `*` stops at `/`
+            ("docs/guide.md", "docs"),
+            ("docs/api/index.md", DEFAULT_CATEGORY),
+        ];
+        for (path, category) in cases {
+            assert_eq!(classify(path, &rules).category, category, "{}", path);
+        }
+    }
+
+    #[test]
+    fn invalid_patterns_name_their_category() {
+        let err = CategoryRules::new(&[rule("test", &["tests/["], 0)]).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("categories.test.patterns"),
+            "{:#}",
+            err
+        );
+    }
+
+    #[test]
+    fn summary_counts_ambiguous_files() {
+        let rules = [
+            rule("generated", &["*_generated.*"], 0),
+            rule("test", &["tests/*"], 0),
+        ];
+        let files: Vec<String> = [
+            "repo/tests/b_generated.rs",
+            "repo/tests/a_generated.rs",
+            "repo/tests/cli.rs",
+            "repo/src/lib.rs",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect();
+
+        let rules = CategoryRules::new(&rules).unwrap();
+        let summary = summarize(&files, "repo", &rules, true);
+        assert_eq!(summary.counts["generated"], 2);
+        assert_eq!(summary.counts["test"], 1);
+        assert_eq!(summary.counts[DEFAULT_CATEGORY], 1);
+        assert_eq!(summary.ambiguous_count, 2);
+        let ambiguous: Vec<&str> = summary
+            .ambiguous
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(ambiguous, ["tests/a_generated.rs", "tests/b_generated.rs"]);
+
+        let quiet = summarize(&files, "repo", &rules, false);
+        assert_eq!(quiet.ambiguous_count, 2);
+        assert!(quiet.ambiguous.is_empty());
+    }
+}
@@ -1,8 +1,26 @@
 use anyhow::{Context, Result};
+use log::warn;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::patterns::{effective_patterns, PatternKind};
+
+/// Serialize a map with its keys in order, so the YAML and JSON written from it, and hashes
+/// of them, are the same on every run
+pub fn serialize_sorted<S, K, V>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
 
 /// Main configuration structure for OverDoc
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,17 +29,123 @@ pub struct Config {
     #[serde(default)]
     pub ignore_patterns: Vec<String>,
     
+    /// Patterns a file must match one of to be analyzed; empty keeps every file
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    
     /// Directory patterns to ignore (e.g., node_modules, target)
     #[serde(default)]
     pub ignore_directories: Vec<String>,
     
     /// Language-specific configuration
-    #[serde(default)]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub languages: HashMap<String, LanguageConfig>,
     
+    /// Use only the languages listed under `languages`, without the built-in ones
+    #[serde(default = "default_as_false")]
+    pub languages_override: bool,
+    
     /// Default settings to apply when language-specific ones aren't provided
     #[serde(default)]
     pub default_settings: DefaultSettings,
+    
+    /// Thresholds for the findings reported about analyzed files
+    #[serde(default)]
+    pub findings: FindingsConfig,
+    
+    /// Color thresholds for the SVG badges
+    #[serde(default)]
+    pub badges: BadgesConfig,
+    
+    /// Expected dependency directions between top-level directories
+    #[serde(default)]
+    pub architecture: ArchitectureConfig,
+    
+    /// Limits on the details listed in the report
+    #[serde(default)]
+    pub report: ReportConfig,
+    
+    /// Sentence templates of the executive summary at the top of the markdown and HTML reports
+    #[serde(default)]
+    pub executive_summary: ExecutiveSummaryConfig,
+    
+    /// Rules sorting files into categories (code, test, config, ...)
+    #[serde(default = "default_categories")]
+    pub categories: Vec<CategoryRule>,
+    
+    /// Weights of the terms making up the knowledge score
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    
+    /// Labels files are classified under by their complexity, from the simplest
+    #[serde(default = "default_complexity_levels")]
+    pub complexity_levels: Vec<ComplexityLevel>,
+    
+    /// Exports left out of the "Unused Exports" section
+    #[serde(default)]
+    pub unused_exports: UnusedExportsConfig,
+    
+    /// Which files the expensive analysis passes run on
+    #[serde(default)]
+    pub deep_analysis: DeepAnalysisConfig,
+    
+    /// Metric limits failing the run with exit code 2, for CI
+    #[serde(default)]
+    pub thresholds: ThresholdsConfig,
+    
+    /// Smallest score changes listed by `overdoc diff`
+    #[serde(default)]
+    pub diff: DiffConfig,
+    
+    /// Which files are tests, for --exclude-tests, --only-tests and the production/test totals
+    #[serde(default)]
+    pub tests: TestsConfig,
+    
+    /// Settings for the files matching a path glob, on top of the ones above
+    #[serde(default)]
+    pub overrides: Vec<PathOverride>,
+}
+
+/// Settings for the files matching `path`, e.g. `services/legacy/**`. Where several overrides
+/// match a file, the most specific one (the longest glob) setting a value decides it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PathOverride {
+    /// Glob matched like --include ones, against paths relative to the repository root
+    pub path: String,
+    
+    /// Patterns ignoring files on top of the global `ignore_patterns`
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    
+    #[serde(default)]
+    pub default_settings: SettingsOverride,
+    
+    #[serde(default)]
+    pub thresholds: ThresholdsOverride,
+}
+
+/// `default_settings` values an override can change; unset ones keep the base value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SettingsOverride {
+    /// File size limit in KB (0 means no limit)
+    #[serde(default)]
+    pub max_file_size_kb: Option<usize>,
+    
+    /// Time budget in milliseconds for the complexity analysis of one file (0 means no limit)
+    #[serde(default)]
+    pub complexity_time_budget_ms: Option<u64>,
+    
+    /// Leave out the complexity analysis of the files
+    #[serde(default)]
+    pub skip_complexity: Option<bool>,
+}
+
+/// `thresholds` an override can change; the averages stay repository-wide
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThresholdsOverride {
+    /// Limit on the cyclomatic complexity of each file
+    #[serde(default)]
+    pub file_cyclomatic_above: Option<f64>,
 }
 
 /// Configuration for a specific programming language
@@ -30,6 +154,11 @@ pub struct LanguageConfig {
     /// File extensions for this language
     pub extensions: Vec<String>,
     
+    /// Patterns the files of this language must match one of to be analyzed; empty keeps
+    /// them all
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    
     /// Language-specific files to ignore
     #[serde(default)]
     pub ignore_files: Vec<String>,
@@ -38,13 +167,44 @@ pub struct LanguageConfig {
     #[serde(default)]
     pub ignore_directories: Vec<String>,
     
-    /// Import structures to recognize
+    /// Import structures to recognize, on top of or instead of the built-in ones
+    #[serde(default)]
+    pub import_patterns: Vec<PatternEntry>,
+    
+    /// Export structures to recognize, on top of or instead of the built-in ones
+    #[serde(default)]
+    pub export_patterns: Vec<PatternEntry>,
+    
+    /// Names of built-in patterns to leave out (e.g. `ts.export_const`)
     #[serde(default)]
-    pub import_patterns: Vec<String>,
+    pub disable_patterns: Vec<String>,
     
-    /// Export structures to recognize
+    /// Whether `ignore_files` and `ignore_directories` apply only to this
+    /// language's files or to every file in the repository
     #[serde(default)]
-    pub export_patterns: Vec<String>,
+    pub ignore_scope: IgnoreScope,
+}
+
+/// An import or export pattern in a language config. A bare regex makes the list the
+/// complete pattern set; a named one replaces the built-in pattern with that name or adds
+/// a new one next to the built-ins (see `patterns::effective_patterns`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PatternEntry {
+    Plain(String),
+    Named { name: String, pattern: String },
+}
+
+/// Which files a language's ignore rules apply to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnoreScope {
+    /// Only files with one of the language's extensions
+    #[default]
+    Language,
+    
+    /// Every file, whatever its extension (e.g. `__snapshots__` holding `.snap` files)
+    Global,
 }
 
 /// Default settings to use when language-specific ones aren't provided
@@ -57,6 +217,39 @@ pub struct DefaultSettings {
     /// Default file size limit in KB (0 means no limit)
     #[serde(default)]
     pub max_file_size_kb: usize,
+    
+    /// Time budget in milliseconds for the complexity analysis of one file (0 means no limit)
+    #[serde(default = "default_complexity_time_budget_ms")]
+    pub complexity_time_budget_ms: u64,
+    
+    /// How directory keys differing only in casing are merged
+    #[serde(default)]
+    pub path_case: PathCase,
+    
+    /// Follow symlinked files and directories while traversing (--follow-symlinks); files
+    /// reachable through several links are analyzed once
+    #[serde(default = "default_as_false")]
+    pub follow_symlinks: bool,
+    
+    /// Skip what the `.gitignore` files, `.git/info/exclude` and the global git excludes
+    /// ignore, when the repository is a git work tree (--no-gitignore turns it off)
+    #[serde(default = "default_as_true")]
+    pub respect_gitignore: bool,
+}
+
+/// How directory keys are normalized before scores and metrics are aggregated per directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathCase {
+    /// Resolve each directory to its on-disk path, merging casings and symlinks of one directory
+    #[default]
+    OnDisk,
+    
+    /// Lowercase directory keys, for case-insensitive file systems
+    Fold,
+    
+    /// Keep directory keys as the files spell them
+    Preserve,
 }
 
 impl Default for DefaultSettings {
@@ -64,6 +257,484 @@ impl Default for DefaultSettings {
         DefaultSettings {
             include_no_extension: false,
             max_file_size_kb: 1024, // 1MB default limit
+            complexity_time_budget_ms: default_complexity_time_budget_ms(),
+            path_case: PathCase::default(),
+            follow_symlinks: false,
+            respect_gitignore: true,
+        }
+    }
+}
+
+/// Thresholds for the findings reported about analyzed files
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingsConfig {
+    /// Share (0-1) of a file's cyclomatic complexity held by a single function
+    /// above which the file is reported as having concentrated complexity
+    #[serde(default = "default_concentration_threshold")]
+    pub concentration_threshold: f64,
+    
+    /// Minimum cyclomatic complexity of that function for the finding to be reported
+    #[serde(default = "default_concentration_min_cyclomatic")]
+    pub concentration_min_cyclomatic: f64,
+    
+    /// Files with a higher cyclomatic complexity are reported
+    #[serde(default = "default_max_cyclomatic")]
+    pub max_cyclomatic: f64,
+    
+    /// Files with a higher cognitive complexity are reported
+    #[serde(default = "default_max_cognitive")]
+    pub max_cognitive: f64,
+    
+    /// Files with a lower maintainability index (0-100) are reported
+    #[serde(default = "default_min_maintainability")]
+    pub min_maintainability: f64,
+    
+    /// Relative imports climbing more directories (`../` or `super::`) are reported
+    #[serde(default = "default_max_relative_import_depth")]
+    pub max_relative_import_depth: usize,
+}
+
+impl Default for FindingsConfig {
+    fn default() -> Self {
+        FindingsConfig {
+            concentration_threshold: default_concentration_threshold(),
+            concentration_min_cyclomatic: default_concentration_min_cyclomatic(),
+            max_cyclomatic: default_max_cyclomatic(),
+            max_cognitive: default_max_cognitive(),
+            min_maintainability: default_min_maintainability(),
+            max_relative_import_depth: default_max_relative_import_depth(),
+        }
+    }
+}
+
+/// Color thresholds for the SVG badges: at or above `green` is green,
+/// at or above `yellow` is yellow, anything lower is red
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BadgesConfig {
+    /// Average maintainability index (0-100) for a green badge
+    #[serde(default = "default_maintainability_green")]
+    pub maintainability_green: f64,
+    
+    /// Average maintainability index (0-100) for a yellow badge
+    #[serde(default = "default_maintainability_yellow")]
+    pub maintainability_yellow: f64,
+    
+    /// Comment ratio (0-1) for a green badge
+    #[serde(default = "default_comment_ratio_green")]
+    pub comment_ratio_green: f64,
+    
+    /// Comment ratio (0-1) for a yellow badge
+    #[serde(default = "default_comment_ratio_yellow")]
+    pub comment_ratio_yellow: f64,
+}
+
+/// Sentence templates of the executive summary. `{name}` placeholders are replaced with
+/// values from the report; sentences whose facts are missing are left out
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecutiveSummaryConfig {
+    /// Whether the markdown and HTML reports open with the summary
+    #[serde(default = "default_as_true")]
+    pub enabled: bool,
+    
+    /// Repository size: `{files}`, `{lines}`
+    #[serde(default = "default_summary_size")]
+    pub size: String,
+    
+    /// Repository size when metrics were skipped: `{files}`
+    #[serde(default = "default_summary_size_without_lines")]
+    pub size_without_lines: String,
+    
+    /// Dominant languages: `{languages}`, e.g. "typescript (60%), rust (30%) and python (10%)"
+    #[serde(default = "default_summary_languages")]
+    pub languages: String,
+    
+    /// Most important files: `{files}`, the `top_file` clauses joined
+    #[serde(default = "default_summary_top_files")]
+    pub top_files: String,
+    
+    /// One important file: `{path}`, `{score}`, `{dependents}` and `{exports}` (with their
+    /// unit, e.g. "2 files" and "1 export")
+    #[serde(default = "default_summary_top_file")]
+    pub top_file: String,
+    
+    /// Worst knowledge hotspots: `{files}`, the `hotspot` clauses joined
+    #[serde(default = "default_summary_hotspots")]
+    pub hotspots: String,
+    
+    /// One hotspot: `{path}`, `{score}`, `{factor}` (the largest term of its knowledge score)
+    #[serde(default = "default_summary_hotspot")]
+    pub hotspot: String,
+    
+    /// Findings: `{findings}`, e.g. "3 warnings"
+    #[serde(default = "default_summary_findings")]
+    pub findings: String,
+    
+    /// Sentence used when there are no findings
+    #[serde(default = "default_summary_no_findings")]
+    pub no_findings: String,
+    
+    /// Change since the previous run recorded in --db: `{files}`, `{lines}`, `{maintainability}`,
+    /// each an arrow with the difference, e.g. "↑ 12"
+    #[serde(default = "default_summary_trend")]
+    pub trend: String,
+}
+
+/// Expected dependency directions between top-level directories
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ArchitectureConfig {
+    /// Top-level directories holding leaf utilities; they may only depend on
+    /// each other, so their dependencies on any other directory are flagged
+    #[serde(default)]
+    pub leaf_directories: Vec<String>,
+}
+
+/// Weights of the terms making up the knowledge score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringConfig {
+    /// Points each factor adds at its cap
+    #[serde(default)]
+    pub weights: ScoringWeights,
+    
+    /// Values at which the factors stop growing
+    #[serde(default)]
+    pub caps: ScoringCaps,
+    
+    /// Multiplier of the summed factors before the score is clamped to 0-100
+    #[serde(default = "default_scoring_scale")]
+    pub scale: f64,
+    
+    /// Weight of each external signal loaded with --signals, e.g. `incidents: 5.0`;
+    /// signals without a weight don't affect the score
+    #[serde(default)]
+    pub extra: BTreeMap<String, f64>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            weights: ScoringWeights::default(),
+            caps: ScoringCaps::default(),
+            scale: default_scoring_scale(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Weight of each knowledge score factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringWeights {
+    /// Multiplier of the natural log of the line count
+    #[serde(default = "default_weight_size")]
+    pub size: f64,
+    
+    #[serde(default = "default_weight_cyclomatic")]
+    pub cyclomatic: f64,
+    
+    #[serde(default = "default_weight_cognitive")]
+    pub cognitive: f64,
+    
+    /// Reached at a maintainability index of 0
+    #[serde(default = "default_weight_maintainability")]
+    pub maintainability: f64,
+    
+    #[serde(default = "default_weight_functions")]
+    pub functions: f64,
+    
+    #[serde(default = "default_weight_declarations")]
+    pub declarations: f64,
+    
+    /// Reached by the file with the most important exports
+    #[serde(default = "default_weight_exports")]
+    pub exports: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        ScoringWeights {
+            size: default_weight_size(),
+            cyclomatic: default_weight_cyclomatic(),
+            cognitive: default_weight_cognitive(),
+            maintainability: default_weight_maintainability(),
+            functions: default_weight_functions(),
+            declarations: default_weight_declarations(),
+            exports: default_weight_exports(),
+        }
+    }
+}
+
+/// Values past which a knowledge score factor no longer grows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringCaps {
+    #[serde(default = "default_cap_cyclomatic")]
+    pub cyclomatic: f64,
+    
+    #[serde(default = "default_cap_cognitive")]
+    pub cognitive: f64,
+    
+    #[serde(default = "default_cap_functions")]
+    pub functions: usize,
+    
+    #[serde(default = "default_cap_declarations")]
+    pub declarations: usize,
+}
+
+impl Default for ScoringCaps {
+    fn default() -> Self {
+        ScoringCaps {
+            cyclomatic: default_cap_cyclomatic(),
+            cognitive: default_cap_cognitive(),
+            functions: default_cap_functions(),
+            declarations: default_cap_declarations(),
+        }
+    }
+}
+
+/// Exports that are legitimately never imported, such as entry points
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnusedExportsConfig {
+    /// Export names to leave out, as globs (e.g. `test_*`)
+    #[serde(default = "default_unused_exports_ignore_names")]
+    pub ignore_names: Vec<String>,
+}
+
+impl Default for UnusedExportsConfig {
+    fn default() -> Self {
+        UnusedExportsConfig {
+            ignore_names: default_unused_exports_ignore_names(),
+        }
+    }
+}
+
+/// An expensive analysis pass that `deep_analysis` can restrict to some files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeepPass {
+    /// Cyclomatic and cognitive complexity, Halstead metrics and the maintainability index
+    Complexity,
+    
+    /// Per-function metrics
+    Functions,
+}
+
+/// Metric limits checked after the analysis; the run fails with exit code 2 when one is
+/// crossed. Unset limits aren't checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThresholdsConfig {
+    /// Fail when the average maintainability index (0-100) is lower (--fail-on-maintainability-below)
+    #[serde(default)]
+    pub maintainability_below: Option<f64>,
+    
+    /// Fail when the average cyclomatic complexity is higher (--fail-on-avg-cyclomatic-above)
+    #[serde(default)]
+    pub avg_cyclomatic_above: Option<f64>,
+    
+    /// Fail when any file's cyclomatic complexity is higher (--fail-on-file-cyclomatic-above)
+    #[serde(default)]
+    pub file_cyclomatic_above: Option<f64>,
+    
+    /// How much worse than its --baseline value a file's metric may get before it counts
+    #[serde(default)]
+    pub baseline_tolerance: f64,
+}
+
+/// Smallest changes `overdoc diff` lists; smaller moves are left out as noise
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffConfig {
+    /// Importance score change, in dependents
+    #[serde(default = "default_diff_min_importance_delta")]
+    pub min_importance_delta: usize,
+    
+    /// Knowledge score change
+    #[serde(default = "default_diff_min_knowledge_delta")]
+    pub min_knowledge_delta: f64,
+    
+    /// Cyclomatic complexity change
+    #[serde(default = "default_diff_min_cyclomatic_delta")]
+    pub min_cyclomatic_delta: f64,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        DiffConfig {
+            min_importance_delta: default_diff_min_importance_delta(),
+            min_knowledge_delta: default_diff_min_knowledge_delta(),
+            min_cyclomatic_delta: default_diff_min_cyclomatic_delta(),
+        }
+    }
+}
+
+/// Which files are tests
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestsConfig {
+    /// Globs matched against paths relative to the repository; a glob without `/` matches at
+    /// any depth, and a glob matching a directory matches the files below it
+    #[serde(default = "default_test_patterns")]
+    pub patterns: Vec<String>,
+    
+    /// Rust files declared as a `#[cfg(test)] mod name;` module are tests too
+    #[serde(default = "default_as_true")]
+    pub detect_rust_test_modules: bool,
+}
+
+impl Default for TestsConfig {
+    fn default() -> Self {
+        TestsConfig {
+            patterns: default_test_patterns(),
+            detect_rust_test_modules: default_as_true(),
+        }
+    }
+}
+
+/// Pre-filter deciding which files the expensive analysis passes run on. The line-based
+/// metrics always run on every file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeepAnalysisConfig {
+    /// Restrict the gated passes to files passing the pre-filter (false runs them on every file)
+    #[serde(default = "default_as_false")]
+    pub enabled: bool,
+    
+    /// Files with more lines get the deep passes
+    #[serde(default = "default_deep_min_lines")]
+    pub min_lines: usize,
+    
+    /// Files whose line-based cyclomatic estimate is higher get the deep passes
+    #[serde(default = "default_deep_min_cyclomatic")]
+    pub min_cyclomatic: usize,
+    
+    /// The most important files get the deep passes whatever their size
+    #[serde(default = "default_deep_top_importance")]
+    pub top_importance: usize,
+    
+    /// Passes restricted by the pre-filter; the others run on every file
+    #[serde(default = "default_deep_passes")]
+    pub passes: Vec<DeepPass>,
+}
+
+impl Default for DeepAnalysisConfig {
+    fn default() -> Self {
+        DeepAnalysisConfig {
+            enabled: false,
+            min_lines: default_deep_min_lines(),
+            min_cyclomatic: default_deep_min_cyclomatic(),
+            top_importance: default_deep_top_importance(),
+            passes: default_deep_passes(),
+        }
+    }
+}
+
+/// A file category and the path patterns of its files
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRule {
+    /// Name of the category (e.g. test, generated)
+    pub name: String,
+    
+    /// Globs matched like `ignore_patterns` against paths relative to the repository root
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    
+    /// Rules with a higher priority take precedence; equal priorities keep the configuration order
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A complexity label and the largest complexity of its files. A file gets the first level
+/// whose limits it stays within, and the last level when it exceeds them all.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComplexityLevel {
+    /// Label of the level (e.g. simple, very complex)
+    pub label: String,
+    
+    /// Largest cyclomatic complexity of the level's files; unset for no limit
+    #[serde(default)]
+    pub max_cyclomatic: Option<f64>,
+    
+    /// Largest cognitive complexity of the level's files; unset for no limit
+    #[serde(default)]
+    pub max_cognitive: Option<f64>,
+}
+
+/// Limits on the details listed in the report
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportConfig {
+    /// Maximum number of exports listed per file in verbose mode
+    #[serde(default = "default_exports_per_file")]
+    pub exports_per_file: usize,
+    
+    /// Number of top files whose exports are listed in verbose mode
+    #[serde(default = "default_files_with_export_detail")]
+    pub files_with_export_detail: usize,
+    
+    /// Maximum number of files in the "Used by" and "Depends on" lists of a top file
+    #[serde(default = "default_dependency_list_limit")]
+    pub dependency_list_limit: usize,
+    
+    /// Number of files in each "Top Complexity Offenders" list
+    #[serde(default = "default_complexity_offenders")]
+    pub complexity_offenders: usize,
+    
+    /// Maximum number of terms in the glossary written with --glossary
+    #[serde(default = "default_glossary_terms")]
+    pub glossary_terms: usize,
+    
+    /// Categories left out of "Top Important Files" and ranked under "Most Depended-Upon
+    /// Test Utilities" instead (empty to rank every file together)
+    #[serde(default = "default_test_categories")]
+    pub test_categories: Vec<String>,
+    
+    /// Number of files in the "Most Depended-Upon Test Utilities" list
+    #[serde(default = "default_test_utilities")]
+    pub test_utilities: usize,
+    
+    /// Files with a lower importance score are left out of "Top Important Files" (--min-importance)
+    #[serde(default)]
+    pub min_importance: usize,
+    
+    /// Files with a lower knowledge score are left out of "Knowledge Hotspots" (--min-knowledge-score)
+    #[serde(default)]
+    pub min_knowledge_score: f64,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        ReportConfig {
+            exports_per_file: default_exports_per_file(),
+            files_with_export_detail: default_files_with_export_detail(),
+            dependency_list_limit: default_dependency_list_limit(),
+            complexity_offenders: default_complexity_offenders(),
+            glossary_terms: default_glossary_terms(),
+            test_categories: default_test_categories(),
+            test_utilities: default_test_utilities(),
+            min_importance: 0,
+            min_knowledge_score: 0.0,
+        }
+    }
+}
+
+impl Default for ExecutiveSummaryConfig {
+    fn default() -> Self {
+        ExecutiveSummaryConfig {
+            enabled: default_as_true(),
+            size: default_summary_size(),
+            size_without_lines: default_summary_size_without_lines(),
+            languages: default_summary_languages(),
+            top_files: default_summary_top_files(),
+            top_file: default_summary_top_file(),
+            hotspots: default_summary_hotspots(),
+            hotspot: default_summary_hotspot(),
+            findings: default_summary_findings(),
+            no_findings: default_summary_no_findings(),
+            trend: default_summary_trend(),
+        }
+    }
+}
+
+impl Default for BadgesConfig {
+    fn default() -> Self {
+        BadgesConfig {
+            maintainability_green: default_maintainability_green(),
+            maintainability_yellow: default_maintainability_yellow(),
+            comment_ratio_green: default_comment_ratio_green(),
+            comment_ratio_yellow: default_comment_ratio_yellow(),
         }
     }
 }
@@ -78,6 +749,7 @@ impl Default for Config {
                 ".gitignore".to_string(),
                 ".git/*".to_string(),
             ],
+            include_patterns: Vec::new(),
             ignore_directories: vec![
                 "node_modules".to_string(),
                 "target".to_string(),
@@ -85,49 +757,992 @@ impl Default for Config {
                 "build".to_string(),
                 ".git".to_string(),
             ],
-            languages: HashMap::new(),
+            languages: builtin_languages(),
+            languages_override: false,
             default_settings: DefaultSettings::default(),
+            findings: FindingsConfig::default(),
+            badges: BadgesConfig::default(),
+            architecture: ArchitectureConfig::default(),
+            report: ReportConfig::default(),
+            executive_summary: ExecutiveSummaryConfig::default(),
+            categories: default_categories(),
+            scoring: ScoringConfig::default(),
+            complexity_levels: default_complexity_levels(),
+            unused_exports: UnusedExportsConfig::default(),
+            deep_analysis: DeepAnalysisConfig::default(),
+            thresholds: ThresholdsConfig::default(),
+            diff: DiffConfig::default(),
+            tests: TestsConfig::default(),
+            overrides: Vec::new(),
         }
     }
 }
 
+/// A built-in language; its patterns are the built-ins of `patterns::builtin_patterns`
+fn builtin_language(extensions: &[&str], ignore_directories: &[&str]) -> LanguageConfig {
+    LanguageConfig {
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        ignore_directories: ignore_directories.iter().map(|d| d.to_string()).collect(),
+        ..LanguageConfig::default()
+    }
+}
+
+/// Languages analyzed when the configuration doesn't list them itself
+pub fn builtin_languages() -> HashMap<String, LanguageConfig> {
+    HashMap::from([
+        ("rust".to_string(), builtin_language(&["rs"], &["target"])),
+        (
+            "typescript".to_string(),
+            builtin_language(&["ts", "tsx", "mts", "cts"], &["node_modules", "dist", "build"]),
+        ),
+        (
+            "javascript".to_string(),
+            builtin_language(&["js", "jsx", "mjs", "cjs"], &["node_modules", "dist", "build"]),
+        ),
+        (
+            "python".to_string(),
+            builtin_language(&["py"], &["__pycache__", "venv", ".venv", ".tox"]),
+        ),
+        ("go".to_string(), builtin_language(&["go"], &["vendor"])),
+    ])
+}
+
 /// Helper function for default boolean values in serde
 fn default_as_false() -> bool {
     false
 }
 
-/// Load configuration from a YAML file
-pub fn load_config(config_path: &str) -> Result<Config> {
+fn default_as_true() -> bool {
+    true
+}
+
+fn default_complexity_time_budget_ms() -> u64 {
+    2000
+}
+
+fn default_deep_min_lines() -> usize {
+    300
+}
+
+fn default_deep_min_cyclomatic() -> usize {
+    20
+}
+
+fn default_deep_top_importance() -> usize {
+    20
+}
+
+fn default_deep_passes() -> Vec<DeepPass> {
+    vec![DeepPass::Complexity, DeepPass::Functions]
+}
+
+fn default_scoring_scale() -> f64 {
+    0.85
+}
+
+fn default_weight_size() -> f64 {
+    2.0
+}
+
+fn default_weight_cyclomatic() -> f64 {
+    15.0
+}
+
+fn default_weight_cognitive() -> f64 {
+    25.0
+}
+
+fn default_weight_maintainability() -> f64 {
+    20.0
+}
+
+fn default_weight_functions() -> f64 {
+    15.0
+}
+
+fn default_weight_declarations() -> f64 {
+    10.0
+}
+
+fn default_weight_exports() -> f64 {
+    15.0
+}
+
+fn default_cap_cyclomatic() -> f64 {
+    50.0
+}
+
+fn default_cap_cognitive() -> f64 {
+    200.0
+}
+
+fn default_cap_functions() -> usize {
+    20
+}
+
+fn default_cap_declarations() -> usize {
+    10
+}
+
+fn default_diff_min_importance_delta() -> usize {
+    1
+}
+
+fn default_diff_min_knowledge_delta() -> f64 {
+    1.0
+}
+
+fn default_diff_min_cyclomatic_delta() -> f64 {
+    1.0
+}
+
+fn default_test_patterns() -> Vec<String> {
+    ["*_test.*", "*.test.*", "*.spec.*", "tests", "__tests__"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+fn default_concentration_threshold() -> f64 {
+    0.6
+}
+
+fn default_concentration_min_cyclomatic() -> f64 {
+    15.0
+}
+
+fn default_max_cyclomatic() -> f64 {
+    50.0
+}
+
+fn default_max_cognitive() -> f64 {
+    100.0
+}
+
+fn default_min_maintainability() -> f64 {
+    10.0
+}
+
+fn default_max_relative_import_depth() -> usize {
+    3
+}
+
+fn default_unused_exports_ignore_names() -> Vec<String> {
+    vec![
+        "main".to_string(),
+        "default".to_string(),
+        "test_*".to_string(),
+        "*_test".to_string(),
+    ]
+}
+
+fn default_maintainability_green() -> f64 {
+    65.0
+}
+
+fn default_maintainability_yellow() -> f64 {
+    40.0
+}
+
+fn default_comment_ratio_green() -> f64 {
+    0.2
+}
+
+fn default_comment_ratio_yellow() -> f64 {
+    0.1
+}
+
+fn default_categories() -> Vec<CategoryRule> {
+    let rule = |name: &str, patterns: &[&str]| CategoryRule {
+        name: name.to_string(),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        priority: 0,
+    };
+    
+    vec![
+        rule("vendored", &["vendor", "third_party"]),
+        rule("generated", &["generated", "*.generated.*", "*_generated.*", "*.pb.go"]),
+        rule("test", &["tests", "test", "__tests__", "*_test.*", "*.test.*", "*.spec.*"]),
+        rule("docs", &["docs", "*.md", "*.rst"]),
+        rule("config", &["*.yaml", "*.yml", "*.toml", "*.json", "*.ini"]),
+    ]
+}
+
+fn default_complexity_levels() -> Vec<ComplexityLevel> {
+    let level = |label: &str, max_cyclomatic: Option<f64>, max_cognitive: Option<f64>| ComplexityLevel {
+        label: label.to_string(),
+        max_cyclomatic,
+        max_cognitive,
+    };
+    
+    vec![
+        level("simple", Some(10.0), Some(15.0)),
+        level("moderate", Some(20.0), Some(30.0)),
+        level("complex", Some(40.0), Some(60.0)),
+        level("very complex", None, None),
+    ]
+}
+
+fn default_exports_per_file() -> usize {
+    20
+}
+
+fn default_files_with_export_detail() -> usize {
+    5
+}
+
+fn default_dependency_list_limit() -> usize {
+    10
+}
+
+fn default_complexity_offenders() -> usize {
+    10
+}
+
+fn default_glossary_terms() -> usize {
+    50
+}
+
+fn default_test_categories() -> Vec<String> {
+    vec!["test".to_string()]
+}
+
+fn default_test_utilities() -> usize {
+    5
+}
+
+fn default_summary_size() -> String {
+    "The repository has {files} files with {lines} lines.".to_string()
+}
+
+fn default_summary_size_without_lines() -> String {
+    "The repository has {files} analyzed files.".to_string()
+}
+
+fn default_summary_languages() -> String {
+    "Most files are {languages}.".to_string()
+}
+
+fn default_summary_top_files() -> String {
+    "The most important files are {files}.".to_string()
+}
+
+fn default_summary_top_file() -> String {
+    "{path} (used by {dependents})".to_string()
+}
+
+fn default_summary_hotspots() -> String {
+    "The files needing the most knowledge are {files}.".to_string()
+}
+
+fn default_summary_hotspot() -> String {
+    "{path} (score {score}, mostly {factor})".to_string()
+}
+
+fn default_summary_findings() -> String {
+    "The analysis raised {findings}.".to_string()
+}
+
+fn default_summary_no_findings() -> String {
+    "The analysis raised no findings.".to_string()
+}
+
+fn default_summary_trend() -> String {
+    "Since the previous run: files {files}, lines {lines}, maintainability {maintainability}.".to_string()
+}
+
+/// Configuration files looked for, in this order, when no path is given
+pub const CONFIG_FILES: &[&str] = &["overdoc.yaml", "overdoc.yml", "overdoc.toml", "overdoc.json"];
+
+/// The first of `CONFIG_FILES` that exists, if any
+pub fn find_config() -> Option<&'static str> {
+    CONFIG_FILES.iter().copied().find(|name| Path::new(name).is_file())
+}
+
+/// Format of a configuration file, by its extension; files without a known extension are YAML
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "YAML",
+            ConfigFormat::Toml => "TOML",
+            ConfigFormat::Json => "JSON",
+        }
+    }
+    
+    /// Parse a configuration document into the YAML value every format is merged and
+    /// checked as
+    fn parse(&self, content: &str) -> Result<serde_yaml::Value> {
+        Ok(match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+        })
+    }
+    
+    /// Write a configuration document in this format
+    fn render(&self, value: &serde_yaml::Value) -> Result<String> {
+        Ok(match self {
+            ConfigFormat::Yaml => serde_yaml::to_string(value)?,
+            ConfigFormat::Toml => toml::to_string_pretty(value)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(value)?,
+        })
+    }
+}
+
+/// Load configuration from a YAML, TOML or JSON file, by its extension, merged over the files
+/// it `extends`. Returns the
+/// configuration and the files it was read from, the extended ones first.
+pub fn load_config(config_path: &str) -> Result<(Config, Vec<String>)> {
     // Check if config file exists
     let path = Path::new(config_path);
     
     if !path.exists() {
-        return Ok(Config::default());
+        return Ok((Config::default(), Vec::new()));
+    }
+    
+    let mut files = Vec::new();
+    let raw = load_layers(path, &mut Vec::new(), &mut files)?;
+    let mut config: Config = serde_yaml::from_value(raw.clone())
+        .context("Failed to parse the configuration")?;
+    
+    // Languages the configuration doesn't list keep their built-in settings
+    if !config.languages_override {
+        for (name, language) in builtin_languages() {
+            config.languages.entry(name).or_insert(language);
+        }
+    }
+    
+    // Collect every problem before failing, so a single run reports them all
+    let mut problems = config.validate();
+    problems.errors.splice(0..0, unknown_keys(&raw));
+    if raw["default_settings"]["max_file_size_kb"].as_u64() == Some(0) {
+        problems.warnings.push(
+            "default_settings.max_file_size_kb: 0 means no size limit, not a limit of 0 KB".to_string()
+        );
+    }
+    
+    for warning in &problems.warnings {
+        warn!("{}: {}", config_path, warning);
+    }
+    if !problems.errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Invalid configuration in {}:\n  - {}",
+            config_path,
+            problems.errors.join("\n  - ")
+        ));
+    }
+    
+    Ok((config, files))
+}
+
+/// Read a configuration file and merge it over the files its `extends` key names, relative
+/// to its directory; later files in `extends` are merged over earlier ones. `chain` holds
+/// the files being read, to catch files extending each other, and `files` collects every
+/// file read.
+fn load_layers(path: &Path, chain: &mut Vec<PathBuf>, files: &mut Vec<String>) -> Result<serde_yaml::Value> {
+    use serde_yaml::Value;
+    
+    let canonical = path.canonicalize()
+        .context(format!("Failed to read config file at {}", path.display()))?;
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain.iter()
+            .skip_while(|file| **file != canonical)
+            .chain([&canonical])
+            .map(|file| file.display().to_string())
+            .collect();
+        return Err(anyhow::anyhow!("Configuration files extend each other: {}", cycle.join(" -> ")));
     }
     
-    // Read and parse the config file
     let config_str = fs::read_to_string(path)
-        .context(format!("Failed to read config file at {}", config_path))?;
+        .context(format!("Failed to read config file at {}", path.display()))?;
+    let format = ConfigFormat::of(path);
+    let mut value: Value = format.parse(&config_str)
+        .context(format!("Failed to parse {} configuration {}", format.name(), path.display()))?;
     
-    let config: Config = serde_yaml::from_str(&config_str)
-        .context("Failed to parse YAML configuration")?;
+    let extends = match value.as_mapping_mut().and_then(|mapping| mapping.remove("extends")) {
+        None => Vec::new(),
+        Some(Value::String(base)) => vec![base],
+        Some(Value::Sequence(bases)) => bases.into_iter()
+            .map(|base| match base {
+                Value::String(base) => Ok(base),
+                _ => Err(anyhow::anyhow!("{}: every entry of extends must be a path", path.display())),
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => return Err(anyhow::anyhow!("{}: extends must be a path or a list of paths", path.display())),
+    };
     
-    Ok(config)
+    chain.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = Value::Mapping(Default::default());
+    for base in &extends {
+        let base = load_layers(&dir.join(base), chain, files)
+            .context(format!("Failed to load {} extended by {}", base, path.display()))?;
+        merged = merge_layer(merged, base);
+    }
+    chain.pop();
+    
+    files.push(path.display().to_string());
+    Ok(merge_layer(merged, value))
 }
 
-/// Create a default configuration file if one doesn't exist
-pub fn create_default_config(config_path: &str) -> Result<()> {
-    let path = Path::new(config_path);
+/// Merge a configuration layer over the one it extends: sections merge key by key (so
+/// `languages` merge per language), lists are appended to unless tagged `!replace`, and
+/// other values replace the extended ones
+fn merge_layer(base: serde_yaml::Value, layer: serde_yaml::Value) -> serde_yaml::Value {
+    use serde_yaml::Value;
     
-    if path.exists() {
-        return Ok(());
+    match (base, layer) {
+        (Value::Mapping(mut base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                let merged = match base.remove(&key) {
+                    Some(extended) => merge_layer(extended, value),
+                    None => merge_layer(Value::Null, value),
+                };
+                base.insert(key, merged);
+            }
+            Value::Mapping(base)
+        },
+        (Value::Sequence(mut base), Value::Sequence(layer)) => {
+            base.extend(layer.into_iter().map(|value| merge_layer(Value::Null, value)));
+            Value::Sequence(base)
+        },
+        (_, Value::Tagged(tagged)) if tagged.tag == "replace" => merge_layer(Value::Null, tagged.value),
+        (_, Value::Mapping(layer)) => merge_layer(Value::Mapping(Default::default()), Value::Mapping(layer)),
+        (_, Value::Sequence(layer)) => merge_layer(Value::Sequence(Vec::new()), Value::Sequence(layer)),
+        (_, layer) => layer,
     }
+}
+
+/// Keys of the configuration file that no setting reads, such as `ignore_pattern` for
+/// `ignore_patterns`, found by comparing it with the serialized default configuration
+fn unknown_keys(raw: &serde_yaml::Value) -> Vec<String> {
+    let known = serde_yaml::to_value(Config::default()).unwrap_or_default();
+    let mut unknown = Vec::new();
+    collect_unknown_keys(raw, &known, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown_keys(raw: &serde_yaml::Value, known: &serde_yaml::Value, path: &str, unknown: &mut Vec<String>) {
+    use serde_yaml::Value;
     
-    let default_config = Config::default();
-    let yaml = serde_yaml::to_string(&default_config)
-        .context("Failed to serialize default configuration")?;
+    // Overrides are empty by default, so their entries are checked against an empty one
+    let override_template;
+    let known = if path == "overrides" {
+        override_template = serde_yaml::to_value([PathOverride::default()]).unwrap_or_default();
+        &override_template
+    } else {
+        known
+    };
+    
+    match (raw, known) {
+        // Empty sections in the default are free-form maps, like `scoring.extra`
+        (Value::Mapping(raw), Value::Mapping(known)) if !known.is_empty() => {
+            for (key, value) in raw {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let child = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+                
+                // Every entry under `languages` is a language, named by the user
+                if path == "languages" {
+                    let language = serde_yaml::to_value(LanguageConfig::default()).unwrap_or_default();
+                    collect_unknown_keys(value, &language, &child, unknown);
+                    continue;
+                }
+                
+                match known.get(key) {
+                    Some(known) => collect_unknown_keys(value, known, &child, unknown),
+                    None => {
+                        let valid: Vec<&str> = known.keys().filter_map(|k| k.as_str()).collect();
+                        let location = if path.is_empty() { "the configuration".to_string() } else { format!("'{}'", path) };
+                        unknown.push(format!(
+                            "Unknown configuration key '{}' in {} (valid keys: {})",
+                            key,
+                            location,
+                            valid.join(", ")
+                        ));
+                    },
+                }
+            }
+        },
+        // List entries are checked against the first default entry, e.g. of `categories`
+        (Value::Sequence(raw), Value::Sequence(known)) => {
+            if let Some(known) = known.first() {
+                for (idx, value) in raw.iter().enumerate() {
+                    collect_unknown_keys(value, known, &format!("{}[{}]", path, idx), unknown);
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Problems found in a configuration by `Config::validate`
+#[derive(Debug, Default)]
+pub struct ConfigProblems {
+    /// Settings that can't work, failing the load
+    pub errors: Vec<String>,
+    
+    /// Settings that are likely mistakes, logged as warnings
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Check the settings serde can't: every import and export regex must compile and
+    /// extensions must be usable. Every problem is collected instead of stopping at the first.
+    pub fn validate(&self) -> ConfigProblems {
+        let mut problems = ConfigProblems::default();
+        
+        let mut languages: Vec<(&String, &LanguageConfig)> = self.languages.iter().collect();
+        languages.sort_by(|a, b| a.0.cmp(b.0));
+        
+        let mut claimed: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (name, lang) in &languages {
+            if lang.extensions.is_empty() {
+                problems.errors.push(format!("languages.{}: no extensions, so no file belongs to it", name));
+            }
+            for extension in &lang.extensions {
+                if extension.starts_with('.') {
+                    problems.errors.push(format!(
+                        "languages.{}.extensions: '{}' must be given without the leading dot",
+                        name, extension
+                    ));
+                }
+                claimed.entry(extension.as_str()).or_default().push(name.as_str());
+            }
+            for pattern in &lang.include_patterns {
+                if let Err(err) = globset::Glob::new(pattern) {
+                    problems.errors.push(format!("languages.{}.include_patterns: {}", name, err));
+                }
+            }
+            
+            for (key, kind) in [("import_patterns", PatternKind::Import), ("export_patterns", PatternKind::Export)] {
+                for pattern in effective_patterns(name, lang, kind) {
+                    if let Err(err) = Regex::new(&pattern.pattern) {
+                        problems.errors.push(format!(
+                            "languages.{}.{}: {} is not a valid regex: {}",
+                            name,
+                            key,
+                            pattern.name.map_or(format!("'{}'", pattern.pattern), |n| format!("'{}' ({})", pattern.pattern, n)),
+                            err.to_string().replace('\n', "\n      ")
+                        ));
+                    }
+                }
+            }
+        }
+        
+        for (extension, names) in claimed.iter().filter(|(_, names)| names.len() > 1) {
+            problems.warnings.push(format!(
+                "extension '{}' is claimed by several languages ({}); only one of them analyzes its files",
+                extension,
+                names.join(", ")
+            ));
+        }
+        
+        for (key, patterns) in [("ignore_patterns", &self.ignore_patterns), ("include_patterns", &self.include_patterns)] {
+            for pattern in patterns {
+                if let Err(err) = globset::Glob::new(pattern) {
+                    problems.errors.push(format!("{}: {}", key, err));
+                }
+            }
+        }
+        for rule in &self.categories {
+            for pattern in &rule.patterns {
+                if let Err(err) = globset::Glob::new(pattern) {
+                    problems.errors.push(format!("categories.{}.patterns: {}", rule.name, err));
+                }
+            }
+        }
+        for pattern in &self.unused_exports.ignore_names {
+            if let Err(err) = globset::Glob::new(pattern) {
+                problems.errors.push(format!("unused_exports.ignore_names: {}", err));
+            }
+        }
+        for (idx, path_override) in self.overrides.iter().enumerate() {
+            if let Err(err) = globset::Glob::new(&path_override.path) {
+                problems.errors.push(format!("overrides[{}].path: {}", idx, err));
+            }
+            for pattern in &path_override.ignore_patterns {
+                if let Err(err) = globset::Glob::new(pattern) {
+                    problems.errors.push(format!("overrides[{}].ignore_patterns: {}", idx, err));
+                }
+            }
+        }
+        
+        if self.thresholds.baseline_tolerance < 0.0 {
+            problems.errors.push(format!(
+                "thresholds.baseline_tolerance: {} is negative, which would fail files that didn't change",
+                self.thresholds.baseline_tolerance
+            ));
+        }
+        if self.complexity_levels.is_empty() {
+            problems.errors.push("complexity_levels: at least one level is needed to classify files".to_string());
+        }
+        for (idx, level) in self.complexity_levels.iter().enumerate() {
+            if level.label.trim().is_empty() {
+                problems.errors.push(format!("complexity_levels[{}].label: must not be empty", idx));
+            }
+        }
+        
+        let caps = &self.scoring.caps;
+        for (name, cap) in [
+            ("cyclomatic", caps.cyclomatic),
+            ("cognitive", caps.cognitive),
+            ("functions", caps.functions as f64),
+            ("declarations", caps.declarations as f64),
+        ] {
+            if cap <= 0.0 {
+                problems.errors.push(format!("scoring.caps.{}: must be above 0, got {}", name, cap));
+            }
+        }
+        if self.badges.maintainability_yellow > self.badges.maintainability_green
+            || self.badges.comment_ratio_yellow > self.badges.comment_ratio_green
+        {
+            problems.warnings.push(
+                "badges: a yellow threshold is above its green one, so the yellow color is never used".to_string()
+            );
+        }
+        
+        problems
+    }
+}
+
+/// Commented starting configuration written by `overdoc init`, with example languages
+const INIT_CONFIG: &str = include_str!("../templates/overdoc.init.yaml");
+
+/// Write the starting configuration in the format of the file name, refusing to replace an
+/// existing file unless `force` is set. TOML and JSON can't hold the comments of the YAML
+/// template, so they only get its settings.
+pub fn create_default_config(config_path: &str, force: bool) -> Result<()> {
+    let path = Path::new(config_path);
+    
+    if path.exists() && !force {
+        return Err(anyhow::anyhow!(
+            "{} already exists; pass --force to overwrite it",
+            config_path
+        ));
+    }
     
-    fs::write(path, yaml).context("Failed to write default configuration file")?;
+    let content = match ConfigFormat::of(path) {
+        ConfigFormat::Yaml => INIT_CONFIG.to_string(),
+        format => {
+            let value: serde_yaml::Value = serde_yaml::from_str(INIT_CONFIG)
+                .context("Failed to parse the starting configuration")?;
+            format.render(&value)
+                .context(format!("Failed to write the starting configuration as {}", format.name()))?
+        },
+    };
+    fs::write(path, content).context("Failed to write default configuration file")?;
     
     Ok(())
-} 
\ No newline at end of file
+} 
+
+/// Prefix of the environment variables overriding configuration values
+pub const ENV_PREFIX: &str = "OVERDOC_";
+
+/// A single `key=value` override applied on top of the loaded configuration
+#[derive(Debug, Clone)]
+pub struct ConfigOverride {
+    /// Dotted path into the configuration (e.g. `default_settings.max_file_size_kb`)
+    pub key: String,
+    
+    /// Raw value as given by the user
+    pub value: String,
+    
+    /// Whether the value is appended to a list (`key+=value`) instead of replacing it
+    pub append: bool,
+    
+    /// Where the override came from (`--set`, or the name of the environment variable)
+    pub source: String,
+}
+
+impl ConfigOverride {
+    /// Parse a `key=value` or `key+=value` specification
+    pub fn parse(spec: &str, source: &str) -> Result<Self> {
+        let (key, value, append) = if let Some((key, value)) = spec.split_once("+=") {
+            (key, value, true)
+        } else if let Some((key, value)) = spec.split_once('=') {
+            (key, value, false)
+        } else {
+            return Err(anyhow::anyhow!(
+                "Invalid override '{}': expected KEY=VALUE or KEY+=VALUE",
+                spec
+            ));
+        };
+        
+        let key = key.trim();
+        if key.is_empty() || key.split('.').any(|part| part.is_empty()) {
+            return Err(anyhow::anyhow!("Invalid override '{}': empty key", spec));
+        }
+        
+        Ok(ConfigOverride {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+            append,
+            source: source.to_string(),
+        })
+    }
+    
+    /// Overrides from the `OVERDOC_*` environment variables, in name order. The rest of the
+    /// name is the key, lowercased, with `__` between nested keys:
+    /// `OVERDOC_DEFAULT_SETTINGS__MAX_FILE_SIZE_KB` sets `default_settings.max_file_size_kb`.
+    pub fn from_env() -> Result<Vec<Self>> {
+        let mut overrides = Vec::new();
+        for (name, value) in std::env::vars_os() {
+            let Some(name) = name.to_str().filter(|name| name.starts_with(ENV_PREFIX)) else {
+                continue;
+            };
+            let value = value.into_string().map_err(|_| {
+                anyhow::anyhow!("Environment variable {} is not valid UTF-8", name)
+            })?;
+            let key = name[ENV_PREFIX.len()..].to_lowercase().replace("__", ".");
+            overrides.push(Self::parse(&format!("{}={}", key, value), name)?);
+        }
+        overrides.sort_by(|a, b| a.source.cmp(&b.source));
+        Ok(overrides)
+    }
+    
+    /// Describe the override for provenance output
+    pub fn describe(&self) -> String {
+        let op = if self.append { "+=" } else { "=" };
+        format!("{}{}{} (from {})", self.key, op, self.value, self.source)
+    }
+}
+
+impl Config {
+    /// Apply an override by walking the dotted key path through the serialized configuration.
+    /// The value is parsed according to the type of the field it replaces.
+    pub fn apply_override(&mut self, ovr: &ConfigOverride) -> Result<()> {
+        let mut root = serde_yaml::to_value(&*self)
+            .context("Failed to serialize configuration")?;
+        
+        let parts: Vec<&str> = ovr.key.split('.').collect();
+        let mut current = &mut root;
+        
+        for (idx, part) in parts.iter().enumerate() {
+            let walked = parts[..idx].join(".");
+            let mapping = match current {
+                serde_yaml::Value::Mapping(mapping) => mapping,
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot descend into '{}': it is not a section",
+                        walked
+                    ))
+                }
+            };
+            
+            let key = serde_yaml::Value::String(part.to_string());
+            if !mapping.contains_key(&key) {
+                let valid: Vec<String> = mapping
+                    .keys()
+                    .filter_map(|k| k.as_str().map(String::from))
+                    .collect();
+                let location = if walked.is_empty() { "the configuration".to_string() } else { format!("'{}'", walked) };
+                return Err(anyhow::anyhow!(
+                    "Unknown configuration key '{}' in {} (valid keys: {})",
+                    part,
+                    location,
+                    valid.join(", ")
+                ));
+            }
+            
+            current = mapping.get_mut(&key).unwrap();
+        }
+        
+        let field = parts.last().unwrap();
+        let new_value = parse_override_value(current, &ovr.value, ovr.append, field)?;
+        *current = new_value;
+        
+        *self = serde_yaml::from_value(root)
+            .context(format!("Override '{}' produced an invalid configuration", ovr.key))?;
+        
+        Ok(())
+    }
+}
+
+/// Parse an override value according to the type of the value it replaces
+fn parse_override_value(
+    existing: &serde_yaml::Value,
+    raw: &str,
+    append: bool,
+    field: &str,
+) -> Result<serde_yaml::Value> {
+    use serde_yaml::Value;
+    
+    if append {
+        return match existing {
+            Value::Sequence(items) => {
+                let mut items = items.clone();
+                items.extend(split_list(raw));
+                Ok(Value::Sequence(items))
+            }
+            _ => Err(anyhow::anyhow!("'+=' is only supported for list fields, but {} is not a list", field)),
+        };
+    }
+    
+    match existing {
+        Value::Bool(_) => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(anyhow::anyhow!("expected boolean for {}, got '{}'", field, raw)),
+        },
+        Value::Number(number) if number.is_f64() => raw
+            .parse::<f64>()
+            .map(|v| Value::Number(v.into()))
+            .map_err(|_| anyhow::anyhow!("expected number for {}, got '{}'", field, raw)),
+        Value::Number(_) => raw
+            .parse::<u64>()
+            .map(|v| Value::Number(v.into()))
+            .map_err(|_| anyhow::anyhow!("expected integer for {}, got '{}'", field, raw)),
+        Value::String(_) => Ok(Value::String(raw.to_string())),
+        Value::Sequence(_) => Ok(Value::Sequence(split_list(raw))),
+        Value::Null => serde_yaml::from_str(raw)
+            .map_err(|err| anyhow::anyhow!("invalid value for {}: {}", field, err)),
+        Value::Mapping(_) => Err(anyhow::anyhow!(
+            "{} is a section; set one of its keys instead (e.g. {}.<key>=...)",
+            field,
+            field
+        )),
+        Value::Tagged(_) => Err(anyhow::anyhow!("{} cannot be overridden from the command line", field)),
+    }
+}
+
+/// Split a comma-separated override value into list entries
+fn split_list(raw: &str) -> Vec<serde_yaml::Value> {
+    raw.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| serde_yaml::Value::String(item.to_string()))
+        .collect()
+}
+
+/// Render the effective configuration as YAML, annotated with where it came from
+pub fn render_effective_config(
+    config: &Config,
+    config_path: &str,
+    overrides: &[ConfigOverride],
+) -> Result<String> {
+    let yaml = serde_yaml::to_string(config)
+        .context("Failed to serialize effective configuration")?;
+    
+    let mut output = String::from("# Effective OverDoc configuration\n");
+    if Path::new(config_path).exists() {
+        output.push_str(&format!("# Loaded from: {}\n", config_path));
+    } else {
+        output.push_str(&format!("# Loaded from: built-in defaults ({} not found)\n", config_path));
+    }
+    
+    if !overrides.is_empty() {
+        output.push_str("# Overrides:\n");
+        for ovr in overrides {
+            output.push_str(&format!("#   {}\n", ovr.describe()));
+        }
+    }
+    
+    output.push_str(&yaml);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Config {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn set(config: &mut Config, spec: &str) -> Result<()> {
+        config.apply_override(&ConfigOverride::parse(spec, "--set")?)
+    }
+
+    #[test]
+    fn overrides_scalars_by_field_type() {
+        let mut config = parse("default_settings:\n  max_file_size_kb: 512\n");
+        set(&mut config, "default_settings.max_file_size_kb=2048").unwrap();
+        set(&mut config, "default_settings.follow_symlinks=yes").unwrap();
+        set(&mut config, "scoring.weights.cognitive=30").unwrap();
+
+        assert_eq!(config.default_settings.max_file_size_kb, 2048);
+        assert!(config.default_settings.follow_symlinks);
+        assert_eq!(config.scoring.weights.cognitive, 30.0);
+    }
+
+    #[test]
+    fn rejects_scalars_of_the_wrong_type() {
+        let mut config = parse("{}");
+        let err = set(&mut config, "default_settings.max_file_size_kb=big").unwrap_err();
+        assert_eq!(err.to_string(), "expected integer for max_file_size_kb, got 'big'");
+
+        let err = set(&mut config, "default_settings.follow_symlinks=maybe").unwrap_err();
+        assert_eq!(err.to_string(), "expected boolean for follow_symlinks, got 'maybe'");
+    }
+
+    #[test]
+    fn replaces_and_appends_to_lists() {
+        let mut config = parse("ignore_patterns:\n  - \"*.min.js\"\n");
+        set(&mut config, "ignore_patterns+=*.map, dist/**").unwrap();
+        assert_eq!(config.ignore_patterns, ["*.min.js", "*.map", "dist/**"]);
+
+        set(&mut config, "ignore_patterns=vendor/**").unwrap();
+        assert_eq!(config.ignore_patterns, ["vendor/**"]);
+
+        let err = set(&mut config, "default_settings.max_file_size_kb+=1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'+=' is only supported for list fields, but max_file_size_kb is not a list"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_override_keys() {
+        let mut config = parse("{}");
+        let err = set(&mut config, "default_settings.max_size_kb=1").unwrap_err().to_string();
+        assert!(
+            err.starts_with("Unknown configuration key 'max_size_kb' in 'default_settings' (valid keys: "),
+            "{}",
+            err
+        );
+        assert!(err.contains("max_file_size_kb"), "{}", err);
+
+        assert!(ConfigOverride::parse("default_settings.max_file_size_kb", "--set").is_err());
+        assert!(ConfigOverride::parse("default_settings..x=1", "--set").is_err());
+    }
+
+    #[test]
+    fn reports_unknown_keys_in_the_file() {
+        let raw: serde_yaml::Value = serde_yaml::from_str(
+            "ignore_pattern:\n  - \"*.log\"\ndefault_settings:\n  max_file_size: 10\nlanguages:\n  rust:\n    extension: [rs]\n",
+        )
+        .unwrap();
+        let unknown = unknown_keys(&raw);
+
+        assert_eq!(unknown.len(), 3, "{:?}", unknown);
+        assert!(unknown[0].starts_with("Unknown configuration key 'ignore_pattern' in the configuration (valid keys: "));
+        assert!(unknown[1].starts_with("Unknown configuration key 'max_file_size' in 'default_settings' (valid keys: "));
+        assert!(unknown[2].starts_with("Unknown configuration key 'extension' in 'languages.rust' (valid keys: "));
+    }
+
+    #[test]
+    fn accepts_known_keys_and_free_form_sections() {
+        let raw: serde_yaml::Value = serde_yaml::from_str(
+            "ignore_patterns: [\"*.log\"]\nscoring:\n  extra:\n    incidents: 5.0\ncategories:\n  - name: test\n    patterns: [\"tests/**\"]\n",
+        )
+        .unwrap();
+        assert!(unknown_keys(&raw).is_empty(), "{:?}", unknown_keys(&raw));
+    }
+}
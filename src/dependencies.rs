@@ -1,9 +1,75 @@
 use anyhow::Result;
+use clap::ValueEnum;
 use log::{debug, info};
-use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
+use crate::config::LanguageConfig;
+use crate::directories::DirectoryCasing;
 use crate::exports::{ExportsMap, ImportsMap};
+use crate::metrics::RepositoryMetrics;
+use crate::traversal::relative_path;
+
+/// Formats the dependency graph can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT (`dependencies.dot`)
+    Dot,
+
+    /// GraphML for Gephi, yEd and similar tools (`dependencies.graphml`)
+    Graphml,
+}
+
+impl GraphFormat {
+    /// File name of the artifact written for this format
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dependencies.dot",
+            GraphFormat::Graphml => "dependencies.graphml",
+        }
+    }
+}
+
+/// Dependency edges crossing the boundary of a top-level directory
+#[derive(Debug, Serialize)]
+pub struct DirectoryCoupling {
+    /// Top-level directory relative to the repository root (`.` for files at the root)
+    pub directory: String,
+
+    /// Edges from files in other directories to files in this one
+    pub afferent: usize,
+
+    /// Edges from files in this directory to files in other directories
+    pub efferent: usize,
+
+    /// Share of outgoing edges, efferent / (afferent + efferent):
+    /// 0 for a directory others only depend on, 1 for one that only depends on others
+    pub instability: f64,
+
+    /// Whether the directory is configured as a leaf utility
+    pub leaf: bool,
+
+    /// Non-leaf directories a leaf directory depends on, with their edge counts
+    pub unexpected_dependencies: Vec<(String, usize)>,
+}
+
+/// Inputs of a file's importance score
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ImportanceInputs {
+    /// Sum of the usage counts of the file's exports
+    pub export_uses: usize,
+
+    /// Number of files importing from the file
+    pub dependent_files: usize,
+}
+
+impl ImportanceInputs {
+    /// Usage counts plus two points per dependent file
+    pub fn score(&self) -> usize {
+        self.export_uses + self.dependent_files * 2
+    }
+}
 
 /// Represents a dependency graph of the repository
 #[derive(Debug)]
@@ -16,6 +82,9 @@ pub struct DependencyGraph {
 
     /// Map of files to their importance score
     importance_scores: HashMap<String, usize>,
+
+    /// Highest importance score, for normalizing
+    max_importance: usize,
 }
 
 impl DependencyGraph {
@@ -25,6 +94,7 @@ impl DependencyGraph {
             file_dependencies: HashMap::new(),
             reverse_dependencies: HashMap::new(),
             importance_scores: HashMap::new(),
+            max_importance: 0,
         }
     }
 
@@ -36,8 +106,8 @@ impl DependencyGraph {
             .map(|(file, score)| (file.clone(), *score))
             .collect();
 
-        // Sort by score in descending order
-        files.sort_by(|a, b| b.1.cmp(&a.1));
+        // Sort by score in descending order, ties by path so the order is stable
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
         files
     }
@@ -47,6 +117,17 @@ impl DependencyGraph {
         *self.importance_scores.get(file_path).unwrap_or(&0)
     }
 
+    /// Importance score of a file scaled to 0-1 by the highest score, or `None` if the file
+    /// has no exports
+    pub fn normalized_importance(&self, file_path: &str) -> Option<f64> {
+        let score = *self.importance_scores.get(file_path)?;
+        Some(if self.max_importance > 0 {
+            score as f64 / self.max_importance as f64
+        } else {
+            0.0
+        })
+    }
+
     /// Get files that depend on the given file
     pub fn get_dependent_files(&self, file_path: &str) -> Vec<String> {
         match self.reverse_dependencies.get(file_path) {
@@ -55,6 +136,21 @@ impl DependencyGraph {
         }
     }
 
+    /// What the importance score of a file is made of
+    pub fn importance_inputs(&self, file_path: &str, exports_map: &ExportsMap) -> ImportanceInputs {
+        ImportanceInputs {
+            export_uses: exports_map
+                .get(file_path)
+                .map(|exports| exports.iter().map(|e| e.usage_count).sum())
+                .unwrap_or(0),
+            dependent_files: self
+                .reverse_dependencies
+                .get(file_path)
+                .map(|deps| deps.len())
+                .unwrap_or(0),
+        }
+    }
+
     /// Get files that the given file depends on
     pub fn get_dependencies(&self, file_path: &str) -> Vec<String> {
         match self.file_dependencies.get(file_path) {
@@ -62,6 +158,267 @@ impl DependencyGraph {
             None => Vec::new(),
         }
     }
+
+    /// Get every file that depends on the given file, directly or through other files
+    pub fn get_transitive_dependents(&self, file_path: &str) -> Vec<String> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut queue: Vec<String> = self.get_dependent_files(file_path);
+
+        while let Some(file) = queue.pop() {
+            if file == file_path || !seen.insert(file.clone()) {
+                continue;
+            }
+            queue.extend(self.get_dependent_files(&file));
+        }
+
+        let mut dependents: Vec<String> = seen.into_iter().collect();
+        dependents.sort();
+        dependents
+    }
+
+    /// Files included in a graph export: every file with a score or an edge, at or above `min_score`
+    fn graph_nodes(&self, min_score: usize) -> Vec<String> {
+        let mut nodes: HashSet<&String> = self.importance_scores.keys().collect();
+        for (from, deps) in &self.file_dependencies {
+            nodes.insert(from);
+            nodes.extend(deps.iter());
+        }
+
+        let mut nodes: Vec<String> = nodes
+            .into_iter()
+            .filter(|node| self.get_file_importance(node) >= min_score)
+            .cloned()
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// Deduplicated edges between the given nodes, without self-loops, in a stable order
+    fn graph_edges(&self, nodes: &[String]) -> Vec<(String, String)> {
+        let included: HashSet<&String> = nodes.iter().collect();
+        let mut edges: Vec<(String, String)> = self
+            .file_dependencies
+            .iter()
+            .flat_map(|(from, deps)| deps.iter().map(move |to| (from, to)))
+            .filter(|(from, to)| from != to && included.contains(from) && included.contains(to))
+            .map(|(from, to)| (from.clone(), to.clone()))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Count the file edges between top-level directories of `repo_root`,
+    /// keyed by (from, to); edges within a directory are left out
+    pub fn directory_edges(&self, repo_root: &str) -> BTreeMap<(String, String), usize> {
+        let mut edges = BTreeMap::new();
+
+        for (from, deps) in &self.file_dependencies {
+            let from_dir = top_level_directory(from, repo_root);
+            for to in deps {
+                let to_dir = top_level_directory(to, repo_root);
+                if from_dir != to_dir {
+                    *edges.entry((from_dir.clone(), to_dir)).or_default() += 1;
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Incoming and outgoing edge counts of every top-level directory with edges crossing
+    /// its boundary, flagging leaf directories that depend on non-leaf ones
+    pub fn directory_coupling(
+        &self,
+        repo_root: &str,
+        leaf_directories: &[String],
+    ) -> Vec<DirectoryCoupling> {
+        let leaves: HashSet<&str> = leaf_directories
+            .iter()
+            .map(|dir| dir.trim_start_matches("./").trim_end_matches('/'))
+            .collect();
+
+        let edges = self.directory_edges(repo_root);
+        let mut directories: Vec<&String> =
+            edges.keys().flat_map(|(from, to)| [from, to]).collect();
+        directories.sort();
+        directories.dedup();
+
+        directories
+            .into_iter()
+            .map(|directory| {
+                let leaf = leaves.contains(directory.as_str());
+                let outgoing: Vec<(&String, usize)> = edges
+                    .iter()
+                    .filter(|((from, _), _)| from == directory)
+                    .map(|((_, to), count)| (to, *count))
+                    .collect();
+                let afferent: usize = edges
+                    .iter()
+                    .filter(|((_, to), _)| to == directory)
+                    .map(|(_, count)| count)
+                    .sum();
+                let efferent: usize = outgoing.iter().map(|(_, count)| count).sum();
+
+                DirectoryCoupling {
+                    directory: directory.clone(),
+                    afferent,
+                    efferent,
+                    instability: efferent as f64 / (afferent + efferent) as f64,
+                    leaf,
+                    unexpected_dependencies: outgoing
+                        .into_iter()
+                        .filter(|(to, _)| leaf && !leaves.contains(to.as_str()))
+                        .map(|(to, count)| (to.clone(), count))
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    /// Node labels are relative to `repo_root`; color and size scale with the importance score.
+    pub fn to_dot(&self, repo_root: &str, min_score: usize) -> String {
+        let nodes = self.graph_nodes(min_score);
+        let edges = self.graph_edges(&nodes);
+        let max_score = nodes
+            .iter()
+            .map(|node| self.get_file_importance(node))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut dot = String::from("digraph dependencies {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=filled, fontname=\"Helvetica\"];\n");
+
+        for node in &nodes {
+            let weight = self.get_file_importance(node) as f64 / max_score as f64;
+            // Scale from pale yellow (unimportant) to saturated red (most important)
+            let hue = 0.15 * (1.0 - weight);
+            let saturation = 0.15 + 0.75 * weight;
+            let font_size = 10.0 + 10.0 * weight;
+
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\\n({})\", fillcolor=\"{:.3} {:.3} 1.000\", fontsize={:.1}];\n",
+                escape_dot(node),
+                escape_dot(&relative_path(node, repo_root)),
+                self.get_file_importance(node),
+                hue,
+                saturation,
+                font_size
+            ));
+        }
+
+        for (from, to) in &edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                escape_dot(from),
+                escape_dot(to)
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as GraphML.
+    /// Node ids are file paths; nodes carry a label relative to `repo_root`, the importance
+    /// score, the file extension and, when metrics were computed, the knowledge score.
+    pub fn to_graphml(
+        &self,
+        repo_root: &str,
+        min_score: usize,
+        metrics: Option<&RepositoryMetrics>,
+    ) -> String {
+        let nodes = self.graph_nodes(min_score);
+        let edges = self.graph_edges(&nodes);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" ");
+        xml.push_str("xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ");
+        xml.push_str("xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+
+        // Every attribute used below has to be declared before the graph
+        xml.push_str(
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"importance\" for=\"node\" attr.name=\"importance\" attr.type=\"int\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"extension\" for=\"node\" attr.name=\"extension\" attr.type=\"string\"/>\n",
+        );
+        if metrics.is_some() {
+            xml.push_str("  <key id=\"knowledge_score\" for=\"node\" attr.name=\"knowledge_score\" attr.type=\"double\"/>\n");
+        }
+        xml.push_str("  <graph id=\"dependencies\" edgedefault=\"directed\">\n");
+
+        for node in &nodes {
+            let extension = Path::new(node)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            xml.push_str(&format!("    <node id=\"{}\">\n", escape_xml(node)));
+            xml.push_str(&format!(
+                "      <data key=\"label\">{}</data>\n",
+                escape_xml(&relative_path(node, repo_root))
+            ));
+            xml.push_str(&format!(
+                "      <data key=\"importance\">{}</data>\n",
+                self.get_file_importance(node)
+            ));
+            xml.push_str(&format!(
+                "      <data key=\"extension\">{}</data>\n",
+                escape_xml(&extension)
+            ));
+            if let Some(file_metrics) = metrics.and_then(|m| m.file_metrics.get(node)) {
+                xml.push_str(&format!(
+                    "      <data key=\"knowledge_score\">{:.2}</data>\n",
+                    file_metrics.knowledge_score()
+                ));
+            }
+            xml.push_str("    </node>\n");
+        }
+
+        for (from, to) in &edges {
+            xml.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"/>\n",
+                escape_xml(from),
+                escape_xml(to)
+            ));
+        }
+
+        xml.push_str("  </graph>\n");
+        xml.push_str("</graphml>\n");
+        xml
+    }
+}
+
+/// First path component of a file relative to `repo_root`, or `.` for files at the root
+fn top_level_directory(path: &str, repo_root: &str) -> String {
+    let relative = relative_path(path, repo_root);
+    let mut components = Path::new(relative.trim_start_matches("./")).components();
+
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+/// Escape a string for use inside a quoted DOT identifier
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape a string for use in XML text and attribute values
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
 
 /// Build a dependency graph from exports and imports
@@ -125,22 +482,25 @@ pub fn build_dependency_graph(
     Ok(graph)
 }
 
+/// Number of import references whose name matches no export in the repository
+pub fn count_unresolved_imports(exports_map: &ExportsMap, imports_map: &ImportsMap) -> usize {
+    let exported: HashSet<&str> = exports_map
+        .values()
+        .flatten()
+        .map(|export| export.name.as_str())
+        .collect();
+    imports_map
+        .iter()
+        .filter(|(name, _)| !exported.contains(name.as_str()))
+        .map(|(_, refs)| refs.len())
+        .sum()
+}
+
 /// Calculate importance scores for files based on export usage and dependencies
 fn calculate_importance_scores(graph: &mut DependencyGraph, exports_map: &ExportsMap) {
     // For each file, calculate its importance score
-    for (file_path, exports) in exports_map {
-        // Base score is the sum of usage counts for all exports
-        let usage_score: usize = exports.iter().map(|e| e.usage_count).sum();
-
-        // Additional score based on number of files that depend on this file
-        let dependent_files = graph
-            .reverse_dependencies
-            .get(file_path)
-            .map(|deps| deps.len())
-            .unwrap_or(0);
-
-        // Calculate total score
-        let importance_score = usage_score + (dependent_files * 2);
+    for file_path in exports_map.keys() {
+        let importance_score = graph.importance_inputs(file_path, exports_map).score();
 
         // Store the score
         graph
@@ -152,12 +512,15 @@ fn calculate_importance_scores(graph: &mut DependencyGraph, exports_map: &Export
             file_path, importance_score
         );
     }
+
+    graph.max_importance = graph.importance_scores.values().copied().max().unwrap_or(0);
 }
 
 /// Calculate directory importance based on file importance
 pub fn calculate_directory_importance(
     graph: &DependencyGraph,
     exports_map: &ExportsMap,
+    casing: &DirectoryCasing,
 ) -> HashMap<String, usize> {
     let mut dir_scores: HashMap<String, usize> = HashMap::new();
 
@@ -172,11 +535,13 @@ pub fn calculate_directory_importance(
                 break;
             }
 
-            let dir_path = parent.to_string_lossy().to_string();
+            let dir_path = parent.to_string_lossy();
             let file_score = graph.get_file_importance(file_path);
 
-            // Add the file's score to the directory score
-            *dir_scores.entry(dir_path.clone()).or_default() += file_score;
+            // Add the file's score to the directory score, merging spellings of one directory
+            *dir_scores
+                .entry(casing.key(&dir_path).to_string())
+                .or_default() += file_score;
 
             current = parent;
         }
@@ -188,7 +553,7 @@ pub fn calculate_directory_importance(
         .map(|(dir, score)| (dir.clone(), *score))
         .collect();
 
-    dirs.sort_by(|a, b| b.1.cmp(&a.1));
+    dirs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
     info!("Calculated importance for {} directories", dir_scores.len());
     for (dir, score) in dirs.iter().take(5) {
@@ -197,3 +562,216 @@ pub fn calculate_directory_importance(
 
     dir_scores
 }
+
+/// Below this many analyzed files, finding no exports at all is not treated as suspicious
+const MIN_FILES_FOR_DIAGNOSTIC: usize = 5;
+
+/// Explain why every importance score is 0, when the graph suggests analysis went wrong
+/// rather than the repository having no important files: exports were found but no import
+/// resolved to any of them, or a repository of some size has no exports at all.
+pub fn diagnose_zero_importance(
+    exports_map: &ExportsMap,
+    file_paths: &[String],
+    languages: &HashMap<String, LanguageConfig>,
+) -> Option<String> {
+    let total_exports = exports_map.values().map(|v| v.len()).sum::<usize>();
+    let total_usage = exports_map
+        .values()
+        .flatten()
+        .map(|export| export.usage_count)
+        .sum::<usize>();
+
+    if total_exports > 0 && total_usage == 0 {
+        return Some(format!(
+            "All importance scores are 0: {} exports were found in {} files, but no import resolved to any of them. \
+             Likely causes: the import_patterns don't match this code's import syntax, \
+             imports only reference external packages, or imported names don't match the exported names. \
+             Run with --check-config to see the effective patterns and --explain FILE to see what was detected in a file.",
+            total_exports,
+            exports_map.len()
+        ));
+    }
+
+    if total_exports == 0 && file_paths.len() >= MIN_FILES_FOR_DIAGNOSTIC {
+        let mut uncovered: Vec<String> = file_paths
+            .iter()
+            .filter_map(|path| Path::new(path).extension())
+            .map(|ext| ext.to_string_lossy().to_string())
+            .filter(|ext| {
+                !languages
+                    .values()
+                    .any(|lang| lang.extensions.iter().any(|e| e == ext))
+            })
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+        uncovered.sort();
+
+        let missing = if uncovered.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " No language is configured for these extensions: {}.",
+                uncovered
+                    .iter()
+                    .map(|ext| format!(".{}", ext))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        return Some(format!(
+            "All importance scores are 0: no exports were found in {} files.{} \
+             Likely causes: a missing language configuration for these files, \
+             or export_patterns that don't match this code. \
+             Run with --check-config to see the effective patterns and --explain FILE to see what was detected in a file.",
+            file_paths.len(),
+            missing
+        ));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exports::ImportReference;
+    use crate::report::fixture::export;
+    use std::path::PathBuf;
+
+    const REPO: &str = "repo";
+
+    /// Exports of each file, as `(file, name)`
+    const EXPORTS: &[(&str, &str)] = &[
+        ("repo/core/parse.rs", "parse"),
+        ("repo/core/config.rs", "Config"),
+        ("repo/util/format.rs", "format"),
+        ("repo/time/stamp.rs", "stamp"),
+    ];
+
+    /// Imports of each file, as `(file, name)`
+    const IMPORTS: &[(&str, &str)] = &[
+        ("repo/app/main.rs", "parse"),
+        ("repo/app/main.rs", "format"),
+        ("repo/app/cli.rs", "parse"),
+        ("repo/core/parse.rs", "format"),
+        ("repo/core/parse.rs", "Config"),
+        ("repo/util/format.rs", "Config"),
+        ("repo/util/log.rs", "stamp"),
+    ];
+
+    /// `app` depends on everything, `util` and `time` are meant to be leaves,
+    /// but `util/format.rs` reaches back into `core`
+    fn graph() -> DependencyGraph {
+        let mut exports_map = ExportsMap::new();
+        for (file, name) in EXPORTS {
+            exports_map
+                .entry(file.to_string())
+                .or_default()
+                .push(export(file, name, "function"));
+        }
+
+        let mut imports_map = ImportsMap::new();
+        for (file, name) in IMPORTS {
+            imports_map
+                .entry(name.to_string())
+                .or_default()
+                .push(ImportReference {
+                    name: name.to_string(),
+                    file_path: PathBuf::from(file),
+                    line_number: 1,
+                    import_statement: format!("use crate::{};", name),
+                });
+        }
+
+        build_dependency_graph(&mut exports_map, &imports_map).unwrap()
+    }
+
+    fn coupling(leaf_directories: &[&str]) -> Vec<DirectoryCoupling> {
+        let leaves: Vec<String> = leaf_directories.iter().map(|dir| dir.to_string()).collect();
+        graph().directory_coupling(REPO, &leaves)
+    }
+
+    fn edge(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn directory_edges_count_file_edges_crossing_directories() {
+        let edges = graph().directory_edges(REPO);
+
+        // `core/parse.rs -> core/config.rs` stays within `core`
+        let expected = BTreeMap::from([
+            (edge("app", "core"), 2),
+            (edge("app", "util"), 1),
+            (edge("core", "util"), 1),
+            (edge("util", "core"), 1),
+            (edge("util", "time"), 1),
+        ]);
+        assert_eq!(edges, expected);
+    }
+
+    #[test]
+    fn coupling_counts_incoming_and_outgoing_edges() {
+        let coupling = coupling(&[]);
+
+        let counts: Vec<(&str, usize, usize, f64)> = coupling
+            .iter()
+            .map(|dir| {
+                (
+                    dir.directory.as_str(),
+                    dir.afferent,
+                    dir.efferent,
+                    dir.instability,
+                )
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("app", 0, 3, 1.0),
+                ("core", 3, 1, 0.25),
+                ("time", 1, 0, 0.0),
+                ("util", 2, 2, 0.5),
+            ]
+        );
+        assert!(coupling
+            .iter()
+            .all(|dir| !dir.leaf && dir.unexpected_dependencies.is_empty()));
+    }
+
+    #[test]
+    fn leaf_directories_depending_on_non_leaf_ones_are_unexpected() {
+        // Leaf directories are matched with or without `./` and a trailing slash
+        let coupling = coupling(&["./util/", "time"]);
+
+        let leaves: Vec<(&str, &[(String, usize)])> = coupling
+            .iter()
+            .filter(|dir| dir.leaf)
+            .map(|dir| {
+                (
+                    dir.directory.as_str(),
+                    dir.unexpected_dependencies.as_slice(),
+                )
+            })
+            .collect();
+        // `util -> time` is one leaf depending on another, and expected
+        assert_eq!(
+            leaves,
+            [
+                ("time", [].as_slice()),
+                ("util", [("core".to_string(), 1)].as_slice()),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_leaf_directories_have_no_unexpected_dependencies() {
+        let coupling = coupling(&["util"]);
+
+        let app = coupling.iter().find(|dir| dir.directory == "app").unwrap();
+        assert!(!app.leaf);
+        assert!(app.unexpected_dependencies.is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Comparison of a saved JSON report with another one or with a fresh analysis (`overdoc diff`).
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+
+use crate::config::DiffConfig;
+use crate::report::AnalysisReport;
+use crate::traversal::relative_path;
+
+/// Output formats of `overdoc diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// One change per line, for the terminal
+    Text,
+
+    /// Tables for posting as a pull request comment
+    #[value(name = "md")]
+    Markdown,
+
+    /// Machine-readable changes
+    Json,
+}
+
+/// The parts of a saved `analysis_results.json` the comparison needs
+#[derive(Debug, Deserialize)]
+pub struct SavedReport {
+    pub repo_path: String,
+    pub file_importance: HashMap<String, usize>,
+
+    /// Absent in reports written with --skip-metrics
+    #[serde(default)]
+    pub metrics: Option<SavedMetrics>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavedMetrics {
+    pub file_metrics: HashMap<String, SavedFileMetrics>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavedFileMetrics {
+    pub knowledge_score: Option<f64>,
+    pub complexity_metrics: Option<SavedComplexity>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavedComplexity {
+    pub cyclomatic_complexity: f64,
+}
+
+/// Scores of one file in a report
+#[derive(Debug, Default)]
+struct FileScores {
+    importance: usize,
+    knowledge: Option<f64>,
+    cyclomatic: Option<f64>,
+}
+
+impl SavedReport {
+    /// Read a report written with `--format json`
+    pub fn load(path: &str) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).context(format!("Failed to read report {}", path))?;
+        serde_json::from_str(&content).context(format!(
+            "Failed to parse report {}; expected the analysis_results.json of a run with --format json",
+            path
+        ))
+    }
+
+    /// The report as `--format json` would have saved it
+    pub fn from_report(report: &AnalysisReport) -> Result<Self> {
+        let value = serde_json::to_value(report).context("Failed to serialize report to JSON")?;
+        serde_json::from_value(value).context("Failed to read the scores of the analysis")
+    }
+
+    /// Scores of every analyzed file keyed by path relative to the repository, so reports of
+    /// the same repository checked out in different places compare
+    fn scores(&self) -> BTreeMap<String, FileScores> {
+        let mut scores: BTreeMap<String, FileScores> = BTreeMap::new();
+        for (path, importance) in &self.file_importance {
+            scores
+                .entry(relative_path(path, &self.repo_path))
+                .or_default()
+                .importance = *importance;
+        }
+        for (path, metrics) in self.metrics.iter().flat_map(|m| m.file_metrics.iter()) {
+            let file = scores
+                .entry(relative_path(path, &self.repo_path))
+                .or_default();
+            file.knowledge = metrics.knowledge_score;
+            file.cyclomatic = metrics
+                .complexity_metrics
+                .as_ref()
+                .map(|c| c.cyclomatic_complexity);
+        }
+        scores
+    }
+}
+
+/// A score of a file that moved by at least the configured delta
+#[derive(Debug, Serialize)]
+pub struct ScoreChange {
+    pub path: String,
+
+    /// `importance`, `knowledge` or `cyclomatic`
+    pub metric: &'static str,
+    pub old: f64,
+    pub new: f64,
+}
+
+impl ScoreChange {
+    fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+/// A file present in only one of the reports, with its importance score
+#[derive(Debug, Serialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub importance: usize,
+}
+
+/// Files added, removed and rescored between two reports, each in path order
+#[derive(Debug, Default, Serialize)]
+pub struct ReportDiff {
+    pub added: Vec<FileEntry>,
+    pub removed: Vec<FileEntry>,
+    pub changed: Vec<ScoreChange>,
+}
+
+impl ReportDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare the scores of two reports, leaving out moves smaller than the configured deltas
+pub fn compare(old: &SavedReport, new: &SavedReport, limits: &DiffConfig) -> ReportDiff {
+    let old_scores = old.scores();
+    let new_scores = new.scores();
+    let mut diff = ReportDiff::default();
+
+    for (path, scores) in &new_scores {
+        let Some(before) = old_scores.get(path) else {
+            diff.added.push(FileEntry {
+                path: path.clone(),
+                importance: scores.importance,
+            });
+            continue;
+        };
+
+        let mut push = |metric, old: f64, new: f64, min_delta: f64| {
+            if old != new && (new - old).abs() >= min_delta {
+                diff.changed.push(ScoreChange {
+                    path: path.clone(),
+                    metric,
+                    old,
+                    new,
+                });
+            }
+        };
+        push(
+            "importance",
+            before.importance as f64,
+            scores.importance as f64,
+            limits.min_importance_delta as f64,
+        );
+        if let (Some(old), Some(new)) = (before.knowledge, scores.knowledge) {
+            push("knowledge", old, new, limits.min_knowledge_delta);
+        }
+        if let (Some(old), Some(new)) = (before.cyclomatic, scores.cyclomatic) {
+            push("cyclomatic", old, new, limits.min_cyclomatic_delta);
+        }
+    }
+    for (path, scores) in &old_scores {
+        if !new_scores.contains_key(path) {
+            diff.removed.push(FileEntry {
+                path: path.clone(),
+                importance: scores.importance,
+            });
+        }
+    }
+
+    diff
+}
+
+/// Render the differences in the requested format
+pub fn render(diff: &ReportDiff, format: DiffFormat) -> Result<String> {
+    match format {
+        DiffFormat::Text => Ok(render_text(diff)),
+        DiffFormat::Markdown => Ok(render_markdown(diff)),
+        DiffFormat::Json => serde_json::to_string_pretty(diff)
+            .map(|json| json + "\n")
+            .context("Failed to serialize differences to JSON"),
+    }
+}
+
+/// Importance scores are whole numbers, the metrics have one decimal
+fn format_score(metric: &str, value: f64) -> String {
+    if metric == "importance" {
+        format!("{}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Render the differences as plain text, one change per line
+fn render_text(diff: &ReportDiff) -> String {
+    if diff.is_empty() {
+        return "No differences in scores\n".to_string();
+    }
+
+    let mut output = String::new();
+    if !diff.changed.is_empty() {
+        output.push_str(&format!("Changed ({}):\n", diff.changed.len()));
+        for change in &diff.changed {
+            output.push_str(&format!(
+                "  {} {} {} -> {}\n",
+                change.path,
+                change.metric,
+                format_score(change.metric, change.old),
+                format_score(change.metric, change.new)
+            ));
+        }
+    }
+    if !diff.added.is_empty() {
+        output.push_str(&format!("Added ({}):\n", diff.added.len()));
+        for file in &diff.added {
+            output.push_str(&format!("  + {} ({})\n", file.path, file.importance));
+        }
+    }
+    if !diff.removed.is_empty() {
+        output.push_str(&format!("Removed ({}):\n", diff.removed.len()));
+        for file in &diff.removed {
+            output.push_str(&format!("  - {} ({})\n", file.path, file.importance));
+        }
+    }
+    output
+}
+
+/// Render the differences as markdown tables, for a pull request comment
+fn render_markdown(diff: &ReportDiff) -> String {
+    let mut output = "## OverDoc changes\n\n".to_string();
+    if diff.is_empty() {
+        output.push_str("No differences in scores.\n");
+        return output;
+    }
+
+    if !diff.changed.is_empty() {
+        let files: BTreeSet<&String> = diff.changed.iter().map(|change| &change.path).collect();
+        output.push_str(&format!(
+            "### Changed scores ({} {})\n\n| File | Score | Before | After | Change |\n|------|-------|--------|-------|--------|\n",
+            files.len(),
+            if files.len() == 1 { "file" } else { "files" }
+        ));
+        for change in &diff.changed {
+            let delta = format_score(change.metric, change.delta().abs());
+            output.push_str(&format!(
+                "| `{}` | {} | {} | {} | {}{} |\n",
+                change.path,
+                change.metric,
+                format_score(change.metric, change.old),
+                format_score(change.metric, change.new),
+                if change.delta() > 0.0 { "+" } else { "-" },
+                delta
+            ));
+        }
+        output.push('\n');
+    }
+    for (title, files) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+        if files.is_empty() {
+            continue;
+        }
+        output.push_str(&format!(
+            "### {} files ({})\n\n| File | Importance |\n|------|------------|\n",
+            title,
+            files.len()
+        ));
+        for file in files {
+            output.push_str(&format!("| `{}` | {} |\n", file.path, file.importance));
+        }
+        output.push('\n');
+    }
+    output
+}
@@ -0,0 +1,338 @@
+//! Aggregation of file-level metrics, exports and dependencies per directory,
+//! for the directory list of the report and the per-directory pages.
+
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::config::PathCase;
+use crate::dependencies::DependencyGraph;
+use crate::exports::{ExportsMap, ImportsMap};
+use crate::metrics::RepositoryMetrics;
+use crate::traversal::on_disk;
+
+/// Totals of the files under a directory
+#[derive(Debug, Serialize)]
+pub struct DirectoryMetrics {
+    pub file_count: usize,
+    pub line_count: usize,
+    pub function_count: usize,
+}
+
+/// An export defined under a directory
+#[derive(Debug, Serialize)]
+pub struct DirectoryExport {
+    pub name: String,
+    pub export_type: String,
+    pub file: String,
+    pub usage_count: usize,
+
+    /// Import references to the export from files outside the directory
+    pub outside_usage: usize,
+}
+
+/// Everything a directory page shows about one of the top directories
+#[derive(Debug, Serialize)]
+pub struct DirectorySummary {
+    pub path: String,
+    pub file_count: usize,
+
+    /// Totals of the directory's files, if metrics were collected
+    pub metrics: Option<DirectoryMetrics>,
+
+    /// Most important files under the directory, with their importance scores
+    pub top_files: Vec<(String, usize)>,
+
+    /// Exports defined under the directory, most used from outside first
+    pub exports: Vec<DirectoryExport>,
+
+    /// Directories of the files outside this one that depend on its files, with the
+    /// number of file dependencies, most dependent first
+    pub dependent_directories: Vec<(String, usize)>,
+}
+
+/// Whether a file lies under a directory
+pub fn contains(dir_path: &str, file_path: &str) -> bool {
+    Path::new(file_path).starts_with(dir_path)
+}
+
+/// Normalized key of every directory holding analyzed files, so spellings of one directory
+/// that differ in casing (or reach it through a symlink) share their scores and metrics
+#[derive(Debug, Default)]
+pub struct DirectoryCasing {
+    keys: HashMap<String, String>,
+}
+
+impl DirectoryCasing {
+    /// Resolve the directories of `file_paths` and their ancestors once each, warning
+    /// about spellings merged into one key
+    pub fn resolve(mode: PathCase, repo_root: &str, file_paths: &[String]) -> Self {
+        let mut keys = HashMap::new();
+        for file_path in file_paths {
+            for dir in Path::new(file_path).ancestors().skip(1) {
+                let dir = dir.to_string_lossy();
+                if dir.is_empty() || keys.contains_key(dir.as_ref()) {
+                    continue;
+                }
+                let key = match mode {
+                    PathCase::OnDisk => on_disk_key(repo_root, &dir),
+                    PathCase::Fold => dir.to_lowercase(),
+                    PathCase::Preserve => dir.to_string(),
+                };
+                keys.insert(dir.to_string(), key);
+            }
+        }
+
+        let mut spellings: BTreeMap<&String, BTreeSet<&String>> = BTreeMap::new();
+        for (dir, key) in &keys {
+            spellings.entry(key).or_default().insert(dir);
+        }
+        for (key, dirs) in spellings.iter().filter(|(_, dirs)| dirs.len() > 1) {
+            let dirs: Vec<&str> = dirs.iter().map(|dir| dir.as_str()).collect();
+            warn!(
+                "Merged directory keys {} into {} (default_settings.path_case)",
+                dirs.join(", "),
+                key
+            );
+        }
+
+        DirectoryCasing { keys }
+    }
+
+    /// Normalized key of a directory; directories that weren't resolved are their own key
+    pub fn key<'a>(&'a self, dir_path: &'a str) -> &'a str {
+        self.keys.get(dir_path).map_or(dir_path, |key| key.as_str())
+    }
+
+    /// Whether a file lies under the directory with the given normalized key
+    pub fn contains(&self, dir_key: &str, file_path: &str) -> bool {
+        let parent = Path::new(file_path)
+            .parent()
+            .map(|dir| dir.to_string_lossy())
+            .unwrap_or_default();
+        contains(dir_key, self.key(&parent)) || contains(dir_key, file_path)
+    }
+}
+
+/// The directory's path as the file system resolves it, relative to the repository root
+/// unless it was absolute; unresolvable directories and those outside the root keep their path
+fn on_disk_key(repo_root: &str, dir_path: &str) -> String {
+    let (Ok(root), Ok(resolved)) = (
+        fs::canonicalize(repo_root),
+        fs::canonicalize(on_disk(repo_root, dir_path)),
+    ) else {
+        return dir_path.to_string();
+    };
+
+    if Path::new(dir_path).is_absolute() {
+        return resolved.to_string_lossy().to_string();
+    }
+    match resolved.strip_prefix(&root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().to_string(),
+        _ => dir_path.to_string(),
+    }
+}
+
+/// Aggregate the metrics of all files under a directory
+pub fn directory_metrics(
+    dir_path: &str,
+    file_paths: &[String],
+    metrics: &RepositoryMetrics,
+    casing: &DirectoryCasing,
+) -> DirectoryMetrics {
+    let dir_files: Vec<&String> = file_paths
+        .iter()
+        .filter(|path| casing.contains(dir_path, path))
+        .collect();
+
+    let mut line_count = 0;
+    let mut function_count = 0;
+
+    for file in &dir_files {
+        if let Some(file_metrics) = metrics.file_metrics.get(*file) {
+            line_count += file_metrics.line_count;
+            function_count += file_metrics.function_count;
+        }
+    }
+
+    DirectoryMetrics {
+        file_count: dir_files.len(),
+        line_count,
+        function_count,
+    }
+}
+
+/// The analysis results directory summaries are drawn from
+pub struct DirectoryInput<'a> {
+    pub file_paths: &'a [String],
+    pub exports_map: &'a ExportsMap,
+    pub imports_map: &'a ImportsMap,
+    pub graph: &'a DependencyGraph,
+    pub metrics: Option<&'a RepositoryMetrics>,
+    pub casing: &'a DirectoryCasing,
+}
+
+/// Summarize a directory: its totals, most important files, exports and dependents
+pub fn summarize(dir_path: &str, input: &DirectoryInput, top_n: usize) -> DirectorySummary {
+    let DirectoryInput {
+        file_paths,
+        exports_map,
+        imports_map,
+        graph,
+        metrics,
+        casing,
+    } = *input;
+    let files: Vec<&String> = file_paths
+        .iter()
+        .filter(|path| casing.contains(dir_path, path))
+        .collect();
+
+    let mut top_files: Vec<(String, usize)> = files
+        .iter()
+        .map(|path| ((*path).clone(), graph.get_file_importance(path)))
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    top_files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_files.truncate(top_n);
+
+    let mut exports: Vec<DirectoryExport> = exports_map
+        .iter()
+        .filter(|(path, _)| casing.contains(dir_path, path))
+        .flat_map(|(path, entities)| {
+            entities.iter().map(move |export| DirectoryExport {
+                name: export.name.clone(),
+                export_type: export.export_type.clone(),
+                file: path.clone(),
+                usage_count: export.usage_count,
+                outside_usage: imports_map
+                    .get(&export.name)
+                    .map(|references| {
+                        references
+                            .iter()
+                            .filter(|r| !casing.contains(dir_path, &r.file_path.to_string_lossy()))
+                            .count()
+                    })
+                    .unwrap_or(0),
+            })
+        })
+        .collect();
+    exports.sort_by(|a, b| {
+        b.outside_usage
+            .cmp(&a.outside_usage)
+            .then_with(|| (&a.file, &a.name).cmp(&(&b.file, &b.name)))
+    });
+
+    let mut dependents: BTreeMap<String, usize> = BTreeMap::new();
+    for file in &files {
+        for dependent in graph.get_dependent_files(file) {
+            if casing.contains(dir_path, &dependent) {
+                continue;
+            }
+            let directory = Path::new(&dependent)
+                .parent()
+                .map(|dir| casing.key(&dir.to_string_lossy()).to_string())
+                .unwrap_or_default();
+            *dependents.entry(directory).or_default() += 1;
+        }
+    }
+    let mut dependent_directories: Vec<(String, usize)> = dependents.into_iter().collect();
+    dependent_directories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    DirectorySummary {
+        path: dir_path.to_string(),
+        file_count: files.len(),
+        metrics: metrics.map(|m| directory_metrics(dir_path, file_paths, m, casing)),
+        top_files,
+        exports,
+        dependent_directories,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependencies::{build_dependency_graph, calculate_directory_importance};
+    use crate::exports::ImportReference;
+    use crate::report::fixture::export;
+    use std::path::PathBuf;
+
+    /// Root of a repository that doesn't exist, so nothing resolves on disk
+    const REPO: &str = "repo";
+
+    /// Two spellings of one directory, as a case-insensitive file system would hand them out
+    const FILES: &[(&str, &str)] = &[
+        ("repo/Src/Utils/format.rs", "format"),
+        ("repo/src/utils/parse.rs", "parse"),
+    ];
+
+    fn file_paths() -> Vec<String> {
+        FILES.iter().map(|(file, _)| file.to_string()).collect()
+    }
+
+    /// Importance of every directory, each file's export imported by `main.rs`
+    fn directory_importance(casing: &DirectoryCasing) -> (DependencyGraph, HashMap<String, usize>) {
+        let mut exports_map = ExportsMap::new();
+        let mut imports_map = ImportsMap::new();
+        for (file, name) in FILES {
+            exports_map
+                .entry(file.to_string())
+                .or_default()
+                .push(export(file, name, "function"));
+            imports_map.insert(
+                name.to_string(),
+                vec![ImportReference {
+                    name: name.to_string(),
+                    file_path: PathBuf::from("repo/main.rs"),
+                    line_number: 1,
+                    import_statement: format!("use crate::{};", name),
+                }],
+            );
+        }
+
+        let graph = build_dependency_graph(&mut exports_map, &imports_map).unwrap();
+        let scores = calculate_directory_importance(&graph, &exports_map, casing);
+        (graph, scores)
+    }
+
+    #[test]
+    fn fold_merges_spellings_of_one_directory() {
+        let casing = DirectoryCasing::resolve(PathCase::Fold, REPO, &file_paths());
+
+        assert_eq!(casing.key("repo/Src/Utils"), "repo/src/utils");
+        assert_eq!(casing.key("repo/src/utils"), "repo/src/utils");
+        assert_eq!(casing.key("repo/Src"), casing.key("repo/src"));
+    }
+
+    #[test]
+    fn fold_sums_the_importance_of_merged_directories() {
+        let casing = DirectoryCasing::resolve(PathCase::Fold, REPO, &file_paths());
+        let (graph, scores) = directory_importance(&casing);
+
+        let format = graph.get_file_importance("repo/Src/Utils/format.rs");
+        let parse = graph.get_file_importance("repo/src/utils/parse.rs");
+        assert!(format > 0 && parse > 0);
+        assert_eq!(scores.get("repo/src/utils"), Some(&(format + parse)));
+        assert!(!scores.contains_key("repo/Src/Utils"));
+    }
+
+    #[test]
+    fn default_mode_keeps_spellings_apart() {
+        // The default resolves directories on disk; none exist under `REPO`, so each
+        // spelling stays its own key
+        let casing = DirectoryCasing::resolve(PathCase::default(), REPO, &file_paths());
+        assert_eq!(casing.key("repo/Src/Utils"), "repo/Src/Utils");
+        assert_eq!(casing.key("repo/src/utils"), "repo/src/utils");
+
+        let (graph, scores) = directory_importance(&casing);
+        assert_eq!(
+            scores.get("repo/Src/Utils"),
+            Some(&graph.get_file_importance("repo/Src/Utils/format.rs"))
+        );
+        assert_eq!(
+            scores.get("repo/src/utils"),
+            Some(&graph.get_file_importance("repo/src/utils/parse.rs"))
+        );
+    }
+}
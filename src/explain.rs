@@ -0,0 +1,497 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::categories::{self, CategoryRules, Classification};
+use crate::config::Config;
+use crate::dependencies::{DependencyGraph, ImportanceInputs};
+use crate::exports::{ExportsMap, ImportsMap};
+use crate::filter::{
+    self, ConfigPatterns, FilterDecision, FilterRules, LanguageFilter, PathGlobs, PathOverrides,
+    TestFilter,
+};
+use crate::findings::Finding;
+use crate::metrics::{self, FileMetrics, KnowledgeScoreBreakdown, RepositoryMetrics};
+use crate::traversal::{on_disk, relative_path, RepoFile};
+
+/// How confident we are that an import really refers to the file an edge points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeConfidence {
+    /// Exactly one file exports the imported name
+    High,
+
+    /// Several files export the imported name, so an edge was added to each of them
+    Low,
+}
+
+/// An export of the explained file
+#[derive(Debug, Serialize)]
+pub struct ExplainedExport {
+    /// Stable identity across runs
+    pub id: String,
+    pub name: String,
+    pub line: usize,
+    pub export_type: String,
+    pub usage_count: usize,
+
+    /// The source line the export was extracted from
+    pub signature: Option<String>,
+
+    /// File holding the original definition, for re-exports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_path: Option<String>,
+}
+
+/// A dependency edge created by an import
+#[derive(Debug, Serialize)]
+pub struct ImportEdge {
+    pub target: String,
+    pub confidence: EdgeConfidence,
+}
+
+/// An import in the explained file and the edges it produced
+#[derive(Debug, Serialize)]
+pub struct ExplainedImport {
+    pub name: String,
+    pub line: usize,
+    pub statement: String,
+
+    /// Empty when no analyzed file exports the name
+    pub edges: Vec<ImportEdge>,
+}
+
+/// Everything known about a single file
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    /// Path of the file relative to the repository root
+    pub path: String,
+
+    /// Whether traversal reached the file at all (it may sit in an ignored directory)
+    pub traversed: bool,
+
+    /// Outcome of the filter rules, if the file was traversed
+    pub filter: Option<FilterDecision>,
+
+    /// Configured languages whose extensions match the file
+    pub languages: Vec<String>,
+
+    /// Why the file counts as a test, if it does
+    pub test_file: Option<String>,
+
+    /// Category of the file and every category whose rule matched it
+    pub category: Classification,
+
+    pub exports: Vec<ExplainedExport>,
+    pub imports: Vec<ExplainedImport>,
+    pub importance: usize,
+    pub importance_inputs: ImportanceInputs,
+
+    /// Files this file depends on
+    pub dependencies: Vec<String>,
+
+    /// Files that depend on this file directly
+    pub dependents: Vec<String>,
+
+    /// Files that depend on this file directly or through other files
+    pub transitive_dependents: Vec<String>,
+
+    pub metrics: Option<FileMetrics>,
+    pub knowledge_breakdown: Option<KnowledgeScoreBreakdown>,
+    pub findings: Vec<Finding>,
+}
+
+/// Results of the analysis phases needed to explain a file
+pub struct ExplainInput<'a> {
+    pub repo_path: &'a str,
+    pub config: &'a Config,
+    pub overrides: &'a PathOverrides,
+    pub patterns: &'a ConfigPatterns,
+    pub category_rules: &'a CategoryRules,
+    pub path_globs: &'a PathGlobs,
+    pub languages: &'a LanguageFilter,
+    pub tests: &'a TestFilter,
+
+    /// All traversed files, before filtering
+    pub files: &'a [RepoFile],
+    pub exports_map: &'a ExportsMap,
+    pub imports_map: &'a ImportsMap,
+    pub dependency_graph: &'a DependencyGraph,
+    pub metrics: Option<&'a RepositoryMetrics>,
+    pub findings: &'a [Finding],
+}
+
+/// Strip a leading `./` so user input and traversed paths compare equal
+fn normalize(path: &str) -> &str {
+    path.trim_start_matches("./")
+}
+
+/// Gather the dossier for `target`, given relative to the repository root or as a traversed path
+pub fn explain_file(target: &str, input: &ExplainInput) -> Result<Explanation> {
+    let repo_file = input.files.iter().find(|file| {
+        let path = file.path.to_string_lossy();
+        normalize(&path) == normalize(target)
+            || normalize(&relative_path(&path, input.repo_path)) == normalize(target)
+    });
+
+    let path = match repo_file {
+        Some(file) => file.path.to_string_lossy().to_string(),
+        None => {
+            if !on_disk(input.repo_path, normalize(target)).is_file() {
+                return Err(anyhow::anyhow!(
+                    "File {} was not found in repository {}",
+                    target,
+                    input.repo_path
+                ));
+            }
+            normalize(target).to_string()
+        }
+    };
+
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let mut languages: Vec<String> = input
+        .config
+        .languages
+        .iter()
+        .filter(|(_, lang)| {
+            extension
+                .as_ref()
+                .is_some_and(|ext| lang.extensions.contains(ext))
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    languages.sort();
+
+    let source_lines: Vec<String> = fs::read_to_string(on_disk(input.repo_path, &path))
+        .map(|content| content.lines().map(String::from).collect())
+        .unwrap_or_default();
+
+    let mut exports: Vec<ExplainedExport> = input
+        .exports_map
+        .get(&path)
+        .map(|entities| {
+            entities
+                .iter()
+                .map(|export| ExplainedExport {
+                    id: export.id.clone(),
+                    name: export.name.clone(),
+                    line: export.line_number,
+                    export_type: export.export_type.clone(),
+                    usage_count: export.usage_count,
+                    signature: source_lines
+                        .get(export.line_number.saturating_sub(1))
+                        .map(|line| line.trim().to_string()),
+                    canonical_path: export
+                        .canonical_path
+                        .as_ref()
+                        .filter(|canonical| canonical.to_string_lossy() != path)
+                        .map(|canonical| canonical.to_string_lossy().to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    exports.sort_by_key(|export| export.line);
+
+    let mut imports = Vec::new();
+    for (name, refs) in input.imports_map {
+        for import_ref in refs {
+            if import_ref.file_path.to_string_lossy() != path {
+                continue;
+            }
+
+            // Mirror build_dependency_graph: an edge goes to every other file exporting the name
+            let mut targets: Vec<&String> = input
+                .exports_map
+                .iter()
+                .filter(|(file, entities)| {
+                    **file != path && entities.iter().any(|export| export.name == *name)
+                })
+                .map(|(file, _)| file)
+                .collect();
+            targets.sort();
+
+            let confidence = if targets.len() == 1 {
+                EdgeConfidence::High
+            } else {
+                EdgeConfidence::Low
+            };
+
+            imports.push(ExplainedImport {
+                name: name.clone(),
+                line: import_ref.line_number,
+                statement: import_ref.import_statement.trim().to_string(),
+                edges: targets
+                    .into_iter()
+                    .map(|target| ImportEdge {
+                        target: target.clone(),
+                        confidence,
+                    })
+                    .collect(),
+            });
+        }
+    }
+    imports.sort_by(|a, b| (a.line, &a.name).cmp(&(b.line, &b.name)));
+
+    let mut dependencies = input.dependency_graph.get_dependencies(&path);
+    dependencies.sort();
+    let mut dependents = input.dependency_graph.get_dependent_files(&path);
+    dependents.sort();
+
+    let file_metrics = input
+        .metrics
+        .and_then(|metrics| metrics.file_metrics.get(&path))
+        .cloned();
+    let knowledge_breakdown = file_metrics.as_ref().and_then(|file_metrics| {
+        file_metrics.complexity_metrics.as_ref().map(|complexity| {
+            metrics::knowledge_score_breakdown(file_metrics, complexity, &input.config.scoring)
+        })
+    });
+
+    Ok(Explanation {
+        path: relative_path(&path, input.repo_path),
+        traversed: repo_file.is_some(),
+        filter: repo_file.map(|file| {
+            filter::decide(
+                file,
+                &FilterRules {
+                    config: input.config,
+                    overrides: input.overrides,
+                    patterns: input.patterns,
+                    globs: input.path_globs,
+                    languages: input.languages,
+                    tests: input.tests,
+                },
+            )
+        }),
+        languages,
+        test_file: repo_file.and_then(|file| input.tests.test_reason(file)),
+        category: input
+            .category_rules
+            .classify(&relative_path(&path, input.repo_path)),
+        exports,
+        imports,
+        importance: input.dependency_graph.get_file_importance(&path),
+        importance_inputs: input
+            .dependency_graph
+            .importance_inputs(&path, input.exports_map),
+        dependencies,
+        dependents,
+        transitive_dependents: input.dependency_graph.get_transitive_dependents(&path),
+        metrics: file_metrics,
+        knowledge_breakdown,
+        findings: input
+            .findings
+            .iter()
+            .filter(|finding| finding.path == path)
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Serialize the dossier to JSON
+pub fn render_json(explanation: &Explanation) -> Result<String> {
+    serde_json::to_string_pretty(explanation).context("Failed to serialize explanation to JSON")
+}
+
+/// Render the dossier as human readable text
+pub fn render_text(explanation: &Explanation, repo_path: &str) -> String {
+    let mut out = String::new();
+    let rel = |path: &str| relative_path(path, repo_path);
+
+    out.push_str(&format!("# {}\n\n", explanation.path));
+
+    out.push_str("## Classification\n");
+    match &explanation.filter {
+        Some(decision) => out.push_str(&format!(
+            "- Filter: {} ({})\n",
+            if decision.included {
+                "included"
+            } else {
+                "ignored"
+            },
+            decision.reason
+        )),
+        None => out.push_str("- Filter: not reached by traversal (inside an ignored directory)\n"),
+    }
+    if explanation.languages.is_empty() {
+        out.push_str("- Language: none configured for this extension\n");
+    } else {
+        out.push_str(&format!(
+            "- Language: {}\n",
+            explanation.languages.join(", ")
+        ));
+    }
+    if let Some(reason) = &explanation.test_file {
+        out.push_str(&format!("- Test file: yes ({})\n", reason));
+    }
+    if explanation.category.is_ambiguous() {
+        out.push_str(&format!(
+            "- Category: {}\n",
+            categories::describe_ambiguity(&explanation.category)
+        ));
+    } else {
+        out.push_str(&format!("- Category: {}\n", explanation.category.category));
+    }
+    let inputs = &explanation.importance_inputs;
+    out.push_str(&format!(
+        "- Importance score: {} ({} export uses + {} dependent files × 2)\n\n",
+        explanation.importance, inputs.export_uses, inputs.dependent_files
+    ));
+
+    out.push_str(&format!("## Exports ({})\n", explanation.exports.len()));
+    for export in &explanation.exports {
+        out.push_str(&format!(
+            "- {} `{}` at line {} (used {} times)",
+            export.export_type, export.name, export.line, export.usage_count
+        ));
+        if let Some(canonical) = &export.canonical_path {
+            out.push_str(&format!(", defined in {}", rel(canonical)));
+        }
+        out.push('\n');
+        if let Some(signature) = &export.signature {
+            out.push_str(&format!("    {}\n", signature));
+        }
+    }
+    out.push('\n');
+
+    out.push_str(&format!("## Imports ({})\n", explanation.imports.len()));
+    for import in &explanation.imports {
+        out.push_str(&format!(
+            "- `{}` at line {}: {}\n",
+            import.name, import.line, import.statement
+        ));
+        if import.edges.is_empty() {
+            out.push_str("    -> no analyzed file exports this name\n");
+        }
+        for edge in &import.edges {
+            let confidence = match edge.confidence {
+                EdgeConfidence::High => "high",
+                EdgeConfidence::Low => "low",
+            };
+            out.push_str(&format!(
+                "    -> {} (confidence: {})\n",
+                rel(&edge.target),
+                confidence
+            ));
+        }
+    }
+    out.push('\n');
+
+    for (title, files) in [
+        ("Dependencies", &explanation.dependencies),
+        ("Direct dependents", &explanation.dependents),
+        ("Transitive dependents", &explanation.transitive_dependents),
+    ] {
+        out.push_str(&format!("## {} ({})\n", title, files.len()));
+        for file in files {
+            out.push_str(&format!("- {}\n", rel(file)));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Metrics\n");
+    match &explanation.metrics {
+        Some(metrics) => {
+            out.push_str(&format!(
+                "- Lines: {} total, {} code, {} comments, {} blank\n",
+                metrics.line_count, metrics.code_lines, metrics.comment_lines, metrics.blank_lines
+            ));
+            out.push_str(&format!("- Functions: {}\n", metrics.function_count));
+            if let Some(complexity) = &metrics.complexity_metrics {
+                out.push_str(&format!(
+                    "- Cyclomatic complexity: {:.1}\n- Cognitive complexity: {:.1}\n- Maintainability index: {:.1}\n",
+                    complexity.cyclomatic_complexity,
+                    complexity.cognitive_complexity,
+                    complexity.maintainability_index
+                ));
+            }
+            if !metrics.deep_analysis {
+                out.push_str("- Deep analysis: skipped (below the `deep_analysis` thresholds)\n");
+            }
+            out.push_str(&format!(
+                "- Knowledge score: {:.1}\n",
+                metrics.knowledge_score()
+            ));
+        }
+        None => out.push_str("- Not available (file not analyzed or --skip-metrics used)\n"),
+    }
+    // The breakdown only exists for files with complexity metrics
+    let scored = explanation.metrics.as_ref().and_then(|metrics| {
+        let complexity = metrics.complexity_metrics.as_ref()?;
+        Some((
+            explanation.knowledge_breakdown.as_ref()?,
+            metrics,
+            complexity,
+        ))
+    });
+    if let Some((breakdown, metrics, complexity)) = scored {
+        out.push_str("\n### Knowledge Score Breakdown\n");
+        for (factor, value, input) in [
+            (
+                "Size",
+                breakdown.size,
+                format!("{} lines, log scale", metrics.line_count),
+            ),
+            (
+                "Complexity",
+                breakdown.complexity,
+                format!(
+                    "cyclomatic {:.1} of 50, cognitive {:.1} of 200",
+                    complexity.cyclomatic_complexity, complexity.cognitive_complexity
+                ),
+            ),
+            (
+                "Maintainability",
+                breakdown.maintainability,
+                format!(
+                    "index {:.1}, lower weighs more",
+                    complexity.maintainability_index
+                ),
+            ),
+            (
+                "Functions",
+                breakdown.functions,
+                format!("{} of 20", metrics.function_count),
+            ),
+            (
+                "Declarations",
+                breakdown.declarations,
+                format!(
+                    "{} of 10",
+                    metrics.declaration_count.values().sum::<usize>()
+                ),
+            ),
+            (
+                "Exports",
+                breakdown.exports,
+                format!(
+                    "importance {:.2} relative to the top file",
+                    metrics.export_importance()
+                ),
+            ),
+            (
+                "Extra signals",
+                breakdown.extra,
+                "from --signals".to_string(),
+            ),
+        ] {
+            out.push_str(&format!("- {}: {:.1} ({})\n", factor, value, input));
+        }
+        out.push_str(&format!(
+            "- Total (scaled by 0.85, kept within 0-100): {:.1}\n",
+            breakdown.total
+        ));
+    }
+    out.push('\n');
+
+    out.push_str(&format!("## Findings ({})\n", explanation.findings.len()));
+    for finding in &explanation.findings {
+        out.push_str(&format!("- {}\n", finding));
+    }
+
+    out
+}
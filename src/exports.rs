@@ -1,15 +1,21 @@
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
+use rayon::prelude::*;
 use regex::Regex;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
+use crate::audit::PhaseTrace;
 use crate::config::Config;
-use crate::traversal::RepoFile;
+use crate::findings::sort_warnings;
+use crate::patterns::{self, PatternKind};
+use crate::progress;
+use crate::traversal::{on_disk, relative_path, RepoFile};
 
 /// Represents an exported entity from a file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExportedEntity {
     /// Name of the exported entity
     pub name: String,
@@ -23,12 +29,28 @@ pub struct ExportedEntity {
     /// Type of export (e.g., function, class, variable)
     pub export_type: String,
 
+    /// Visibility as written in the source (e.g., pub, pub(crate), export, export default)
+    pub visibility: String,
+
+    /// Stable identity across runs, assigned by `assign_export_ids`
+    pub id: String,
+
     /// Usage count - how many times this export is referenced
     pub usage_count: usize,
+
+    /// Whether this entity re-exports something defined elsewhere (`pub use`, `export { x } from`)
+    pub is_reexport: bool,
+
+    /// Module the entity is re-exported from, as written in the source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reexport_source: Option<String>,
+
+    /// File containing the original definition, once re-export chains are resolved
+    pub canonical_path: Option<PathBuf>,
 }
 
 /// Represents an import reference to an exported entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ImportReference {
     /// Name of the imported entity
     pub name: String,
@@ -49,63 +71,168 @@ pub type ExportsMap = HashMap<String, Vec<ExportedEntity>>;
 /// Map of entity names to import references
 pub type ImportsMap = HashMap<String, Vec<ImportReference>>;
 
-/// Scan a repository for exports and imports
-pub fn scan_repository(files: &[RepoFile], config: &Config) -> Result<(ExportsMap, ImportsMap)> {
+/// Scan a repository for exports and imports, also returning the files that could not be read
+pub fn scan_repository(
+    files: &[RepoFile],
+    repo_root: &str,
+    config: &Config,
+    mut trace: Option<&mut PhaseTrace>,
+) -> Result<(ExportsMap, ImportsMap, Vec<String>)> {
     info!("Scanning repository for exports and imports");
 
     let mut exports_map: ExportsMap = HashMap::new();
     let mut imports_map: ImportsMap = HashMap::new();
+    let mut unreadable = Vec::new();
 
-    for file in files {
-        if let Some(extension) = &file.extension {
-            // Find the language config for this file
-            for (lang_name, lang_config) in &config.languages {
-                if lang_config.extensions.iter().any(|ext| ext == extension) {
-                    debug!("Processing {} file: {}", lang_name, file.path.display());
-
-                    // Read file content
-                    let file_content = match fs::read_to_string(&file.path) {
-                        Ok(content) => content,
-                        Err(err) => {
-                            debug!("Error reading file {}: {}", file.path.display(), err);
-                            continue;
-                        }
-                    };
-
-                    // Extract exports
-                    let file_exports =
-                        extract_exports(&file.path, &file_content, &lang_config.export_patterns);
+    // Resolve each language's patterns against the built-ins once, not per file
+    let language_patterns: HashMap<&String, (Vec<String>, Vec<String>)> = config
+        .languages
+        .iter()
+        .map(|(name, lang)| {
+            (
+                name,
+                (
+                    patterns::effective_regexes(name, lang, PatternKind::Import),
+                    patterns::effective_regexes(name, lang, PatternKind::Export),
+                ),
+            )
+        })
+        .collect();
 
-                    // Store exports
-                    if !file_exports.is_empty() {
-                        let path_str = file.path.to_string_lossy().to_string();
-                        exports_map.insert(path_str.clone(), file_exports);
-                        debug!("Found exports in file: {}", path_str);
-                    }
+    // Files are scanned in parallel, then merged in traversal order so the maps don't depend
+    // on the number of threads, and warnings are logged by path
+    let mut warnings = Vec::new();
+    let progress = progress::files("Scanning exports", files.len());
+    let scanned: Vec<(String, Scanned)> = files
+        .par_iter()
+        .map(|file| {
+            let path_str = file.path.to_string_lossy().to_string();
+            progress::advance(&progress, &path_str);
+            let scanned = scan_file(file, repo_root, config, &language_patterns);
+            (path_str, scanned)
+        })
+        .collect();
 
-                    // Extract imports
-                    let file_imports =
-                        extract_imports(&file.path, &file_content, &lang_config.import_patterns);
+    for (path_str, scanned) in scanned {
+        match scanned {
+            Scanned::Read {
+                file_exports,
+                file_imports,
+            } => {
+                // Store exports
+                if !file_exports.is_empty() {
+                    exports_map.insert(path_str.clone(), file_exports);
+                    debug!("Found exports in file: {}", path_str);
+                }
 
-                    // Store imports
-                    for import in file_imports {
-                        imports_map
-                            .entry(import.name.clone())
-                            .or_default()
-                            .push(import);
-                    }
+                // Store imports
+                for import in file_imports {
+                    imports_map
+                        .entry(import.name.clone())
+                        .or_default()
+                        .push(import);
+                }
 
-                    // We found the language for this file, no need to check others
-                    break;
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.emit(&path_str);
+                }
+            }
+            Scanned::Unreadable(err) => {
+                warnings.push((path_str.clone(), format!("Failed to read {} for exports: {}", path_str, err)));
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.exclude(&path_str, format!("could not be read: {}", err));
+                }
+                unreadable.push(path_str);
+            }
+            Scanned::NoLanguage => {
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.exclude(&path_str, "no language is configured for its extension");
                 }
             }
         }
     }
+    progress.finish_and_clear();
+
+    sort_warnings(&mut warnings);
+    for (_, warning) in &warnings {
+        warn!("{}", warning);
+    }
 
     info!("Found exports in {} files", exports_map.len());
     info!("Found imports for {} unique entities", imports_map.len());
 
-    Ok((exports_map, imports_map))
+    Ok((exports_map, imports_map, unreadable))
+}
+
+/// Outcome of scanning one file for exports and imports
+enum Scanned {
+    Read {
+        file_exports: Vec<ExportedEntity>,
+        file_imports: Vec<ImportReference>,
+    },
+    Unreadable(std::io::Error),
+    NoLanguage,
+}
+
+/// Scan one file with the patterns of the first language configured for its extension
+fn scan_file(
+    file: &RepoFile,
+    repo_root: &str,
+    config: &Config,
+    language_patterns: &HashMap<&String, (Vec<String>, Vec<String>)>,
+) -> Scanned {
+    let Some(extension) = &file.extension else {
+        return Scanned::NoLanguage;
+    };
+
+    // Find the language config for this file
+    let Some(lang_name) = config
+        .languages
+        .iter()
+        .find(|(_, lang_config)| lang_config.extensions.iter().any(|ext| ext == extension))
+        .map(|(name, _)| name)
+    else {
+        return Scanned::NoLanguage;
+    };
+    debug!("Processing {} file: {}", lang_name, file.path.display());
+    let (import_patterns, export_patterns) = &language_patterns[lang_name];
+
+    // Read file content
+    let file_content = match fs::read_to_string(on_disk(repo_root, &file.path)) {
+        Ok(content) => content,
+        Err(err) => return Scanned::Unreadable(err),
+    };
+
+    Scanned::Read {
+        file_exports: extract_exports(&file.path, &file_content, export_patterns),
+        file_imports: extract_imports(&file.path, &file_content, import_patterns),
+    }
+}
+
+/// Scan content written in the configured language of `extension` for exports and imports,
+/// without a repository around it; `None` when no language is configured for the extension
+pub fn scan_content(
+    file_path: &Path,
+    extension: &str,
+    content: &str,
+    config: &Config,
+) -> Option<(String, Vec<ExportedEntity>, Vec<ImportReference>)> {
+    let (name, lang) = config
+        .languages
+        .iter()
+        .find(|(_, lang)| lang.extensions.iter().any(|ext| ext == extension))?;
+
+    let exports = extract_exports(
+        file_path,
+        content,
+        &patterns::effective_regexes(name, lang, PatternKind::Export),
+    );
+    let imports = extract_imports(
+        file_path,
+        content,
+        &patterns::effective_regexes(name, lang, PatternKind::Import),
+    );
+    Some((name.clone(), exports, imports))
 }
 
 /// Extract exports from file content using regex patterns
@@ -124,6 +251,14 @@ fn extract_exports(file_path: &Path, content: &str, patterns: &[String]) -> Vec<
         })
         .collect();
 
+    let reexport_patterns = [
+        // Rust: pub use crate::module::Name;
+        Regex::new(r"^\s*pub(?:\([^)]*\))?\s+use\s+([\w:]+)::(?:\w+|\{[^}]*\}|\*)").unwrap(),
+        // JavaScript/TypeScript: export { Name } from './module';
+        Regex::new(r#"^\s*export\s+(?:type\s+)?(?:\*|\{[^}]*\})\s*from\s*['"]([^'"]+)['"]"#)
+            .unwrap(),
+    ];
+
     // Apply each pattern to the content
     for (line_num, line) in content.lines().enumerate() {
         let line_num = line_num + 1; // 1-indexed line numbers
@@ -135,15 +270,27 @@ fn extract_exports(file_path: &Path, content: &str, patterns: &[String]) -> Vec<
                     if let Some(name_match) = captures.get(captures.len() - 1) {
                         let name = name_match.as_str().trim().to_string();
 
+                        // Re-exports point at the module the entity comes from
+                        let reexport_source = extract_reexport_source(line, &reexport_patterns);
+
                         // Determine export type based on the regex pattern or content
-                        let export_type = determine_export_type(line);
+                        let export_type = if reexport_source.is_some() {
+                            "re-export".to_string()
+                        } else {
+                            determine_export_type(line)
+                        };
 
                         exports.push(ExportedEntity {
                             name,
                             file_path: file_path.to_path_buf(),
                             line_number: line_num,
                             export_type,
-                            usage_count: 0, // Will be updated later
+                            visibility: determine_visibility(line),
+                            id: String::new(), // Assigned once re-exports are resolved
+                            usage_count: 0,    // Will be updated later
+                            is_reexport: reexport_source.is_some(),
+                            reexport_source,
+                            canonical_path: None, // Resolved by resolve_reexports
                         });
                     }
                 }
@@ -355,6 +502,75 @@ fn determine_export_type(line: &str) -> String {
     "unknown".to_string()
 }
 
+/// Determine the visibility of an export based on the line content
+fn determine_visibility(line: &str) -> String {
+    let line = line.trim_start();
+
+    if let Some(rest) = line.strip_prefix("pub") {
+        if rest.starts_with('(') {
+            if let Some(end) = rest.find(')') {
+                let scope: String = rest[..=end].split_whitespace().collect();
+                return format!("pub{}", scope);
+            }
+        }
+        if rest.starts_with(char::is_whitespace) {
+            return "pub".to_string();
+        }
+    }
+
+    if line.starts_with("export default") {
+        "export default".to_string()
+    } else if line.starts_with("export") || line.contains("exports") {
+        "export".to_string()
+    } else {
+        "public".to_string()
+    }
+}
+
+/// Stable identity of an export: a hash of its canonical file (relative to the repository
+/// root), name, export type and visibility. Line numbers are left out so the id survives
+/// code moving within the file. Renaming the entity, or changing its kind, visibility or
+/// defining file, intentionally produces a new id. Re-exports also hash the file they are
+/// re-exported from, so each re-export site keeps its own id.
+pub fn export_id(export: &ExportedEntity, repo_root: &str) -> String {
+    let relative =
+        |path: &Path| relative_path(&path.to_string_lossy(), repo_root).replace('\\', "/");
+
+    let canonical = relative(export.canonical_path.as_ref().unwrap_or(&export.file_path));
+    let site = if export.is_reexport {
+        relative(&export.file_path)
+    } else {
+        String::new()
+    };
+
+    // FNV-1a, which unlike the std hashers is stable across Rust versions and platforms
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for field in [
+        canonical.as_str(),
+        &export.name,
+        &export.export_type,
+        &export.visibility,
+        &site,
+    ] {
+        // Terminate each field so ("ab", "c") and ("a", "bc") hash differently
+        for byte in field.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// Assign every export its stable id; run after `resolve_reexports`
+pub fn assign_export_ids(exports_map: &mut ExportsMap, repo_root: &str) {
+    for exports in exports_map.values_mut() {
+        for export in exports.iter_mut() {
+            export.id = export_id(export, repo_root);
+        }
+    }
+}
+
 /// Check if an entity with the given name exists in the exports map
 pub fn find_export_by_name<'a>(
     exports_map: &'a ExportsMap,
@@ -367,3 +583,265 @@ pub fn find_export_by_name<'a>(
     }
     None
 }
+
+/// Return the source module if the line re-exports entities from another module
+fn extract_reexport_source(line: &str, patterns: &[Regex]) -> Option<String> {
+    patterns
+        .iter()
+        .find_map(|regex| regex.captures(line))
+        .and_then(|captures| captures.get(1))
+        .map(|source| source.as_str().to_string())
+}
+
+/// Resolve re-export chains so every entity points at the file of its original definition.
+/// Chains are followed through intermediate re-exports; cycles stop with a warning.
+pub fn resolve_reexports(exports_map: &mut ExportsMap) {
+    // Index exports map keys by their normalized path so module paths can be matched
+    let keys: HashMap<PathBuf, String> = exports_map
+        .keys()
+        .map(|key| (normalize_path(Path::new(key)), key.clone()))
+        .collect();
+
+    let mut resolved = Vec::new();
+    for (file_path, exports) in exports_map.iter() {
+        for (idx, export) in exports.iter().enumerate() {
+            let canonical = if export.is_reexport {
+                resolve_canonical_path(exports_map, &keys, file_path, export)
+            } else {
+                Some(export.file_path.clone())
+            };
+            resolved.push((file_path.clone(), idx, canonical));
+        }
+    }
+
+    let mut unresolved = 0;
+    for (file_path, idx, canonical) in resolved {
+        if canonical.is_none() {
+            unresolved += 1;
+        }
+        if let Some(exports) = exports_map.get_mut(&file_path) {
+            exports[idx].canonical_path = canonical;
+        }
+    }
+
+    if unresolved > 0 {
+        debug!(
+            "Could not resolve the definition of {} re-exports",
+            unresolved
+        );
+    }
+}
+
+/// Follow a re-export through the modules it names until the original definition is found
+fn resolve_canonical_path(
+    exports_map: &ExportsMap,
+    keys: &HashMap<PathBuf, String>,
+    file_path: &str,
+    export: &ExportedEntity,
+) -> Option<PathBuf> {
+    let mut visited = HashSet::new();
+    let mut current_file = file_path.to_string();
+    let mut current = export;
+
+    loop {
+        if !visited.insert(current_file.clone()) {
+            warn!(
+                "Re-export cycle detected for `{}` starting at {}; leaving it unresolved",
+                export.name, file_path
+            );
+            return None;
+        }
+
+        let source = current.reexport_source.as_deref()?;
+        let target = resolve_module_path(&current.file_path, source)
+            .into_iter()
+            .find_map(|candidate| keys.get(&normalize_path(&candidate)))
+            .and_then(|key| {
+                exports_map[key]
+                    .iter()
+                    .find(|e| e.name == export.name)
+                    .map(|entity| (key, entity))
+            });
+
+        match target {
+            Some((_, entity)) if !entity.is_reexport => return Some(entity.file_path.clone()),
+            Some((key, entity)) => {
+                current_file = key.clone();
+                current = entity;
+            }
+            // Fall back to a unique definition with the same name anywhere in the repository
+            None => return find_unique_definition(exports_map, &export.name),
+        }
+    }
+}
+
+/// Find the only non-re-export entity with the given name, if there is exactly one
+fn find_unique_definition(exports_map: &ExportsMap, name: &str) -> Option<PathBuf> {
+    let mut definitions = exports_map
+        .values()
+        .flatten()
+        .filter(|e| e.name == name && !e.is_reexport);
+
+    match (definitions.next(), definitions.next()) {
+        (Some(definition), None) => Some(definition.file_path.clone()),
+        _ => None,
+    }
+}
+
+/// Candidate files a module path may refer to, relative to the importing file
+fn resolve_module_path(from_file: &Path, source: &str) -> Vec<PathBuf> {
+    let dir = from_file.parent().unwrap_or_else(|| Path::new(""));
+
+    // JavaScript/TypeScript relative module specifier
+    if source.starts_with('.') {
+        let base = dir.join(source);
+        let mut candidates = vec![base.clone()];
+        for ext in ["ts", "tsx", "js", "jsx"] {
+            candidates.push(base.with_extension(ext));
+            candidates.push(base.join(format!("index.{}", ext)));
+        }
+        return candidates;
+    }
+
+    // Rust module path
+    let mut segments = source.split("::").peekable();
+    let mut module_dir = match segments.peek() {
+        Some(&"crate") => {
+            segments.next();
+            match crate_root(from_file) {
+                Some(root) => root,
+                None => return Vec::new(),
+            }
+        }
+        Some(&"self") => {
+            segments.next();
+            module_dir(from_file)
+        }
+        Some(&"super") => module_dir(from_file),
+        _ => return Vec::new(),
+    };
+
+    while let Some(&"super") = segments.peek() {
+        segments.next();
+        module_dir = module_dir
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+    }
+
+    let module: PathBuf = segments.collect();
+    if module.as_os_str().is_empty() {
+        return Vec::new();
+    }
+
+    let base = module_dir.join(module);
+    vec![base.with_extension("rs"), base.join("mod.rs")]
+}
+
+/// Directory holding the submodules of the module defined by a Rust file
+fn module_dir(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+    match file.file_stem().and_then(|s| s.to_str()) {
+        Some("mod") | Some("lib") | Some("main") => dir,
+        Some(stem) => dir.join(stem),
+        None => dir,
+    }
+}
+
+/// The `src` directory containing a Rust file, used to resolve `crate::` paths
+fn crate_root(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .find(|ancestor| ancestor.file_name().map(|n| n == "src").unwrap_or(false))
+        .map(Path::to_path_buf)
+}
+
+/// Lexically normalize a path, removing `.` and resolving `..` components
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Files that re-export the given definition, grouped under its canonical definition
+pub fn reexport_sites(exports_map: &ExportsMap, definition: &ExportedEntity) -> Vec<PathBuf> {
+    let mut sites: Vec<PathBuf> = exports_map
+        .values()
+        .flatten()
+        .filter(|e| {
+            e.is_reexport
+                && e.name == definition.name
+                && e.canonical_path.as_deref() == Some(definition.file_path.as_path())
+        })
+        .map(|e| e.file_path.clone())
+        .collect();
+    sites.sort();
+    sites.dedup();
+    sites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixture::export;
+
+    #[test]
+    fn export_id_ignores_the_line_number() {
+        let mut moved = export("repo/src/lib.rs", "parse", "function");
+        let id = export_id(&moved, "repo");
+        moved.line_number = 120;
+
+        assert_eq!(export_id(&moved, "repo"), id);
+        assert_eq!(id.len(), 16);
+    }
+
+    #[test]
+    fn export_id_changes_with_the_identity() {
+        let original = export("repo/src/lib.rs", "parse", "function");
+        let id = export_id(&original, "repo");
+
+        let renamed = export("repo/src/lib.rs", "parse_file", "function");
+        let retyped = export("repo/src/lib.rs", "parse", "const");
+        let mut narrowed = export("repo/src/lib.rs", "parse", "function");
+        narrowed.visibility = "pub(crate)".to_string();
+        let moved = export("repo/src/parse.rs", "parse", "function");
+
+        for changed in [renamed, retyped, narrowed, moved] {
+            assert_ne!(export_id(&changed, "repo"), id, "{:?}", changed);
+        }
+    }
+
+    #[test]
+    fn export_id_is_relative_to_the_repository_root() {
+        let relative = export("repo/src/lib.rs", "parse", "function");
+        let absolute = export("/home/ci/workspace/repo/src/lib.rs", "parse", "function");
+
+        assert_eq!(
+            export_id(&relative, "repo"),
+            export_id(&absolute, "/home/ci/workspace/repo")
+        );
+        assert_eq!(
+            export_id(&absolute, "/home/ci/workspace/repo/"),
+            export_id(&absolute, "/home/ci/workspace/repo")
+        );
+    }
+
+    #[test]
+    fn reexports_keep_their_own_id() {
+        let definition = export("repo/src/parse.rs", "parse", "function");
+        let mut reexport = export("repo/src/lib.rs", "parse", "function");
+        reexport.is_reexport = true;
+        reexport.canonical_path = Some(PathBuf::from("repo/src/parse.rs"));
+
+        assert_ne!(export_id(&reexport, "repo"), export_id(&definition, "repo"));
+    }
+}
@@ -1,16 +1,54 @@
-use log::{debug, info};
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use log::{debug, info, warn};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::path::Path;
+use std::process::{Command, Output};
 
-use crate::config::Config;
-use crate::traversal::RepoFile;
+use crate::audit::PhaseTrace;
+use crate::config::{Config, IgnoreScope, LanguageConfig, PathOverride, TestsConfig};
+use crate::traversal::{on_disk, relative_path, RepoFile};
 
-/// Apply configured filters to the list of files
-pub fn apply_filters(files: Vec<RepoFile>, config: &Config) -> Vec<RepoFile> {
+/// Every rule deciding which traversed files are analyzed, compiled for a run
+pub struct FilterRules<'a> {
+    pub config: &'a Config,
+    pub overrides: &'a PathOverrides,
+    pub patterns: &'a ConfigPatterns,
+    pub globs: &'a PathGlobs,
+    pub languages: &'a LanguageFilter,
+    pub tests: &'a TestFilter,
+}
+
+/// Apply configured filters to the list of files, recording each decision in `trace`, and
+/// flag the kept test files
+pub fn apply_filters(
+    files: Vec<RepoFile>,
+    rules: &FilterRules,
+    mut trace: Option<&mut PhaseTrace>,
+) -> Vec<RepoFile> {
     info!("Applying filters to {} files", files.len());
 
     let filtered_files: Vec<RepoFile> = files
         .into_iter()
-        .filter(|file| !should_ignore_file(file, config))
+        .filter_map(|mut file| {
+            let decision = decide(&file, rules);
+            if let Some(trace) = trace.as_deref_mut() {
+                let path = file.path.to_string_lossy();
+                if decision.included {
+                    trace.emit(&path);
+                } else {
+                    trace.exclude(&path, decision.reason.clone());
+                }
+            }
+            if !decision.included {
+                return None;
+            }
+            rules.tests.mark(&mut file);
+            Some(file)
+        })
         .collect();
 
     info!("After filtering, {} files remain", filtered_files.len());
@@ -18,138 +56,1510 @@ pub fn apply_filters(files: Vec<RepoFile>, config: &Config) -> Vec<RepoFile> {
     filtered_files
 }
 
-/// Check if a file should be ignored based on configuration rules
-fn should_ignore_file(file: &RepoFile, config: &Config) -> bool {
-    let path = &file.path;
-    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-    let path_str = path.to_string_lossy().to_string();
+/// Run the configured filter rules on a file, then `--include`/`--exclude`, `--languages` and
+/// `--exclude-tests`/`--only-tests`
+pub fn decide(file: &RepoFile, rules: &FilterRules) -> FilterDecision {
+    let decision = filter_decision(file, rules.config, rules.overrides, rules.patterns);
+    let decision = rules.globs.decide(file, decision);
+    rules
+        .tests
+        .decide(file, rules.languages.decide(file, decision))
+}
 
-    // Allow our own source files for development purposes
-    if path_str.contains("src") && file.extension.as_deref() == Some("rs") {
-        debug!(
-            "Including Rust source file for analysis: {}",
-            path.display()
-        );
-        return false;
+/// List every traversed file in path order with its status and the rule deciding it, followed
+/// by the totals (`--list-files`)
+pub fn render_file_list(files: &[RepoFile], repo_root: &str, rules: &FilterRules) -> String {
+    let mut rows: Vec<(String, FilterDecision)> = files
+        .iter()
+        .map(|file| {
+            (
+                relative_path(&file.path.to_string_lossy(), repo_root),
+                decide(file, rules),
+            )
+        })
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let included = rows
+        .iter()
+        .filter(|(_, decision)| decision.included)
+        .count();
+    let mut content = String::new();
+    for (path, decision) in &rows {
+        let status = if decision.included {
+            "included"
+        } else {
+            "excluded"
+        };
+        content.push_str(&format!("{}  {}  ({})\n", status, path, decision.reason));
+    }
+    content.push_str(&format!(
+        "\n{} files: {} included, {} excluded\n",
+        rows.len(),
+        included,
+        rows.len() - included
+    ));
+    content
+}
+
+/// `--include` and `--exclude` globs, applied on top of the configured filter rules
+#[derive(Debug, Default)]
+pub struct PathGlobs {
+    include: Vec<String>,
+    include_set: GlobSet,
+    exclude: Vec<String>,
+    exclude_set: GlobSet,
+    repo_root: String,
+}
+
+impl PathGlobs {
+    /// Compile the globs, matched against paths relative to `repo_root` with `/` separators.
+    /// `*` stays within a directory and `**` spans any number of them; a glob without a `/`
+    /// matches at any depth, and a glob matching a directory matches the files below it.
+    pub fn new(include: &[String], exclude: &[String], repo_root: &str) -> Result<Self> {
+        Ok(PathGlobs {
+            include_set: compile_globs(include, "--include")?,
+            exclude_set: compile_globs(exclude, "--exclude")?,
+            include: include.to_vec(),
+            exclude: exclude.to_vec(),
+            repo_root: repo_root.to_string(),
+        })
     }
 
-    // Special handling for external Python and TypeScript/JavaScript files
-    if let Some(ext) = &file.extension {
-        let ext_str = ext.as_str();
-        if (ext_str == "py" || ext_str == "ts" || ext_str == "tsx" || ext_str == "js")
-            && !path_str.contains("node_modules")
-            && !path_str.contains("venv")
-            && !path_str.contains(".venv")
+    /// Apply the globs to the decision of the configured rules: files those rules ignore stay
+    /// ignored, an `--exclude` match always ignores, and with any `--include` only files
+    /// matching one are kept
+    pub fn decide(&self, file: &RepoFile, decision: FilterDecision) -> FilterDecision {
+        if !decision.included || (self.include.is_empty() && self.exclude.is_empty()) {
+            return decision;
+        }
+
+        let path = relative_path(&file.path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        if let Some(idx) = first_match(&self.exclude_set, &path) {
+            debug!("Ignoring file by --exclude: {}", path);
+            return FilterDecision::ignore(format!("matches --exclude `{}`", self.exclude[idx]));
+        }
+        if self.include.is_empty() {
+            return decision;
+        }
+        match first_match(&self.include_set, &path) {
+            Some(idx) => FilterDecision::include(format!(
+                "{}, and matches --include `{}`",
+                decision.reason, self.include[idx]
+            )),
+            None => {
+                debug!("Ignoring file matching no --include: {}", path);
+                FilterDecision::ignore("matches no --include glob")
+            }
+        }
+    }
+}
+
+/// The `overrides` of the configuration, with their path globs compiled
+#[derive(Debug, Default)]
+pub struct PathOverrides {
+    overrides: Vec<PathOverride>,
+    set: GlobSet,
+    ignore_patterns: Vec<PatternSet>,
+    repo_root: String,
+}
+
+impl PathOverrides {
+    /// Compile the globs of the overrides, matched like --include ones against paths relative
+    /// to `repo_root`, and their ignore patterns
+    pub fn new(overrides: &[PathOverride], repo_root: &str) -> Result<Self> {
+        let globs: Vec<String> = overrides.iter().map(|o| o.path.clone()).collect();
+        let ignore_patterns = overrides
+            .iter()
+            .enumerate()
+            .map(|(idx, o)| {
+                PatternSet::new(
+                    &o.ignore_patterns,
+                    &format!("overrides[{}].ignore_patterns", idx),
+                )
+            })
+            .collect::<Result<_>>()?;
+        Ok(PathOverrides {
+            set: compile_globs(&globs, "overrides")?,
+            overrides: overrides.to_vec(),
+            ignore_patterns,
+            repo_root: repo_root.to_string(),
+        })
+    }
+
+    /// Indices of the overrides matching a traversed path or one of its directories, the
+    /// most specific (longest glob) first; of two as long, the later entry comes first
+    fn matching(&self, path: &Path) -> Vec<usize> {
+        if self.overrides.is_empty() {
+            return Vec::new();
+        }
+        let path = relative_path(&path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        let matched: BTreeSet<usize> = Path::new(&path)
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .flat_map(|ancestor| self.set.matches(ancestor))
+            .collect();
+
+        let mut matching: Vec<usize> = matched.into_iter().collect();
+        matching.sort_by_key(|idx| std::cmp::Reverse((self.overrides[*idx].path.len(), *idx)));
+        matching
+    }
+
+    /// The value the most specific override matching a path and setting it gives, with the
+    /// glob of that override
+    pub fn value<T>(
+        &self,
+        path: &Path,
+        field: impl Fn(&PathOverride) -> Option<T>,
+    ) -> Option<(T, &str)> {
+        self.matching(path).into_iter().find_map(|idx| {
+            let o = &self.overrides[idx];
+            Some((field(o)?, o.path.as_str()))
+        })
+    }
+
+    /// The first ignore pattern of the overrides matching a path that matches it too, with
+    /// the glob of its override
+    fn ignore_match(&self, path: &Path) -> Option<(&str, &str)> {
+        let relative = relative_path(&path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        self.matching(path).into_iter().find_map(|idx| {
+            let pattern = self.ignore_patterns[idx].first_match(&relative)?;
+            Some((pattern, self.overrides[idx].path.as_str()))
+        })
+    }
+}
+
+/// The `ignore_patterns` and `include_patterns` of the configuration, compiled once
+#[derive(Debug, Default)]
+pub struct ConfigPatterns {
+    ignore: PatternSet,
+    include: PatternSet,
+    language_include: BTreeMap<String, PatternSet>,
+    repo_root: String,
+}
+
+impl ConfigPatterns {
+    /// Compile the patterns, matched against paths relative to `repo_root`
+    pub fn new(config: &Config, repo_root: &str) -> Result<Self> {
+        let mut language_include = BTreeMap::new();
+        for (name, lang) in config
+            .languages
+            .iter()
+            .filter(|(_, l)| !l.include_patterns.is_empty())
         {
-            debug!(
-                "Including {} file for analysis: {}",
-                ext_str.to_uppercase(),
-                path.display()
+            let what = format!("languages.{}.include_patterns", name);
+            language_include.insert(
+                name.clone(),
+                PatternSet::new(&lang.include_patterns, &what)?,
             );
-            return false;
         }
+        Ok(ConfigPatterns {
+            ignore: PatternSet::new(&config.ignore_patterns, "ignore_patterns")?,
+            include: PatternSet::new(&config.include_patterns, "include_patterns")?,
+            language_include,
+            repo_root: repo_root.to_string(),
+        })
+    }
+
+    /// The first global ignore pattern matching a traversed path
+    fn ignore_match(&self, path: &Path) -> Option<&str> {
+        let path = relative_path(&path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        self.ignore.first_match(&path)
+    }
+
+    /// Why a traversed path of the given languages is left out by the include patterns, or
+    /// `None` when it matches the global ones and those of its languages that have any
+    fn include_miss(&self, path: &Path, languages: &[&String]) -> Option<String> {
+        let path = relative_path(&path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        if !self.include.patterns.is_empty() && self.include.first_match(&path).is_none() {
+            return Some("matches no include pattern".to_string());
+        }
+        languages.iter().find_map(|lang| {
+            let set = self.language_include.get(lang.as_str())?;
+            set.first_match(&path)
+                .is_none()
+                .then(|| format!("matches no include pattern of language `{}`", lang))
+        })
+    }
+}
+
+/// Patterns like `ignore_patterns`, compiled into a glob set. A pattern with glob
+/// metacharacters is matched like an `--include` glob; one without is a literal name or path
+/// matching the files or directories it ends the path of.
+#[derive(Debug, Default)]
+pub struct PatternSet {
+    patterns: Vec<String>,
+    set: GlobSet,
+}
+
+impl PatternSet {
+    /// Compile the patterns; `what` names them in errors
+    pub fn new(patterns: &[String], what: &str) -> Result<Self> {
+        let globs: Vec<String> = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = pattern.trim_start_matches("./");
+                if pattern.contains(['*', '?', '[', '{']) {
+                    pattern.to_string()
+                } else {
+                    format!("**/{}", pattern)
+                }
+            })
+            .collect();
+        Ok(PatternSet {
+            set: compile_globs(&globs, what)?,
+            patterns: patterns.to_vec(),
+        })
+    }
+
+    /// The first pattern matching a path relative to the repository root, with `/`
+    /// separators, or one of its directories
+    pub fn first_match(&self, path: &str) -> Option<&str> {
+        first_match(&self.set, path).map(|idx| self.patterns[idx].as_str())
+    }
+}
+
+fn compile_globs(globs: &[String], flag: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let pattern = glob.trim_start_matches("./").trim_end_matches('/');
+        let pattern = if pattern.contains('/') {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+        let compiled = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .context(format!("Invalid {} glob `{}`", flag, glob))?;
+        builder.add(compiled);
+    }
+    builder
+        .build()
+        .context(format!("Failed to compile the {} globs", flag))
+}
+
+/// Part of the repository the report is limited to (`--only`), while every file is still
+/// scanned for imports
+#[derive(Debug)]
+pub struct Subtree {
+    prefix: String,
+    repo_root: String,
+}
+
+impl Subtree {
+    /// `prefix` is a directory or file, relative to `repo_root` or absolute below it
+    pub fn new(prefix: &str, repo_root: &str) -> Result<Self> {
+        let prefix = relative_path(prefix, repo_root).replace('\\', "/");
+        let prefix = prefix.trim_start_matches("./").trim_end_matches('/');
+        if prefix.is_empty() || prefix == "." {
+            bail!("--only needs a path below the repository root, e.g. `src/core`");
+        }
+        Ok(Subtree {
+            prefix: prefix.to_string(),
+            repo_root: repo_root.to_string(),
+        })
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Whether a traversed path is the prefix itself or lies below it
+    pub fn contains(&self, path: &str) -> bool {
+        let path = relative_path(path, &self.repo_root).replace('\\', "/");
+        path == self.prefix
+            || path
+                .strip_prefix(&self.prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// Files changed between a git ref and the working tree (`--since`), while every file is
+/// still scanned for imports
+#[derive(Debug)]
+pub struct ChangedFiles {
+    reference: String,
+    repo_root: String,
+
+    /// Changed paths relative to `repo_root`, with `/` separators
+    paths: BTreeSet<String>,
+}
+
+impl ChangedFiles {
+    /// Ask git for the files below `repo_root` that differ from `reference`; deleted files
+    /// are left out since there is nothing left to analyze
+    pub fn new(reference: &str, repo_root: &str) -> Result<Self> {
+        let inside = git(repo_root, &["rev-parse", "--is-inside-work-tree"])?;
+        if !inside.status.success() {
+            bail!(
+                "--since needs a git repository, but {} is not inside one",
+                repo_root
+            );
+        }
+
+        let diff = git(
+            repo_root,
+            &[
+                "diff",
+                "--name-only",
+                "--relative",
+                "--diff-filter=d",
+                reference,
+                "--",
+            ],
+        )?;
+        if !diff.status.success() {
+            bail!(
+                "git diff against --since {} failed: {}",
+                reference,
+                String::from_utf8_lossy(&diff.stderr).trim()
+            );
+        }
+        let paths: BTreeSet<String> = String::from_utf8_lossy(&diff.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        debug!("{} files changed since {}", paths.len(), reference);
+
+        Ok(ChangedFiles {
+            reference: reference.to_string(),
+            repo_root: repo_root.to_string(),
+            paths,
+        })
+    }
+
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Whether a traversed path is a changed file, or a directory holding one
+    pub fn contains(&self, path: &str) -> bool {
+        let path = relative_path(path, &self.repo_root).replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        self.paths.contains(path)
+            || self
+                .paths
+                .range(format!("{}/", path)..)
+                .next()
+                .is_some_and(|changed| changed.starts_with(&format!("{}/", path)))
+    }
+}
+
+/// Run git in `directory`
+fn git(directory: &str, args: &[&str]) -> Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .output()
+        .context("Failed to run git; --since needs git on the PATH")
+}
+
+/// Configured languages selected with `--languages`; without any, every file passes
+#[derive(Debug, Default)]
+pub struct LanguageFilter {
+    /// Names of the selected languages, in order
+    names: Vec<String>,
+
+    /// Lowercase extensions of the selected languages
+    extensions: BTreeSet<String>,
+}
+
+impl LanguageFilter {
+    /// Resolve each requested language, given by its configured name or one of its extensions
+    /// (`typescript` or `ts`), to the extensions of the configured language
+    pub fn new(requested: &[String], config: &Config) -> Result<Self> {
+        let mut filter = LanguageFilter::default();
+        for request in requested {
+            let request = request.trim().trim_start_matches('.');
+            if request.is_empty() {
+                continue;
+            }
+            let mut matching: Vec<(&String, &LanguageConfig)> = config
+                .languages
+                .iter()
+                .filter(|(name, lang)| {
+                    name.eq_ignore_ascii_case(request)
+                        || lang
+                            .extensions
+                            .iter()
+                            .any(|ext| ext.eq_ignore_ascii_case(request))
+                })
+                .collect();
+            if matching.is_empty() {
+                bail!(
+                    "Unknown language `{}` in --languages; configured languages: {}",
+                    request,
+                    describe_languages(config)
+                );
+            }
+            matching.sort_by_key(|(name, _)| name.as_str());
+            for (name, lang) in matching {
+                if !filter.names.contains(name) {
+                    filter.names.push(name.clone());
+                }
+                filter
+                    .extensions
+                    .extend(lang.extensions.iter().map(|ext| ext.to_lowercase()));
+            }
+        }
+        if !filter.names.is_empty() {
+            info!("Restricting analysis to {}", filter.names.join(", "));
+        }
+        Ok(filter)
+    }
+
+    /// Ignore files of other languages, keeping the decision of the earlier rules otherwise
+    pub fn decide(&self, file: &RepoFile, decision: FilterDecision) -> FilterDecision {
+        if !decision.included || self.names.is_empty() {
+            return decision;
+        }
+        let selected = file
+            .extension
+            .as_ref()
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()));
+        if selected {
+            decision
+        } else {
+            debug!("Ignoring file outside --languages: {}", file.path.display());
+            FilterDecision::ignore(format!("not in --languages {}", self.names.join(",")))
+        }
+    }
+}
+
+/// Which files are analyzed by whether they are tests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TestSelection {
+    /// Test files are analyzed with the others, and the totals split between them
+    #[default]
+    All,
+
+    /// Test files are left out (--exclude-tests)
+    Exclude,
+
+    /// Only test files are analyzed (--only-tests)
+    Only,
+}
+
+/// Test files recognized by the `tests` configuration, kept or left out by `TestSelection`
+#[derive(Debug, Default)]
+pub struct TestFilter {
+    patterns: Vec<String>,
+    pattern_set: GlobSet,
+    detect_rust_test_modules: bool,
+    selection: TestSelection,
+    repo_root: String,
+}
+
+impl TestFilter {
+    /// Compile the test patterns, matched like `--include` globs against paths relative to
+    /// `repo_root`
+    pub fn new(config: &TestsConfig, selection: TestSelection, repo_root: &str) -> Result<Self> {
+        Ok(TestFilter {
+            pattern_set: compile_globs(&config.patterns, "tests.patterns")?,
+            patterns: config.patterns.clone(),
+            detect_rust_test_modules: config.detect_rust_test_modules,
+            selection,
+            repo_root: repo_root.to_string(),
+        })
+    }
+
+    /// Why the file is a test, or `None` when it isn't one
+    pub fn test_reason(&self, file: &RepoFile) -> Option<String> {
+        let path = relative_path(&file.path.to_string_lossy(), &self.repo_root).replace('\\', "/");
+        if let Some(idx) = first_match(&self.pattern_set, &path) {
+            return Some(format!("matches test pattern `{}`", self.patterns[idx]));
+        }
+        if self.detect_rust_test_modules && file.extension.as_deref() == Some("rs") {
+            if let Some(parent) = declaring_test_module(Path::new(&path), &self.repo_root) {
+                return Some(format!("declared as a #[cfg(test)] module in {}", parent));
+            }
+        }
+        None
+    }
+
+    /// Apply --exclude-tests or --only-tests, keeping the decision of the earlier rules otherwise
+    pub fn decide(&self, file: &RepoFile, decision: FilterDecision) -> FilterDecision {
+        if !decision.included || self.selection == TestSelection::All {
+            return decision;
+        }
+        match (self.selection, self.test_reason(file)) {
+            (TestSelection::Exclude, Some(reason)) => {
+                debug!(
+                    "Ignoring test file by --exclude-tests: {}",
+                    file.path.display()
+                );
+                FilterDecision::ignore(format!("test file ({}), and --exclude-tests", reason))
+            }
+            (TestSelection::Only, None) => {
+                debug!(
+                    "Ignoring non-test file by --only-tests: {}",
+                    file.path.display()
+                );
+                FilterDecision::ignore("not a test file, and --only-tests")
+            }
+            (_, Some(reason)) => {
+                FilterDecision::include(format!("{}, and test file ({})", decision.reason, reason))
+            }
+            (_, None) => decision,
+        }
+    }
+
+    /// Flag a kept file as a test. With --exclude-tests or --only-tests `decide` already sorted
+    /// them out, so the files aren't read again for the Rust test modules.
+    pub fn mark(&self, file: &mut RepoFile) {
+        file.is_test = match self.selection {
+            TestSelection::All => self.test_reason(file).is_some(),
+            TestSelection::Exclude => false,
+            TestSelection::Only => true,
+        };
+    }
+}
+
+/// The module file declaring the Rust file at `path` (relative to `repo_root`) as a
+/// `#[cfg(test)] mod name;`, looked up where `mod name;` resolves from: `name.rs` and
+/// `name/mod.rs` are declared by the `mod.rs`, `lib.rs` or `main.rs` of their directory, or by
+/// the file named after it
+fn declaring_test_module(path: &Path, repo_root: &str) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let (name, directory) = match stem {
+        "mod" => {
+            let directory = path.parent()?;
+            (directory.file_name()?.to_str()?, directory.parent()?)
+        }
+        "lib" | "main" => return None,
+        _ => (stem, path.parent()?),
+    };
+
+    let declaration = Regex::new(&format!(
+        r"#\[cfg\(test\)\]\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+{}\s*;",
+        regex::escape(name)
+    ))
+    .ok()?;
+    let mut candidates: Vec<_> = ["mod.rs", "lib.rs", "main.rs"]
+        .iter()
+        .map(|file| directory.join(file))
+        .collect();
+    if let Some(directory_name) = directory.file_name() {
+        let mut file_name = directory_name.to_os_string();
+        file_name.push(".rs");
+        candidates.push(directory.with_file_name(file_name));
+    }
+
+    candidates.into_iter().find_map(|candidate| {
+        let content = fs::read_to_string(on_disk(repo_root, &candidate)).ok()?;
+        declaration
+            .is_match(&content)
+            .then(|| candidate.to_string_lossy().replace('\\', "/"))
+    })
+}
+
+/// "javascript (js, jsx), rust (rs)", in name order
+fn describe_languages(config: &Config) -> String {
+    let mut names: Vec<&String> = config.languages.keys().collect();
+    if names.is_empty() {
+        return "none".to_string();
+    }
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            format!(
+                "{} ({})",
+                name,
+                config.languages[name].extensions.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Index of the first glob matching the path or one of its directories
+fn first_match(set: &GlobSet, path: &str) -> Option<usize> {
+    Path::new(path)
+        .ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .filter_map(|ancestor| set.matches(ancestor).into_iter().min())
+        .min()
+}
+
+/// How `--max-files` cut down the files left after filtering, which makes the report partial
+#[derive(Debug, Clone, Serialize)]
+pub struct Truncation {
+    /// The `--max-files` limit
+    pub max_files: usize,
+
+    /// Files left after filtering, before the limit was applied
+    pub found: usize,
+
+    /// Files kept and files found per language (or extension, if no language covers it)
+    pub by_language: BTreeMap<String, (usize, usize)>,
+}
+
+impl Truncation {
+    /// Notice shown at the top of the report
+    pub fn notice(&self) -> String {
+        format!(
+            "Partial result: {} files remained after filtering, more than --max-files {}; only the largest files of each language were analyzed, {} in total, in proportion to each language's share",
+            self.found, self.max_files, self.max_files
+        )
+    }
+}
+
+/// Keep at most `max_files` files, choosing the largest of each language so every language
+/// keeps its share of the files. Returns the files unchanged when they fit the limit.
+pub fn limit_files(
+    files: Vec<RepoFile>,
+    max_files: usize,
+    config: &Config,
+    mut trace: Option<&mut PhaseTrace>,
+) -> (Vec<RepoFile>, Option<Truncation>) {
+    let found = files.len();
+    if found <= max_files {
+        if let Some(trace) = trace.as_deref_mut() {
+            for file in &files {
+                trace.emit(&file.path.to_string_lossy());
+            }
+        }
+        return (files, None);
+    }
+
+    let mut by_language: BTreeMap<String, Vec<RepoFile>> = BTreeMap::new();
+    for file in files {
+        by_language
+            .entry(language_of(&file, config))
+            .or_default()
+            .push(file);
+    }
+
+    // Largest remainder: every language gets the floor of its share, and the files left
+    // over go to the languages with the largest fractional parts
+    let mut quotas: BTreeMap<&String, usize> = BTreeMap::new();
+    let mut remainders: Vec<(usize, &String)> = Vec::new();
+    for (language, files) in &by_language {
+        let share = files.len() * max_files;
+        quotas.insert(language, share / found);
+        remainders.push((share % found, language));
+    }
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    let left = max_files - quotas.values().sum::<usize>();
+    for (_, language) in remainders.into_iter().take(left) {
+        *quotas.get_mut(language).unwrap() += 1;
+    }
+    let quotas: BTreeMap<String, usize> = quotas
+        .into_iter()
+        .map(|(language, quota)| (language.clone(), quota))
+        .collect();
+
+    let mut kept = Vec::with_capacity(max_files);
+    let mut counts = BTreeMap::new();
+    for (language, mut files) in by_language {
+        let total = files.len();
+        let quota = quotas[&language];
+        files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        for dropped in files.split_off(quota) {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.exclude(
+                    &dropped.path.to_string_lossy(),
+                    format!("over --max-files {}", max_files),
+                );
+            }
+        }
+        if let Some(trace) = trace.as_deref_mut() {
+            for file in &files {
+                trace.emit(&file.path.to_string_lossy());
+            }
+        }
+        info!(
+            "--max-files: keeping the {} largest of {} {} files",
+            quota, total, language
+        );
+        counts.insert(language, (quota, total));
+        kept.extend(files);
     }
 
+    let truncation = Truncation {
+        max_files,
+        found,
+        by_language: counts,
+    };
+    warn!(
+        "{} files remain after filtering, more than --max-files {}; analyzing the largest files of each language only. Check --repo-path, or raise --max-files",
+        found, max_files
+    );
+
+    (kept, Some(truncation))
+}
+
+/// Name of the configured language covering a file, or its extension if there is none
+fn language_of(file: &RepoFile, config: &Config) -> String {
+    let Some(extension) = &file.extension else {
+        return "(no extension)".to_string();
+    };
+    config
+        .languages
+        .iter()
+        .filter(|(_, lang)| lang.extensions.iter().any(|ext| ext == extension))
+        .map(|(name, _)| name.clone())
+        .min()
+        .unwrap_or_else(|| extension.clone())
+}
+
+/// Outcome of running the filter rules on a single file
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterDecision {
+    /// Whether the file is kept for analysis
+    pub included: bool,
+
+    /// The rule that decided the outcome
+    pub reason: String,
+}
+
+impl FilterDecision {
+    fn include(reason: impl Into<String>) -> Self {
+        FilterDecision {
+            included: true,
+            reason: reason.into(),
+        }
+    }
+
+    fn ignore(reason: impl Into<String>) -> Self {
+        FilterDecision {
+            included: false,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Run the filter rules on a file and report which rule decided its fate.
+///
+/// Rules are checked in a fixed order and the first match wins, the same for every
+/// extension: dot directories, the size limit, `include_patterns`
+/// (global and of the file's languages), global `ignore_patterns` and those of the matching
+/// `overrides`, the rules of the file's own language, and finally the rules of languages with
+/// `ignore_scope: global`. The size limit of the most specific matching override replaces
+/// `max_file_size_kb`.
+pub fn filter_decision(
+    file: &RepoFile,
+    config: &Config,
+    overrides: &PathOverrides,
+    patterns: &ConfigPatterns,
+) -> FilterDecision {
+    let path = &file.path;
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
     // Ignore files in dot directories (like .git)
     if file.in_dot_directory {
         debug!("Ignoring file in dot directory: {}", path.display());
-        return true;
+        return FilterDecision::ignore("inside a dot directory");
     }
 
     // Check file size limit
-    if config.default_settings.max_file_size_kb > 0 {
+    let (max_file_size_kb, source) = overrides
+        .value(path, |o| o.default_settings.max_file_size_kb)
+        .map_or(
+            (config.default_settings.max_file_size_kb, String::new()),
+            |(limit, glob)| (limit, format!(" of override `{}`", glob)),
+        );
+    if max_file_size_kb > 0 {
         let size_kb = file.size / 1024;
-        if size_kb > config.default_settings.max_file_size_kb as u64 {
+        if size_kb > max_file_size_kb as u64 {
             debug!("Ignoring large file ({}KB): {}", size_kb, path.display());
-            return true;
+            return FilterDecision::ignore(format!(
+                "larger than max_file_size_kb{} ({}KB > {}KB)",
+                source, size_kb, max_file_size_kb
+            ));
         }
     }
 
+    // Check language-specific rules, in language name order so the reported rule is stable
+    let mut languages: Vec<(&String, &LanguageConfig)> = config.languages.iter().collect();
+    languages.sort_by_key(|(lang, _)| *lang);
+
+    let matches_language = |lang_config: &LanguageConfig| {
+        file.extension
+            .as_ref()
+            .is_some_and(|ext| lang_config.extensions.iter().any(|e| e == ext))
+    };
+
+    // With include patterns only the files matching them are kept; ignore patterns still apply
+    let file_languages: Vec<&String> = languages
+        .iter()
+        .filter(|(_, c)| matches_language(c))
+        .map(|(lang, _)| *lang)
+        .collect();
+    if let Some(reason) = patterns.include_miss(path, &file_languages) {
+        debug!(
+            "Ignoring file matching no include pattern: {}",
+            path.display()
+        );
+        return FilterDecision::ignore(reason);
+    }
+
     // Check global ignore patterns
-    if matches_any_pattern(path, &config.ignore_patterns) {
+    if let Some(pattern) = patterns.ignore_match(path) {
         debug!("Ignoring file by global pattern: {}", path.display());
-        return true;
+        return FilterDecision::ignore(format!("matches global ignore pattern `{}`", pattern));
+    }
+    if let Some((pattern, glob)) = overrides.ignore_match(path) {
+        debug!("Ignoring file by override pattern: {}", path.display());
+        return FilterDecision::ignore(format!(
+            "matches ignore pattern `{}` of override `{}`",
+            pattern, glob
+        ));
     }
 
-    // Check language-specific rules
-    if let Some(ext) = &file.extension {
-        // Find matching language config
-        for (lang, lang_config) in &config.languages {
-            if lang_config.extensions.iter().any(|e| e == ext) {
-                debug!("File {} matches language: {}", path.display(), lang);
-
-                // Check language-specific ignore files
-                if lang_config.ignore_files.iter().any(|f| file_name == f) {
-                    debug!("Ignoring language-specific file: {}", path.display());
-                    return true;
-                }
+    if file.extension.is_some() {
+        for (lang, lang_config) in languages.iter().filter(|(_, c)| matches_language(c)) {
+            debug!("File {} matches language: {}", path.display(), lang);
 
-                // Check if file is in a language-specific ignored directory
-                for ignore_dir in &lang_config.ignore_directories {
-                    if path.components().any(|c| {
-                        if let std::path::Component::Normal(name) = c {
-                            if let Some(name_str) = name.to_str() {
-                                return name_str == ignore_dir;
-                            }
-                        }
-                        false
-                    }) {
-                        debug!(
-                            "Ignoring file in language-specific directory: {}",
-                            path.display()
-                        );
-                        return true;
-                    }
-                }
+            if let Some(reason) = language_ignore_reason(path, file_name, lang, lang_config) {
+                return FilterDecision::ignore(reason);
             }
         }
     } else if !config.default_settings.include_no_extension {
         // Ignore files with no extension if configured to do so
         debug!("Ignoring file with no extension: {}", path.display());
-        return true;
+        return FilterDecision::ignore("has no extension and include_no_extension is off");
+    }
+
+    // Language rules with global scope also apply to files of other languages
+    for (lang, lang_config) in &languages {
+        if lang_config.ignore_scope == IgnoreScope::Global && !matches_language(lang_config) {
+            if let Some(reason) = language_ignore_reason(path, file_name, lang, lang_config) {
+                return FilterDecision::ignore(format!("{}, applied with global scope", reason));
+            }
+        }
     }
 
     // Don't ignore this file
-    false
+    FilterDecision::include("no filter rule matched")
 }
 
-/// Check if a path matches any of the given patterns
-fn matches_any_pattern(path: &Path, patterns: &[String]) -> bool {
-    // Simplified pattern matching
-    let path_str = path.to_string_lossy().to_string();
+/// Check a file against one language's `ignore_files` and `ignore_directories`
+fn language_ignore_reason(
+    path: &Path,
+    file_name: &str,
+    lang: &str,
+    lang_config: &LanguageConfig,
+) -> Option<String> {
+    // Check language-specific ignore files
+    if lang_config.ignore_files.iter().any(|f| file_name == f) {
+        debug!("Ignoring language-specific file: {}", path.display());
+        return Some(format!("listed in ignore_files for language `{}`", lang));
+    }
 
-    for pattern in patterns {
-        // Simple wildcard matching for now
-        if pattern_matches(&path_str, pattern) {
-            return true;
+    // Check if file is in a language-specific ignored directory
+    for ignore_dir in &lang_config.ignore_directories {
+        if path.components().any(|c| {
+            if let std::path::Component::Normal(name) = c {
+                if let Some(name_str) = name.to_str() {
+                    return name_str == ignore_dir;
+                }
+            }
+            false
+        }) {
+            debug!(
+                "Ignoring file in language-specific directory: {}",
+                path.display()
+            );
+            return Some(format!(
+                "inside `{}`, an ignored directory for language `{}`",
+                ignore_dir, lang
+            ));
         }
     }
 
-    false
+    None
 }
 
-/// Simple pattern matching implementation
-fn pattern_matches(path: &str, pattern: &str) -> bool {
-    // Very basic wildcard matching
-    if pattern == "*" {
-        return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PathOverride;
+    use std::path::PathBuf;
+
+    /// A traversed file at `path` of `size` bytes
+    fn file(path: &str, size: u64) -> RepoFile {
+        RepoFile {
+            path: PathBuf::from(path),
+            extension: Path::new(path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase()),
+            size,
+            in_dot_directory: false,
+            root: 0,
+            is_test: false,
+        }
     }
 
-    if pattern.starts_with("*") && pattern.ends_with("*") {
-        let inner = &pattern[1..pattern.len() - 1];
-        return path.contains(inner);
+    /// `count` files with extension `extension`, the first the largest
+    fn files(extension: &str, count: usize) -> Vec<RepoFile> {
+        (0..count)
+            .map(|i| {
+                file(
+                    &format!("src/{}{}.{}", extension, i, extension),
+                    1000 - i as u64,
+                )
+            })
+            .collect()
     }
 
-    if pattern.starts_with("*") {
-        let suffix = &pattern[1..];
-        return path.ends_with(suffix);
+    fn kept_per_language(kept: &[RepoFile], config: &Config) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for file in kept {
+            *counts.entry(language_of(file, config)).or_insert(0) += 1;
+        }
+        counts
     }
 
-    if pattern.ends_with("*") {
-        let prefix = &pattern[..pattern.len() - 1];
-        return path.starts_with(prefix);
+    #[test]
+    fn max_files_keeps_each_language_share() {
+        let config = Config::default();
+        let mut all = files("rs", 60);
+        all.extend(files("ts", 30));
+        all.extend(files("py", 10));
+
+        let (kept, truncation) = limit_files(all, 20, &config, None);
+        let truncation = truncation.unwrap();
+
+        assert_eq!(kept.len(), 20);
+        assert_eq!((truncation.found, truncation.max_files), (100, 20));
+        assert_eq!(
+            truncation.by_language,
+            BTreeMap::from([
+                ("python".to_string(), (2, 10)),
+                ("rust".to_string(), (12, 60)),
+                ("typescript".to_string(), (6, 30)),
+            ])
+        );
+        // Every language keeps the percentage of the files it had before
+        for (language, kept) in kept_per_language(&kept, &config) {
+            let (_, found) = truncation.by_language[&language];
+            assert_eq!(kept * 100 / 20, found * 100 / 100, "{}", language);
+        }
     }
 
-    // Exact match
-    path == pattern
+    #[test]
+    fn max_files_keeps_the_largest_files_of_each_language() {
+        let config = Config::default();
+        let mut all = files("rs", 6);
+        all.extend(files("ts", 4));
+
+        let (kept, _) = limit_files(all, 5, &config, None);
+        let mut paths: Vec<String> = kept
+            .iter()
+            .map(|file| file.path.to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            [
+                "src/rs0.rs",
+                "src/rs1.rs",
+                "src/rs2.rs",
+                "src/ts0.ts",
+                "src/ts1.ts"
+            ]
+        );
+    }
+
+    #[test]
+    fn max_files_gives_leftover_files_to_the_largest_remainders() {
+        // Shares 3.5, 1 and 0.5: the leftover file goes to the tied remainder first by name
+        let config = Config::default();
+        let mut all = files("rs", 7);
+        all.extend(files("ts", 2));
+        all.extend(files("py", 1));
+
+        let (kept, truncation) = limit_files(all, 5, &config, None);
+
+        assert_eq!(kept.len(), 5);
+        assert_eq!(
+            truncation.unwrap().by_language,
+            BTreeMap::from([
+                ("python".to_string(), (1, 1)),
+                ("rust".to_string(), (3, 7)),
+                ("typescript".to_string(), (1, 2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn max_files_within_the_limit_keeps_everything() {
+        let config = Config::default();
+
+        let (kept, truncation) = limit_files(files("rs", 3), 3, &config, None);
+        assert_eq!(kept.len(), 3);
+        assert!(truncation.is_none());
+
+        // No files found: nothing to divide among the languages
+        let (kept, truncation) = limit_files(Vec::new(), 0, &config, None);
+        assert!(kept.is_empty());
+        assert!(truncation.is_none());
+    }
+
+    #[test]
+    fn max_files_of_zero_keeps_nothing() {
+        let config = Config::default();
+        let mut all = files("rs", 3);
+        all.push(file("README", 10));
+
+        let (kept, truncation) = limit_files(all, 0, &config, None);
+
+        assert!(kept.is_empty());
+        assert_eq!(
+            truncation.unwrap().by_language,
+            BTreeMap::from([
+                ("(no extension)".to_string(), (0, 1)),
+                ("rust".to_string(), (0, 3)),
+            ])
+        );
+    }
+
+    /// The default configuration with `patterns` as its `ignore_patterns`
+    fn ignoring(patterns: &[&str]) -> Config {
+        Config {
+            ignore_patterns: strings(patterns),
+            ..Config::default()
+        }
+    }
+
+    /// The global ignore pattern of `config` matching a repository-relative path
+    fn ignored_by(config: &Config, path: &str) -> Option<String> {
+        let patterns = ConfigPatterns::new(config, "").unwrap();
+        patterns.ignore_match(Path::new(path)).map(String::from)
+    }
+
+    #[test]
+    fn default_ignore_patterns_match_their_files_only() {
+        let config = Config::default();
+        assert_eq!(
+            config.ignore_patterns,
+            ["*.min.*", "*.map", "*.lock", ".gitignore", ".git/*"],
+            "a default pattern was changed; update the cases below"
+        );
+
+        // (path, pattern expected to ignore it)
+        let cases = [
+            // `*` doesn't cross a directory, but a pattern without `/` matches at any depth
+            ("app.min.js", Some("*.min.*")),
+            ("web/static/app.min.css", Some("*.min.*")),
+            ("web/admin.js", None),
+            ("web/minimal.ts", None),
+            ("app.js.map", Some("*.map")),
+            ("web/dist/app.js.map", Some("*.map")),
+            ("src/map.rs", None),
+            ("src/mapper.rs", None),
+            ("Cargo.lock", Some("*.lock")),
+            ("web/yarn.lock", Some("*.lock")),
+            ("src/lock.rs", None),
+            // A plain name matches the file of that name in any directory, and nothing else
+            (".gitignore", Some(".gitignore")),
+            ("web/.gitignore", Some(".gitignore")),
+            ("web/.gitignore.bak", None),
+            ("docs/gitignore.md", None),
+            // A pattern with `/` is anchored at the root, and covers files below what it matches
+            (".git/config", Some(".git/*")),
+            (".git/refs/heads/main", Some(".git/*")),
+            ("vendor/.git/config", None),
+            (".github/workflows/ci.yml", None),
+            ("src/main.rs", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(ignored_by(&config, path).as_deref(), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn ignore_patterns_support_glob_syntax_and_plain_names() {
+        let config = ignoring(&[
+            "src/**/generated/*.ts",
+            "*.{min.js,map}",
+            "docs/**",
+            "[Tt]emp_*.rs",
+            "fixture_?.json",
+            "vendor",
+            "lib/legacy.js",
+            "./scripts/setup.sh",
+        ]);
+
+        // (path, pattern expected to ignore it)
+        let cases = [
+            // `**` spans any number of directories, none included
+            ("src/generated/api.ts", Some("src/**/generated/*.ts")),
+            ("src/a/b/generated/api.ts", Some("src/**/generated/*.ts")),
+            ("src/generated/nested/api.ts", None),
+            ("src/generated/api.rs", None),
+            ("lib/src/generated/api.ts", None),
+            ("docs/guide.md", Some("docs/**")),
+            ("docs/api/v1/index.md", Some("docs/**")),
+            ("src/docs/guide.md", None),
+            // `{a,b}` alternation
+            ("web/app.min.js", Some("*.{min.js,map}")),
+            ("web/app.js.map", Some("*.{min.js,map}")),
+            ("web/app.min.css", None),
+            // Character classes and `?`
+            ("src/temp_data.rs", Some("[Tt]emp_*.rs")),
+            ("src/Temp_data.rs", Some("[Tt]emp_*.rs")),
+            ("src/TEMP_data.rs", None),
+            ("tests/fixture_a.json", Some("fixture_?.json")),
+            ("tests/fixture_ab.json", None),
+            // A pattern without metacharacters is an exact name or a path suffix
+            ("vendor", Some("vendor")),
+            ("vendor/lib.js", Some("vendor")),
+            ("web/vendor/lib.js", Some("vendor")),
+            ("vendored/lib.js", None),
+            ("web/my_vendor/lib.js", None),
+            ("lib/legacy.js", Some("lib/legacy.js")),
+            ("web/lib/legacy.js", Some("lib/legacy.js")),
+            ("web/mylib/legacy.js", None),
+            ("lib/legacy.jsx", None),
+            ("scripts/setup.sh", Some("./scripts/setup.sh")),
+            ("tools/scripts/setup.sh", Some("./scripts/setup.sh")),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(ignored_by(&config, path).as_deref(), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn ignore_patterns_report_the_first_matching_pattern() {
+        let config = ignoring(&["*.js", "*.min.*"]);
+        assert_eq!(ignored_by(&config, "app.min.js").as_deref(), Some("*.js"));
+
+        let config = ignoring(&["*.min.*", "*.js"]);
+        assert_eq!(
+            ignored_by(&config, "app.min.js").as_deref(),
+            Some("*.min.*")
+        );
+    }
+
+    #[test]
+    fn invalid_ignore_pattern_names_the_setting() {
+        let err = ConfigPatterns::new(&ignoring(&["src/["]), "").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid ignore_patterns glob `src/[`"),
+            "{:?}",
+            err
+        );
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    /// Decision of the configured rules and the `--include`/`--exclude` globs on a small file
+    fn glob_decision(
+        config: &Config,
+        include: &[&str],
+        exclude: &[&str],
+        path: &str,
+    ) -> FilterDecision {
+        let patterns = ConfigPatterns::new(config, "").unwrap();
+        let globs = PathGlobs::new(&strings(include), &strings(exclude), "").unwrap();
+        let file = file(path, 100);
+        let decision = filter_decision(&file, config, &PathOverrides::default(), &patterns);
+        globs.decide(&file, decision)
+    }
+
+    #[test]
+    fn include_and_exclude_globs_match_relative_paths() {
+        let config = Config::default();
+
+        // (include, exclude, path, included)
+        let cases: &[(&[&str], &[&str], &str, bool)] = &[
+            // `**` spans directories, `*` stays within one
+            (&["src/**"], &[], "src/lib.rs", true),
+            (&["src/**"], &[], "src/a/b/lib.rs", true),
+            (&["src/**"], &[], "tests/lib.rs", false),
+            (&["src/*.rs"], &[], "src/lib.rs", true),
+            (&["src/*.rs"], &[], "src/a/lib.rs", false),
+            (&[], &["**/generated/**"], "src/generated/api.ts", false),
+            (&[], &["**/generated/**"], "generated/api.ts", false),
+            (&[], &["**/generated/**"], "src/generator.ts", true),
+            // A glob matching a directory covers the files below it
+            (&["src"], &[], "src/a/lib.rs", true),
+            (&[], &["web/legacy"], "web/legacy/app.ts", false),
+            // Extension globs without `/` match at any depth
+            (&["*.rs"], &[], "lib.rs", true),
+            (&["*.rs"], &[], "src/a/lib.rs", true),
+            (&["*.rs"], &[], "web/app.ts", false),
+            (&["*.{ts,tsx}"], &[], "web/view.tsx", true),
+            (&["*.{ts,tsx}"], &[], "web/view.js", false),
+            (&[], &["*.test.ts"], "web/app.test.ts", false),
+            (&[], &["*.test.ts"], "web/app.ts", true),
+            // With several includes matching any one keeps the file; an exclude always wins
+            (&["src/**", "*.ts"], &[], "web/app.ts", true),
+            (&["src/**"], &["*_test.rs"], "src/parse_test.rs", false),
+            (&["*.rs"], &["src/**"], "src/lib.rs", false),
+        ];
+        for (include, exclude, path, included) in cases {
+            let decision = glob_decision(&config, include, exclude, path);
+            assert_eq!(
+                decision.included, *included,
+                "{:?} {:?} {}: {}",
+                include, exclude, path, decision.reason
+            );
+        }
+    }
+
+    #[test]
+    fn include_globs_do_not_bring_back_ignored_files() {
+        let config = ignoring(&["*.min.*", "src/**/generated/*.ts"]);
+
+        let decision = glob_decision(&config, &["web/**"], &[], "web/app.min.js");
+        assert!(!decision.included);
+        assert_eq!(decision.reason, "matches global ignore pattern `*.min.*`");
+
+        let decision = glob_decision(&config, &["*.ts"], &[], "src/generated/api.ts");
+        assert_eq!(
+            decision.reason,
+            "matches global ignore pattern `src/**/generated/*.ts`"
+        );
+
+        // Files the patterns keep are matched against the globs
+        let decision = glob_decision(&config, &["web/**"], &[], "web/app.js");
+        assert!(decision.included);
+        assert_eq!(
+            decision.reason,
+            "no filter rule matched, and matches --include `web/**`"
+        );
+        let decision = glob_decision(&config, &[], &["web/**"], "web/app.js");
+        assert_eq!(decision.reason, "matches --exclude `web/**`");
+    }
+
+    #[test]
+    fn include_patterns_keep_only_matching_files_before_ignore_patterns() {
+        let config = Config {
+            include_patterns: strings(&["src/**", "*.md"]),
+            ..ignoring(&["*.min.*", "src/**/generated/**"])
+        };
+
+        // (path, reason)
+        let cases = [
+            ("src/lib.rs", "no filter rule matched"),
+            ("src/a/b/lib.rs", "no filter rule matched"),
+            ("docs/guide.md", "no filter rule matched"),
+            ("web/app.ts", "matches no include pattern"),
+            // Ignore patterns still apply to the included files
+            ("src/app.min.js", "matches global ignore pattern `*.min.*`"),
+            (
+                "src/generated/api.rs",
+                "matches global ignore pattern `src/**/generated/**`",
+            ),
+        ];
+        for (path, reason) in cases {
+            assert_eq!(
+                glob_decision(&config, &[], &[], path).reason,
+                reason,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_include_glob_names_the_flag() {
+        let err = PathGlobs::new(&strings(&["src/{a"]), &[], "").unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid --include glob `src/{a`"),
+            "{:?}",
+            err
+        );
+    }
+
+    /// Decision of the configured rules alone on a file of `size` bytes
+    fn configured_decision(config: &Config, path: &str, size: u64) -> FilterDecision {
+        let patterns = ConfigPatterns::new(config, "").unwrap();
+        filter_decision(
+            &file(path, size),
+            config,
+            &PathOverrides::default(),
+            &patterns,
+        )
+    }
+
+    #[test]
+    fn minified_bundles_are_ignored_by_default() {
+        let config = Config::default();
+
+        for path in ["web/app.min.js", "src/app.min.js", "web/app.min.css"] {
+            let decision = configured_decision(&config, path, 100);
+            assert!(!decision.included, "{}", path);
+            assert_eq!(decision.reason, "matches global ignore pattern `*.min.*`");
+        }
+        assert!(configured_decision(&config, "web/app.js", 100).included);
+    }
+
+    #[test]
+    fn size_limit_applies_to_every_language() {
+        let config = Config::default();
+        let limit = config.default_settings.max_file_size_kb as u64;
+
+        for path in [
+            "web/bundle.ts",
+            "src/generated.py",
+            "src/schema.rs",
+            "web/vendor.js",
+        ] {
+            let decision = configured_decision(&config, path, (limit + 1) * 1024);
+            assert!(!decision.included, "{}", path);
+            assert_eq!(
+                decision.reason,
+                format!(
+                    "larger than max_file_size_kb ({}KB > {}KB)",
+                    limit + 1,
+                    limit
+                ),
+                "{}",
+                path
+            );
+
+            // At the limit the file is kept
+            assert!(
+                configured_decision(&config, path, limit * 1024).included,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn dot_directory_and_ignore_rules_apply_to_source_files() {
+        let config = ignoring(&["src/legacy/**"]);
+
+        let mut hidden = file("src/.cache/lib.rs", 100);
+        hidden.in_dot_directory = true;
+        let patterns = ConfigPatterns::new(&config, "").unwrap();
+        let hidden = filter_decision(&hidden, &config, &PathOverrides::default(), &patterns);
+        assert_eq!(hidden.reason, "inside a dot directory");
+
+        for path in [
+            "src/legacy/lib.rs",
+            "src/legacy/app.py",
+            "src/legacy/app.ts",
+        ] {
+            assert_eq!(
+                configured_decision(&config, path, 100).reason,
+                "matches global ignore pattern `src/legacy/**`",
+                "{}",
+                path
+            );
+        }
+    }
+
+    /// Overrides of `services/legacy/**`, raising the size limit and adding an ignore pattern,
+    /// and of `src/core/**`, lowering the size limit
+    fn legacy_and_core_overrides() -> PathOverrides {
+        let mut legacy = PathOverride {
+            path: "services/legacy/**".to_string(),
+            ignore_patterns: strings(&["*.generated.ts"]),
+            ..PathOverride::default()
+        };
+        legacy.default_settings.max_file_size_kb = Some(4096);
+        let mut core = PathOverride {
+            path: "src/core/**".to_string(),
+            ..PathOverride::default()
+        };
+        core.default_settings.max_file_size_kb = Some(64);
+        PathOverrides::new(&[legacy, core], "").unwrap()
+    }
+
+    /// Decision of the configured rules and `overrides` on a file of `size_kb` KB
+    fn overridden_decision(overrides: &PathOverrides, path: &str, size_kb: u64) -> FilterDecision {
+        let config = Config::default();
+        let patterns = ConfigPatterns::new(&config, "").unwrap();
+        filter_decision(&file(path, size_kb * 1024), &config, overrides, &patterns)
+    }
+
+    #[test]
+    fn overrides_relax_and_tighten_the_size_limit() {
+        let overrides = legacy_and_core_overrides();
+        let limit = Config::default().default_settings.max_file_size_kb as u64;
+        assert_eq!(limit, 1024);
+
+        // Relaxed: over the base limit but within that of the override
+        assert!(overridden_decision(&overrides, "services/legacy/billing.ts", 2048).included);
+        assert_eq!(
+            overridden_decision(&overrides, "services/legacy/billing.ts", 5000).reason,
+            "larger than max_file_size_kb of override `services/legacy/**` (5000KB > 4096KB)"
+        );
+
+        // Tightened: within the base limit but over that of the override
+        assert_eq!(
+            overridden_decision(&overrides, "src/core/graph.rs", 100).reason,
+            "larger than max_file_size_kb of override `src/core/**` (100KB > 64KB)"
+        );
+        assert!(overridden_decision(&overrides, "src/core/graph.rs", 64).included);
+
+        // Elsewhere the base limit applies
+        assert_eq!(
+            overridden_decision(&overrides, "src/util.rs", 2048).reason,
+            "larger than max_file_size_kb (2048KB > 1024KB)"
+        );
+        assert!(overridden_decision(&overrides, "src/util.rs", 100).included);
+    }
+
+    #[test]
+    fn override_ignore_patterns_apply_below_their_path_only() {
+        let overrides = legacy_and_core_overrides();
+
+        assert_eq!(
+            overridden_decision(&overrides, "services/legacy/api.generated.ts", 1).reason,
+            "matches ignore pattern `*.generated.ts` of override `services/legacy/**`"
+        );
+        assert!(overridden_decision(&overrides, "services/legacy/api.ts", 1).included);
+        assert!(overridden_decision(&overrides, "services/api/api.generated.ts", 1).included);
+
+        // The global patterns still apply inside the override's path
+        assert_eq!(
+            overridden_decision(&overrides, "services/legacy/app.min.js", 1).reason,
+            "matches global ignore pattern `*.min.*`"
+        );
+    }
+
+    /// Configuration loaded from a YAML file holding `yaml`
+    fn load(yaml: &str) -> Config {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overdoc.yaml");
+        fs::write(&path, yaml).unwrap();
+        crate::config::load_config(path.to_str().unwrap())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn global_scope_applies_language_directories_to_every_file() {
+        let config = load(
+            "languages:\n  typescript:\n    extensions: [ts, tsx]\n    ignore_directories: [node_modules, __snapshots__]\n    ignore_scope: global\n",
+        );
+
+        assert_eq!(
+            configured_decision(&config, "web/__snapshots__/a.ts.snap", 100).reason,
+            "inside `__snapshots__`, an ignored directory for language `typescript`, applied with global scope"
+        );
+        assert_eq!(
+            configured_decision(&config, "web/__snapshots__/a.ts", 100).reason,
+            "inside `__snapshots__`, an ignored directory for language `typescript`"
+        );
+        assert!(configured_decision(&config, "web/app.snap", 100).included);
+    }
+
+    #[test]
+    fn language_scope_keeps_other_files_in_language_directories() {
+        let config = load(
+            "languages:\n  typescript:\n    extensions: [ts, tsx]\n    ignore_directories: [node_modules, __snapshots__]\n",
+        );
+        let snapshot = "web/__snapshots__/a.ts.snap";
+
+        let decision = configured_decision(&config, snapshot, 100);
+        assert!(decision.included, "{}", decision.reason);
+        assert_eq!(language_of(&file(snapshot, 100), &config), "snap");
+        assert!(!configured_decision(&config, "web/__snapshots__/a.ts", 100).included);
+    }
 }
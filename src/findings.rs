@@ -0,0 +1,506 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::config::FindingsConfig;
+use crate::import_stats::ImportStatement;
+use crate::metrics::RepositoryMetrics;
+
+/// Rule id for files whose complexity is concentrated in a single function
+pub const CONCENTRATED_COMPLEXITY: &str = "concentrated-complexity";
+
+/// Rule id for files whose cyclomatic complexity exceeds `findings.max_cyclomatic`
+pub const HIGH_CYCLOMATIC_COMPLEXITY: &str = "high-cyclomatic-complexity";
+
+/// Rule id for files whose cognitive complexity exceeds `findings.max_cognitive`
+pub const HIGH_COGNITIVE_COMPLEXITY: &str = "high-cognitive-complexity";
+
+/// Rule id for files whose maintainability index is below `findings.min_maintainability`
+pub const LOW_MAINTAINABILITY: &str = "low-maintainability";
+
+/// Rule id for relative imports climbing more than `findings.max_relative_import_depth` directories
+pub const DEEP_RELATIVE_IMPORT: &str = "deep-relative-import";
+
+/// Every rule with a short description, for formats that list the rules they report on
+pub const RULES: &[(&str, &str)] = &[
+    (
+        CONCENTRATED_COMPLEXITY,
+        "A single function holds most of the file's cyclomatic complexity",
+    ),
+    (
+        HIGH_CYCLOMATIC_COMPLEXITY,
+        "The file's cyclomatic complexity exceeds the configured maximum",
+    ),
+    (
+        HIGH_COGNITIVE_COMPLEXITY,
+        "The file's cognitive complexity exceeds the configured maximum",
+    ),
+    (
+        LOW_MAINTAINABILITY,
+        "The file's maintainability index is below the configured minimum",
+    ),
+    (
+        DEEP_RELATIVE_IMPORT,
+        "A relative import climbs more directories than the configured maximum",
+    ),
+];
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Analysis phase that produced a finding, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Imports,
+    Metrics,
+}
+
+/// A problem detected in a file during analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Identifier of the rule that produced the finding
+    pub rule_id: String,
+
+    pub phase: Phase,
+
+    pub severity: Severity,
+
+    /// Path of the file the finding refers to
+    pub path: String,
+
+    /// Line the finding points at, if it refers to a specific location
+    pub line: Option<usize>,
+
+    /// Human readable description
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "{}:{}: {} [{}] {}",
+                self.path, line, self.severity, self.rule_id, self.message
+            ),
+            None => write!(
+                f,
+                "{}: {} [{}] {}",
+                self.path, self.severity, self.rule_id, self.message
+            ),
+        }
+    }
+}
+
+impl Finding {
+    /// Key for the canonical ordering: phase, then path, then line, then rule id.
+    /// The message only breaks ties so that the order never depends on insertion order.
+    fn sort_key(&self) -> (Phase, &str, Option<usize>, &str, &str) {
+        (
+            self.phase,
+            &self.path,
+            self.line,
+            &self.rule_id,
+            &self.message,
+        )
+    }
+}
+
+/// Sort findings into their canonical order so reports and baselines are stable
+/// regardless of the order in which rules (or threads) produced them
+pub fn sort_findings(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+}
+
+/// Sort warnings raised about files, as `(path, message)` pairs, by path so they are logged
+/// in the same order whichever thread raised them first
+pub fn sort_warnings(warnings: &mut [(String, String)]) {
+    warnings.sort();
+}
+
+/// Run all finding rules against the repository metrics
+pub fn collect_findings(metrics: &RepositoryMetrics, config: &FindingsConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    findings.extend(concentrated_complexity(metrics, config));
+    findings.extend(complexity_thresholds(metrics, config));
+
+    sort_findings(&mut findings);
+    findings
+}
+
+/// Flag relative imports climbing too many directories, which a path alias would shorten
+pub fn import_findings(statements: &[ImportStatement], config: &FindingsConfig) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = statements
+        .iter()
+        .filter(|statement| statement.relative_depth > config.max_relative_import_depth)
+        .map(|statement| Finding {
+            rule_id: DEEP_RELATIVE_IMPORT.to_string(),
+            phase: Phase::Imports,
+            severity: Severity::Warning,
+            path: statement.path.clone(),
+            line: Some(statement.line),
+            message: format!(
+                "Relative import of `{}` climbs {} directories (maximum {})",
+                statement.module.as_deref().unwrap_or_default(),
+                statement.relative_depth,
+                config.max_relative_import_depth
+            ),
+        })
+        .collect();
+
+    sort_findings(&mut findings);
+    findings
+}
+
+/// Flag files where one function holds most of the cyclomatic complexity, which
+/// file-level averages would otherwise hide
+fn concentrated_complexity(metrics: &RepositoryMetrics, config: &FindingsConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (path, file_metrics) in &metrics.file_metrics {
+        // A single function trivially holds all of the complexity
+        if file_metrics.functions.len() < 2 {
+            continue;
+        }
+
+        if let Some((function, share)) = file_metrics.complexity_concentration() {
+            if share >= config.concentration_threshold
+                && function.cyclomatic_complexity >= config.concentration_min_cyclomatic
+            {
+                findings.push(Finding {
+                    rule_id: CONCENTRATED_COMPLEXITY.to_string(),
+                    phase: Phase::Metrics,
+                    severity: Severity::Warning,
+                    path: path.clone(),
+                    line: Some(function.start_line),
+                    message: format!(
+                        "Function `{}` holds {:.0}% of the file's cyclomatic complexity ({:.0} across {} functions)",
+                        function.name,
+                        share * 100.0,
+                        function.cyclomatic_complexity,
+                        file_metrics.functions.len()
+                    ),
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+/// Flag files whose file-level complexity metrics cross the configured thresholds
+fn complexity_thresholds(metrics: &RepositoryMetrics, config: &FindingsConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (path, file_metrics) in &metrics.file_metrics {
+        let Some(complexity) = &file_metrics.complexity_metrics else {
+            continue;
+        };
+
+        let mut report = |rule_id: &str, message: String| {
+            findings.push(Finding {
+                rule_id: rule_id.to_string(),
+                phase: Phase::Metrics,
+                severity: Severity::Warning,
+                path: path.clone(),
+                line: None,
+                message,
+            });
+        };
+
+        if complexity.cyclomatic_complexity > config.max_cyclomatic {
+            report(
+                HIGH_CYCLOMATIC_COMPLEXITY,
+                format!(
+                    "Cyclomatic complexity is {:.0} (maximum {:.0})",
+                    complexity.cyclomatic_complexity, config.max_cyclomatic
+                ),
+            );
+        }
+
+        if complexity.cognitive_complexity > config.max_cognitive {
+            report(
+                HIGH_COGNITIVE_COMPLEXITY,
+                format!(
+                    "Cognitive complexity is {:.1} (maximum {:.0})",
+                    complexity.cognitive_complexity, config.max_cognitive
+                ),
+            );
+        }
+
+        // A partial analysis may stop before the maintainability index is computed
+        if !complexity.partial && complexity.maintainability_index < config.min_maintainability {
+            report(
+                LOW_MAINTAINABILITY,
+                format!(
+                    "Maintainability index is {:.1} (minimum {:.0})",
+                    complexity.maintainability_index, config.min_maintainability
+                ),
+            );
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ScoringConfig;
+    use crate::filter::PathOverrides;
+    use crate::metrics::{analyze_repository, AnalysisOptions};
+    use std::fs;
+    use std::sync::Mutex;
+    use std::thread;
+
+    /// Deterministic Fisher-Yates shuffle driven by a xorshift generator
+    fn shuffle<T>(items: &mut [T], mut seed: u64) {
+        for idx in (1..items.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            items.swap(idx, (seed % (idx as u64 + 1)) as usize);
+        }
+    }
+
+    /// Push `items` from four threads at once, in the order the threads happen to run
+    fn gather_from_threads<T: Clone + Send + Sync>(items: &[T]) -> Vec<T> {
+        let gathered = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for chunk in items.chunks(items.len().div_ceil(4)) {
+                let gathered = &gathered;
+                scope.spawn(move || {
+                    for item in chunk {
+                        gathered.lock().unwrap().push(item.clone());
+                    }
+                });
+            }
+        });
+        gathered.into_inner().unwrap()
+    }
+
+    fn finding(phase: Phase, path: &str, line: Option<usize>, rule_id: &str) -> Finding {
+        Finding {
+            rule_id: rule_id.to_string(),
+            phase,
+            severity: Severity::Warning,
+            path: path.to_string(),
+            line,
+            message: format!("{} in {}", rule_id, path),
+        }
+    }
+
+    #[test]
+    fn sorts_findings_from_threads_into_one_order() {
+        let findings = vec![
+            finding(Phase::Imports, "src/b.ts", Some(3), DEEP_RELATIVE_IMPORT),
+            finding(Phase::Imports, "src/a.ts", Some(9), DEEP_RELATIVE_IMPORT),
+            finding(Phase::Imports, "src/a.ts", Some(2), DEEP_RELATIVE_IMPORT),
+            finding(Phase::Metrics, "src/a.ts", None, LOW_MAINTAINABILITY),
+            finding(Phase::Metrics, "src/a.ts", None, HIGH_COGNITIVE_COMPLEXITY),
+            finding(Phase::Metrics, "src/a.ts", Some(4), CONCENTRATED_COMPLEXITY),
+            finding(Phase::Metrics, "lib/c.rs", None, HIGH_CYCLOMATIC_COMPLEXITY),
+            finding(Phase::Metrics, "lib/c.rs", Some(12), CONCENTRATED_COMPLEXITY),
+        ];
+
+        let mut expected = findings.clone();
+        sort_findings(&mut expected);
+        let expected = serde_json::to_string(&expected).unwrap();
+
+        for seed in 1..=20 {
+            let mut shuffled = findings.clone();
+            shuffle(&mut shuffled, seed);
+            let mut gathered = gather_from_threads(&shuffled);
+            sort_findings(&mut gathered);
+            assert_eq!(serde_json::to_string(&gathered).unwrap(), expected, "seed {}", seed);
+        }
+
+        let mut sorted = findings;
+        sort_findings(&mut sorted);
+        let order: Vec<(Phase, &str, Option<usize>)> = sorted
+            .iter()
+            .map(|finding| (finding.phase, finding.path.as_str(), finding.line))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (Phase::Imports, "src/a.ts", Some(2)),
+                (Phase::Imports, "src/a.ts", Some(9)),
+                (Phase::Imports, "src/b.ts", Some(3)),
+                (Phase::Metrics, "lib/c.rs", None),
+                (Phase::Metrics, "lib/c.rs", Some(12)),
+                (Phase::Metrics, "src/a.ts", None),
+                (Phase::Metrics, "src/a.ts", None),
+                (Phase::Metrics, "src/a.ts", Some(4)),
+            ]
+        );
+        assert_eq!(sorted[5].rule_id, HIGH_COGNITIVE_COMPLEXITY);
+    }
+
+    #[test]
+    fn sorts_warnings_from_threads_by_path() {
+        let warnings: Vec<(String, String)> = (0..16)
+            .map(|idx| (format!("src/file{:02}.rs", idx % 8), format!("warning {}", idx)))
+            .collect();
+
+        let mut expected = warnings.clone();
+        sort_warnings(&mut expected);
+        assert!(expected.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        for seed in 1..=20 {
+            let mut shuffled = warnings.clone();
+            shuffle(&mut shuffled, seed);
+            let mut gathered = gather_from_threads(&shuffled);
+            sort_warnings(&mut gathered);
+            assert_eq!(gathered, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn findings_do_not_depend_on_file_order_or_threads() {
+        let repo = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for idx in 0..12 {
+            let path = format!("src/module{}.rs", idx);
+            let branches: String = (0..=idx)
+                .map(|branch| format!("    if x == {} {{ return {}; }}\n", branch, branch))
+                .collect();
+            fs::create_dir_all(repo.path().join("src")).unwrap();
+            fs::write(
+                repo.path().join(&path),
+                format!("pub fn small() -> u32 {{ 1 }}\n\npub fn large(x: u32) -> u32 {{\n{}    0\n}}\n", branches),
+            )
+            .unwrap();
+            paths.push(path);
+        }
+
+        let config = FindingsConfig {
+            concentration_threshold: 0.5,
+            concentration_min_cyclomatic: 1.0,
+            max_cyclomatic: 1.0,
+            max_cognitive: 1.0,
+            min_maintainability: 100.0,
+            ..FindingsConfig::default()
+        };
+        let scoring = ScoringConfig::default();
+        let overrides = PathOverrides::new(&[], &repo.path().to_string_lossy()).unwrap();
+        let options = AnalysisOptions {
+            retain_files: true,
+            complexity_budget: None,
+            deep_gate: None,
+            time_files: false,
+            overrides: &overrides,
+            scoring: &scoring,
+        };
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let run = |paths: &[String]| {
+            let metrics = pool
+                .install(|| {
+                    analyze_repository(paths, &repo.path().to_string_lossy(), &options, None, None, |_, _| Ok(()))
+                })
+                .unwrap();
+            serde_json::to_string(&collect_findings(&metrics, &config)).unwrap()
+        };
+
+        let expected = run(&paths);
+        assert!(expected.contains(CONCENTRATED_COMPLEXITY), "{}", expected);
+        for seed in 1..=5 {
+            let mut shuffled = paths.clone();
+            shuffle(&mut shuffled, seed);
+            assert_eq!(run(&shuffled), expected, "seed {}", seed);
+        }
+    }
+
+    /// Rust function `name` with one `if` per branch
+    fn function(name: &str, branches: usize) -> String {
+        let body: String = (0..branches)
+            .map(|branch| {
+                format!("    if x == {} {{\n        return {};\n    }}\n", branch, branch)
+            })
+            .collect();
+        format!("pub fn {}(x: u32) -> u32 {{\n{}    0\n}}\n\n", name, body)
+    }
+
+    /// Concentration findings of a single file with `source`
+    fn concentration_findings(source: &str, config: &FindingsConfig) -> Vec<Finding> {
+        let path = "src/crafted.rs";
+        let (file_metrics, _) = crate::metrics::analyze_content(
+            path,
+            source,
+            "rs",
+            None,
+            false,
+            None,
+            &ScoringConfig::default(),
+        );
+        let mut metrics = RepositoryMetrics::from_complexities(&[(path, 1.0, 100.0)]);
+        metrics.file_metrics.insert(path.to_string(), file_metrics);
+        concentrated_complexity(&metrics, config)
+    }
+
+    #[test]
+    fn dominant_function_is_flagged_by_name() {
+        let source = [
+            function("format", 0),
+            function("parse", 20),
+            function("render", 1),
+        ]
+        .concat();
+        let parse_line = source.lines().position(|line| line.contains("fn parse")).unwrap() + 1;
+
+        let findings = concentration_findings(&source, &FindingsConfig::default());
+
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        let finding = &findings[0];
+        assert_eq!(finding.rule_id, CONCENTRATED_COMPLEXITY);
+        assert_eq!(finding.line, Some(parse_line));
+        assert!(finding.message.starts_with("Function `parse` holds "), "{}", finding.message);
+    }
+
+    #[test]
+    fn evenly_spread_complexity_is_not_flagged() {
+        let source: String = ["parse", "render", "format", "write"]
+            .iter()
+            .map(|name| function(name, 16))
+            .collect();
+
+        let findings = concentration_findings(&source, &FindingsConfig::default());
+        assert!(findings.is_empty(), "{:?}", findings);
+
+        // Each function is complex enough; only the even spread keeps them from being flagged
+        let config = FindingsConfig {
+            concentration_threshold: 0.25,
+            ..FindingsConfig::default()
+        };
+        assert_eq!(concentration_findings(&source, &config).len(), 1);
+    }
+
+    #[test]
+    fn low_total_complexity_is_not_flagged() {
+        // `parse` holds most of the complexity, but too little of it to matter
+        let source = [function("parse", 6), function("render", 0)].concat();
+
+        let findings = concentration_findings(&source, &FindingsConfig::default());
+        assert!(findings.is_empty(), "{:?}", findings);
+
+        // Only the minimum complexity keeps it from being flagged
+        let config = FindingsConfig {
+            concentration_min_cyclomatic: 1.0,
+            ..FindingsConfig::default()
+        };
+        assert_eq!(concentration_findings(&source, &config).len(), 1);
+    }
+}
@@ -0,0 +1,397 @@
+//! Glossary of domain terms mined from exported names and Halstead operands (`--glossary`).
+//!
+//! Identifiers are split at camelCase and snake_case boundaries, programming stopwords
+//! are dropped, and plural forms are merged with their singular (User/Users).
+
+use log::debug;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::exports::ExportsMap;
+use crate::metrics::halstead_operands;
+use crate::traversal::{on_disk, relative_path};
+
+/// File name of the glossary in the output directory
+pub const FILE_NAME: &str = "GLOSSARY.md";
+
+/// An exported name counts this many times as much as an operand occurrence
+const EXPORT_WEIGHT: usize = 5;
+
+/// Terms seen fewer times than this are left out
+const MIN_OCCURRENCES: usize = 2;
+
+/// Number of files listed per term
+const FILES_PER_TERM: usize = 3;
+
+/// Words too generic to describe a domain: keywords, common verbs and type names
+const STOPWORDS: &[&str] = &[
+    "abstract",
+    "add",
+    "all",
+    "and",
+    "any",
+    "arg",
+    "args",
+    "array",
+    "as",
+    "assert",
+    "async",
+    "await",
+    "bool",
+    "boolean",
+    "break",
+    "buf",
+    "buffer",
+    "byte",
+    "bytes",
+    "call",
+    "case",
+    "catch",
+    "char",
+    "check",
+    "class",
+    "clone",
+    "const",
+    "constructor",
+    "continue",
+    "count",
+    "crate",
+    "create",
+    "def",
+    "default",
+    "delete",
+    "dict",
+    "do",
+    "done",
+    "dyn",
+    "elif",
+    "else",
+    "empty",
+    "end",
+    "enum",
+    "err",
+    "error",
+    "export",
+    "extends",
+    "false",
+    "field",
+    "fields",
+    "file",
+    "final",
+    "finally",
+    "find",
+    "float",
+    "fmt",
+    "for",
+    "from",
+    "func",
+    "function",
+    "get",
+    "handle",
+    "has",
+    "idx",
+    "if",
+    "impl",
+    "implements",
+    "import",
+    "index",
+    "info",
+    "init",
+    "input",
+    "instance",
+    "int",
+    "interface",
+    "into",
+    "is",
+    "item",
+    "items",
+    "iter",
+    "key",
+    "keys",
+    "len",
+    "length",
+    "let",
+    "list",
+    "log",
+    "loop",
+    "main",
+    "make",
+    "map",
+    "match",
+    "method",
+    "mod",
+    "module",
+    "mut",
+    "name",
+    "new",
+    "next",
+    "nil",
+    "none",
+    "not",
+    "null",
+    "num",
+    "number",
+    "obj",
+    "object",
+    "of",
+    "ok",
+    "option",
+    "or",
+    "out",
+    "output",
+    "package",
+    "param",
+    "params",
+    "parse",
+    "private",
+    "prop",
+    "props",
+    "protected",
+    "pub",
+    "public",
+    "push",
+    "read",
+    "ref",
+    "remove",
+    "require",
+    "res",
+    "reset",
+    "result",
+    "ret",
+    "return",
+    "run",
+    "self",
+    "set",
+    "size",
+    "some",
+    "start",
+    "static",
+    "str",
+    "string",
+    "struct",
+    "super",
+    "switch",
+    "sync",
+    "test",
+    "tests",
+    "the",
+    "this",
+    "throw",
+    "tmp",
+    "to",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "unwrap",
+    "update",
+    "use",
+    "util",
+    "utils",
+    "val",
+    "value",
+    "values",
+    "var",
+    "vec",
+    "void",
+    "where",
+    "while",
+    "with",
+    "write",
+    "yield",
+];
+
+/// A domain term and where it is used
+#[derive(Debug)]
+pub struct GlossaryTerm {
+    /// Most frequent spelling of the term
+    pub term: String,
+
+    /// Weighted number of occurrences
+    pub occurrences: usize,
+
+    /// Files using the term most, with their occurrences, relative to the repository
+    pub files: Vec<(String, usize)>,
+}
+
+#[derive(Default)]
+struct TermStats {
+    occurrences: usize,
+    spellings: HashMap<String, usize>,
+    files: HashMap<String, usize>,
+}
+
+/// Split an identifier into lowercase words at non-alphanumeric characters, lower-to-upper
+/// case changes, the end of acronyms (`HTTPServer` → http, server) and letter/digit changes
+fn split_identifier(identifier: &str) -> Vec<String> {
+    let chars: Vec<char> = identifier.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if let Some(prev) = current.chars().last() {
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            let boundary = (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_uppercase() && c.is_uppercase() && next_is_lower)
+                || (prev.is_ascii_digit() != c.is_ascii_digit());
+            if boundary {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words.into_iter().map(|word| word.to_lowercase()).collect()
+}
+
+/// Reduce a word to its singular form, so that plural and singular count as one term
+fn stem(word: &str) -> String {
+    if word.len() > 4 && word.ends_with("ies") {
+        return format!("{}y", &word[..word.len() - 3]);
+    }
+    for suffix in ["sses", "xes", "ches", "shes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    if word.len() > 3
+        && word.ends_with('s')
+        && !word.ends_with("ss")
+        && !word.ends_with("us")
+        && !word.ends_with("is")
+    {
+        return word[..word.len() - 1].to_string();
+    }
+    word.to_string()
+}
+
+/// Whether a word can be a domain term
+fn is_candidate(word: &str) -> bool {
+    word.len() >= 3
+        && word.chars().all(|c| c.is_alphabetic())
+        && !STOPWORDS.contains(&word)
+        && !STOPWORDS.contains(&stem(word).as_str())
+}
+
+/// Count the domain terms of the analyzed files and return the `limit` most frequent,
+/// most frequent first, ties broken by the number of files and then alphabetically
+pub fn build(
+    file_paths: &[String],
+    exports_map: &ExportsMap,
+    repo_path: &str,
+    limit: usize,
+) -> Vec<GlossaryTerm> {
+    let mut stats: HashMap<String, TermStats> = HashMap::new();
+    let mut record = |identifier: &str, file: &str, weight: usize| {
+        for word in split_identifier(identifier) {
+            if !is_candidate(&word) {
+                continue;
+            }
+            let entry = stats.entry(stem(&word)).or_default();
+            entry.occurrences += weight;
+            *entry.spellings.entry(word).or_default() += weight;
+            *entry.files.entry(file.to_string()).or_default() += weight;
+        }
+    };
+
+    let mut sorted_paths: Vec<&String> = file_paths.iter().collect();
+    sorted_paths.sort();
+    for path in sorted_paths {
+        let content = match fs::read_to_string(on_disk(repo_path, path)) {
+            Ok(content) => content,
+            Err(err) => {
+                debug!("Skipping {} in the glossary: {}", path, err);
+                continue;
+            }
+        };
+        let relative = relative_path(path, repo_path);
+        for (operand, count) in halstead_operands(path, &content) {
+            record(&operand, &relative, count);
+        }
+        for export in exports_map.get(path).into_iter().flatten() {
+            record(&export.name, &relative, EXPORT_WEIGHT);
+        }
+    }
+
+    let mut terms: Vec<GlossaryTerm> = stats
+        .into_values()
+        .filter(|term| term.occurrences >= MIN_OCCURRENCES)
+        .map(|term| {
+            let spelling = term
+                .spellings
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .map(|(spelling, _)| spelling)
+                .unwrap_or_default();
+            let mut files: Vec<(String, usize)> = term.files.into_iter().collect();
+            files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            GlossaryTerm {
+                term: spelling,
+                occurrences: term.occurrences,
+                files,
+            }
+        })
+        .collect();
+    terms.sort_by(|a, b| {
+        b.occurrences
+            .cmp(&a.occurrences)
+            .then_with(|| b.files.len().cmp(&a.files.len()))
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    terms.truncate(limit);
+    for term in &mut terms {
+        term.files.truncate(FILES_PER_TERM);
+    }
+
+    terms
+}
+
+/// Render the glossary as markdown, terms in alphabetical order with a blank description
+/// for each
+pub fn render(terms: &[GlossaryTerm], repo_path: &str) -> String {
+    let mut sorted: BTreeMap<&str, &GlossaryTerm> = BTreeMap::new();
+    for term in terms {
+        sorted.insert(&term.term, term);
+    }
+
+    let repo_name = Path::new(repo_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| repo_path.to_string());
+
+    let mut content = format!("# Glossary of {}\n\n", repo_name);
+    content.push_str(&format!(
+        "{} domain terms used most in identifiers and exported names. Fill in a description for each.\n\n",
+        terms.len()
+    ));
+
+    for (name, term) in sorted {
+        content.push_str(&format!("## {}\n\n", name));
+        let files: Vec<String> = term
+            .files
+            .iter()
+            .map(|(file, count)| format!("`{}` ({})", file, count))
+            .collect();
+        content.push_str(&format!(
+            "{} occurrences, most in {}\n\n",
+            term.occurrences,
+            files.join(", ")
+        ));
+        content.push_str("Description:\n\n");
+    }
+
+    content
+}
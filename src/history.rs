@@ -0,0 +1,367 @@
+//! Historical tracking of analysis runs in a SQLite database (`--db`).
+//!
+//! Only available when built with the `sqlite` feature.
+
+use anyhow::Result;
+
+use crate::report::AnalysisReport;
+
+/// Totals of the latest run recorded for a repository, for the trends of the next report
+#[derive(Debug, Clone)]
+pub struct PreviousRun {
+    pub total_files: usize,
+    pub total_lines: Option<usize>,
+    pub avg_maintainability_index: Option<f64>,
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use anyhow::{Context, Result};
+    use rusqlite::{params, Connection};
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::PreviousRun;
+    use crate::report::AnalysisReport;
+    use crate::traversal::relative_path;
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            repo_path TEXT NOT NULL,
+            total_files INTEGER NOT NULL,
+            total_exports INTEGER NOT NULL,
+            total_lines INTEGER,
+            total_code_lines INTEGER,
+            total_comment_lines INTEGER,
+            total_blank_lines INTEGER,
+            total_size_bytes INTEGER,
+            avg_cyclomatic_complexity REAL,
+            avg_cognitive_complexity REAL,
+            avg_maintainability_index REAL
+        );
+        CREATE TABLE IF NOT EXISTS file_metrics (
+            run_id INTEGER NOT NULL REFERENCES runs(id),
+            path TEXT NOT NULL,
+            line_count INTEGER NOT NULL,
+            code_lines INTEGER NOT NULL,
+            comment_lines INTEGER NOT NULL,
+            blank_lines INTEGER NOT NULL,
+            file_size_bytes INTEGER NOT NULL,
+            function_count INTEGER NOT NULL,
+            cyclomatic_complexity REAL,
+            cognitive_complexity REAL,
+            maintainability_index REAL,
+            knowledge_score REAL,
+            importance INTEGER NOT NULL,
+            PRIMARY KEY (run_id, path)
+        );
+        CREATE TABLE IF NOT EXISTS exports (
+            run_id INTEGER NOT NULL REFERENCES runs(id),
+            export_id TEXT,
+            path TEXT NOT NULL,
+            name TEXT NOT NULL,
+            line_number INTEGER NOT NULL,
+            export_type TEXT NOT NULL,
+            usage_count INTEGER NOT NULL,
+            is_reexport INTEGER NOT NULL,
+            canonical_path TEXT
+        );
+        CREATE INDEX IF NOT EXISTS file_metrics_path ON file_metrics (path);
+    ";
+
+    /// Exports are joined across runs by their stable id
+    const EXPORT_ID_INDEX: &str =
+        "CREATE INDEX IF NOT EXISTS exports_export_id ON exports (export_id);";
+
+    fn open(db_path: &str) -> Result<Connection> {
+        if let Some(parent) = Path::new(db_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .context(format!("Failed to create directory for {}", db_path))?;
+            }
+        }
+
+        let conn =
+            Connection::open(db_path).context(format!("Failed to open database {}", db_path))?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create database schema")?;
+
+        // Databases created before exports had stable ids lack the column
+        let has_export_id: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('exports') WHERE name = 'export_id'",
+                params![],
+                |row| row.get(0),
+            )
+            .context("Failed to inspect database schema")?;
+        if !has_export_id {
+            conn.execute_batch("ALTER TABLE exports ADD COLUMN export_id TEXT;")
+                .context("Failed to add export ids to the database")?;
+        }
+        conn.execute_batch(EXPORT_ID_INDEX)
+            .context("Failed to create database schema")?;
+
+        Ok(conn)
+    }
+
+    pub fn record_run(db_path: &str, report: &AnalysisReport) -> Result<i64> {
+        let mut conn = open(db_path)?;
+        let tx = conn.transaction()?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let metrics = report.metrics.as_ref();
+
+        tx.execute(
+            "INSERT INTO runs (timestamp, repo_path, total_files, total_exports, total_lines,
+                total_code_lines, total_comment_lines, total_blank_lines, total_size_bytes,
+                avg_cyclomatic_complexity, avg_cognitive_complexity, avg_maintainability_index)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                timestamp,
+                report.repo_path,
+                report.summary.total_files as i64,
+                report.summary.total_exports as i64,
+                metrics.map(|m| m.total_lines as i64),
+                metrics.map(|m| m.total_code_lines as i64),
+                metrics.map(|m| m.total_comment_lines as i64),
+                metrics.map(|m| m.total_blank_lines as i64),
+                metrics.map(|m| m.total_size_bytes as i64),
+                metrics.map(|m| m.avg_cyclomatic_complexity),
+                metrics.map(|m| m.avg_cognitive_complexity),
+                metrics.map(|m| m.avg_maintainability_index),
+            ],
+        )
+        .context("Failed to insert run")?;
+        let run_id = tx.last_insert_rowid();
+
+        // Paths are stored relative to the repository so runs from different
+        // working directories line up
+        if let Some(metrics) = metrics {
+            let mut insert = tx.prepare(
+                "INSERT INTO file_metrics (run_id, path, line_count, code_lines, comment_lines,
+                    blank_lines, file_size_bytes, function_count, cyclomatic_complexity,
+                    cognitive_complexity, maintainability_index, knowledge_score, importance)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for (path, file) in &metrics.file_metrics {
+                let complexity = file.complexity_metrics.as_ref();
+                insert
+                    .execute(params![
+                        run_id,
+                        relative_path(path, &report.repo_path),
+                        file.line_count as i64,
+                        file.code_lines as i64,
+                        file.comment_lines as i64,
+                        file.blank_lines as i64,
+                        file.file_size_bytes as i64,
+                        file.function_count as i64,
+                        complexity.map(|c| c.cyclomatic_complexity),
+                        complexity.map(|c| c.cognitive_complexity),
+                        complexity.map(|c| c.maintainability_index),
+                        file.knowledge_score,
+                        report.file_importance.get(path).copied().unwrap_or(0) as i64,
+                    ])
+                    .context(format!("Failed to insert metrics for {}", path))?;
+            }
+        }
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO exports (run_id, export_id, path, name, line_number, export_type,
+                    usage_count, is_reexport, canonical_path)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for (path, exports) in &report.exports {
+                for export in exports {
+                    insert
+                        .execute(params![
+                            run_id,
+                            export.id,
+                            relative_path(path, &report.repo_path),
+                            export.name,
+                            export.line_number as i64,
+                            export.export_type,
+                            export.usage_count as i64,
+                            export.is_reexport,
+                            export.canonical_path.as_ref().map(|canonical| {
+                                relative_path(&canonical.to_string_lossy(), &report.repo_path)
+                            }),
+                        ])
+                        .context(format!("Failed to insert exports for {}", path))?;
+                }
+            }
+        }
+
+        tx.commit().context("Failed to commit run")?;
+        Ok(run_id)
+    }
+
+    pub fn previous_run(db_path: &str, repo_path: &str) -> Result<Option<PreviousRun>> {
+        let conn = open(db_path)?;
+
+        let mut query = conn.prepare(
+            "SELECT total_files, total_lines, avg_maintainability_index
+             FROM runs WHERE repo_path = ?1
+             ORDER BY id DESC LIMIT 1",
+        )?;
+        let mut rows = query.query_map(params![repo_path], |row| {
+            Ok(PreviousRun {
+                total_files: row.get::<_, i64>(0)? as usize,
+                total_lines: row.get::<_, Option<i64>>(1)?.map(|lines| lines as usize),
+                avg_maintainability_index: row.get(2)?,
+            })
+        })?;
+        rows.next()
+            .transpose()
+            .context(format!("Failed to read the previous run from {}", db_path))
+    }
+
+    pub fn print_history(db_path: &str, file: &str) -> Result<()> {
+        let conn = open(db_path)?;
+        let file = file.trim_start_matches("./");
+
+        let mut query = conn.prepare(
+            "SELECT r.id, datetime(r.timestamp, 'unixepoch'), f.knowledge_score,
+                    f.code_lines, f.cyclomatic_complexity
+             FROM file_metrics f JOIN runs r ON r.id = f.run_id
+             WHERE f.path = ?1
+             ORDER BY r.id",
+        )?;
+        let rows = query
+            .query_map(params![file], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<f64>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<f64>>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context(format!("Failed to read history for {}", file))?;
+
+        if rows.is_empty() {
+            println!("No recorded runs for {} in {}", file, db_path);
+            return Ok(());
+        }
+
+        println!("Knowledge score history for {}", file);
+        println!(
+            "{:>5}  {:<19}  {:>9}  {:>6}  {:>10}  {:>10}",
+            "Run", "Date (UTC)", "Knowledge", "Change", "Code lines", "Cyclomatic"
+        );
+
+        let mut previous: Option<f64> = None;
+        for (run_id, date, score, code_lines, cyclomatic) in rows {
+            let score = score.unwrap_or(0.0);
+            let change = previous
+                .map(|p| format!("{:+.1}", score - p))
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "{:>5}  {:<19}  {:>9.1}  {:>6}  {:>10}  {:>10}",
+                run_id,
+                date,
+                score,
+                change,
+                code_lines,
+                cyclomatic
+                    .map(|c| format!("{:.0}", c))
+                    .unwrap_or_else(|| "-".to_string())
+            );
+            previous = Some(score);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn not_enabled() -> anyhow::Error {
+    anyhow::anyhow!(
+        "OverDoc was built without SQLite support; rebuild with `--features sqlite` to use --db"
+    )
+}
+
+/// Append this run (totals, per-file metrics and exports) to the database, returning the run id
+pub fn record_run(db_path: &str, report: &AnalysisReport) -> Result<i64> {
+    #[cfg(feature = "sqlite")]
+    return sqlite::record_run(db_path, report);
+
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = (db_path, report);
+        Err(not_enabled())
+    }
+}
+
+/// Totals of the latest run recorded for the repository, if there is one
+pub fn previous_run(db_path: &str, repo_path: &str) -> Result<Option<PreviousRun>> {
+    #[cfg(feature = "sqlite")]
+    return sqlite::previous_run(db_path, repo_path);
+
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = (db_path, repo_path);
+        Err(not_enabled())
+    }
+}
+
+/// Print the knowledge score trend of a file (relative to the repository root) across recorded runs
+pub fn print_history(db_path: &str, file: &str) -> Result<()> {
+    #[cfg(feature = "sqlite")]
+    return sqlite::print_history(db_path, file);
+
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = (db_path, file);
+        Err(not_enabled())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use rusqlite::{params, Connection};
+
+    use super::*;
+    use crate::report::fixture::Fixture;
+
+    #[test]
+    fn runs_join_exports_on_their_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        let db_path = db_path.to_str().unwrap();
+
+        let fixture = Fixture::new();
+        let first = record_run(db_path, &fixture.report()).unwrap();
+
+        // Code moving within the file shifts every line, but keeps the ids
+        let mut moved = Fixture::new();
+        for export in moved.exports_map.values_mut().flatten() {
+            export.line_number += 40;
+        }
+        let second = record_run(db_path, &moved.report()).unwrap();
+
+        let conn = Connection::open(db_path).unwrap();
+        let joined: Vec<(String, i64, i64)> = conn
+            .prepare(
+                "SELECT a.name, a.line_number, b.line_number
+                 FROM exports a JOIN exports b ON a.export_id = b.export_id
+                 WHERE a.run_id = ?1 AND b.run_id = ?2
+                 ORDER BY a.name",
+            )
+            .unwrap()
+            .query_map(params![first, second], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let names: Vec<&str> = joined.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["Config", "format_path", "parse", "sample_repo", "unused_helper"]);
+        assert!(joined.iter().all(|(_, before, after)| after - before == 40));
+    }
+}
@@ -0,0 +1,511 @@
+//! Aggregates the import statements found by the scanner, for auditing import style:
+//! the most imported internal modules, the deepest relative imports, wildcard imports
+//! and the files importing the most distinct modules.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use crate::exports::ImportsMap;
+use crate::traversal::relative_path;
+
+/// One import statement, with what can be read from its text
+#[derive(Debug, Clone)]
+pub struct ImportStatement {
+    /// Path of the importing file
+    pub path: String,
+    pub line: usize,
+
+    /// Module imported from, as written (`../utils`, `crate::config`, `os.path`)
+    pub module: Option<String>,
+
+    /// Module key for internal imports: relative JS/TS imports are resolved to a
+    /// repository-relative path, crate-local Rust paths are kept as written
+    pub internal_module: Option<String>,
+
+    /// Directories climbed by a relative import (`../` or `super::`); 0 for other imports
+    pub relative_depth: usize,
+
+    /// Whether the statement imports everything from a module (`*`)
+    pub wildcard: bool,
+}
+
+/// An internal module and the number of statements importing it
+#[derive(Debug, Serialize)]
+pub struct ModuleCount {
+    pub module: String,
+    pub imports: usize,
+}
+
+/// A relative import and how many directories it climbs
+#[derive(Debug, Serialize)]
+pub struct RelativeImport {
+    pub path: String,
+    pub line: usize,
+    pub module: String,
+    pub depth: usize,
+}
+
+/// A file and a number of imports
+#[derive(Debug, Serialize)]
+pub struct FileCount {
+    pub path: String,
+    pub count: usize,
+}
+
+/// Repository-wide aggregation of import statements
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    /// Number of distinct import statements
+    pub statements: usize,
+
+    /// Number of statements importing a module of the repository
+    pub internal_statements: usize,
+
+    /// Most imported internal modules, most imported first
+    pub most_imported_modules: Vec<ModuleCount>,
+
+    /// Deepest relative imports, deepest first
+    pub deepest_relative_imports: Vec<RelativeImport>,
+
+    /// Number of wildcard import statements
+    pub wildcard_imports: usize,
+
+    /// Files with the most wildcard imports
+    pub wildcard_files: Vec<FileCount>,
+
+    /// Files importing the most distinct modules
+    pub files_by_distinct_imports: Vec<FileCount>,
+}
+
+/// Regexes reading the module of an import statement, tried in order
+struct ModulePatterns {
+    js: Vec<Regex>,
+    python: Vec<Regex>,
+    rust: Regex,
+}
+
+impl ModulePatterns {
+    fn new() -> Self {
+        ModulePatterns {
+            js: vec![
+                Regex::new(r#"from\s+['"]([^'"]+)['"]"#).unwrap(),
+                Regex::new(r#"require\(\s*['"]([^'"]+)['"]\s*\)"#).unwrap(),
+                Regex::new(r#"^import\s+['"]([^'"]+)['"]"#).unwrap(),
+            ],
+            python: vec![
+                Regex::new(r"^from\s+(\.*[\w.]*)\s+import").unwrap(),
+                Regex::new(r"^import\s+([\w.]+)").unwrap(),
+            ],
+            rust: Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?use\s+([^;]+);").unwrap(),
+        }
+    }
+}
+
+/// Collect the distinct import statements (one per file and line) of the repository
+pub fn collect_statements(imports_map: &ImportsMap, repo_root: &str) -> Vec<ImportStatement> {
+    let patterns = ModulePatterns::new();
+    let mut seen: BTreeMap<(String, usize), String> = BTreeMap::new();
+
+    for references in imports_map.values() {
+        for reference in references {
+            seen.entry((
+                reference.file_path.to_string_lossy().to_string(),
+                reference.line_number,
+            ))
+            .or_insert_with(|| reference.import_statement.trim().to_string());
+        }
+    }
+
+    seen.into_iter()
+        .map(|((path, line), statement)| {
+            parse_statement(path, line, statement, repo_root, &patterns)
+        })
+        .collect()
+}
+
+fn parse_statement(
+    path: String,
+    line: usize,
+    statement: String,
+    repo_root: &str,
+    patterns: &ModulePatterns,
+) -> ImportStatement {
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let capture = |regexes: &[Regex]| {
+        regexes.iter().find_map(|regex| {
+            regex
+                .captures(&statement)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().trim().to_string())
+        })
+    };
+
+    let mut internal_module = None;
+    let mut relative_depth = 0;
+    let mut wildcard = false;
+
+    let module = match extension.as_str() {
+        "rs" => capture(std::slice::from_ref(&patterns.rust)).map(|use_path| {
+            wildcard = use_path.ends_with("::*");
+
+            // The module is everything before the imported item or group
+            let module = match use_path.find("::{") {
+                Some(idx) => use_path[..idx].to_string(),
+                None => use_path
+                    .rsplit_once("::")
+                    .map(|(module, _)| module.to_string())
+                    .unwrap_or_else(|| use_path.clone()),
+            };
+            relative_depth = module.split("::").take_while(|s| *s == "super").count();
+            if ["crate", "super", "self"]
+                .iter()
+                .any(|root| module == *root || module.starts_with(&format!("{}::", root)))
+            {
+                internal_module = Some(module.clone());
+            }
+            module
+        }),
+        "py" => capture(&patterns.python).inspect(|module| {
+            wildcard = statement.trim_end().ends_with("import *");
+
+            let dots = module.chars().take_while(|c| *c == '.').count();
+            if dots > 0 {
+                relative_depth = dots - 1;
+                internal_module = Some(module.clone());
+            }
+        }),
+        _ => capture(&patterns.js).inspect(|module| {
+            wildcard = statement.contains("import *");
+
+            if module.starts_with('.') {
+                relative_depth = module.split('/').take_while(|s| *s == "..").count();
+                internal_module = Some(resolve_relative(&path, module, repo_root));
+            }
+        }),
+    };
+
+    ImportStatement {
+        path,
+        line,
+        module,
+        internal_module,
+        relative_depth,
+        wildcard,
+    }
+}
+
+/// Resolve a relative module specifier against the importing file, relative to the repository root
+fn resolve_relative(importer: &str, specifier: &str, repo_root: &str) -> String {
+    let relative_importer = relative_path(importer, repo_root);
+    let mut resolved = PathBuf::new();
+
+    let base = Path::new(&relative_importer)
+        .parent()
+        .map(|dir| dir.join(specifier))
+        .unwrap_or_else(|| PathBuf::from(specifier));
+    for component in base.components() {
+        match component {
+            // Above the repository root the `..` components pile up instead of cancelling
+            Component::ParentDir => {
+                if matches!(
+                    resolved.components().next_back(),
+                    Some(Component::Normal(_))
+                ) {
+                    resolved.pop();
+                } else {
+                    resolved.push("..");
+                }
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+
+    resolved.to_string_lossy().replace('\\', "/")
+}
+
+/// Aggregate the statements, keeping the first `top_n` entries of every list
+pub fn aggregate(statements: &[ImportStatement], repo_root: &str, top_n: usize) -> ImportStats {
+    let mut module_counts: HashMap<&str, usize> = HashMap::new();
+    let mut wildcard_counts: HashMap<&str, usize> = HashMap::new();
+    let mut distinct_modules: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+
+    for statement in statements {
+        if let Some(module) = &statement.internal_module {
+            *module_counts.entry(module).or_default() += 1;
+        }
+        if statement.wildcard {
+            *wildcard_counts.entry(&statement.path).or_default() += 1;
+        }
+        if let Some(module) = &statement.module {
+            distinct_modules
+                .entry(&statement.path)
+                .or_default()
+                .insert(module);
+        }
+    }
+
+    let mut most_imported_modules: Vec<ModuleCount> = module_counts
+        .into_iter()
+        .map(|(module, imports)| ModuleCount {
+            module: module.to_string(),
+            imports,
+        })
+        .collect();
+    most_imported_modules.sort_by(|a, b| b.imports.cmp(&a.imports).then(a.module.cmp(&b.module)));
+    most_imported_modules.truncate(top_n);
+
+    let mut deepest_relative_imports: Vec<RelativeImport> = statements
+        .iter()
+        .filter(|statement| statement.relative_depth > 0)
+        .map(|statement| RelativeImport {
+            path: relative_path(&statement.path, repo_root),
+            line: statement.line,
+            module: statement.module.clone().unwrap_or_default(),
+            depth: statement.relative_depth,
+        })
+        .collect();
+    deepest_relative_imports.sort_by(|a, b| {
+        b.depth
+            .cmp(&a.depth)
+            .then_with(|| a.path.cmp(&b.path))
+            .then(a.line.cmp(&b.line))
+    });
+    deepest_relative_imports.truncate(top_n);
+
+    let file_counts = |counts: HashMap<&str, usize>| {
+        let mut files: Vec<FileCount> = counts
+            .into_iter()
+            .map(|(path, count)| FileCount {
+                path: relative_path(path, repo_root),
+                count,
+            })
+            .collect();
+        files.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.path.cmp(&b.path)));
+        files.truncate(top_n);
+        files
+    };
+
+    ImportStats {
+        statements: statements.len(),
+        internal_statements: statements
+            .iter()
+            .filter(|statement| statement.internal_module.is_some())
+            .count(),
+        most_imported_modules,
+        deepest_relative_imports,
+        wildcard_imports: statements.iter().filter(|s| s.wildcard).count(),
+        wildcard_files: file_counts(wildcard_counts),
+        files_by_distinct_imports: file_counts(
+            distinct_modules
+                .into_iter()
+                .map(|(path, modules)| (path, modules.len()))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FindingsConfig;
+    use crate::exports::ImportReference;
+    use crate::findings::import_findings;
+
+    /// Statements of `(file, line)` pairs, as the scanner would report them
+    fn statements(lines: &[(&str, &str)]) -> Vec<ImportStatement> {
+        let mut imports_map = ImportsMap::new();
+        for (idx, (file, statement)) in lines.iter().enumerate() {
+            imports_map
+                .entry(format!("name{}", idx))
+                .or_default()
+                .push(ImportReference {
+                    name: format!("name{}", idx),
+                    file_path: PathBuf::from(file),
+                    line_number: idx + 1,
+                    import_statement: statement.to_string(),
+                });
+        }
+        collect_statements(&imports_map, "repo")
+    }
+
+    fn statement(file: &str, line: &str) -> ImportStatement {
+        statements(&[(file, line)]).remove(0)
+    }
+
+    #[test]
+    fn relative_depth_counts_the_directories_climbed() {
+        // (file, statement, depth)
+        let cases = [
+            ("repo/web/app.ts", "import { a } from './a';", 0),
+            ("repo/web/app.ts", "import { a } from '../a';", 1),
+            ("repo/web/app.ts", "const a = require('../../a');", 2),
+            ("repo/web/app.ts", "import { a } from 'react';", 0),
+            ("repo/src/a/b.rs", "use super::super::config::Config;", 2),
+            ("repo/src/a/b.rs", "use crate::config::Config;", 0),
+            ("repo/pkg/mod.py", "from ..utils import helper", 1),
+            ("repo/pkg/mod.py", "from . import helper", 0),
+            ("repo/pkg/mod.py", "import os", 0),
+        ];
+        for (file, line, depth) in cases {
+            assert_eq!(statement(file, line).relative_depth, depth, "{}", line);
+        }
+    }
+
+    #[test]
+    fn wildcard_imports_are_detected_per_language() {
+        // (file, statement, wildcard)
+        let cases = [
+            ("repo/web/app.ts", "import * as utils from './utils';", true),
+            ("repo/web/app.ts", "import { all } from './utils';", false),
+            ("repo/src/lib.rs", "use crate::prelude::*;", true),
+            (
+                "repo/src/lib.rs",
+                "use crate::prelude::{Config, Parser};",
+                false,
+            ),
+            ("repo/pkg/mod.py", "from .models import *", true),
+            ("repo/pkg/mod.py", "from .models import User", false),
+        ];
+        for (file, line, wildcard) in cases {
+            assert_eq!(statement(file, line).wildcard, wildcard, "{}", line);
+        }
+    }
+
+    #[test]
+    fn internal_modules_resolve_relative_to_the_repository() {
+        // (file, statement, internal module)
+        let cases = [
+            (
+                "repo/web/app.ts",
+                "import { a } from './utils';",
+                Some("web/utils"),
+            ),
+            (
+                "repo/web/views/list.ts",
+                "import { a } from '../utils';",
+                Some("web/utils"),
+            ),
+            (
+                "repo/web/views/list.ts",
+                "import { a } from './../../lib/x';",
+                Some("lib/x"),
+            ),
+            ("repo/web/app.ts", "import React from 'react';", None),
+            (
+                "repo/src/a/b.rs",
+                "use crate::config::Config;",
+                Some("crate::config"),
+            ),
+            (
+                "repo/src/a/b.rs",
+                "use super::{parse, render};",
+                Some("super"),
+            ),
+            ("repo/src/a/b.rs", "use std::collections::HashMap;", None),
+            (
+                "repo/pkg/mod.py",
+                "from .models import User",
+                Some(".models"),
+            ),
+            ("repo/pkg/mod.py", "from os import path", None),
+        ];
+        for (file, line, module) in cases {
+            assert_eq!(
+                statement(file, line).internal_module.as_deref(),
+                module,
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn imports_climbing_above_the_root_keep_every_parent() {
+        assert_eq!(
+            resolve_relative("repo/web/app.ts", "../lib/thing", "repo"),
+            "lib/thing"
+        );
+        assert_eq!(
+            resolve_relative("repo/web/app.ts", "../../lib/thing", "repo"),
+            "../lib/thing"
+        );
+        assert_eq!(
+            resolve_relative("repo/web/app.ts", "../../../../lib/thing", "repo"),
+            "../../../lib/thing"
+        );
+        assert_eq!(
+            resolve_relative("repo/app.ts", "../../a/../b", "repo"),
+            "../../b"
+        );
+
+        let stats = aggregate(
+            &statements(&[(
+                "repo/web/app.ts",
+                "import { t } from '../../../../lib/thing';",
+            )]),
+            "repo",
+            10,
+        );
+        assert_eq!(stats.most_imported_modules[0].module, "../../../lib/thing");
+        assert_eq!(stats.deepest_relative_imports[0].depth, 4);
+    }
+
+    #[test]
+    fn aggregate_counts_modules_wildcards_and_distinct_imports() {
+        let stats = aggregate(
+            &statements(&[
+                ("repo/web/a.ts", "import { x } from './utils';"),
+                ("repo/web/b.ts", "import * as u from './utils';"),
+                ("repo/web/b.ts", "import { y } from './api';"),
+                ("repo/web/b.ts", "import React from 'react';"),
+            ]),
+            "repo",
+            10,
+        );
+
+        assert_eq!((stats.statements, stats.internal_statements), (4, 3));
+        let modules: Vec<(&str, usize)> = stats
+            .most_imported_modules
+            .iter()
+            .map(|m| (m.module.as_str(), m.imports))
+            .collect();
+        assert_eq!(modules, [("web/utils", 2), ("web/api", 1)]);
+        assert_eq!(stats.wildcard_imports, 1);
+        assert_eq!(stats.wildcard_files[0].path, "web/b.ts");
+        let distinct: Vec<(&str, usize)> = stats
+            .files_by_distinct_imports
+            .iter()
+            .map(|f| (f.path.as_str(), f.count))
+            .collect();
+        assert_eq!(distinct, [("web/b.ts", 3), ("web/a.ts", 1)]);
+    }
+
+    #[test]
+    fn imports_deeper_than_three_directories_are_findings() {
+        let statements = statements(&[
+            ("repo/a/b/c/d/e.ts", "import { x } from '../../../x';"),
+            ("repo/a/b/c/d/e.ts", "import { y } from '../../../../y';"),
+            ("repo/src/a/b.rs", "use super::super::super::super::z;"),
+        ]);
+
+        let findings = import_findings(&statements, &FindingsConfig::default());
+
+        let flagged: Vec<(&str, usize)> = findings
+            .iter()
+            .map(|finding| (finding.path.as_str(), finding.line.unwrap()))
+            .collect();
+        assert_eq!(flagged, [("repo/a/b/c/d/e.ts", 2), ("repo/src/a/b.rs", 3)]);
+        assert_eq!(
+            findings[0].message,
+            "Relative import of `../../../../y` climbs 4 directories (maximum 3)"
+        );
+    }
+}
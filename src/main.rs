@@ -1,97 +1,1435 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use env_logger::Builder;
-use log::{info, LevelFilter};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use env_logger::{Builder, WriteStyle};
+use log::{debug, error, info, warn, LevelFilter};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
 use std::path::Path;
+use std::time::{Duration, Instant};
 
+mod archive;
+mod audit;
+mod baseline;
+mod categories;
 mod config;
 mod dependencies;
+mod diff;
+mod directories;
+mod explain;
 mod exports;
 mod filter;
+mod findings;
+mod glossary;
+mod history;
+mod import_stats;
 mod metrics;
+mod owners;
+mod patterns;
+mod progress;
+mod report;
+mod run_summary;
+mod signals;
+mod term_report;
+mod thresholds;
 mod traversal;
+mod watch;
 
 /// OverDoc: Automatic documentation generation tool
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
-struct Args {
-    /// Repository directory to analyze (absolute or relative path)
-    #[clap(short, long, default_value = ".", value_name = "DIRECTORY")]
-    repo_path: String,
+struct Cli {
+    #[clap(flatten)]
+    global: GlobalArgs,
 
-    /// Path to configuration file
-    #[clap(short, long, value_name = "FILE")]
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Options of `analyze`, which runs when no subcommand is given
+    #[clap(flatten)]
+    analyze: AnalyzeArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze the repository and write the report (the default)
+    Analyze(Box<AnalyzeArgs>),
+
+    /// Write a commented starting configuration to --config-path
+    Init(InitArgs),
+
+    /// Only build the dependency graph and write it in the given formats
+    Graph(GraphArgs),
+
+    /// Compare the scores of a report saved with `--format json` with another one or with
+    /// the repository as it is now (--baseline)
+    Diff(DiffArgs),
+
+    /// Analyze the repository and print why one file scored the way it did, the same as
+    /// `--explain FILE`
+    Explain(ExplainArgs),
+
+    /// Record the metrics of every file, so `--baseline` only fails the quality thresholds for
+    /// new files and files that got worse
+    Baseline(BaselineArgs),
+
+    /// Print the metrics, complexity and exports of a single file as JSON, without scanning the
+    /// repository, e.g. for editor integrations
+    File(FileArgs),
+}
+
+/// Options shared by every subcommand
+#[derive(clap::Args, Debug)]
+struct GlobalArgs {
+    /// Repository directory to analyze (absolute or relative path), the current directory by
+    /// default; repeat to analyze several directories as one repository
+    #[clap(short, long, global = true, value_name = "DIRECTORY")]
+    repo_path: Vec<String>,
+
+    /// Path to the configuration file, YAML, TOML or JSON by its extension; by default the first
+    /// of overdoc.yaml, overdoc.yml, overdoc.toml and overdoc.json found
+    #[clap(short, long, global = true, value_name = "FILE")]
     config_path: Option<String>,
 
-    /// Verbose output
-    #[clap(short, long)]
+    /// Override a configuration value, e.g. `default_settings.max_file_size_kb=2048`
+    /// or `languages.rust.ignore_directories+=benches` (repeatable)
+    #[clap(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Only analyze files matching this glob, e.g. `src/**` (repeatable); paths are relative
+    /// to the repository and a glob without `/` matches at any depth
+    #[clap(long, global = true, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Leave out files matching this glob, even when they match --include (repeatable)
+    #[clap(long, global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Only analyze files of these configured languages, given by name or extension and
+    /// comma separated, e.g. `typescript,rs`
+    #[clap(long, global = true, value_delimiter = ',', value_name = "LANGUAGE")]
+    languages: Vec<String>,
+
+    /// Follow symlinked files and directories while traversing, analyzing files reachable
+    /// through several links once (sets `default_settings.follow_symlinks`)
+    #[clap(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Traverse paths the repository's `.gitignore` files and git excludes ignore (clears
+    /// `default_settings.respect_gitignore`)
+    #[clap(long, global = true)]
+    no_gitignore: bool,
+
+    /// Leave out test files, as matched by the `tests` configuration
+    #[clap(long, global = true, conflicts_with = "only_tests")]
+    exclude_tests: bool,
+
+    /// Only analyze test files, as matched by the `tests` configuration
+    #[clap(long, global = true)]
+    only_tests: bool,
+
+    /// Threads analyzing files in parallel; 0 uses every core. `--threads 1` produces the
+    /// same reports as any other count.
+    #[clap(long, global = true, default_value_t = 0, value_name = "N")]
+    threads: usize,
+
+    /// Verbose output: debug logging (same as --log-level debug) and longer listings
+    #[clap(short, long, global = true, conflicts_with = "log_level")]
     verbose: bool,
 
+    /// Only log warnings and errors (same as --log-level warn)
+    #[clap(short, long, global = true, conflicts_with_all = ["verbose", "log_level"])]
+    quiet: bool,
+
+    /// Lowest level of the log messages written to stderr
+    #[clap(long, global = true, value_enum, value_name = "LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// Color the log messages and the summary tables
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value = "auto",
+        value_name = "WHEN"
+    )]
+    color: term_report::ColorChoice,
+
+    /// Same as --color never
+    #[clap(long, global = true)]
+    no_color: bool,
+}
+
+impl GlobalArgs {
+    /// Log level from --log-level, --verbose or --quiet, `info` by default
+    fn log_level(&self) -> LevelFilter {
+        match self.log_level {
+            Some(level) => level.into(),
+            None if self.verbose => LevelFilter::Debug,
+            None if self.quiet => LevelFilter::Warn,
+            None => LevelFilter::Info,
+        }
+    }
+
+    /// --color, or `never` with --no-color
+    fn color(&self) -> term_report::ColorChoice {
+        if self.no_color {
+            term_report::ColorChoice::Never
+        } else {
+            self.color
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => LevelFilter::Trace,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Error => LevelFilter::Error,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct InitArgs {
+    /// Overwrite the configuration file if it exists
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct GraphArgs {
+    /// Graph formats to write, comma separated
+    #[clap(long, value_enum, value_delimiter = ',', default_value = "dot")]
+    format: Vec<dependencies::GraphFormat>,
+
+    /// Only include files with at least this importance score
+    #[clap(long, default_value = "0", value_name = "N")]
+    min_score: usize,
+
+    /// Output directory for the graph files
+    #[clap(short = 'o', long, default_value = "out", value_name = "DIRECTORY")]
+    output_dir: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Earlier `analysis_results.json`
+    #[clap(value_name = "OLD", required_unless_present = "baseline")]
+    old: Option<String>,
+
+    /// Later `analysis_results.json`
+    #[clap(value_name = "NEW", required_unless_present = "baseline")]
+    new: Option<String>,
+
+    /// Analyze the repository now and compare it with this `analysis_results.json` instead
+    /// of comparing two saved reports
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["old", "new"])]
+    baseline: Option<String>,
+
+    /// Output format of the differences
+    #[clap(long, value_enum, default_value = "text")]
+    format: diff::DiffFormat,
+}
+
+#[derive(clap::Args, Debug)]
+struct BaselineArgs {
+    /// Baseline file to write
+    #[clap(value_name = "FILE", default_value = baseline::DEFAULT_PATH)]
+    file: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct FileArgs {
+    /// File to analyze, or `-` to read its content from stdin
+    #[clap(value_name = "PATH")]
+    path: String,
+
+    /// Extension of the file's language, e.g. `rs`; needed for stdin and takes precedence over
+    /// the extension of PATH
+    #[clap(long, value_name = "EXTENSION")]
+    extension: Option<String>,
+}
+
+/// What `overdoc file` prints
+#[derive(Serialize)]
+struct FileAnalysis {
+    /// Configured language the exports and imports were extracted with, if any
+    language: Option<String>,
+    metrics: metrics::FileMetrics,
+    exports: Vec<exports::ExportedEntity>,
+    imports: Vec<exports::ImportReference>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExplainArgs {
+    /// File to explain, relative to the repository
+    #[clap(value_name = "FILE")]
+    file: String,
+
+    /// Print the explanation as JSON
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct AnalyzeArgs {
+    /// Repositories to analyze together, e.g. `overdoc repoA repoB`, in addition to any
+    /// --repo-path; the report sums up each of them
+    #[clap(value_name = "DIRECTORY")]
+    repos: Vec<String>,
+
+    /// Key files by their absolute path instead of their path relative to the repository
+    #[clap(long)]
+    absolute_paths: bool,
+
+    /// Analyze only the files listed in this file, one path per line relative to the
+    /// repository, instead of traversing it; `-` reads the list from stdin. Directories are
+    /// expanded, missing files are skipped with a warning, and the filters still apply
+    #[clap(long, value_name = "FILE")]
+    files_from: Option<String>,
+
+    /// Analyze at most this many files: beyond it, keep the largest files of each language in
+    /// proportion to the language's share and mark the report as partial
+    #[clap(long, value_name = "N")]
+    max_files: Option<usize>,
+
+    /// Only traverse this many directory levels below the repository root (1 for the files
+    /// directly in it) and mark the report as partial when directories were left out
+    #[clap(long, value_name = "N", conflicts_with = "files_from")]
+    max_depth: Option<usize>,
+
     /// Show top N important files
     #[clap(short = 'n', long, default_value = "10")]
     top_files: usize,
 
+    /// Order of the top files; orders other than importance need metrics
+    #[clap(long, value_enum, default_value = "importance")]
+    sort_by: report::TopFilesOrder,
+
+    /// Show top N important directories (defaults to --top-files)
+    #[clap(long, value_name = "N")]
+    top_dirs: Option<usize>,
+
+    /// List the N most used exported functions, types and other entities across the
+    /// repository in a "Most Used Exports" section
+    #[clap(long, default_value_t = 0, value_name = "N")]
+    top_exports: usize,
+
+    /// Leave files with a lower importance score out of the top files and the file tables,
+    /// e.g. 1 to hide files nobody imports (overrides `report.min_importance`)
+    #[clap(long, value_name = "N")]
+    min_importance: Option<usize>,
+
+    /// Leave files with a lower knowledge score out of the knowledge hotspots and the file
+    /// tables (overrides `report.min_knowledge_score`)
+    #[clap(long, value_name = "SCORE")]
+    min_knowledge_score: Option<f64>,
+
+    /// Limit the top files, hotspots and findings to paths matching this CODEOWNERS-style
+    /// glob, e.g. `src/payments/**` (repeatable); repository totals are kept
+    #[clap(long, value_name = "GLOB")]
+    scope: Vec<String>,
+
+    /// Limit the top files, hotspots and findings to files CODEOWNERS assigns to this owner
+    #[clap(long, value_name = "OWNER")]
+    scope_owner: Option<String>,
+
+    /// Scan the whole repository but measure and report only the files under this path, e.g.
+    /// `src/core`; usage counts still include importers outside it
+    #[clap(long, value_name = "PATH")]
+    only: Option<String>,
+
+    /// Scan the whole repository but measure and report only the files changed since this git
+    /// ref, e.g. `origin/main`, for pull request runs
+    #[clap(long, value_name = "REF")]
+    since: Option<String>,
+
     /// Output directory for analysis results
     #[clap(short = 'o', long, default_value = "out", value_name = "DIRECTORY")]
     output_dir: String,
 
+    /// Name of the report file in --output-dir, e.g. `report.md`; its stem names the report
+    /// in every format (`report.json`, `report.html`, ...)
+    #[clap(long, value_name = "FILE")]
+    output_file: Option<String>,
+
+    /// Overwrite reports left in --output-dir by an earlier run
+    #[clap(long)]
+    force: bool,
+
     /// Skip metrics analysis (for faster processing)
     #[clap(long)]
     skip_metrics: bool,
+
+    /// Output formats to write, comma separated (md, json, jsonl, yaml, html, sarif, text).
+    /// Defaults to md, or text with --print
+    #[clap(long, value_enum, value_delimiter = ',')]
+    format: Vec<report::OutputFormat>,
+
+    /// Look of the HTML report: light, dark, print (tables fit the page) or plain (no
+    /// heading, for embedding in a wiki)
+    #[clap(long, value_enum, default_value = "light")]
+    theme: report::html::Theme,
+
+    /// Heading of the HTML report instead of "OverDoc Analysis Results"
+    #[clap(long, value_name = "TEXT")]
+    title: Option<String>,
+
+    /// Embed the stylesheet and script in index.html instead of writing them to `assets/`,
+    /// for a single self-contained file
+    #[clap(long)]
+    inline_assets: bool,
+
+    /// Render the markdown report through this Tera template instead of the built-in layout
+    #[clap(long, value_name = "FILE")]
+    template: Option<String>,
+
+    /// Export the dependency graph in the given formats (dot, graphml)
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "FORMAT")]
+    emit_graph: Vec<dependencies::GraphFormat>,
+
+    /// Only include files with at least this importance score in graph exports
+    #[clap(long, default_value = "0", value_name = "N")]
+    graph_min_score: usize,
+
+    /// Write treemap.json and a self-contained treemap.html of the repository
+    #[clap(long)]
+    emit_treemap: bool,
+
+    /// Exit with an error when the report lists unused exports, for CI
+    #[clap(long)]
+    fail_on_unused_exports: bool,
+
+    /// Exit with code 2 when the average maintainability index is lower
+    /// (overrides `thresholds.maintainability_below`)
+    #[clap(long, value_name = "INDEX")]
+    fail_on_maintainability_below: Option<f64>,
+
+    /// Exit with code 2 when the average cyclomatic complexity is higher
+    /// (overrides `thresholds.avg_cyclomatic_above`)
+    #[clap(long, value_name = "COMPLEXITY")]
+    fail_on_avg_cyclomatic_above: Option<f64>,
+
+    /// Exit with code 2 when any file's cyclomatic complexity is higher
+    /// (overrides `thresholds.file_cyclomatic_above`)
+    #[clap(long, value_name = "COMPLEXITY")]
+    fail_on_file_cyclomatic_above: Option<f64>,
+
+    /// Only fail the quality thresholds for files that are new or got worse than recorded in
+    /// this file (written by `overdoc baseline`)
+    #[clap(long, value_name = "FILE")]
+    baseline: Option<String>,
+
+    /// Record this run's metrics in the --baseline file instead of checking against it
+    #[clap(long, requires = "baseline")]
+    update_baseline: bool,
+
+    /// Trace every file through filtering, the exports scan, metrics and the report, and
+    /// report files lost between phases without an exclusion or warning (`pipeline_audit.json`)
+    #[clap(long)]
+    audit_pipeline: bool,
+
+    /// Write GLOSSARY.md with the most frequent domain terms in identifiers, for documentation
+    #[clap(long)]
+    glossary: bool,
+
+    /// JSON file of per-file signals from other tools, e.g.
+    /// `{"src/payments.rs": {"coverage": 0.42, "incidents": 3}}`, weighted by `scoring.extra`
+    #[clap(long, value_name = "FILE")]
+    signals: Option<String>,
+
+    /// Write symbols.json with every export and import the scanner extracted,
+    /// for tuning export_patterns and import_patterns
+    #[clap(long)]
+    emit_symbols: bool,
+
+    /// Write per-page JSON for the top files and directories plus nav.json to `docs-data/`,
+    /// named for a docs site generator
+    #[clap(long, value_enum, value_name = "SITE")]
+    docs_data: Option<report::docs_data::DocsSite>,
+
+    /// Metric used for the size of treemap rectangles
+    #[clap(long, value_enum, default_value = "lines")]
+    treemap_value: report::treemap::TreemapValue,
+
+    /// Append this run to a SQLite database for historical tracking (requires the `sqlite` feature).
+    /// Without a value the database is `overdoc.sqlite` in the output directory
+    #[clap(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+    db: Option<String>,
+
+    /// Print the knowledge score trend of a file (relative to the repository) from --db and exit
+    #[clap(long, value_name = "FILE", requires = "db")]
+    history: Option<String>,
+
+    /// Keep running, re-analyzing and rewriting the outputs whenever files of the repository
+    /// change, until Ctrl-C
+    #[clap(
+        long,
+        conflicts_with_all = ["explain", "list_files", "history", "summary_json", "files_from"]
+    )]
+    watch: bool,
+
+    /// Print everything known about one file (path relative to the repository) and exit
+    #[clap(long, value_name = "FILE")]
+    explain: Option<String>,
+
+    /// Print every traversed file with whether it is analyzed and the rule deciding it, then
+    /// exit without analyzing or writing anything
+    #[clap(long, conflicts_with = "explain")]
+    list_files: bool,
+
+    /// Print the --explain output as JSON
+    #[clap(long, requires = "explain")]
+    json: bool,
+
+    /// Write a markdown page per top file to `files/` and link to them from the report
+    #[clap(long)]
+    per_file_pages: bool,
+
+    /// Write a page per top directory to `dirs/` and link to them from the report
+    #[clap(long)]
+    per_dir_pages: bool,
+
+    /// Write a sub-report per configured language to `by-language/` and link to them from the report
+    #[clap(long)]
+    by_language: bool,
+
+    /// Rank the top files per language, each language with its own totals, below the overall summary
+    #[clap(long)]
+    group_by_language: bool,
+
+    /// Write SVG badges (maintainability, comment ratio, files) to `badges/`
+    #[clap(long)]
+    badges: bool,
+
+    /// Don't write anything to disk; print the report in the requested formats to stdout
+    #[clap(long, conflicts_with = "db")]
+    ephemeral: bool,
+
+    /// Print the report to stdout instead of writing files (implies --ephemeral).
+    /// Prints a compact text summary unless --format is given, e.g. `--format json --print | jq`
+    #[clap(long, alias = "stdout", conflicts_with = "db")]
+    print: bool,
+
+    /// Print the effective configuration (including overrides) and exit
+    #[clap(long)]
+    print_effective_config: bool,
+
+    /// Print the effective import/export patterns of every language and exit
+    #[clap(long)]
+    check_config: bool,
+
+    /// With --check-config, also classify the repository's files and list those
+    /// matching several categories
+    #[clap(long, requires = "check_config")]
+    paths: bool,
+
+    /// Print a JSON object with file counts, phase timings and the written artifacts to stdout
+    /// at the end of the run, whether or not it succeeds; logs stay on stderr
+    #[clap(long, conflicts_with_all = ["ephemeral", "print", "explain"])]
+    summary_json: bool,
+
+    /// Log how long each phase took and the 10 files the metrics analysis spent the most time
+    /// on; with --summary-json they are in the summary too
+    #[clap(long)]
+    timings: bool,
+
+    /// Bundle the files this run wrote to the output directory into overdoc-report.zip
+    /// (requires the `archive` feature)
+    #[clap(long, conflicts_with_all = ["ephemeral", "print", "explain"])]
+    archive: bool,
 }
 
 fn main() -> Result<()> {
     // Initialize logger with appropriate level
     let mut builder = Builder::new();
 
-    // Set log level based on verbose flag
-    let args = Args::parse();
+    // Set log level based on --log-level, --verbose or --quiet
+    let Cli {
+        global,
+        command,
+        analyze,
+    } = parse_cli();
+
+    // Progress bars stay off for --quiet and stricter levels, where only problems are shown
+    let level = global.log_level();
+    builder.filter_level(level);
+    builder.write_style(match global.color() {
+        term_report::ColorChoice::Auto => WriteStyle::Auto,
+        term_report::ColorChoice::Always => WriteStyle::Always,
+        term_report::ColorChoice::Never => WriteStyle::Never,
+    });
+    progress::init(builder.build(), level >= LevelFilter::Info)
+        .context("Failed to set up logging")?;
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(global.threads)
+        .build_global()
+        .context("Failed to set up the thread pool")?;
+
+    // Without a subcommand, `overdoc -r . -n 20` analyzes as it always did
+    let result = match command {
+        None => analyze_command(&global, analyze),
+        Some(Command::Analyze(args)) => analyze_command(&global, *args),
+        Some(Command::Init(args)) => init_command(&global, &args),
+        Some(Command::Graph(args)) => graph_command(&global, &args),
+        Some(Command::Diff(args)) => diff_command(&global, &args, analyze),
+        Some(Command::Explain(args)) => explain_command(&global, args, analyze),
+        Some(Command::Baseline(args)) => baseline_command(&global, args, analyze),
+        Some(Command::File(args)) => file_command(&global, &args),
+    };
+
+    // Crossed quality thresholds exit with 2, apart from operational errors (1)
+    if let Some(violations) = result
+        .as_ref()
+        .err()
+        .and_then(|err| err.downcast_ref::<thresholds::Violations>())
+    {
+        eprintln!("Error: {}", violations);
+        std::process::exit(2);
+    }
+    result
+}
+
+/// Parse the command line, rejecting `analyze` options given before another subcommand
+/// instead of silently ignoring them
+fn parse_cli() -> Cli {
+    let mut command = Cli::command();
+    let matches = command.get_matches_mut();
+
+    if matches.subcommand_name().is_some() {
+        let analyze_only: Vec<&str> = command
+            .get_arguments()
+            .filter(|arg| !arg.is_global_set())
+            .map(|arg| arg.get_id().as_str())
+            .collect();
+        let misplaced = matches.ids().map(|id| id.as_str()).find(|id| {
+            analyze_only.contains(id) && matches.value_source(id) == Some(ValueSource::CommandLine)
+        });
+        if let Some(id) = misplaced {
+            command
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--{} only applies to `analyze`; pass subcommand options after the subcommand",
+                        id.replace('_', "-")
+                    ),
+                )
+                .exit();
+        }
+    }
+
+    Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit())
+}
+
+/// `overdoc analyze`: run the full analysis, printing the --summary-json whatever the outcome
+fn analyze_command(global: &GlobalArgs, mut args: AnalyzeArgs) -> Result<()> {
+    if args.print {
+        args.ephemeral = true;
+    }
+    if args.format.is_empty() {
+        args.format.push(if args.print {
+            report::OutputFormat::Text
+        } else {
+            report::OutputFormat::Markdown
+        });
+    }
+
+    if args.watch {
+        return watch_command(global, &args);
+    }
+
+    let mut summary = run_summary::RunSummary::new();
+    let result = run(global, &args, &mut summary, None).map(|_| ());
+    if args.timings {
+        summary.log_timings();
+    }
+    if args.summary_json {
+        println!("{}", summary.render(&result)?);
+    }
+    result
+}
+
+/// `overdoc analyze --watch`: analyze, then again on every change until Ctrl-C
+fn watch_command(global: &GlobalArgs, args: &AnalyzeArgs) -> Result<()> {
+    let (config, config_path, _) = load_config(global)?;
+    if !args.ephemeral {
+        check_overwrite(
+            args,
+            Path::new(&args.output_dir),
+            output_stem(args)?.as_deref(),
+        )?;
+    }
+    let repo_roots = traversal::resolve_roots(&repo_paths(global, &args.repos))?;
+    watch::watch(
+        &repo_roots.roots,
+        &config,
+        &config_path,
+        Path::new(&args.output_dir),
+        |session| {
+            let mut summary = run_summary::RunSummary::new();
+            let report = run(global, args, &mut summary, Some(&mut session.metrics_cache))?;
+            if let Some(report) = report {
+                session.record(&report);
+            }
+            Ok(())
+        },
+    )
+}
+
+/// `overdoc init`: write the starting configuration and suggest a --max-files value for the
+/// repository
+fn init_command(global: &GlobalArgs, args: &InitArgs) -> Result<()> {
+    let config_path = config_path(global);
+    config::create_default_config(&config_path, args.force)?;
+    println!("Wrote {}", config_path);
+
+    // Count the files the new configuration keeps, reading it back like a run would
+    let (config, _, _) = load_config(global)?;
+    let repo_roots = traversal::resolve_roots(&repo_paths(global, &[]))?;
+    let path_globs = path_globs(global, &repo_roots.base)?;
+    let path_overrides = filter::PathOverrides::new(&config.overrides, &repo_roots.base)?;
+    let config_patterns = filter::ConfigPatterns::new(&config, &repo_roots.base)?;
+    let languages = filter::LanguageFilter::new(&global.languages, &config)?;
+    let tests = test_filter(global, &config, &repo_roots.base)?;
+    let filter_rules = filter::FilterRules {
+        config: &config,
+        overrides: &path_overrides,
+        patterns: &config_patterns,
+        globs: &path_globs,
+        languages: &languages,
+        tests: &tests,
+    };
+    let files = traversal::traverse_repository(&repo_roots, &config, false, None)
+        .context("Failed to traverse repository")?
+        .files;
+    let kept = filter::apply_filters(files, &filter_rules, None).len();
+    println!(
+        "{} files in {} would be analyzed; pass --max-files {} to stop a run on the wrong directory from analyzing everything",
+        kept,
+        repo_roots.base,
+        suggested_max_files(kept)
+    );
+    Ok(())
+}
+
+/// Every --repo-path and positional directory, or the current directory without any
+fn repo_paths(global: &GlobalArgs, positional: &[String]) -> Vec<String> {
+    let paths: Vec<String> = global.repo_path.iter().chain(positional).cloned().collect();
+    if paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        paths
+    }
+}
+
+/// Compile --include and --exclude against paths relative to `repo_root`
+fn path_globs(global: &GlobalArgs, repo_root: &str) -> Result<filter::PathGlobs> {
+    filter::PathGlobs::new(&global.include, &global.exclude, repo_root)
+}
+
+/// Recognize the test files of the `tests` configuration, keeping or leaving them out by
+/// --exclude-tests and --only-tests
+fn test_filter(
+    global: &GlobalArgs,
+    config: &config::Config,
+    repo_root: &str,
+) -> Result<filter::TestFilter> {
+    let selection = if global.exclude_tests {
+        filter::TestSelection::Exclude
+    } else if global.only_tests {
+        filter::TestSelection::Only
+    } else {
+        filter::TestSelection::All
+    };
+    filter::TestFilter::new(&config.tests, selection, repo_root)
+}
+
+/// Twice the file count rounded up to a thousand, leaving room for the repository to grow
+fn suggested_max_files(files: usize) -> usize {
+    (files * 2).div_ceil(1000).max(1) * 1000
+}
+
+/// `overdoc graph`: scan the repository and write only the dependency graph
+fn graph_command(global: &GlobalArgs, args: &GraphArgs) -> Result<()> {
+    let (config, _, _) = load_config(global)?;
+    let repo_roots = traversal::resolve_roots(&repo_paths(global, &[]))?;
+    let repo_root = &repo_roots.base;
+    let path_globs = path_globs(global, repo_root)?;
+    let path_overrides = filter::PathOverrides::new(&config.overrides, repo_root)?;
+    let config_patterns = filter::ConfigPatterns::new(&config, repo_root)?;
+    let languages = filter::LanguageFilter::new(&global.languages, &config)?;
+    let tests = test_filter(global, &config, repo_root)?;
+    let filter_rules = filter::FilterRules {
+        config: &config,
+        overrides: &path_overrides,
+        patterns: &config_patterns,
+        globs: &path_globs,
+        languages: &languages,
+        tests: &tests,
+    };
+    let output_dir = Path::new(&args.output_dir);
+    ensure_writable_dir(output_dir)?;
+
+    let files = traversal::traverse_repository(&repo_roots, &config, false, None)
+        .context("Failed to traverse repository")?
+        .files;
+    let filtered_files = filter::apply_filters(files, &filter_rules, None);
+
+    let (mut exports_map, imports_map, _) =
+        exports::scan_repository(&filtered_files, repo_root, &config, None)
+            .context("Failed to scan repository for exports and imports")?;
+    exports::resolve_reexports(&mut exports_map);
+    exports::assign_export_ids(&mut exports_map, repo_root);
+    let dependency_graph = dependencies::build_dependency_graph(&mut exports_map, &imports_map)
+        .context("Failed to build dependency graph")?;
+
+    for graph_format in &args.format {
+        let content = match graph_format {
+            dependencies::GraphFormat::Dot => dependency_graph.to_dot(repo_root, args.min_score),
+            dependencies::GraphFormat::Graphml => {
+                dependency_graph.to_graphml(repo_root, args.min_score, None)
+            }
+        };
+        let graph_file = output_dir.join(graph_format.file_name());
+        fs::write(&graph_file, content).context(format!(
+            "Failed to write dependency graph to {}",
+            graph_file.display()
+        ))?;
+        info!("Dependency graph saved to {}", graph_file.display());
+    }
+    Ok(())
+}
+
+/// `overdoc diff`: print the files whose scores changed between two saved reports, or between
+/// a saved report and a fresh analysis with the default options of `analyze`
+fn diff_command(global: &GlobalArgs, args: &DiffArgs, mut analyze: AnalyzeArgs) -> Result<()> {
+    let (config, _, _) = load_config(global)?;
+    let (old, new) = match (&args.baseline, &args.old, &args.new) {
+        (Some(baseline), _, _) => {
+            let old = diff::SavedReport::load(baseline)?;
+
+            // Keep the report in memory only
+            analyze.ephemeral = true;
+            analyze.format.clear();
+            let report = run(global, &analyze, &mut run_summary::RunSummary::new(), None)?
+                .context("The analysis produced no report to compare")?;
+            (old, diff::SavedReport::from_report(&report)?)
+        }
+        (None, Some(old), Some(new)) => {
+            (diff::SavedReport::load(old)?, diff::SavedReport::load(new)?)
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Give two reports to compare, or --baseline"
+            ))
+        }
+    };
+
+    let differences = diff::compare(&old, &new, &config.diff);
+    print!("{}", diff::render(&differences, args.format)?);
+    Ok(())
+}
+
+fn explain_command(global: &GlobalArgs, args: ExplainArgs, mut analyze: AnalyzeArgs) -> Result<()> {
+    analyze.explain = Some(args.file);
+    analyze.json = args.json;
+    analyze_command(global, analyze)
+}
+
+/// `overdoc baseline`: analyze the repository and record the metrics of every file
+fn baseline_command(
+    global: &GlobalArgs,
+    args: BaselineArgs,
+    mut analyze: AnalyzeArgs,
+) -> Result<()> {
+    // Keep the report in memory only; the baseline is written in place of the gates
+    analyze.ephemeral = true;
+    analyze.format.clear();
+    analyze.baseline = Some(args.file);
+    analyze.update_baseline = true;
+    run(global, &analyze, &mut run_summary::RunSummary::new(), None).map(|_| ())
+}
+
+/// `overdoc file`: analyze one file, or content from stdin, without the repository around it;
+/// usage counts and the export importance are left at 0
+fn file_command(global: &GlobalArgs, args: &FileArgs) -> Result<()> {
+    let (config, _, _) = load_config(global)?;
+    let stdin = args.path == "-";
+    let extension = match &args.extension {
+        Some(extension) => extension.trim_start_matches('.').to_string(),
+        None if stdin => {
+            return Err(anyhow::anyhow!(
+                "Reading from stdin needs --extension, e.g. `--extension rs`"
+            ))
+        }
+        None => Path::new(&args.path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
 
-    if args.verbose {
-        builder.filter_level(LevelFilter::Debug);
+    let content = if stdin {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .context("Failed to read stdin")?;
+        content
     } else {
-        builder.filter_level(LevelFilter::Info);
+        fs::read_to_string(&args.path).context(format!("Failed to read {}", args.path))?
+    };
+
+    let complexity_budget = Some(config.default_settings.complexity_time_budget_ms)
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis);
+    let (mut metrics, warnings) = metrics::analyze_content(
+        &args.path,
+        &content,
+        &extension.to_lowercase(),
+        complexity_budget,
+        false,
+        None,
+        &config.scoring,
+    );
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    metrics.complexity_level = metrics
+        .complexity_metrics
+        .as_ref()
+        .map(|complexity| complexity.classification(&config).to_string());
+
+    let (language, exports, imports) =
+        match exports::scan_content(Path::new(&args.path), &extension, &content, &config) {
+            Some((language, mut exports, imports)) => {
+                for export in &mut exports {
+                    export.id = exports::export_id(export, ".");
+                }
+                (Some(language), exports, imports)
+            }
+            None => (None, Vec::new(), Vec::new()),
+        };
+
+    let analysis = FileAnalysis {
+        language,
+        metrics,
+        exports,
+        imports,
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&analysis).context("Failed to serialize the analysis")?
+    );
+    Ok(())
+}
+
+/// --config-path, or the first configuration file found in the working directory (`overdoc.yaml`
+/// without any)
+fn config_path(global: &GlobalArgs) -> String {
+    global.config_path.clone().unwrap_or_else(|| {
+        config::find_config()
+            .unwrap_or(config::CONFIG_FILES[0])
+            .to_string()
+    })
+}
+
+/// Load the configuration and apply the OVERDOC_* environment variables, the --set overrides,
+/// --follow-symlinks and --no-gitignore on top of it, returning the configuration, its path
+/// and the applied overrides
+fn load_config(
+    global: &GlobalArgs,
+) -> Result<(config::Config, String, Vec<config::ConfigOverride>)> {
+    let config_path = config_path(global);
+    if global.config_path.is_none() && Path::new(&config_path).exists() {
+        info!("Using configuration file {}", config_path);
+    }
+    let (mut config, files) = config::load_config(&config_path)
+        .context(format!("Failed to load configuration from {}", config_path))?;
+    if let Some((file, extended)) = files.split_last().filter(|(_, rest)| !rest.is_empty()) {
+        info!(
+            "Loaded configuration from {}, extending {}",
+            file,
+            extended.join(", ")
+        );
+    }
+
+    // Apply OVERDOC_* environment variables, then command line overrides on top of them
+    let mut overrides = Vec::new();
+    for ovr in config::ConfigOverride::from_env()? {
+        config
+            .apply_override(&ovr)
+            .context(format!("Failed to apply {}", ovr.source))?;
+        debug!("Applied environment override: {}", ovr.describe());
+        overrides.push(ovr);
+    }
+    for spec in &global.set {
+        let ovr = config::ConfigOverride::parse(spec, "--set")?;
+        config
+            .apply_override(&ovr)
+            .context(format!("Failed to apply --set {}", spec))?;
+        info!("Applied config override: {}", ovr.describe());
+        overrides.push(ovr);
+    }
+    if global.follow_symlinks {
+        config.default_settings.follow_symlinks = true;
+    }
+    if global.no_gitignore {
+        config.default_settings.respect_gitignore = false;
+    }
+
+    // Show what the overrides produced, without --print-effective-config stopping the run
+    if !overrides.is_empty() && log::log_enabled!(log::Level::Debug) {
+        debug!(
+            "Effective configuration:\n{}",
+            config::render_effective_config(&config, &config_path, &overrides)?
+        );
     }
 
-    builder.init();
+    Ok((config, config_path, overrides))
+}
 
-    if args.verbose {
+/// Analyze the repository and write the requested outputs, recording the run in `summary`,
+/// and return the report; runs that only print something else (--explain, --list-files, ...)
+/// return none. `metrics_cache` provides the metrics of files unchanged since an earlier run.
+fn run(
+    global: &GlobalArgs,
+    args: &AnalyzeArgs,
+    summary: &mut run_summary::RunSummary,
+    metrics_cache: Option<&mut metrics::MetricsCache>,
+) -> Result<Option<report::AnalysisReport>> {
+    if global.verbose {
         info!("Verbose mode enabled");
     }
 
-    // Create output directory if it doesn't exist
+    // Load configuration
+    let (config, config_path, overrides) = load_config(global)?;
+
+    // Every file path is relative to the canonical root, so `.`, trailing slashes and
+    // symlinked roots all give the same paths; with several roots, to their common ancestor
+    let repo_roots = traversal::resolve_roots(&repo_paths(global, &args.repos))?;
+    let repo_root = repo_roots.base.clone();
+    let path_globs = path_globs(global, &repo_root)?;
+    let path_overrides = filter::PathOverrides::new(&config.overrides, &repo_root)?;
+    let config_patterns = filter::ConfigPatterns::new(&config, &repo_root)?;
+    let category_rules = categories::CategoryRules::new(&config.categories)?;
+    let languages = filter::LanguageFilter::new(&global.languages, &config)?;
+    let tests = test_filter(global, &config, &repo_root)?;
+    let filter_rules = filter::FilterRules {
+        config: &config,
+        overrides: &path_overrides,
+        patterns: &config_patterns,
+        globs: &path_globs,
+        languages: &languages,
+        tests: &tests,
+    };
+
+    if args.print_effective_config {
+        print!(
+            "{}",
+            config::render_effective_config(&config, &config_path, &overrides)?
+        );
+        return Ok(None);
+    }
+
+    if args.check_config {
+        let (report, problems) = patterns::render_pattern_report(&config);
+        print!("{}", report);
+
+        if args.paths {
+            let files = traversal::traverse_repository(
+                &repo_roots,
+                &config,
+                args.absolute_paths,
+                args.max_depth,
+            )
+            .context("Failed to traverse repository")?
+            .files;
+            let file_paths: Vec<String> = filter::apply_filters(files, &filter_rules, None)
+                .iter()
+                .map(|file| file.path.to_string_lossy().to_string())
+                .collect();
+            let summary = categories::summarize(&file_paths, &repo_root, &category_rules, true);
+            print!("{}", categories::render_category_report(&summary));
+        }
+
+        if problems > 0 {
+            return Err(anyhow::anyhow!(
+                "Found {} problems in the pattern configuration",
+                problems
+            ));
+        }
+        return Ok(None);
+    }
+
+    if args.archive {
+        archive::ensure_enabled()?;
+    }
+
+    // Parse the report template up front so mistakes don't cost a full analysis
+    let template = args
+        .template
+        .as_deref()
+        .map(report::template::ReportTemplate::load)
+        .transpose()?;
+
+    // Recorded in every output so reports of different runs can be compared
+    let run_metadata = report::meta::RunMetadata::new(&repo_root, &config_path, &config)?;
+
+    // Persistent artifacts live under the output directory unless given explicitly
     let output_dir = Path::new(&args.output_dir);
-    if !output_dir.exists() {
-        info!("Creating output directory: {}", output_dir.display());
-        fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+    let output_stem = output_stem(args)?;
+    let db_path = args.db.as_ref().map(|db| {
+        if db.is_empty() {
+            output_dir
+                .join("overdoc.sqlite")
+                .to_string_lossy()
+                .to_string()
+        } else {
+            db.clone()
+        }
+    });
+
+    if let (Some(db_path), Some(file)) = (&db_path, &args.history) {
+        return history::print_history(db_path, file).map(|()| None);
     }
 
-    // Load configuration
-    let config_path = args
-        .config_path
-        .unwrap_or_else(|| "overdoc.yaml".to_string());
-    let config = config::load_config(&config_path)
-        .context(format!("Failed to load configuration from {}", config_path))?;
+    // The executive summary compares against the latest run recorded before this one
+    let previous_run = match &db_path {
+        Some(db_path) if config.executive_summary.enabled => {
+            history::previous_run(db_path, &repo_root)?
+        }
+        _ => None,
+    };
 
-    info!("Starting repository analysis at: {}", args.repo_path);
+    // Make sure results can be written before spending time on the analysis
+    // (--explain, --list-files and --ephemeral only print)
+    if args.explain.is_none() && !args.list_files && !args.ephemeral {
+        ensure_writable_dir(output_dir)?;
 
-    // Phase 1: Traverse repository and filter files
-    let files = traversal::traverse_repository(&args.repo_path, &config)
-        .context("Failed to traverse repository")?;
+        // --watch checks once before its first run, then keeps rewriting its own report
+        if !args.watch {
+            check_overwrite(args, output_dir, output_stem.as_deref())?;
+        }
+
+        if let Some(db_dir) = db_path.as_deref().and_then(|db| Path::new(db).parent()) {
+            if !db_dir.as_os_str().is_empty() {
+                ensure_writable_dir(db_dir)?;
+            }
+        }
+    }
+
+    info!("Starting repository analysis at: {}", repo_root);
+
+    // Phase 1: Traverse repository (or take the listed files) and filter files
+    let started = Instant::now();
+    if args.max_depth == Some(0) {
+        return Err(anyhow::anyhow!(
+            "--max-depth must be at least 1, the files directly in the repository root"
+        ));
+    }
+    let traversal = match &args.files_from {
+        Some(source) => traversal::Traversal {
+            files: traversal::files_from_list(
+                &traversal::read_file_list(source)?,
+                &repo_roots,
+                &config,
+                args.absolute_paths,
+            )?,
+            cut_directories: 0,
+        },
+        None => traversal::traverse_repository(
+            &repo_roots,
+            &config,
+            args.absolute_paths,
+            args.max_depth,
+        )
+        .context("Failed to traverse repository")?,
+    };
+    let files = traversal.files;
+    summary.phase("traversal", started);
 
     info!("Found {} files for analysis", files.len());
 
-    let filtered_files = filter::apply_filters(files, &config);
+    if args.list_files {
+        print!(
+            "{}",
+            filter::render_file_list(&files, &repo_root, &filter_rules)
+        );
+        return Ok(None);
+    }
+
+    // --explain reports on filtered-out files too, so keep the full list around
+    let all_files = if args.explain.is_some() {
+        files.clone()
+    } else {
+        Vec::new()
+    };
+
+    // With --audit-pipeline every phase records the files it received, emitted and excluded
+    let mut pipeline_audit = args.audit_pipeline.then(audit::PipelineAudit::new);
+    let mut filter_trace = pipeline_audit.as_ref().map(|_| {
+        audit::PhaseTrace::new(
+            "filter",
+            files
+                .iter()
+                .map(|file| file.path.to_string_lossy().to_string()),
+        )
+    });
+
+    let traversed = files.len();
+    let filter_started = Instant::now();
+    let mut filtered_files = filter::apply_filters(files, &filter_rules, filter_trace.as_mut());
+
+    // The baseline file holds recorded metrics, not code to measure
+    if let Some(baseline_file) = args
+        .baseline
+        .as_ref()
+        .and_then(|path| fs::canonicalize(path).ok())
+    {
+        filtered_files.retain(|file| {
+            let is_baseline = fs::canonicalize(traversal::on_disk(&repo_root, &file.path))
+                .is_ok_and(|path| path == baseline_file);
+            if is_baseline {
+                if let Some(trace) = filter_trace.as_mut() {
+                    trace.exclude(&file.path.to_string_lossy(), "the --baseline file");
+                }
+            }
+            !is_baseline
+        });
+    }
+    summary.files(traversed, filtered_files.len());
+
+    // Guard against analyzing far more than intended, e.g. a home directory
+    let mut limit_trace = None;
+    let (filtered_files, truncation) = match args.max_files {
+        Some(max_files) => {
+            limit_trace = pipeline_audit.as_ref().map(|_| {
+                audit::PhaseTrace::new(
+                    "max-files",
+                    filtered_files
+                        .iter()
+                        .map(|file| file.path.to_string_lossy().to_string()),
+                )
+            });
+            filter::limit_files(filtered_files, max_files, &config, limit_trace.as_mut())
+        }
+        None => (filtered_files, None),
+    };
+    if let Some(truncation) = &truncation {
+        summary.files_truncated = truncation.found - truncation.max_files;
+    }
+    summary.phase("filter", filter_started);
 
     info!(
         "After filtering, {} files remain for documentation",
         filtered_files.len()
     );
 
+    // With --only every file is scanned for imports, so usage counts include importers
+    // outside the prefix, but only the files below it are measured and reported
+    let only = args
+        .only
+        .as_deref()
+        .map(|prefix| filter::Subtree::new(prefix, &repo_root))
+        .transpose()?;
+    let since = args
+        .since
+        .as_deref()
+        .map(|reference| filter::ChangedFiles::new(reference, &repo_root))
+        .transpose()?;
+    let reported_path = |path: &str| {
+        only.as_ref().is_none_or(|only| only.contains(path))
+            && since.as_ref().is_none_or(|since| since.contains(path))
+    };
+    let reported = |file: &traversal::RepoFile| reported_path(&file.path.to_string_lossy());
+
+    // Convert filtered_files to a vector of strings
+    let file_paths: Vec<String> = filtered_files
+        .iter()
+        .filter(|file| reported(file))
+        .map(|file| file.path.to_string_lossy().to_string())
+        .collect();
+    if let Some(only) = &only {
+        if !filtered_files
+            .iter()
+            .any(|file| only.contains(&file.path.to_string_lossy()))
+        {
+            return Err(anyhow::anyhow!(
+                "--only {} matches none of the {} analyzed files",
+                only.prefix(),
+                filtered_files.len()
+            ));
+        }
+        info!(
+            "Reporting on the {} files under {}; all {} files are scanned for imports",
+            file_paths.len(),
+            only.prefix(),
+            filtered_files.len()
+        );
+    }
+    if let Some(since) = &since {
+        if file_paths.is_empty() {
+            return Err(anyhow::anyhow!(
+                "None of the {} analyzed files{} changed since {}",
+                filtered_files.len(),
+                only.as_ref()
+                    .map(|only| format!(" under {}", only.prefix()))
+                    .unwrap_or_default(),
+                since.reference()
+            ));
+        }
+        info!(
+            "Reporting on the {} files changed since {}; all {} files are scanned for imports",
+            file_paths.len(),
+            since.reference(),
+            filtered_files.len()
+        );
+    }
+
+    // With several repositories, the files of each for its own totals
+    let repositories: Vec<(String, Vec<String>)> = if repo_roots.roots.len() > 1 {
+        repo_roots
+            .names()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let paths = filtered_files
+                    .iter()
+                    .filter(|file| file.root == idx && reported(file))
+                    .map(|file| file.path.to_string_lossy().to_string())
+                    .collect();
+                (name, paths)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let test_files: Vec<String> = filtered_files
+        .iter()
+        .filter(|file| file.is_test && reported(file))
+        .map(|file| file.path.to_string_lossy().to_string())
+        .collect();
+
+    // Load external signals up front so a broken file fails before the analysis
+    let mut file_signals = match &args.signals {
+        Some(path) => signals::load(path, &repo_root, &file_paths)?,
+        None => HashMap::new(),
+    };
+    if args.signals.is_some() && args.skip_metrics {
+        warn!("--signals has no effect with --skip-metrics");
+    }
+
+    // Command line limits take precedence over the `thresholds` section
+    let limits = config::ThresholdsConfig {
+        maintainability_below: args
+            .fail_on_maintainability_below
+            .or(config.thresholds.maintainability_below),
+        avg_cyclomatic_above: args
+            .fail_on_avg_cyclomatic_above
+            .or(config.thresholds.avg_cyclomatic_above),
+        file_cyclomatic_above: args
+            .fail_on_file_cyclomatic_above
+            .or(config.thresholds.file_cyclomatic_above),
+        baseline_tolerance: config.thresholds.baseline_tolerance,
+    };
+    let any_limit = limits.maintainability_below.is_some()
+        || limits.avg_cyclomatic_above.is_some()
+        || limits.file_cyclomatic_above.is_some();
+    if any_limit && args.skip_metrics {
+        return Err(anyhow::anyhow!(
+            "Quality thresholds (--fail-on-* or the `thresholds` section) need metrics; drop --skip-metrics"
+        ));
+    }
+
+    if args.baseline.is_some() && args.skip_metrics {
+        return Err(anyhow::anyhow!(
+            "--baseline compares metrics; drop --skip-metrics"
+        ));
+    }
+    let baseline = match &args.baseline {
+        Some(path) if !args.update_baseline => Some(baseline::Baseline::load(path)?),
+        _ => None,
+    };
+
+    if args.sort_by.needs_metrics() && args.skip_metrics {
+        return Err(anyhow::anyhow!(
+            "--sort-by {} needs metrics; drop --skip-metrics or use --sort-by importance",
+            format!("{:?}", args.sort_by).to_lowercase()
+        ));
+    }
+
+    // Sort files into categories; the first matching rule wins
+    let category_summary =
+        categories::summarize(&file_paths, &repo_root, &category_rules, global.verbose);
+    if category_summary.ambiguous_count > 0 && !global.verbose {
+        info!(
+            "{} files match several categories; run with --verbose or --check-config --paths to list them",
+            category_summary.ambiguous_count
+        );
+    }
+
     // Phase 2: Scan for exports and imports
-    let (mut exports_map, imports_map) = exports::scan_repository(&filtered_files, &config)
-        .context("Failed to scan repository for exports and imports")?;
+    let started = Instant::now();
+    let mut exports_trace = pipeline_audit.as_ref().map(|_| {
+        audit::PhaseTrace::new(
+            "exports",
+            filtered_files
+                .iter()
+                .map(|file| file.path.to_string_lossy().to_string()),
+        )
+    });
+    let (mut exports_map, imports_map, unreadable) =
+        exports::scan_repository(&filtered_files, &repo_root, &config, exports_trace.as_mut())
+            .context("Failed to scan repository for exports and imports")?;
+    let narrowed = only.is_some() || since.is_some();
+    let only_trace = pipeline_audit.as_ref().filter(|_| narrowed).map(|_| {
+        let mut trace = audit::PhaseTrace::new(
+            "only",
+            filtered_files
+                .iter()
+                .map(|file| file.path.to_string_lossy().to_string()),
+        );
+        for file in &filtered_files {
+            let path = file.path.to_string_lossy();
+            if reported(file) {
+                trace.emit(&path);
+            } else {
+                trace.exclude(&path, "scanned for imports only, outside --only or --since");
+            }
+        }
+        trace
+    });
+    if let Some(pipeline_audit) = pipeline_audit.as_mut() {
+        for trace in filter_trace
+            .into_iter()
+            .chain(limit_trace)
+            .chain(exports_trace)
+            .chain(only_trace)
+        {
+            pipeline_audit.push(trace);
+        }
+    }
+
+    // Point re-exports at the file holding their original definition
+    exports::resolve_reexports(&mut exports_map);
+    exports::assign_export_ids(&mut exports_map, &repo_root);
+    summary.unreadable(&unreadable);
+    summary.phase("exports", started);
 
     // Count exports
     let total_exports = exports_map.values().map(|v| v.len()).sum::<usize>();
@@ -100,70 +1438,148 @@ fn main() -> Result<()> {
         total_exports,
         exports_map.len()
     );
+    summary.total_exports = total_exports;
+    summary.unresolved_imports = dependencies::count_unresolved_imports(&exports_map, &imports_map);
 
     // Build dependency graph
+    let started = Instant::now();
     let dependency_graph = dependencies::build_dependency_graph(&mut exports_map, &imports_map)
         .context("Failed to build dependency graph")?;
+    summary.phase("graph", started);
 
-    // Calculate directory importance
-    let dir_importance =
-        dependencies::calculate_directory_importance(&dependency_graph, &exports_map);
+    // The usage counts are in, so the exports outside --only and --since can go
+    if narrowed {
+        exports_map.retain(|path, _| reported_path(path));
+    }
 
-    // Display top important files
-    let top_files = dependency_graph.get_files_by_importance();
+    // Flag runs where every importance score is 0, which usually means the analysis is misconfigured
+    let mut notices: Vec<String> =
+        dependencies::diagnose_zero_importance(&exports_map, &file_paths, &config.languages)
+            .into_iter()
+            .collect();
+    for notice in &notices {
+        warn!("{}", notice);
+    }
 
-    info!("Top {} important files:", args.top_files);
+    // The traversal already warned about the directories --max-depth left out
+    if traversal.cut_directories > 0 {
+        notices.insert(
+            0,
+            format!(
+                "Partial result: traversal stopped at --max-depth {}, so the contents of {} directories were not analyzed",
+                args.max_depth.unwrap_or_default(),
+                traversal.cut_directories
+            ),
+        );
+    }
+
+    // Aggregate the import statements for the import style audit
+    let mut import_statements = import_stats::collect_statements(&imports_map, &repo_root);
+    if narrowed {
+        import_statements.retain(|statement| reported_path(&statement.path));
+    }
+    let import_summary = import_stats::aggregate(&import_statements, &repo_root, args.top_files);
+
+    // Calculate directory importance
+    let directory_casing = directories::DirectoryCasing::resolve(
+        config.default_settings.path_case,
+        &repo_root,
+        &file_paths,
+    );
+    let mut dir_importance = dependencies::calculate_directory_importance(
+        &dependency_graph,
+        &exports_map,
+        &directory_casing,
+    );
+    if narrowed {
+        dir_importance.retain(|dir, _| reported_path(dir));
+    }
+
+    // JSON Lines are streamed while the metrics are computed instead of after the report is built
+    let mut jsonl_sink: Option<Box<dyn Write>> =
+        if args.format.contains(&report::OutputFormat::Jsonl)
+            && !args.skip_metrics
+            && args.explain.is_none()
+        {
+            if args.ephemeral {
+                Some(Box::new(io::stdout().lock()))
+            } else {
+                let jsonl_file = output_dir
+                    .join(report::OutputFormat::Jsonl.output_name(output_stem.as_deref()));
+                let file = fs::File::create(&jsonl_file)
+                    .context(format!("Failed to create {}", jsonl_file.display()))?;
+                Some(Box::new(BufWriter::new(file)))
+            }
+        } else {
+            None
+        };
 
     // Phase 3: Detailed metrics analysis (new)
+    let started = Instant::now();
     let repository_metrics = if !args.skip_metrics {
         info!("Starting detailed metrics analysis...");
-        // Convert filtered_files to a vector of strings
-        let file_paths: Vec<String> = filtered_files
-            .iter()
-            .map(|file| file.path.to_string_lossy().to_string())
-            .collect();
 
-        // Calculate initial metrics
-        let mut metrics = metrics::analyze_repository(&file_paths)
-            .context("Failed to analyze repository metrics")?;
+        let retain_files = needs_file_metrics(args);
+        if !retain_files {
+            info!(
+                "Only JSON Lines requested; per-file metrics are streamed and not kept in memory"
+            );
+        }
 
-        // Calculate export importance for each file using data from exports_map
-        let max_importance = dependency_graph
-            .get_files_by_importance()
-            .iter()
-            .map(|(_, score)| *score)
-            .max()
-            .unwrap_or(1);
-
-        // Normalize export importance and add to metrics
-        for (file_path, importance) in dependency_graph.get_files_by_importance().iter() {
-            if let Some(file_metrics) = metrics.file_metrics.get_mut(file_path) {
-                // Normalize to 0-1 scale
-                let normalized_importance = *importance as f64 / max_importance as f64;
-                file_metrics.with_export_importance(normalized_importance);
-
-                // Recalculate knowledge score if complexity metrics exist
-                if let Some(complexity) = &file_metrics.complexity_metrics {
-                    // Clone complexity before we use it
-                    let complexity_clone = complexity.clone();
-                    file_metrics.knowledge_score = Some(metrics::calculate_knowledge_score(
+        let complexity_budget = Some(config.default_settings.complexity_time_budget_ms)
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+
+        let deep_gate = config
+            .deep_analysis
+            .enabled
+            .then(|| metrics::DeepAnalysisGate::new(&config.deep_analysis, &dependency_graph));
+
+        let mut metrics_trace = pipeline_audit
+            .as_ref()
+            .map(|_| audit::PhaseTrace::new("metrics", file_paths.iter().cloned()));
+        let options = metrics::AnalysisOptions {
+            retain_files,
+            complexity_budget,
+            deep_gate: deep_gate.as_ref(),
+            time_files: args.timings,
+            overrides: &path_overrides,
+            scoring: &config.scoring,
+        };
+        let metrics = metrics::analyze_repository(
+            &file_paths,
+            &repo_root,
+            &options,
+            metrics_cache,
+            metrics_trace.as_mut(),
+            |file_path, file_metrics| {
+                if let Some(signals) = file_signals.remove(file_path) {
+                    file_metrics.with_signals(signals, &config.scoring.extra);
+                }
+
+                // Streamed lines are written before finalize, so merge the importance now
+                if let Some(sink) = jsonl_sink.as_mut() {
+                    file_metrics.with_export_importance(
+                        dependency_graph.normalized_importance(file_path),
+                        &config.scoring,
+                    );
+                    let line = report::jsonl::render_file(
                         file_metrics,
-                        &complexity_clone,
-                    ));
+                        dependency_graph.get_file_importance(file_path),
+                    )?;
+                    writeln!(sink, "{}", line).context("Failed to write JSON Lines output")?;
                 }
-            }
+                Ok(())
+            },
+        );
+        let mut metrics = metrics.context("Failed to analyze repository metrics")?;
+        if let (Some(pipeline_audit), Some(trace)) = (pipeline_audit.as_mut(), metrics_trace) {
+            pipeline_audit.push(trace);
         }
-
-        // Rebuild knowledge hotspots with updated scores
-        let mut knowledge_hotspots: Vec<(String, f64)> = metrics
-            .file_metrics
-            .iter()
-            .map(|(path, metrics)| (path.clone(), metrics.knowledge_score()))
-            .collect();
-
-        knowledge_hotspots
-            .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        metrics.knowledge_hotspots = knowledge_hotspots;
+        metrics.finalize(&dependency_graph, &config);
+        summary.unreadable(&metrics.failed_files);
+        summary.file_timings(&metrics.file_timings);
+        summary.phase("metrics", started);
 
         info!(
             "Metrics analysis complete: {} files, {} total lines, {} code lines",
@@ -176,221 +1592,682 @@ fn main() -> Result<()> {
         None
     };
 
-    // Create a markdown file with the analysis results
-    let mut analysis_content = format!("# OverDoc Analysis Results\n\n");
-    analysis_content.push_str("## Repository: ");
-    analysis_content.push_str(&args.repo_path);
-    analysis_content.push_str("\n\n");
+    // Run finding rules against the import statements and the collected metrics
+    let mut findings = findings::import_findings(&import_statements, &config.findings);
+    if let Some(metrics) = &repository_metrics {
+        findings.extend(findings::collect_findings(metrics, &config.findings));
+    }
+    findings::sort_findings(&mut findings);
 
-    // Add summary statistics
-    analysis_content.push_str("## Summary\n\n");
-    analysis_content.push_str(&format!(
-        "- Total files analyzed: {}\n",
-        filtered_files.len()
-    ));
-    analysis_content.push_str(&format!("- Total exported entities: {}\n", total_exports));
-    analysis_content.push_str(&format!("- Files with exports: {}\n", exports_map.len()));
+    if !findings.is_empty() {
+        info!("Found {} findings", findings.len());
+        for finding in &findings {
+            debug!("{}", finding);
+        }
+    }
 
-    // Add metrics summary if available
-    if let Some(metrics) = &repository_metrics {
-        analysis_content.push_str(&format!("- Total lines of code: {}\n", metrics.total_lines));
-        analysis_content.push_str(&format!("- Code lines: {}\n", metrics.total_code_lines));
-        analysis_content.push_str(&format!(
-            "- Comment lines: {}\n",
-            metrics.total_comment_lines
-        ));
-        analysis_content.push_str(&format!("- Blank lines: {}\n", metrics.total_blank_lines));
-        analysis_content.push_str(&format!(
-            "- Comment ratio: {:.2}%\n",
-            metrics.avg_comment_ratio * 100.0
-        ));
-        analysis_content.push_str(&format!(
-            "- Average lines per file: {}\n",
-            metrics.avg_lines_per_file
-        ));
+    if let Some(target) = &args.explain {
+        let explanation = explain::explain_file(
+            target,
+            &explain::ExplainInput {
+                repo_path: &repo_root,
+                config: &config,
+                overrides: &path_overrides,
+                patterns: &config_patterns,
+                category_rules: &category_rules,
+                path_globs: &path_globs,
+                languages: &languages,
+                tests: &tests,
+                files: &all_files,
+                exports_map: &exports_map,
+                imports_map: &imports_map,
+                dependency_graph: &dependency_graph,
+                metrics: repository_metrics.as_ref(),
+                findings: &findings,
+            },
+        )?;
+
+        if args.json {
+            println!("{}", explain::render_json(&explanation)?);
+        } else {
+            print!("{}", explain::render_text(&explanation, &repo_root));
+        }
+        return Ok(None);
+    }
 
-        // Add complexity metrics summary
-        analysis_content.push_str(&format!(
-            "- Average cyclomatic complexity: {:.2}\n",
-            metrics.avg_cyclomatic_complexity
-        ));
-        analysis_content.push_str(&format!(
-            "- Average cognitive complexity: {:.2}\n",
-            metrics.avg_cognitive_complexity
-        ));
-        analysis_content.push_str(&format!(
-            "- Average maintainability index: {:.2}\n",
-            metrics.avg_maintainability_index
-        ));
+    // Attribute files to owners if the repository has a CODEOWNERS file
+    let code_owners = owners::CodeOwners::discover(&repo_root)?;
+    if let Some(code_owners) = &code_owners {
+        info!("Using code owners from {}", code_owners.source.display());
+    }
 
-        // Add language distribution
-        analysis_content.push_str("\n### Language Distribution\n\n");
-        let mut lang_dist: Vec<(String, usize)> = metrics
-            .language_distribution
-            .iter()
-            .map(|(k, v)| (k.clone(), *v))
-            .collect();
-        lang_dist.sort_by(|a, b| b.1.cmp(&a.1));
+    let scope = if args.scope.is_empty() && args.scope_owner.is_none() {
+        None
+    } else {
+        Some(report::scope::Scope::new(
+            &args.scope,
+            args.scope_owner.as_deref(),
+            code_owners.as_ref(),
+        )?)
+    };
 
-        for (lang, count) in lang_dist {
-            let percentage = (count as f64 / metrics.total_files as f64) * 100.0;
-            analysis_content.push_str(&format!(
-                "- {}: {} files ({:.1}%)\n",
-                lang, count, percentage
-            ));
+    let report = report::build_report(report::ReportInput {
+        meta: run_metadata,
+        repo_path: &repo_root,
+        file_paths: &file_paths,
+        exports_map: &exports_map,
+        imports_map: &imports_map,
+        dependency_graph: &dependency_graph,
+        dir_importance,
+        repositories,
+        test_files,
+        only: only.as_ref().map(|only| only.prefix()),
+        since: since.as_ref().map(|since| since.reference()),
+        partial: truncation,
+        output_stem: output_stem.as_deref(),
+        directory_casing: &directory_casing,
+        metrics: repository_metrics,
+        findings,
+        notices,
+        categories: category_summary,
+        category_rules: &category_rules,
+        import_stats: import_summary,
+        code_owners: code_owners.as_ref(),
+        leaf_directories: &config.architecture.leaf_directories,
+        top_n: args.top_files,
+        top_files_order: args.sort_by,
+        min_importance: args.min_importance.unwrap_or(config.report.min_importance),
+        min_knowledge_score: args
+            .min_knowledge_score
+            .unwrap_or(config.report.min_knowledge_score),
+        top_dirs: args.top_dirs.unwrap_or(args.top_files),
+        top_exports: args.top_exports,
+        per_file_pages: args.per_file_pages,
+        per_dir_pages: args.per_dir_pages,
+        limits: &config.report,
+        thresholds: &config.findings,
+        unused_exports: &config.unused_exports,
+        configured_languages: &config.languages,
+        languages: if args.by_language || args.group_by_language {
+            config.languages.iter().collect()
+        } else {
+            Vec::new()
+        },
+        language_pages: args.by_language,
+        group_by_language: args.group_by_language,
+        verbose: global.verbose,
+        scope: scope.as_ref(),
+        html: report::html::HtmlOptions {
+            theme: args.theme,
+            title: args.title.clone(),
+            // Printed output can't link to files
+            inline_assets: args.inline_assets || args.ephemeral,
+        },
+        executive_summary: &config.executive_summary,
+        previous_run: previous_run.as_ref(),
+    });
+
+    // The report phase receives what the metrics phase emitted and keeps per-file metrics for it
+    if let Some(pipeline_audit) = pipeline_audit.as_mut() {
+        if let Some(metrics) = report.metrics.as_ref().filter(|_| needs_file_metrics(args)) {
+            let received: Vec<String> = pipeline_audit
+                .phase("metrics")
+                .map(|trace| trace.emitted().cloned().collect())
+                .unwrap_or_default();
+            let mut trace = audit::PhaseTrace::new("report", received);
+            for path in metrics.file_metrics.keys() {
+                trace.emit(path);
+            }
+            pipeline_audit.push(trace);
         }
+        pipeline_audit.log();
+    }
 
-        // Add knowledge hotspots section
-        if !metrics.knowledge_hotspots.is_empty() {
-            analysis_content.push_str("\n### Knowledge Hotspots\n\n");
-            analysis_content.push_str("Files with highest knowledge scores (combining complexity, size, and importance):\n\n");
+    // On a terminal the top files and directories are tables on stdout instead of log lines,
+    // unless stdout carries the report (--print) or the run summary (--summary-json)
+    let color = global.color();
+    if term_report::wanted(color)
+        && global.log_level() >= LevelFilter::Info
+        && !args.ephemeral
+        && !args.summary_json
+    {
+        print!(
+            "{}",
+            term_report::render(&report, color.enabled(io::stdout().is_terminal()))
+        );
+    } else {
+        log_top_entries(args, &report);
+    }
 
-            for (idx, (file, score)) in metrics.knowledge_hotspots.iter().take(5).enumerate() {
-                analysis_content.push_str(&format!(
-                    "{}. **{}** (Knowledge Score: {:.1})\n",
-                    idx + 1,
-                    file,
-                    score
-                ));
+    // Print instead of writing any files
+    let mut failed_formats: Vec<report::OutputFormat> = Vec::new();
+    if args.ephemeral {
+        for format in &args.format {
+            let result = match jsonl_sink
+                .as_mut()
+                .filter(|_| *format == report::OutputFormat::Jsonl)
+            {
+                Some(sink) => finish_jsonl(sink, &report),
+                None => render_format(*format, &report, template.as_ref())
+                    .map(|content| println!("{}", content)),
+            };
+            if let Err(err) = result {
+                error!(
+                    "Failed to produce {}: {:#}",
+                    report.output_name(*format),
+                    err
+                );
+                failed_formats.push(*format);
             }
         }
+
+        if args.per_file_pages
+            || args.per_dir_pages
+            || args.by_language
+            || args.badges
+            || args.emit_treemap
+            || args.emit_symbols
+            || args.docs_data.is_some()
+            || args.glossary
+            || args.audit_pipeline
+            || !args.emit_graph.is_empty()
+        {
+            warn!(
+                "--ephemeral is set; skipping pages, sub-reports, badges, treemap, symbols, docs data, glossary, audit and graph files"
+            );
+        }
+        check_failed_formats(&report, &failed_formats, args.format.len())?;
+        check_unused_exports(args, &report)?;
+        check_thresholds(args, &report, &limits, &path_overrides, baseline.as_ref())?;
+        return Ok(Some(report));
     }
 
-    analysis_content.push_str("\n");
+    // Write the report in every requested format; a failing format doesn't stop the others
+    for format in &args.format {
+        let output_file = output_dir.join(report.output_name(*format));
+
+        // Streamed JSON Lines only lack the closing summary line
+        let result = match jsonl_sink
+            .as_mut()
+            .filter(|_| *format == report::OutputFormat::Jsonl)
+        {
+            Some(sink) => finish_jsonl(sink, &report),
+            None => render_format(*format, &report, template.as_ref()).and_then(|content| {
+                fs::write(&output_file, content).context(format!(
+                    "Failed to write analysis to {}",
+                    output_file.display()
+                ))
+            }),
+        };
+
+        match result {
+            Ok(()) => {
+                info!("Analysis saved to {}", output_file.display());
+                summary.artifact(&output_file);
+            }
+            Err(err) => {
+                error!("Failed to produce {}: {:#}", output_file.display(), err);
+                failed_formats.push(*format);
+            }
+        }
+    }
 
-    // Add top important files
-    analysis_content.push_str("## Top Important Files\n\n");
-    for (idx, (file_path, score)) in top_files.iter().take(args.top_files).enumerate() {
-        info!("  {}. {} (Score: {})", idx + 1, file_path, score);
-        analysis_content.push_str(&format!(
-            "{}. **{}** (Score: {})\n",
-            idx + 1,
-            file_path,
-            score
-        ));
+    // Write the stylesheet and script index.html links to
+    if args.format.contains(&report::OutputFormat::Html) && !report.html.inline_assets {
+        let assets_dir = output_dir.join(report::html::ASSETS_DIR);
+        fs::create_dir_all(&assets_dir).context(format!(
+            "Failed to create directory {}",
+            assets_dir.display()
+        ))?;
+
+        for (name, content) in report::html::assets(&report.html) {
+            let asset_file = assets_dir.join(name);
+            fs::write(&asset_file, content)
+                .context(format!("Failed to write {}", asset_file.display()))?;
+            summary.artifact(&asset_file);
+        }
+    }
 
-        // If verbose, show the exports and their usage counts
-        if args.verbose && idx < 5 {
-            if let Some(exports) = exports_map.get(file_path) {
-                for export in exports {
-                    info!(
-                        "     - {} {} (used {} times)",
-                        export.export_type, export.name, export.usage_count
-                    );
-                    analysis_content.push_str(&format!(
-                        "   - {} `{}` (used {} times)\n",
-                        export.export_type, export.name, export.usage_count
-                    ));
-                }
+    // Write one page per top file
+    if args.per_file_pages {
+        let pages_dir = output_dir.join(report::pages::PAGES_DIR);
+        fs::create_dir_all(&pages_dir).context(format!(
+            "Failed to create directory {}",
+            pages_dir.display()
+        ))?;
+
+        for top_file in &report.top_files {
+            if let Some(page) = report.file_pages.get(&top_file.path) {
+                let page_file = pages_dir.join(page);
+                fs::write(
+                    &page_file,
+                    report::pages::render_file_page(top_file, &report),
+                )
+                .context(format!("Failed to write {}", page_file.display()))?;
+                summary.artifact(&page_file);
             }
         }
 
-        // Add metrics for this file if available
-        if let Some(metrics) = &repository_metrics {
-            if let Some(file_metrics) = metrics.file_metrics.get(file_path) {
-                analysis_content.push_str(&format!(
-                    "   - Lines: {} (Code: {}, Comments: {}, Blank: {})\n",
-                    file_metrics.line_count,
-                    file_metrics.code_lines,
-                    file_metrics.comment_lines,
-                    file_metrics.blank_lines
-                ));
-
-                analysis_content.push_str(&format!(
-                    "   - Functions: {}, Comment ratio: {:.1}%\n",
-                    file_metrics.function_count,
-                    file_metrics.comment_ratio() * 100.0
-                ));
-
-                if !file_metrics.declaration_count.is_empty() {
-                    let decl_str = file_metrics
-                        .declaration_count
-                        .iter()
-                        .map(|(k, v)| format!("{}: {}", k, v))
-                        .collect::<Vec<String>>()
-                        .join(", ");
+        info!(
+            "Wrote {} file pages to {}",
+            report.file_pages.len(),
+            pages_dir.display()
+        );
+    }
 
-                    analysis_content.push_str(&format!("   - Declarations: {}\n", decl_str));
-                }
+    // Write one page per top directory
+    if args.per_dir_pages {
+        let dirs_dir = output_dir.join(report::pages::DIRS_DIR);
+        fs::create_dir_all(&dirs_dir)
+            .context(format!("Failed to create directory {}", dirs_dir.display()))?;
+
+        for (dir, dir_summary) in report.top_directories.iter().zip(&report.directories) {
+            if let Some(page) = report.dir_pages.get(&dir.path) {
+                let page_file = dirs_dir.join(page);
+                fs::write(
+                    &page_file,
+                    report::pages::render_directory_page(dir, dir_summary, &report),
+                )
+                .context(format!("Failed to write {}", page_file.display()))?;
+                summary.artifact(&page_file);
+            }
+        }
+
+        info!(
+            "Wrote {} directory pages to {}",
+            report.dir_pages.len(),
+            dirs_dir.display()
+        );
+    }
+
+    // Write one sub-report per language
+    if args.by_language {
+        let languages_dir = output_dir.join(report::markdown::LANGUAGES_DIR);
+        fs::create_dir_all(&languages_dir).context(format!(
+            "Failed to create directory {}",
+            languages_dir.display()
+        ))?;
+
+        for language in &report.languages {
+            let language_file = languages_dir.join(&language.page);
+            fs::write(
+                &language_file,
+                report::markdown::render_language(&report, language),
+            )
+            .context(format!("Failed to write {}", language_file.display()))?;
+            summary.artifact(&language_file);
+        }
+
+        info!(
+            "Wrote {} language reports to {}",
+            report.languages.len(),
+            languages_dir.display()
+        );
+    }
 
-                // Add complexity metrics if available
-                if let Some(complexity) = &file_metrics.complexity_metrics {
-                    analysis_content.push_str(&format!(
-                        "   - Complexity: {} (Cyclomatic: {:.1}, Cognitive: {:.1})\n",
-                        complexity.description(),
-                        complexity.cyclomatic_complexity,
-                        complexity.cognitive_complexity
-                    ));
-
-                    analysis_content.push_str(&format!(
-                        "   - Maintainability Index: {:.1} (Higher is better)\n",
-                        complexity.maintainability_index
-                    ));
-
-                    analysis_content.push_str(&format!(
-                        "   - Knowledge Score: {:.1}\n",
-                        file_metrics.knowledge_score()
-                    ));
+    // Write badges from the repository-wide metrics
+    if args.badges {
+        match &report.metrics {
+            Some(metrics) => {
+                let badges_dir = output_dir.join(report::badges::BADGES_DIR);
+                fs::create_dir_all(&badges_dir).context(format!(
+                    "Failed to create directory {}",
+                    badges_dir.display()
+                ))?;
+
+                for (name, svg) in report::badges::render_badges(metrics, &config.badges) {
+                    let badge_file = badges_dir.join(name);
+                    fs::write(&badge_file, svg)
+                        .context(format!("Failed to write {}", badge_file.display()))?;
+                    summary.artifact(&badge_file);
                 }
+
+                info!("Badges saved to {}", badges_dir.display());
             }
+            None => warn!("Skipping badges: they need metrics, which --skip-metrics disabled"),
         }
+    }
 
-        analysis_content.push_str("\n");
+    // Record the run for historical tracking
+    if let Some(db_path) = &db_path {
+        let run_id = history::record_run(db_path, &report)
+            .context(format!("Failed to record run in {}", db_path))?;
+        info!("Recorded run {} in {}", run_id, db_path);
+        summary.artifact(Path::new(db_path));
     }
 
-    // Display top important directories
-    let mut dir_scores: Vec<(String, usize)> = dir_importance.into_iter().collect();
-    dir_scores.sort_by(|a, b| b.1.cmp(&a.1));
+    // Write the raw exports and imports if requested
+    if args.emit_symbols {
+        let symbols_file = output_dir.join(report::symbols::FILE_NAME);
+        fs::write(
+            &symbols_file,
+            report::symbols::render(&exports_map, &imports_map)?,
+        )
+        .context(format!(
+            "Failed to write symbols to {}",
+            symbols_file.display()
+        ))?;
+        info!("Symbols saved to {}", symbols_file.display());
+        summary.artifact(&symbols_file);
+    }
 
-    info!("Top {} important directories:", args.top_files);
-    analysis_content.push_str("## Top Important Directories\n\n");
+    // Write the docs-site data if requested
+    if let Some(site) = args.docs_data {
+        let docs_dir = output_dir.join(report::docs_data::DOCS_DATA_DIR);
+        let pages = report::docs_data::render(&report, site)?;
+        for (name, content) in &pages {
+            let page_file = docs_dir.join(name);
+            if let Some(parent) = page_file.parent() {
+                fs::create_dir_all(parent)
+                    .context(format!("Failed to create directory {}", parent.display()))?;
+            }
+            fs::write(&page_file, content)
+                .context(format!("Failed to write {}", page_file.display()))?;
+            summary.artifact(&page_file);
+        }
+        info!(
+            "Wrote {} docs data pages and nav.json to {}",
+            pages.len() - 1,
+            docs_dir.display()
+        );
+    }
 
-    for (idx, (dir_path, score)) in dir_scores.iter().take(args.top_files).enumerate() {
-        info!("  {}. {} (Score: {})", idx + 1, dir_path, score);
-        analysis_content.push_str(&format!(
-            "{}. **{}** (Score: {})\n",
-            idx + 1,
-            dir_path,
-            score
-        ));
+    // Write the treemap if requested
+    if args.emit_treemap {
+        let tree = report::treemap::build_treemap(&report, args.treemap_value)?;
+
+        let json_file = output_dir.join("treemap.json");
+        fs::write(&json_file, report::treemap::render_json(&tree)?).context(format!(
+            "Failed to write treemap to {}",
+            json_file.display()
+        ))?;
+
+        let html_file = output_dir.join("treemap.html");
+        fs::write(
+            &html_file,
+            report::treemap::render_html(&tree, args.treemap_value)?,
+        )
+        .context(format!(
+            "Failed to write treemap to {}",
+            html_file.display()
+        ))?;
+
+        info!("Treemap saved to {}", html_file.display());
+        summary.artifact(&json_file);
+        summary.artifact(&html_file);
+    }
 
-        // If we have metrics, add directory metrics summary
-        if let Some(metrics) = &repository_metrics {
-            // Get all files in this directory
-            let dir_files: Vec<String> = filtered_files
-                .iter()
-                .map(|file| file.path.to_string_lossy().to_string())
-                .filter(|path| path.starts_with(dir_path))
-                .collect();
+    // Write the pipeline audit if requested
+    if let Some(pipeline_audit) = &pipeline_audit {
+        let audit_file = output_dir.join(audit::FILE_NAME);
+        fs::write(&audit_file, pipeline_audit.render_json()?).context(format!(
+            "Failed to write pipeline audit to {}",
+            audit_file.display()
+        ))?;
+        info!("Pipeline audit saved to {}", audit_file.display());
+        summary.artifact(&audit_file);
+    }
 
-            let dir_file_count = dir_files.len();
-            let mut dir_line_count = 0;
-            let mut dir_function_count = 0;
+    // Write the glossary if requested
+    if args.glossary {
+        let terms = glossary::build(
+            &file_paths,
+            &exports_map,
+            &repo_root,
+            config.report.glossary_terms,
+        );
+        let glossary_file = output_dir.join(glossary::FILE_NAME);
+        fs::write(&glossary_file, glossary::render(&terms, &repo_root)).context(format!(
+            "Failed to write glossary to {}",
+            glossary_file.display()
+        ))?;
+        info!(
+            "Glossary of {} terms saved to {}",
+            terms.len(),
+            glossary_file.display()
+        );
+        summary.artifact(&glossary_file);
+    }
 
-            for file in &dir_files {
-                if let Some(file_metrics) = metrics.file_metrics.get(file) {
-                    dir_line_count += file_metrics.line_count;
-                    dir_function_count += file_metrics.function_count;
-                }
+    // Export the dependency graph if requested
+    for graph_format in &args.emit_graph {
+        let content = match graph_format {
+            dependencies::GraphFormat::Dot => {
+                dependency_graph.to_dot(&repo_root, args.graph_min_score)
             }
+            dependencies::GraphFormat::Graphml => dependency_graph.to_graphml(
+                &repo_root,
+                args.graph_min_score,
+                report.metrics.as_ref(),
+            ),
+        };
+        let graph_file = output_dir.join(graph_format.file_name());
+        fs::write(&graph_file, content).context(format!(
+            "Failed to write dependency graph to {}",
+            graph_file.display()
+        ))?;
+
+        info!("Dependency graph saved to {}", graph_file.display());
+        summary.artifact(&graph_file);
+    }
 
-            analysis_content.push_str(&format!(
-                "   - Files: {}, Total lines: {}, Functions: {}\n",
-                dir_file_count, dir_line_count, dir_function_count
-            ));
+    // Bundle everything written above, once every renderer has finished
+    if args.archive {
+        let archive_file = archive::write_archive(output_dir, summary.artifacts())?;
+        info!("Archive saved to {}", archive_file.display());
+        summary.artifact(&archive_file);
+    }
+
+    check_failed_formats(&report, &failed_formats, args.format.len())?;
+    check_unused_exports(args, &report)?;
+    check_thresholds(args, &report, &limits, &path_overrides, baseline.as_ref())?;
+    Ok(Some(report))
+}
+
+/// Fail the run with the crossed quality thresholds, once every output is written; `main`
+/// exits with code 2 on them. With --update-baseline the metrics are recorded instead.
+fn check_thresholds(
+    args: &AnalyzeArgs,
+    report: &report::AnalysisReport,
+    limits: &config::ThresholdsConfig,
+    path_overrides: &filter::PathOverrides,
+    baseline: Option<&baseline::Baseline>,
+) -> Result<()> {
+    let Some(metrics) = &report.metrics else {
+        return Ok(());
+    };
+
+    if let Some(path) = args.baseline.as_ref().filter(|_| args.update_baseline) {
+        let recorded = baseline::Baseline::from_metrics(metrics, &report.repo_path);
+        recorded.save(path)?;
+        info!(
+            "Baseline of {} files saved to {}",
+            recorded.files.len(),
+            path
+        );
+        return Ok(());
+    }
+    Ok(thresholds::check(
+        metrics,
+        limits,
+        path_overrides,
+        &report.repo_path,
+        baseline,
+    )?)
+}
+
+/// With --fail-on-unused-exports, fail the run when the report lists unused exports
+fn check_unused_exports(args: &AnalyzeArgs, report: &report::AnalysisReport) -> Result<()> {
+    if !args.fail_on_unused_exports || report.unused_exports.is_empty() {
+        return Ok(());
+    }
+
+    let count: usize = report
+        .unused_exports
+        .iter()
+        .map(|file| file.exports.len())
+        .sum();
+    Err(anyhow::anyhow!(
+        "Found {} unused exports in {} files (--fail-on-unused-exports); see the Unused Exports section of the report",
+        count,
+        report.unused_exports.len()
+    ))
+}
+
+/// Log the top files and directories, one line each
+fn log_top_entries(args: &AnalyzeArgs, report: &report::AnalysisReport) {
+    info!("Top {} important files:", args.top_files);
+    for (idx, top_file) in report.top_files.iter().enumerate() {
+        info!(
+            "  {}. {} (Score: {})",
+            idx + 1,
+            top_file.path,
+            top_file.score
+        );
+
+        // If verbose, show the exports and their usage counts
+        if let Some(detail) = &top_file.export_detail {
+            for export in &detail.listed {
+                info!(
+                    "     - {} {} (used {} times)",
+                    export.export_type, export.name, export.usage_count
+                );
+            }
+            if let Some(more) = detail.more_line(&report.output_name(report::OutputFormat::Json)) {
+                info!("     {}", more);
+            }
         }
+    }
 
-        analysis_content.push_str("\n");
+    info!(
+        "Top {} important directories:",
+        args.top_dirs.unwrap_or(args.top_files)
+    );
+    for (idx, dir) in report.top_directories.iter().enumerate() {
+        info!("  {}. {} (Score: {})", idx + 1, dir.path, dir.score);
     }
+}
 
-    // Save the analysis to a file
-    let output_file = output_dir.join("analysis_results.md");
-    fs::write(&output_file, analysis_content).context(format!(
-        "Failed to write analysis to {}",
-        output_file.display()
-    ))?;
+/// Fail the run if any report format could not be produced, once every other output is written
+fn check_failed_formats(
+    report: &report::AnalysisReport,
+    failed: &[report::OutputFormat],
+    requested: usize,
+) -> Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<String> = failed
+        .iter()
+        .map(|format| report.output_name(*format))
+        .collect();
+    Err(anyhow::anyhow!(
+        "Failed to produce {} of {} report formats: {} (see the errors above)",
+        failed.len(),
+        requested,
+        names.join(", ")
+    ))
+}
+
+/// Whether anything besides streamed JSON Lines needs the per-file metrics kept in memory;
+/// without any format the caller takes the report itself (`diff --baseline`)
+fn needs_file_metrics(args: &AnalyzeArgs) -> bool {
+    args.format
+        .iter()
+        .any(|format| *format != report::OutputFormat::Jsonl)
+        || args.format.is_empty()
+        || args.explain.is_some()
+        || args.per_file_pages
+        || args.per_dir_pages
+        || args.by_language
+        || args.emit_treemap
+        || args.docs_data.is_some()
+        || args.db.is_some()
+        || args.baseline.is_some()
+        || args
+            .emit_graph
+            .contains(&dependencies::GraphFormat::Graphml)
+}
+
+/// Render the report in one format, using the --template for markdown when one is given
+fn render_format(
+    format: report::OutputFormat,
+    report: &report::AnalysisReport,
+    template: Option<&report::template::ReportTemplate>,
+) -> Result<String> {
+    match template {
+        Some(template) if format == report::OutputFormat::Markdown => template.render(report),
+        _ => format.render(report),
+    }
+}
 
-    info!("Analysis saved to {}", output_file.display());
+/// Close a JSON Lines stream with the summary line
+fn finish_jsonl(sink: &mut dyn Write, report: &report::AnalysisReport) -> Result<()> {
+    writeln!(sink, "{}", report::jsonl::render_summary(report)?)
+        .and_then(|_| sink.flush())
+        .context("Failed to write JSON Lines output")
+}
+
+/// Stem of the report file names given by --output-file, which must be a plain file name
+fn output_stem(args: &AnalyzeArgs) -> Result<Option<String>> {
+    let Some(output_file) = &args.output_file else {
+        return Ok(None);
+    };
+    let path = Path::new(output_file);
+    if path.components().count() != 1 {
+        return Err(anyhow::anyhow!(
+            "--output-file takes a file name such as `report.md`, not {}; use --output-dir for the directory",
+            output_file
+        ));
+    }
+    match path.file_stem().map(|stem| stem.to_string_lossy()) {
+        Some(stem) if !stem.is_empty() && stem != ".." => Ok(Some(stem.to_string())),
+        _ => Err(anyhow::anyhow!(
+            "--output-file {} has no file name to use",
+            output_file
+        )),
+    }
+}
+
+/// Refuse to replace reports of an earlier run unless --force is given
+fn check_overwrite(args: &AnalyzeArgs, output_dir: &Path, stem: Option<&str>) -> Result<()> {
+    if args.force {
+        return Ok(());
+    }
+
+    let existing: Vec<String> = args
+        .format
+        .iter()
+        .map(|format| output_dir.join(format.output_name(stem)))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if existing.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "{} already exists; pass --force to overwrite it or --output-file to write the report under another name",
+        existing.join(", ")
+    ))
+}
+
+/// Create the directory if needed and check that files can be written to it
+fn ensure_writable_dir(dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        info!("Creating output directory: {}", dir.display());
+        fs::create_dir_all(dir).context(format!(
+            "Failed to create output directory {}; use --output-dir to write elsewhere or --ephemeral to only print results",
+            dir.display()
+        ))?;
+    }
+
+    let probe = dir.join(".overdoc-write-test");
+    fs::write(&probe, b"").context(format!(
+        "Output directory {} is not writable; use --output-dir to write elsewhere or --ephemeral to only print results",
+        dir.display()
+    ))?;
+    fs::remove_file(&probe).ok();
 
     Ok(())
 }
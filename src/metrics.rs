@@ -1,11 +1,24 @@
 use anyhow::{Context, Result};
 use log::{debug, warn};
-use std::collections::HashMap;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::audit::PhaseTrace;
+use crate::config::{serialize_sorted, Config, DeepAnalysisConfig, DeepPass, ScoringConfig};
+use crate::dependencies::DependencyGraph;
+use crate::filter::PathOverrides;
+use crate::findings::sort_warnings;
+use crate::progress;
+use crate::signals::{self, Signals};
+use crate::traversal::on_disk;
 
 /// Stores basic metrics for a single file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FileMetrics {
     pub path: String,
     pub line_count: usize,
@@ -14,14 +27,41 @@ pub struct FileMetrics {
     pub blank_lines: usize,
     pub file_size_bytes: u64,
     pub function_count: usize,
+    #[serde(serialize_with = "serialize_sorted")]
     pub declaration_count: HashMap<String, usize>, // Types like struct, enum, trait, etc.
     pub complexity_metrics: Option<ComplexityMetrics>,
     pub knowledge_score: Option<f64>,
     pub export_importance: Option<f64>, // New field to track importance based on exports
+    pub functions: Vec<FunctionMetrics>, // Per-function metrics, if the language is supported
+
+    /// Signals from external tools (--signals), e.g. coverage or incident counts
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_signals: Signals,
+
+    /// Contribution of the signals to the knowledge score, weighted by `scoring.extra`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_signal_score: Option<f64>,
+
+    /// Label of the `complexity_levels` entry the file falls in, set by finalize
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity_level: Option<String>,
+
+    /// Whether the passes gated by `deep_analysis` ran; without them missing complexity and
+    /// per-function data doesn't mean the file is simple
+    pub deep_analysis: bool,
+}
+
+/// Metrics for a single function within a file
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub cyclomatic_complexity: f64,
 }
 
 /// Enhanced metrics for code complexity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ComplexityMetrics {
     pub cyclomatic_complexity: f64,
     pub max_nesting_depth: f64,
@@ -31,6 +71,11 @@ pub struct ComplexityMetrics {
     pub halstead_effort: f64,
     pub halstead_time: f64,
     pub maintainability_index: f64,
+
+    /// Whether the time budget ran out before every metric was computed; metrics not
+    /// reached are left at zero
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
 }
 
 impl ComplexityMetrics {
@@ -45,14 +90,36 @@ impl ComplexityMetrics {
             halstead_effort: 0.0,
             halstead_time: 0.0,
             maintainability_index: 0.0,
+            partial: false,
         }
     }
 
+    /// Label of the first of `config.complexity_levels` whose limits the metrics stay
+    /// within, or of the last level when they exceed every one
+    pub fn classification<'a>(&self, config: &'a Config) -> &'a str {
+        let levels = &config.complexity_levels;
+        levels
+            .iter()
+            .find(|level| {
+                level
+                    .max_cyclomatic
+                    .is_none_or(|max| self.cyclomatic_complexity <= max)
+                    && level
+                        .max_cognitive
+                        .is_none_or(|max| self.cognitive_complexity <= max)
+            })
+            .or(levels.last())
+            .map_or("unclassified", |level| level.label.as_str())
+    }
+
     /// Returns a formatted description of the metrics
     pub fn description(&self) -> String {
         format!(
-            "Cyclomatic: {:.1}, Cognitive: {:.1}, Maintainability: {:.1}",
-            self.cyclomatic_complexity, self.cognitive_complexity, self.maintainability_index
+            "Cyclomatic: {:.1}, Cognitive: {:.1}, Maintainability: {:.1}{}",
+            self.cyclomatic_complexity,
+            self.cognitive_complexity,
+            self.maintainability_index,
+            if self.partial { " (partial)" } else { "" }
         )
     }
 }
@@ -67,11 +134,15 @@ impl FileMetrics {
     }
 
     /// Add complexity metrics to this file metrics
-    pub fn with_complexity(&mut self, complexity: ComplexityMetrics) -> &mut Self {
+    pub fn with_complexity(
+        &mut self,
+        complexity: ComplexityMetrics,
+        scoring: &ScoringConfig,
+    ) -> &mut Self {
         // Clone complexity before moving it into the Option
         let complexity_clone = complexity.clone();
         self.complexity_metrics = Some(complexity);
-        self.knowledge_score = Some(calculate_knowledge_score(self, &complexity_clone));
+        self.knowledge_score = Some(calculate_knowledge_score(self, &complexity_clone, scoring));
         self
     }
 
@@ -80,9 +151,24 @@ impl FileMetrics {
         self.knowledge_score.unwrap_or(0.0)
     }
 
-    /// Add export importance data to this file
-    pub fn with_export_importance(&mut self, importance: f64) -> &mut Self {
-        self.export_importance = Some(importance);
+    /// Add export importance data to this file and recalculate the knowledge score with it
+    pub fn with_export_importance(
+        &mut self,
+        importance: Option<f64>,
+        scoring: &ScoringConfig,
+    ) -> &mut Self {
+        self.export_importance = importance;
+        if let Some(complexity) = self.complexity_metrics.clone() {
+            self.knowledge_score = Some(calculate_knowledge_score(self, &complexity, scoring));
+        }
+        self
+    }
+
+    /// Attach signals from external tools, weighted by `scoring.extra`; the knowledge score
+    /// picks them up the next time it is calculated
+    pub fn with_signals(&mut self, signals: Signals, weights: &BTreeMap<String, f64>) -> &mut Self {
+        self.extra_signal_score = Some(signals::weighted_score(&signals, weights));
+        self.extra_signals = signals;
         self
     }
 
@@ -90,11 +176,30 @@ impl FileMetrics {
     pub fn export_importance(&self) -> f64 {
         self.export_importance.unwrap_or(0.0)
     }
+
+    /// The most complex function and the share (0-1) of the file's total
+    /// function complexity that it holds
+    pub fn complexity_concentration(&self) -> Option<(&FunctionMetrics, f64)> {
+        let total: f64 = self.functions.iter().map(|f| f.cyclomatic_complexity).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        self.functions
+            .iter()
+            .max_by(|a, b| {
+                a.cyclomatic_complexity
+                    .partial_cmp(&b.cyclomatic_complexity)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|function| (function, function.cyclomatic_complexity / total))
+    }
 }
 
 /// File metrics for the entire repository
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RepositoryMetrics {
+    #[serde(serialize_with = "serialize_sorted")]
     pub file_metrics: HashMap<String, FileMetrics>,
     pub total_files: usize,
     pub total_lines: usize,
@@ -102,6 +207,7 @@ pub struct RepositoryMetrics {
     pub total_comment_lines: usize,
     pub total_blank_lines: usize,
     pub total_size_bytes: u64,
+    #[serde(serialize_with = "serialize_sorted")]
     pub language_distribution: HashMap<String, usize>, // Extension -> file count
     pub avg_file_size: u64,
     pub avg_lines_per_file: usize,
@@ -109,19 +215,129 @@ pub struct RepositoryMetrics {
     pub avg_cyclomatic_complexity: f64,
     pub avg_cognitive_complexity: f64,
     pub avg_maintainability_index: f64,
-    pub knowledge_hotspots: Vec<(String, f64)>, // Files sorted by knowledge score
+    pub partial_complexity_files: usize, // Files whose complexity analysis ran out of time
+    pub shallow_files: usize,            // Files the passes gated by `deep_analysis` skipped
+    pub failed_files: Vec<String>,       // Files that could not be read or analyzed
+    pub knowledge_hotspots: Vec<(String, f64)>, // Files sorted by knowledge score, set by finalize
+    pub complexity_levels: Vec<(String, usize)>, // Files per complexity level, set by finalize
+    #[serde(skip)]
+    pub file_timings: Vec<(String, Duration)>, // Time spent on each file, with --timings
 }
 
-/// Analyzes a file to extract metrics
-fn analyze_file(file_path: &Path) -> Result<FileMetrics> {
-    debug!("Analyzing metrics for file: {}", file_path.display());
+impl RepositoryMetrics {
+    /// Merge the export importance of every file into its knowledge score, classify the files
+    /// by complexity and rebuild the knowledge hotspots. This is the only place the hotspots
+    /// are built, so it must run once the dependency graph is known, before the metrics are
+    /// reported.
+    pub fn finalize(&mut self, importance: &DependencyGraph, config: &Config) {
+        let mut level_counts: HashMap<&str, usize> = HashMap::new();
+        for (path, metrics) in self.file_metrics.iter_mut() {
+            metrics.with_export_importance(importance.normalized_importance(path), &config.scoring);
+            metrics.complexity_level = metrics
+                .complexity_metrics
+                .as_ref()
+                .map(|complexity| complexity.classification(config).to_string());
+            if let Some(level) = &metrics.complexity_level {
+                *level_counts.entry(level.as_str()).or_default() += 1;
+            }
+        }
 
-    // Get file size
-    let metadata = fs::metadata(file_path).context("Failed to get file metadata")?;
-    let file_size = metadata.len();
+        // Every level is listed, in the configuration order, so empty levels show as 0
+        self.complexity_levels = config
+            .complexity_levels
+            .iter()
+            .map(|level| {
+                let files = level_counts.get(level.label.as_str()).copied().unwrap_or(0);
+                (level.label.clone(), files)
+            })
+            .collect();
+
+        // Identify knowledge hotspots (files with highest knowledge scores)
+        self.knowledge_hotspots = self
+            .file_metrics
+            .iter()
+            .map(|(path, metrics)| (path.clone(), metrics.knowledge_score()))
+            .collect();
+
+        // Sort by knowledge score in descending order, ties by path so the order is stable
+        self.knowledge_hotspots.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+    }
+
+    /// Files per complexity level, e.g. "simple 12, moderate 3, complex 1, very complex 0"
+    pub fn describe_complexity_levels(&self) -> String {
+        self.complexity_levels
+            .iter()
+            .map(|(label, files)| format!("{} {}", label, files))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Analyzes a file, given by its traversed path, to extract metrics. Problems short of a
+/// failure are added to `warnings`.
+fn analyze_file(
+    file_path: &Path,
+    repo_root: &str,
+    options: &AnalysisOptions,
+    warnings: &mut Vec<String>,
+) -> Result<FileMetrics> {
+    debug!("Analyzing metrics for file: {}", file_path.display());
+    let source = on_disk(repo_root, file_path);
 
     // Read file contents
-    let content = fs::read_to_string(file_path).context("Failed to read file")?;
+    let content = fs::read_to_string(&source).context("Failed to read file")?;
+
+    // Determine file language from extension
+    let extension = file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // The most specific override matching the file may change its complexity settings
+    let complexity_budget = match options
+        .overrides
+        .value(file_path, |o| o.default_settings.complexity_time_budget_ms)
+    {
+        Some((ms, _)) => Some(ms).filter(|ms| *ms > 0).map(Duration::from_millis),
+        None => options.complexity_budget,
+    };
+    let skip_complexity = options
+        .overrides
+        .value(file_path, |o| o.default_settings.skip_complexity)
+        .is_some_and(|(skip, _)| skip);
+
+    let (metrics, content_warnings) = analyze_content(
+        &file_path.to_string_lossy(),
+        &content,
+        &extension,
+        complexity_budget,
+        skip_complexity,
+        options.deep_gate,
+        options.scoring,
+    );
+    warnings.extend(content_warnings);
+    Ok(metrics)
+}
+
+/// Analyzes the content of a file written in the language of `extension` (lowercase, without
+/// the dot); `file_path` only names the file in the metrics. With `skip_complexity` only the
+/// line-based metrics are computed. Problems with the complexity analysis are returned as
+/// warnings for the caller to log.
+pub fn analyze_content(
+    file_path: &str,
+    content: &str,
+    extension: &str,
+    complexity_budget: Option<Duration>,
+    skip_complexity: bool,
+    deep_gate: Option<&DeepAnalysisGate>,
+    scoring: &ScoringConfig,
+) -> (FileMetrics, Vec<String>) {
+    let mut warnings = Vec::new();
+    let file_size = content.len() as u64;
     let lines: Vec<&str> = content.lines().collect();
 
     let mut code_lines = 0;
@@ -131,12 +347,6 @@ fn analyze_file(file_path: &Path) -> Result<FileMetrics> {
     let mut function_count = 0;
     let mut declarations = HashMap::new();
 
-    // Determine file language from extension
-    let extension = file_path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
     // Process lines based on file type
     for line in &lines {
         let trimmed = line.trim();
@@ -146,7 +356,7 @@ fn analyze_file(file_path: &Path) -> Result<FileMetrics> {
             continue;
         }
 
-        match extension.as_str() {
+        match extension {
             "rs" => {
                 // Rust language
                 if in_block_comment {
@@ -223,11 +433,9 @@ fn analyze_file(file_path: &Path) -> Result<FileMetrics> {
         }
     }
 
-    let file_path_str = file_path.to_string_lossy().to_string();
-
     // Create basic file metrics
     let mut file_metrics = FileMetrics {
-        path: file_path_str.clone(),
+        path: file_path.to_string(),
         line_count: lines.len(),
         code_lines,
         comment_lines,
@@ -238,31 +446,213 @@ fn analyze_file(file_path: &Path) -> Result<FileMetrics> {
         complexity_metrics: None,
         knowledge_score: None,
         export_importance: None,
+        functions: Vec::new(),
+        extra_signals: BTreeMap::new(),
+        extra_signal_score: None,
+        complexity_level: None,
+        deep_analysis: true,
+    };
+
+    // Gated passes only run on files passing the pre-filter
+    if let Some(gate) = deep_gate {
+        file_metrics.deep_analysis = gate.admits(
+            file_path,
+            lines.len(),
+            estimate_cyclomatic(&lines, extension),
+        );
+    }
+    let runs = |pass: DeepPass| {
+        file_metrics.deep_analysis || deep_gate.is_none_or(|gate| !gate.gates(pass))
     };
+    let (run_complexity, run_functions) = (runs(DeepPass::Complexity), runs(DeepPass::Functions));
+    if skip_complexity {
+        debug!(
+            "Skipping the complexity analysis of {} by override",
+            file_path
+        );
+    }
 
     // Calculate complexity metrics if the file isn't too large
-    if file_size < 1024 * 1024 {
+    if file_size < 1024 * 1024 && run_complexity && !skip_complexity {
         // Skip files larger than 1MB for performance
-        match analyze_file_complexity(&file_path_str, &content) {
+        match analyze_file_complexity(extension, content, complexity_budget) {
             Ok(complexity) => {
-                file_metrics.with_complexity(complexity);
+                if complexity.partial {
+                    warnings.push(format!(
+                        "Complexity analysis of {} exceeded the {}ms budget; keeping partial metrics",
+                        file_path,
+                        complexity_budget.unwrap_or_default().as_millis()
+                    ));
+                }
+                file_metrics.with_complexity(complexity, scoring);
+                if run_functions {
+                    file_metrics.functions = analyze_functions(&lines, extension);
+                }
             }
             Err(err) => {
-                warn!(
-                    "Failed to analyze complexity for {}: {}",
-                    file_path.display(),
-                    err
-                );
+                warnings.push(format!("Failed to analyze complexity for {}: {}", file_path, err));
+            }
+        }
+    }
+
+    (file_metrics, warnings)
+}
+
+/// How the files of a repository are analyzed
+pub struct AnalysisOptions<'a> {
+    /// Whether the per-file metrics are kept. Without them only the repository totals are
+    /// returned, which keeps memory flat on very large repositories.
+    pub retain_files: bool,
+
+    /// Bound on the complexity analysis of each file; files that exceed it keep partial
+    /// complexity metrics, which are left out of the repository averages
+    pub complexity_budget: Option<Duration>,
+
+    /// Restricts the passes of `deep_analysis` to the files passing its pre-filter
+    pub deep_gate: Option<&'a DeepAnalysisGate<'a>>,
+
+    /// Whether the time spent on each file is measured (--timings)
+    pub time_files: bool,
+
+    /// Settings of the `overrides` matching each file
+    pub overrides: &'a PathOverrides,
+
+    /// Weights and caps of the knowledge score
+    pub scoring: &'a ScoringConfig,
+}
+
+/// Metrics of earlier runs, reused for files that haven't been modified since (--watch)
+#[derive(Default)]
+pub struct MetricsCache {
+    entries: HashMap<String, (SystemTime, FileMetrics)>,
+
+    /// Files the deep analysis pre-filter admitted by importance when the entries were made
+    deep_files: Vec<String>,
+
+    /// Files reused by the latest analysis
+    pub hits: usize,
+}
+
+impl MetricsCache {
+    /// Drop every entry, e.g. after the configuration changed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Start an analysis; entries made with a different deep analysis pre-filter are dropped
+    fn begin(&mut self, deep_gate: Option<&DeepAnalysisGate>) {
+        let mut deep_files: Vec<String> = deep_gate
+            .map(|gate| gate.top_files.iter().cloned().collect())
+            .unwrap_or_default();
+        deep_files.sort();
+        if deep_files != self.deep_files {
+            self.entries.clear();
+            self.deep_files = deep_files;
+        }
+        self.hits = 0;
+    }
+
+    /// Metrics of a file modified at `modified`, if they were cached since
+    fn get(&self, path: &str, modified: SystemTime) -> Option<FileMetrics> {
+        let (cached_at, metrics) = self.entries.get(path)?;
+        if *cached_at != modified {
+            return None;
+        }
+        Some(metrics.clone())
+    }
+
+    /// Count a reused file, or keep the metrics of a file analyzed afresh
+    fn record(&mut self, path: &str, source: Source, metrics: &FileMetrics) {
+        match source {
+            Source::Cached => self.hits += 1,
+            Source::Fresh(modified) => {
+                self.entries
+                    .insert(path.to_string(), (modified, metrics.clone()));
             }
+            Source::Uncached => {}
         }
     }
+}
+
+/// Where the metrics of a file came from
+enum Source {
+    /// Taken from the cache
+    Cached,
 
-    Ok(file_metrics)
+    /// Analyzed, to be cached as of the file's modification time
+    Fresh(SystemTime),
+
+    /// Analyzed without a cache, or without a modification time to cache them under
+    Uncached,
 }
 
-/// Analyze all files in a repository to gather metrics
-pub fn analyze_repository(file_paths: &[String]) -> Result<RepositoryMetrics> {
+/// Outcome of analyzing a file, the warnings it raised, and how long it took when files are
+/// timed
+type TimedAnalysis = (Result<(FileMetrics, Source)>, Vec<String>, Option<Duration>);
+
+/// Number of files analyzed in parallel before their metrics are folded into the totals, so
+/// the metrics of every file aren't held at once when they aren't retained
+const CHUNK_SIZE: usize = 512;
+
+/// Analyze a file, or take its metrics from the cache when it hasn't been modified since
+fn analyze_cached(
+    file_path: &Path,
+    repo_root: &str,
+    options: &AnalysisOptions,
+    cache: Option<&MetricsCache>,
+    warnings: &mut Vec<String>,
+) -> Result<(FileMetrics, Source)> {
+    let mut analyze = || analyze_file(file_path, repo_root, options, warnings);
+    let Some(cache) = cache else {
+        return Ok((analyze()?, Source::Uncached));
+    };
+
+    // Without a modification time the file is analyzed every time
+    let modified = fs::metadata(on_disk(repo_root, file_path)).and_then(|m| m.modified());
+    let Ok(modified) = modified else {
+        return Ok((analyze()?, Source::Uncached));
+    };
+    let key = file_path.to_string_lossy();
+    if let Some(metrics) = cache.get(&key, modified) {
+        debug!("Reusing cached metrics for file: {}", key);
+        return Ok((metrics, Source::Cached));
+    }
+
+    Ok((analyze()?, Source::Fresh(modified)))
+}
+
+/// Analyze all files in a repository to gather metrics. `file_paths` are the traversed
+/// paths, read relative to `repo_root`.
+///
+/// `on_file` is called with each file's metrics as soon as the file is analyzed, so callers
+/// can adjust them or stream them out. The metrics are kept afterwards only with
+/// `options.retain_files`.
+///
+/// `cache` provides the metrics of files unchanged since an earlier analysis, and is updated
+/// with the files analyzed.
+///
+/// `trace` records the analyzed files and the files whose analysis failed.
+///
+/// Files are analyzed in parallel on the rayon thread pool, then folded in the order of
+/// `file_paths`, so `on_file` calls and the results don't depend on the number of threads.
+/// Warnings about files are logged at the end, sorted by path.
+pub fn analyze_repository<F>(
+    file_paths: &[String],
+    repo_root: &str,
+    options: &AnalysisOptions,
+    mut cache: Option<&mut MetricsCache>,
+    mut trace: Option<&mut PhaseTrace>,
+    mut on_file: F,
+) -> Result<RepositoryMetrics>
+where
+    F: FnMut(&str, &mut FileMetrics) -> Result<()>,
+{
+    if let Some(cache) = cache.as_deref_mut() {
+        cache.begin(options.deep_gate);
+    }
+
     let mut file_metrics = HashMap::new();
+    let mut total_files = 0;
     let mut total_lines = 0;
     let mut total_code_lines = 0;
     let mut total_comment_lines = 0;
@@ -273,46 +663,99 @@ pub fn analyze_repository(file_paths: &[String]) -> Result<RepositoryMetrics> {
     let mut total_cognitive_complexity = 0.0;
     let mut total_maintainability_index = 0.0;
     let mut files_with_complexity = 0;
+    let mut partial_complexity_files = 0;
+    let mut shallow_files = 0;
+    let mut failed_files = Vec::new();
+    let mut file_timings = Vec::new();
+    let mut warnings = Vec::new();
+
+    let progress = progress::files("Analyzing metrics", file_paths.len());
+    for chunk in file_paths.chunks(CHUNK_SIZE) {
+        let analyzed: Vec<TimedAnalysis> = {
+            let cache = cache.as_deref();
+            chunk
+                .par_iter()
+                .map(|file_path| {
+                    progress::advance(&progress, file_path);
+                    let started = options.time_files.then(Instant::now);
+                    let mut warnings = Vec::new();
+                    let analyzed =
+                        analyze_cached(Path::new(file_path), repo_root, options, cache, &mut warnings);
+                    (analyzed, warnings, started.map(|started| started.elapsed()))
+                })
+                .collect()
+        };
+
+        for (file_path, (analyzed, file_warnings, elapsed)) in chunk.iter().zip(analyzed) {
+            let path = Path::new(file_path);
+            warnings.extend(file_warnings.into_iter().map(|warning| (file_path.clone(), warning)));
+            if let Some(elapsed) = elapsed {
+                file_timings.push((file_path.clone(), elapsed));
+            }
+            match analyzed {
+                Ok((mut metrics, source)) => {
+                    if let Some(cache) = cache.as_deref_mut() {
+                        cache.record(file_path, source, &metrics);
+                    }
+                    on_file(file_path, &mut metrics)?;
+
+                    total_files += 1;
+                    // Update totals
+                    total_lines += metrics.line_count;
+                    total_code_lines += metrics.code_lines;
+                    total_comment_lines += metrics.comment_lines;
+                    total_blank_lines += metrics.blank_lines;
+                    total_size_bytes += metrics.file_size_bytes;
+                    if !metrics.deep_analysis {
+                        shallow_files += 1;
+                    }
 
-    for file_path in file_paths {
-        let path = Path::new(file_path);
-
-        match analyze_file(path) {
-            Ok(metrics) => {
-                // Update totals
-                total_lines += metrics.line_count;
-                total_code_lines += metrics.code_lines;
-                total_comment_lines += metrics.comment_lines;
-                total_blank_lines += metrics.blank_lines;
-                total_size_bytes += metrics.file_size_bytes;
-
-                // Update language distribution
-                if let Some(ext) = path.extension() {
-                    let extension = ext.to_string_lossy().to_lowercase();
-                    *language_distribution.entry(extension).or_insert(0) += 1;
-                } else {
-                    *language_distribution
-                        .entry("unknown".to_string())
-                        .or_insert(0) += 1;
-                }
+                    // Update language distribution
+                    if let Some(ext) = path.extension() {
+                        let extension = ext.to_string_lossy().to_lowercase();
+                        *language_distribution.entry(extension).or_insert(0) += 1;
+                    } else {
+                        *language_distribution
+                            .entry("unknown".to_string())
+                            .or_insert(0) += 1;
+                    }
 
-                // Update complexity metrics if available
-                if let Some(complexity) = &metrics.complexity_metrics {
-                    total_cyclomatic_complexity += complexity.cyclomatic_complexity;
-                    total_cognitive_complexity += complexity.cognitive_complexity;
-                    total_maintainability_index += complexity.maintainability_index;
-                    files_with_complexity += 1;
-                }
+                    // Update complexity metrics if available
+                    // Partial metrics would skew the averages, so they are only counted
+                    if let Some(complexity) = &metrics.complexity_metrics {
+                        if complexity.partial {
+                            partial_complexity_files += 1;
+                        } else {
+                            total_cyclomatic_complexity += complexity.cyclomatic_complexity;
+                            total_cognitive_complexity += complexity.cognitive_complexity;
+                            total_maintainability_index += complexity.maintainability_index;
+                            files_with_complexity += 1;
+                        }
+                    }
 
-                file_metrics.insert(file_path.clone(), metrics);
-            }
-            Err(err) => {
-                warn!("Failed to analyze file {}: {}", file_path, err);
+                    if options.retain_files {
+                        file_metrics.insert(file_path.clone(), metrics);
+                    }
+                    if let Some(trace) = trace.as_deref_mut() {
+                        trace.emit(file_path);
+                    }
+                }
+                Err(err) => {
+                    warnings.push((file_path.clone(), format!("Failed to analyze file {}: {}", file_path, err)));
+                    if let Some(trace) = trace.as_deref_mut() {
+                        trace.exclude(file_path, format!("analysis failed: {}", err));
+                    }
+                    failed_files.push(file_path.clone());
+                }
             }
         }
     }
+    progress.finish_and_clear();
 
-    let total_files = file_metrics.len();
+    sort_warnings(&mut warnings);
+    for (_, warning) in &warnings {
+        warn!("{}", warning);
+    }
 
     // Calculate averages
     let avg_file_size = if total_files > 0 {
@@ -352,15 +795,6 @@ pub fn analyze_repository(file_paths: &[String]) -> Result<RepositoryMetrics> {
         0.0
     };
 
-    // Identify knowledge hotspots (files with highest knowledge scores)
-    let mut knowledge_hotspots: Vec<(String, f64)> = file_metrics
-        .iter()
-        .map(|(path, metrics)| (path.clone(), metrics.knowledge_score()))
-        .collect();
-
-    // Sort by knowledge score in descending order
-    knowledge_hotspots.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
     Ok(RepositoryMetrics {
         file_metrics,
         total_files,
@@ -376,24 +810,22 @@ pub fn analyze_repository(file_paths: &[String]) -> Result<RepositoryMetrics> {
         avg_cyclomatic_complexity,
         avg_cognitive_complexity,
         avg_maintainability_index,
-        knowledge_hotspots,
+        partial_complexity_files,
+        shallow_files,
+        failed_files,
+        knowledge_hotspots: Vec::new(),
+        complexity_levels: Vec::new(),
+        file_timings,
     })
 }
 
 /// Calculate complexity metrics for a file
 pub fn calculate_complexity_metrics(
-    file_path: &str,
+    extension: &str,
     content: &str,
 ) -> Result<HashMap<String, f64>> {
     let mut metrics = HashMap::new();
 
-    // Get file extension to determine language
-    let path = Path::new(file_path);
-    let extension = path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
     // Simple implementation - will need to be extended with a proper parser for more accurate results
     let lines: Vec<&str> = content.lines().collect();
 
@@ -426,64 +858,196 @@ pub fn calculate_complexity_metrics(
     // Count branching statements
     let mut complexity = 1; // Base complexity
 
-    match extension.as_str() {
+    for line in &lines {
+        if is_branch_line(line.trim(), extension) {
+            complexity += 1;
+        }
+    }
+
+    metrics.insert("cyclomatic_complexity".to_string(), complexity as f64);
+
+    Ok(metrics)
+}
+
+/// Line-based cyclomatic complexity estimate: one plus the number of branching lines
+fn estimate_cyclomatic(lines: &[&str], language: &str) -> usize {
+    1 + lines
+        .iter()
+        .filter(|line| is_branch_line(line.trim(), language))
+        .count()
+}
+
+/// Decides which files the passes of `deep_analysis` run on
+pub struct DeepAnalysisGate<'a> {
+    config: &'a DeepAnalysisConfig,
+
+    /// The `top_importance` most important files
+    top_files: HashSet<String>,
+}
+
+impl<'a> DeepAnalysisGate<'a> {
+    pub fn new(config: &'a DeepAnalysisConfig, graph: &DependencyGraph) -> Self {
+        DeepAnalysisGate {
+            config,
+            top_files: graph
+                .get_files_by_importance()
+                .into_iter()
+                .filter(|(_, score)| *score > 0)
+                .take(config.top_importance)
+                .map(|(path, _)| path)
+                .collect(),
+        }
+    }
+
+    /// Whether the pre-filter restricts a pass
+    fn gates(&self, pass: DeepPass) -> bool {
+        self.config.passes.contains(&pass)
+    }
+
+    /// Whether a file passes the pre-filter, given its line count and cyclomatic estimate
+    fn admits(&self, path: &str, line_count: usize, cyclomatic: usize) -> bool {
+        line_count > self.config.min_lines
+            || cyclomatic > self.config.min_cyclomatic
+            || self.top_files.contains(path)
+    }
+}
+
+/// Whether a line contains a branching statement that adds to cyclomatic complexity
+fn is_branch_line(trimmed: &str, language: &str) -> bool {
+    match language {
         "rs" => {
-            // Rust language
-            for line in &lines {
-                let trimmed = line.trim();
-                if trimmed.contains("if ")
-                    || trimmed.contains("else ")
-                    || trimmed.contains("match ")
-                    || trimmed.contains("for ")
-                    || trimmed.contains("while ")
-                {
-                    complexity += 1;
-                }
-            }
+            trimmed.contains("if ")
+                || trimmed.contains("else ")
+                || trimmed.contains("match ")
+                || trimmed.contains("for ")
+                || trimmed.contains("while ")
         }
         "js" | "ts" | "tsx" | "jsx" => {
-            // JavaScript/TypeScript
-            for line in &lines {
-                let trimmed = line.trim();
-                if trimmed.contains("if ")
-                    || trimmed.contains("else ")
-                    || trimmed.contains("switch ")
-                    || trimmed.contains("case ")
-                    || trimmed.contains("for ")
-                    || trimmed.contains("while ")
-                    || trimmed.contains("? ")
-                {
-                    complexity += 1;
-                }
-            }
+            trimmed.contains("if ")
+                || trimmed.contains("else ")
+                || trimmed.contains("switch ")
+                || trimmed.contains("case ")
+                || trimmed.contains("for ")
+                || trimmed.contains("while ")
+                || trimmed.contains("? ")
         }
-        // Add more languages as needed
         _ => {
-            // Generic fallback - simple approximation
-            for line in &lines {
-                let trimmed = line.trim();
-                if trimmed.contains("if ")
-                    || trimmed.contains("else ")
-                    || trimmed.contains("for ")
-                    || trimmed.contains("while ")
-                {
-                    complexity += 1;
-                }
+            trimmed.contains("if ")
+                || trimmed.contains("else ")
+                || trimmed.contains("for ")
+                || trimmed.contains("while ")
+        }
+    }
+}
+
+/// Split a file into functions and calculate the cyclomatic complexity of each.
+/// Brace-delimited languages are segmented by brace depth, Python by indentation.
+pub fn analyze_functions(lines: &[&str], language: &str) -> Vec<FunctionMetrics> {
+    let name_regex = match language {
+        "rs" => Regex::new(r"\bfn\s+(\w+)").unwrap(),
+        "js" | "ts" | "tsx" | "jsx" => Regex::new(
+            r"(?:function\s*\*?\s*(\w+)|(\w+)\s*[:=]\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::\s*[\w<>\[\]]+\s*)?=>))",
+        )
+        .unwrap(),
+        "py" => Regex::new(r"^\s*(?:async\s+)?def\s+(\w+)").unwrap(),
+        _ => return Vec::new(),
+    };
+
+    let mut functions = Vec::new();
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let trimmed = lines[idx].trim();
+
+        let is_start = match language {
+            "rs" => trimmed.contains("fn ") && !trimmed.contains(';') && !trimmed.starts_with("//"),
+            "py" => name_regex.is_match(lines[idx]),
+            _ => {
+                (trimmed.contains("function ") || trimmed.contains("=>"))
+                    && !trimmed.contains(';')
+                    && !trimmed.starts_with("//")
             }
+        };
+
+        if !is_start {
+            idx += 1;
+            continue;
         }
+
+        let name = name_regex
+            .captures(lines[idx])
+            .and_then(|caps| caps.iter().skip(1).flatten().next())
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_else(|| "<anonymous>".to_string());
+
+        let end = if language == "py" {
+            python_block_end(lines, idx)
+        } else {
+            brace_block_end(lines, idx)
+        };
+
+        let complexity = 1 + lines[idx..=end]
+            .iter()
+            .filter(|line| is_branch_line(line.trim(), language))
+            .count();
+
+        functions.push(FunctionMetrics {
+            name,
+            start_line: idx + 1,
+            end_line: end + 1,
+            cyclomatic_complexity: complexity as f64,
+        });
+
+        idx = end + 1;
     }
 
-    metrics.insert("cyclomatic_complexity".to_string(), complexity as f64);
+    functions
+}
 
-    Ok(metrics)
+/// Index of the line closing the brace block that starts at `start`
+fn brace_block_end(lines: &[&str], start: usize) -> usize {
+    let mut depth: isize = 0;
+    let mut opened = false;
+
+    for (idx, line) in lines.iter().enumerate().skip(start) {
+        depth += line.matches('{').count() as isize - line.matches('}').count() as isize;
+        if line.contains('{') {
+            opened = true;
+        }
+        if opened && depth <= 0 {
+            return idx;
+        }
+    }
+
+    lines.len() - 1
+}
+
+/// Index of the last line of the indented block that starts at `start`
+fn python_block_end(lines: &[&str], start: usize) -> usize {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let base = indent(lines[start]);
+    let mut end = start;
+
+    for (idx, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent(line) <= base {
+            break;
+        }
+        end = idx;
+    }
+
+    end
 }
 
 /// Store Halstead metrics operators and operands
 struct HalsteadData {
-    unique_operators: usize, // n1
-    total_operators: usize,  // N1
-    unique_operands: usize,  // n2
-    total_operands: usize,   // N2
+    unique_operators: usize,          // n1
+    total_operators: usize,           // N1
+    unique_operands: usize,           // n2
+    total_operands: usize,            // N2
+    operands: HashMap<String, usize>, // Occurrences of each operand
 }
 
 impl HalsteadData {
@@ -522,34 +1086,47 @@ impl HalsteadData {
     }
 }
 
-/// Analyze file to calculate enhanced complexity metrics
-pub fn analyze_file_complexity(file_path: &str, content: &str) -> Result<ComplexityMetrics> {
+/// Analyze the complexity of a file in stages (cyclomatic, cognitive, Halstead, maintainability
+/// index). When `budget` runs out between two stages, the metrics computed so far are returned
+/// with `partial` set.
+pub fn analyze_file_complexity(
+    extension: &str,
+    content: &str,
+    budget: Option<Duration>,
+) -> Result<ComplexityMetrics> {
+    let started = Instant::now();
+    let out_of_time = || budget.is_some_and(|budget| started.elapsed() > budget);
     let mut metrics = ComplexityMetrics::new();
 
-    // Get file extension to determine language
-    let path = Path::new(file_path);
-    let extension = path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
     let lines: Vec<&str> = content.lines().collect();
 
     // Calculate basic complexity metrics first
-    let basic_metrics = calculate_complexity_metrics(file_path, content)?;
+    let basic_metrics = calculate_complexity_metrics(extension, content)?;
     metrics.cyclomatic_complexity = *basic_metrics.get("cyclomatic_complexity").unwrap_or(&1.0);
     metrics.max_nesting_depth = *basic_metrics.get("max_nesting_depth").unwrap_or(&0.0);
+    if out_of_time() {
+        metrics.partial = true;
+        return Ok(metrics);
+    }
 
     // Calculate cognitive complexity
-    metrics.cognitive_complexity = calculate_cognitive_complexity(&lines, &extension);
+    metrics.cognitive_complexity = calculate_cognitive_complexity(&lines, extension);
+    if out_of_time() {
+        metrics.partial = true;
+        return Ok(metrics);
+    }
 
     // Calculate Halstead metrics
-    let halstead_data = calculate_halstead_data(&lines, &extension);
+    let halstead_data = calculate_halstead_data(&lines, extension);
     metrics.halstead_volume = halstead_data.volume();
     metrics.halstead_difficulty = halstead_data.difficulty();
     metrics.halstead_effort =
         halstead_data.effort(metrics.halstead_volume, metrics.halstead_difficulty);
     metrics.halstead_time = halstead_data.time(metrics.halstead_effort);
+    if out_of_time() {
+        metrics.partial = true;
+        return Ok(metrics);
+    }
 
     // Calculate maintainability index
     // MI = 171 - 5.2 * ln(V) - 0.23 * CC - 16.2 * ln(LOC)
@@ -853,9 +1430,21 @@ fn calculate_halstead_data(lines: &[&str], language: &str) -> HalsteadData {
         total_operators,
         unique_operands,
         total_operands,
+        operands,
     }
 }
 
+/// Occurrences of each Halstead operand (identifiers and numeric literals) in a file
+pub fn halstead_operands(file_path: &str, content: &str) -> HashMap<String, usize> {
+    let extension = Path::new(file_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+
+    calculate_halstead_data(&lines, &extension).operands
+}
+
 /// Count occurrences of a pattern in a string
 fn count_occurrences(text: &str, pattern: &str) -> usize {
     // This is a simplified approach; a more accurate implementation would use regex
@@ -875,40 +1464,75 @@ fn count_occurrences(text: &str, pattern: &str) -> usize {
     count
 }
 
+/// Contribution of each factor to a file's knowledge score
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeScoreBreakdown {
+    pub size: f64,
+    pub complexity: f64,
+    pub maintainability: f64,
+    pub functions: f64,
+    pub declarations: f64,
+    pub exports: f64,
+
+    /// Weighted signals from external tools (--signals); negative weights lower the score
+    pub extra: f64,
+
+    /// Final score, normalized to 0-100
+    pub total: f64,
+}
+
 /// Calculate "knowledge score" for a file based on various metrics
 pub fn calculate_knowledge_score(
     file_metrics: &FileMetrics,
     complexity: &ComplexityMetrics,
+    scoring: &ScoringConfig,
 ) -> f64 {
+    knowledge_score_breakdown(file_metrics, complexity, scoring).total
+}
+
+/// Calculate each factor of the knowledge score separately
+pub fn knowledge_score_breakdown(
+    file_metrics: &FileMetrics,
+    complexity: &ComplexityMetrics,
+    scoring: &ScoringConfig,
+) -> KnowledgeScoreBreakdown {
+    let weights = &scoring.weights;
+    let caps = &scoring.caps;
+
     // File size factor - using log scale to avoid overweighting large files
     // but still giving some importance to file size
-    let size_factor = (file_metrics.line_count as f64).ln().max(1.0) * 2.0;
+    let size_factor = (file_metrics.line_count as f64).ln().max(1.0) * weights.size;
 
     // Complexity factors - core of the knowledge score
     // Higher values indicate more complex code requiring more knowledge
-    let cc_norm = complexity.cyclomatic_complexity.min(50.0) / 50.0; // Normalize to 0-1
-    let cog_norm = complexity.cognitive_complexity.min(200.0) / 200.0; // Normalize to 0-1
+    let cc_norm = complexity.cyclomatic_complexity.min(caps.cyclomatic) / caps.cyclomatic; // Normalize to 0-1
+    let cog_norm = complexity.cognitive_complexity.min(caps.cognitive) / caps.cognitive; // Normalize to 0-1
 
-    // Combined complexity - cognitive complexity is weighted higher
+    // Combined complexity - by default cognitive complexity is weighted higher
     // as it better represents mental effort to understand
-    let complexity_factor = (cc_norm * 15.0) + (cog_norm * 25.0);
+    let complexity_factor = (cc_norm * weights.cyclomatic) + (cog_norm * weights.cognitive);
 
     // Maintainability - lower maintainability means higher knowledge required
     // Inverse relationship with maintainability index
     let maintainability_norm = ((100.0 - complexity.maintainability_index) / 100.0).min(1.0);
-    let maintainability_factor = maintainability_norm * 20.0;
+    let maintainability_factor = maintainability_norm * weights.maintainability;
 
     // Code structure complexity - more functions and declarations means more knowledge
-    let functions_norm = (file_metrics.function_count as f64).min(20.0) / 20.0;
-    let function_factor = functions_norm * 15.0;
+    let functions_cap = caps.functions as f64;
+    let functions_norm = (file_metrics.function_count as f64).min(functions_cap) / functions_cap;
+    let function_factor = functions_norm * weights.functions;
 
     // Declarations indicate entities that need to be understood
+    let decl_cap = caps.declarations as f64;
     let decl_count = file_metrics.declaration_count.values().sum::<usize>() as f64;
-    let decl_norm = decl_count.min(10.0) / 10.0;
-    let declaration_factor = decl_norm * 10.0;
+    let decl_norm = decl_count.min(decl_cap) / decl_cap;
+    let declaration_factor = decl_norm * weights.declarations;
 
     // Export importance - files with more exports are more important
-    let export_factor = file_metrics.export_importance() * 15.0;
+    let export_factor = file_metrics.export_importance() * weights.exports;
+
+    // Signals from external tools, already weighted by the scoring config
+    let extra_factor = file_metrics.extra_signal_score.unwrap_or(0.0);
 
     // Combined knowledge score with all factors
     let knowledge_score = size_factor
@@ -916,11 +1540,211 @@ pub fn calculate_knowledge_score(
         + maintainability_factor
         + function_factor
         + declaration_factor
-        + export_factor;
+        + export_factor
+        + extra_factor;
 
     // Normalize to a 0-100 scale with a more balanced distribution
     // This ensures we get a range of values rather than most files at 100
-    let normalized_score = (knowledge_score * 0.85).min(100.0);
+    KnowledgeScoreBreakdown {
+        size: size_factor,
+        complexity: complexity_factor,
+        maintainability: maintainability_factor,
+        functions: function_factor,
+        declarations: declaration_factor,
+        exports: export_factor,
+        extra: extra_factor,
+        total: (knowledge_score * scoring.scale).clamp(0.0, 100.0),
+    }
+}
+
+#[cfg(test)]
+impl RepositoryMetrics {
+    /// Metrics of files given as `(path, cyclomatic complexity, maintainability index)`, with
+    /// their averages and nothing else measured
+    pub fn from_complexities(files: &[(&str, f64, f64)]) -> Self {
+        let count = files.len() as f64;
+        let file_metrics: HashMap<String, FileMetrics> = files
+            .iter()
+            .map(|(path, cyclomatic, maintainability)| {
+                let complexity = ComplexityMetrics {
+                    cyclomatic_complexity: *cyclomatic,
+                    maintainability_index: *maintainability,
+                    ..ComplexityMetrics::new()
+                };
+                let file = FileMetrics {
+                    path: path.to_string(),
+                    line_count: 0,
+                    code_lines: 0,
+                    comment_lines: 0,
+                    blank_lines: 0,
+                    file_size_bytes: 0,
+                    function_count: 0,
+                    declaration_count: HashMap::new(),
+                    complexity_metrics: Some(complexity),
+                    knowledge_score: None,
+                    export_importance: None,
+                    functions: Vec::new(),
+                    extra_signals: BTreeMap::new(),
+                    extra_signal_score: None,
+                    complexity_level: None,
+                    deep_analysis: true,
+                };
+                (path.to_string(), file)
+            })
+            .collect();
+        RepositoryMetrics {
+            file_metrics,
+            total_files: files.len(),
+            total_lines: 0,
+            total_code_lines: 0,
+            total_comment_lines: 0,
+            total_blank_lines: 0,
+            total_size_bytes: 0,
+            language_distribution: HashMap::new(),
+            avg_file_size: 0,
+            avg_lines_per_file: 0,
+            avg_comment_ratio: 0.0,
+            avg_cyclomatic_complexity: files.iter().map(|f| f.1).sum::<f64>() / count,
+            avg_cognitive_complexity: 0.0,
+            avg_maintainability_index: files.iter().map(|f| f.2).sum::<f64>() / count,
+            partial_complexity_files: 0,
+            shallow_files: 0,
+            failed_files: Vec::new(),
+            knowledge_hotspots: Vec::new(),
+            complexity_levels: Vec::new(),
+            file_timings: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PathOverride;
+    use crate::dependencies::build_dependency_graph;
+    use crate::exports::{ExportsMap, ImportReference, ImportsMap};
+    use crate::filter::PathOverrides;
+    use crate::report::fixture::export;
+    use std::path::PathBuf;
+
+    const SOURCE: &str = "pub fn run(x: u32) -> u32 {\n    if x > 1 {\n        return x;\n    }\n    0\n}\n";
+
+    /// Metrics of identical files, whose knowledge scores tie until importance is merged in
+    fn identical_files(paths: &[&str]) -> RepositoryMetrics {
+        analyze_identical_files(paths, &[])
+    }
+
+    /// Metrics of identical files analyzed with the settings of `overrides`
+    fn analyze_identical_files(paths: &[&str], overrides: &[PathOverride]) -> RepositoryMetrics {
+        let repo = tempfile::tempdir().unwrap();
+        for path in paths {
+            let file = repo.path().join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, SOURCE).unwrap();
+        }
+
+        let config = Config::default();
+        let root = repo.path().to_string_lossy().to_string();
+        let overrides = PathOverrides::new(overrides, &root).unwrap();
+        let options = AnalysisOptions {
+            retain_files: true,
+            complexity_budget: None,
+            deep_gate: None,
+            time_files: false,
+            overrides: &overrides,
+            scoring: &config.scoring,
+        };
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        analyze_repository(&paths, &root, &options, None, None, |_, _| Ok(())).unwrap()
+    }
+
+    /// A graph where `imported` is imported `uses` times and the other files not at all
+    fn graph(imported: &[(&str, usize)]) -> DependencyGraph {
+        let mut exports_map = ExportsMap::new();
+        let mut imports_map = ImportsMap::new();
+        for (path, uses) in imported {
+            let name = format!("run_{}", path.replace(['/', '.'], "_"));
+            exports_map
+                .entry(path.to_string())
+                .or_default()
+                .push(export(path, &name, "function"));
+            imports_map.insert(
+                name.clone(),
+                (0..*uses)
+                    .map(|idx| ImportReference {
+                        name: name.clone(),
+                        file_path: PathBuf::from(format!("src/user{}.rs", idx)),
+                        line_number: 1,
+                        import_statement: format!("use crate::{};", name),
+                    })
+                    .collect(),
+            );
+        }
+        build_dependency_graph(&mut exports_map, &imports_map).unwrap()
+    }
+
+    fn hotspot_paths(metrics: &RepositoryMetrics) -> Vec<&str> {
+        metrics
+            .knowledge_hotspots
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn tied_hotspots_are_ordered_by_path() {
+        let mut metrics = identical_files(&["src/c.rs", "src/a.rs", "lib/b.rs"]);
+        metrics.finalize(&DependencyGraph::new(), &Config::default());
+
+        assert_eq!(hotspot_paths(&metrics), ["lib/b.rs", "src/a.rs", "src/c.rs"]);
+        let scores: Vec<f64> = metrics.knowledge_hotspots.iter().map(|(_, score)| *score).collect();
+        assert!(scores.windows(2).all(|pair| pair[0] == pair[1]), "{:?}", scores);
+    }
+
+    #[test]
+    fn export_importance_reorders_the_hotspots() {
+        let paths = ["src/c.rs", "src/a.rs", "lib/b.rs"];
+        let mut metrics = identical_files(&paths);
+        metrics.finalize(&graph(&[("src/c.rs", 4), ("src/a.rs", 1)]), &Config::default());
 
-    normalized_score
+        assert_eq!(hotspot_paths(&metrics), ["src/c.rs", "src/a.rs", "lib/b.rs"]);
+        let scores: Vec<f64> = metrics.knowledge_hotspots.iter().map(|(_, score)| *score).collect();
+        assert!(scores[0] > scores[1] && scores[1] > scores[2], "{:?}", scores);
+
+        // Finalizing again with another graph rebuilds the list rather than merging into it
+        metrics.finalize(&graph(&[("lib/b.rs", 2)]), &Config::default());
+        assert_eq!(hotspot_paths(&metrics), ["lib/b.rs", "src/a.rs", "src/c.rs"]);
+    }
+
+    #[test]
+    fn equally_important_files_keep_the_path_order() {
+        let mut metrics = identical_files(&["src/c.rs", "src/a.rs", "lib/b.rs"]);
+        metrics.finalize(&graph(&[("src/c.rs", 3), ("src/a.rs", 3)]), &Config::default());
+
+        assert_eq!(hotspot_paths(&metrics), ["src/a.rs", "src/c.rs", "lib/b.rs"]);
+    }
+
+    #[test]
+    fn overrides_skip_and_restore_the_complexity_analysis() {
+        let skipping = |path: &str, skip: bool| {
+            let mut path_override = PathOverride {
+                path: path.to_string(),
+                ..PathOverride::default()
+            };
+            path_override.default_settings.skip_complexity = Some(skip);
+            path_override
+        };
+        let metrics = analyze_identical_files(
+            &["services/legacy/billing.rs", "services/legacy/core/ledger.rs", "src/lib.rs"],
+            &[skipping("services/legacy/**", true), skipping("services/legacy/core/**", false)],
+        );
+
+        let analyzed = |path: &str| metrics.file_metrics[path].complexity_metrics.is_some();
+        assert!(!analyzed("services/legacy/billing.rs"));
+        // The more specific override turns the analysis back on
+        assert!(analyzed("services/legacy/core/ledger.rs"));
+        assert!(analyzed("src/lib.rs"));
+        // Line metrics are kept either way
+        assert_eq!(metrics.file_metrics["services/legacy/billing.rs"].line_count, 6);
+    }
 }
@@ -0,0 +1,357 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metrics::RepositoryMetrics;
+use crate::traversal::relative_path;
+
+/// Locations GitHub looks for a CODEOWNERS file, in order of precedence
+const CODEOWNERS_LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A single `pattern owner...` line of a CODEOWNERS file
+#[derive(Debug)]
+struct OwnerRule {
+    pattern: String,
+    regex: Regex,
+
+    /// Owners of matching files; empty means the files are explicitly unowned
+    owners: Vec<String>,
+}
+
+/// Parsed CODEOWNERS file
+#[derive(Debug)]
+pub struct CodeOwners {
+    /// Path of the file the rules were read from
+    pub source: PathBuf,
+    rules: Vec<OwnerRule>,
+}
+
+impl CodeOwners {
+    /// Find and parse the repository's CODEOWNERS file, if it has one
+    pub fn discover(repo_root: &str) -> Result<Option<CodeOwners>> {
+        for location in CODEOWNERS_LOCATIONS {
+            let path = Path::new(repo_root).join(location);
+            if path.is_file() {
+                let content = fs::read_to_string(&path)
+                    .context(format!("Failed to read {}", path.display()))?;
+                return Ok(Some(CodeOwners::parse(&content, path)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Parse CODEOWNERS content; invalid patterns are skipped with a warning
+    pub fn parse(content: &str, source: PathBuf) -> CodeOwners {
+        let mut rules = Vec::new();
+
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Owners end at an inline comment
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else {
+                continue;
+            };
+            let owners: Vec<String> = fields
+                .take_while(|field| !field.starts_with('#'))
+                .map(String::from)
+                .collect();
+
+            match pattern_to_regex(pattern) {
+                Ok(regex) => rules.push(OwnerRule {
+                    pattern: pattern.to_string(),
+                    regex,
+                    owners,
+                }),
+                Err(err) => warn!(
+                    "Skipping CODEOWNERS pattern '{}' on line {} of {}: {}",
+                    pattern,
+                    idx + 1,
+                    source.display(),
+                    err
+                ),
+            }
+        }
+
+        debug!(
+            "Loaded {} CODEOWNERS rules from {}",
+            rules.len(),
+            source.display()
+        );
+        CodeOwners { source, rules }
+    }
+
+    /// Owners of a path relative to the repository root. The last matching rule
+    /// wins; `None` means no rule matched or the matching rule lists no owners.
+    pub fn owners_of(&self, relative_path: &str) -> Option<&[String]> {
+        let path = relative_path.trim_start_matches("./").replace('\\', "/");
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.regex.is_match(&path))
+            .inspect(|rule| debug!("{} matches CODEOWNERS pattern {}", path, rule.pattern))
+            .map(|rule| rule.owners.as_slice())
+            .filter(|owners| !owners.is_empty())
+    }
+
+    /// Patterns of the rules listing an owner, in file order
+    pub fn patterns_of(&self, owner: &str) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.owners.iter().any(|o| o == owner))
+            .map(|rule| rule.pattern.clone())
+            .collect()
+    }
+}
+
+/// Translate a CODEOWNERS pattern (gitignore-style, as documented by GitHub) into a regex
+/// matched against `/`-separated paths relative to the repository root:
+///
+/// - a leading `/`, or a `/` inside the pattern, anchors it to the root;
+///   otherwise it matches at any depth
+/// - a trailing `/` matches everything inside the directory
+/// - `*` and `?` do not cross `/`; `**` does
+/// - a last segment without wildcards names a file or a directory, so it also
+///   matches everything below it, while `docs/*` only matches direct children
+pub fn pattern_to_regex(pattern: &str) -> Result<Regex> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.starts_with('/') || trimmed.trim_start_matches('/').contains('/');
+    let body = trimmed.trim_start_matches('/');
+
+    if body.is_empty() {
+        return Err(anyhow::anyhow!("empty pattern"));
+    }
+
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+
+    let segments: Vec<&str> = body.split('/').collect();
+    for (idx, segment) in segments.iter().enumerate() {
+        let last = idx == segments.len() - 1;
+
+        if *segment == "**" {
+            // `**/` matches zero or more directories, a trailing `/**` everything inside
+            regex.push_str(if last { ".*" } else { "(?:[^/]+/)*" });
+            continue;
+        }
+
+        for ch in segment.chars() {
+            match ch {
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                _ => regex.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+
+        if !last {
+            regex.push('/');
+        }
+    }
+
+    let last_segment = segments.last().copied().unwrap_or_default();
+    if dir_only {
+        regex.push_str("/.*");
+    } else if !last_segment.contains(['*', '?']) {
+        regex.push_str("(?:/.*)?");
+    }
+    regex.push('$');
+
+    Regex::new(&regex).context(format!("invalid pattern '{}'", pattern))
+}
+
+/// Totals for the files attributed to one owner
+#[derive(Debug, Serialize)]
+pub struct OwnerRollup {
+    pub owner: String,
+    pub file_count: usize,
+    pub line_count: usize,
+
+    /// Average maintainability index of the owner's files with complexity metrics
+    pub avg_maintainability: Option<f64>,
+
+    /// Owned files among the top knowledge hotspots
+    pub hotspot_count: usize,
+}
+
+/// Per-owner rollups plus the files no rule assigns an owner to
+#[derive(Debug, Serialize)]
+pub struct OwnersReport {
+    /// CODEOWNERS file the attribution is based on
+    pub source: String,
+    pub owners: Vec<OwnerRollup>,
+    pub unowned_files: Vec<String>,
+}
+
+/// Attribute analyzed files to their owners and roll up metrics per owner.
+/// Files with several owners count towards each of them.
+pub fn rollup(
+    code_owners: &CodeOwners,
+    repo_path: &str,
+    file_paths: &[String],
+    metrics: Option<&RepositoryMetrics>,
+    hotspot_limit: usize,
+) -> OwnersReport {
+    let hotspots: HashSet<&String> = metrics
+        .map(|m| {
+            m.knowledge_hotspots
+                .iter()
+                .take(hotspot_limit)
+                .map(|(path, _)| path)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // owner -> (files, lines, maintainability sum, maintainability count, hotspots)
+    let mut totals: BTreeMap<String, (usize, usize, f64, usize, usize)> = BTreeMap::new();
+    let mut unowned_files = Vec::new();
+
+    for path in file_paths {
+        let relative = relative_path(path, repo_path);
+        let Some(owners) = code_owners.owners_of(&relative) else {
+            unowned_files.push(relative);
+            continue;
+        };
+
+        let file_metrics = metrics.and_then(|m| m.file_metrics.get(path));
+        let maintainability = file_metrics
+            .and_then(|m| m.complexity_metrics.as_ref())
+            .map(|c| c.maintainability_index);
+
+        for owner in owners {
+            let entry = totals.entry(owner.clone()).or_default();
+            entry.0 += 1;
+            entry.1 += file_metrics.map(|m| m.line_count).unwrap_or(0);
+            if let Some(mi) = maintainability {
+                entry.2 += mi;
+                entry.3 += 1;
+            }
+            if hotspots.contains(path) {
+                entry.4 += 1;
+            }
+        }
+    }
+
+    unowned_files.sort();
+
+    OwnersReport {
+        source: code_owners.source.to_string_lossy().to_string(),
+        owners: totals
+            .into_iter()
+            .map(
+                |(owner, (files, lines, mi_sum, mi_count, hotspot_count))| OwnerRollup {
+                    owner,
+                    file_count: files,
+                    line_count: lines,
+                    avg_maintainability: (mi_count > 0).then(|| mi_sum / mi_count as f64),
+                    hotspot_count,
+                },
+            )
+            .collect(),
+        unowned_files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owners(content: &str) -> CodeOwners {
+        CodeOwners::parse(content, PathBuf::from("CODEOWNERS"))
+    }
+
+    fn owners_of<'a>(code_owners: &'a CodeOwners, path: &str) -> Option<Vec<&'a str>> {
+        code_owners
+            .owners_of(path)
+            .map(|owners| owners.iter().map(String::as_str).collect())
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let code_owners = owners("* @everyone\n*.rs @rustaceans\nsrc/legacy/ @legacy @archivists\n");
+
+        assert_eq!(owners_of(&code_owners, "README.md"), Some(vec!["@everyone"]));
+        assert_eq!(owners_of(&code_owners, "src/main.rs"), Some(vec!["@rustaceans"]));
+        assert_eq!(
+            owners_of(&code_owners, "src/legacy/old.rs"),
+            Some(vec!["@legacy", "@archivists"])
+        );
+
+        let reordered = owners("src/legacy/ @legacy\n*.rs @rustaceans\n");
+        assert_eq!(owners_of(&reordered, "src/legacy/old.rs"), Some(vec!["@rustaceans"]));
+    }
+
+    #[test]
+    fn anchored_and_unanchored_patterns() {
+        let code_owners = owners("/build.rs @root-build\nconfig.yaml @config\ndocs/*.md @writers\n");
+
+        assert_eq!(owners_of(&code_owners, "build.rs"), Some(vec!["@root-build"]));
+        assert_eq!(owners_of(&code_owners, "tools/build.rs"), None);
+
+        assert_eq!(owners_of(&code_owners, "config.yaml"), Some(vec!["@config"]));
+        assert_eq!(owners_of(&code_owners, "deploy/prod/config.yaml"), Some(vec!["@config"]));
+
+        // A `/` inside the pattern anchors it too, and `*` doesn't cross directories
+        assert_eq!(owners_of(&code_owners, "docs/intro.md"), Some(vec!["@writers"]));
+        assert_eq!(owners_of(&code_owners, "site/docs/intro.md"), None);
+        assert_eq!(owners_of(&code_owners, "docs/guides/intro.md"), None);
+        assert_eq!(owners_of(&code_owners, "./docs/intro.md"), Some(vec!["@writers"]));
+    }
+
+    #[test]
+    fn directory_patterns() {
+        let code_owners = owners("vendor/ @vendoring\n/apps/web @web\nlib/** @lib\n");
+
+        assert_eq!(owners_of(&code_owners, "vendor/a.js"), Some(vec!["@vendoring"]));
+        assert_eq!(owners_of(&code_owners, "third_party/vendor/b/c.js"), Some(vec!["@vendoring"]));
+        assert_eq!(owners_of(&code_owners, "vendor"), None);
+
+        // A last segment without wildcards names a file or a directory
+        assert_eq!(owners_of(&code_owners, "apps/web/src/index.ts"), Some(vec!["@web"]));
+        assert_eq!(owners_of(&code_owners, "apps/webhooks/index.ts"), None);
+
+        assert_eq!(owners_of(&code_owners, "lib/a/b/c.rs"), Some(vec!["@lib"]));
+        assert_eq!(owners_of(&code_owners, "src/lib/c.rs"), None);
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let code_owners = owners(
+            "# Owners of the repository\n\n   \n*.go @gophers # inline comment @ignored\n  # indented comment\n",
+        );
+
+        assert_eq!(code_owners.rules.len(), 1);
+        assert_eq!(owners_of(&code_owners, "cmd/main.go"), Some(vec!["@gophers"]));
+        assert_eq!(code_owners.patterns_of("@gophers"), ["*.go"]);
+        assert!(code_owners.patterns_of("@ignored").is_empty());
+    }
+
+    #[test]
+    fn reports_unowned_files() {
+        let code_owners = owners("src/ @core\nsrc/generated/\n");
+
+        assert_eq!(owners_of(&code_owners, "src/lib.rs"), Some(vec!["@core"]));
+        // A rule without owners leaves its files unowned, overriding earlier rules
+        assert_eq!(owners_of(&code_owners, "src/generated/api.rs"), None);
+        assert_eq!(owners_of(&code_owners, "README.md"), None);
+
+        let files: Vec<String> = ["repo/src/lib.rs", "repo/src/generated/api.rs", "repo/README.md"]
+            .iter()
+            .map(|path| path.to_string())
+            .collect();
+        let report = rollup(&code_owners, "repo", &files, None, 10);
+
+        assert_eq!(report.unowned_files, ["README.md", "src/generated/api.rs"]);
+        assert_eq!(report.owners.len(), 1);
+        assert_eq!(report.owners[0].owner, "@core");
+        assert_eq!(report.owners[0].file_count, 1);
+    }
+}
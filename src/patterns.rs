@@ -0,0 +1,554 @@
+//! Built-in import/export patterns.
+//!
+//! Every built-in pattern has a stable name (`rust.pub_fn`, `ts.export_const`, ...) so a
+//! language config can disable or replace single patterns instead of the whole list.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::{Config, LanguageConfig, PatternEntry};
+
+/// Whether a pattern recognizes imports or exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    Import,
+    Export,
+}
+
+/// A pattern shipped with OverDoc
+pub struct BuiltinPattern {
+    pub name: &'static str,
+    pub kind: PatternKind,
+    pub pattern: &'static str,
+}
+
+/// Where an effective pattern comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternSource {
+    /// Built-in pattern used as is
+    Builtin,
+
+    /// Built-in pattern replaced by a named entry in the configuration
+    Overridden,
+
+    /// Pattern only defined in the configuration
+    Config,
+}
+
+/// A pattern in the final set used for a language
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectivePattern {
+    /// Name of the pattern; anonymous configured patterns have none
+    pub name: Option<String>,
+    pub pattern: String,
+    pub source: PatternSource,
+}
+
+macro_rules! builtin {
+    ($kind:ident, $name:literal, $pattern:literal) => {
+        BuiltinPattern {
+            name: $name,
+            kind: PatternKind::$kind,
+            pattern: $pattern,
+        }
+    };
+}
+
+const RUST: &[BuiltinPattern] = &[
+    builtin!(Import, "rust.use", r"use\s+([\w:]+)"),
+    builtin!(Import, "rust.use_group", r"use\s+[\w:]+\s*::\s*\{([^}]+)\}"),
+    builtin!(Import, "rust.extern_crate", r"extern crate\s+(\w+)"),
+    builtin!(Export, "rust.pub_fn", r"pub\s+fn\s+(\w+)"),
+    builtin!(Export, "rust.pub_struct", r"pub\s+struct\s+(\w+)"),
+    builtin!(Export, "rust.pub_enum", r"pub\s+enum\s+(\w+)"),
+    builtin!(Export, "rust.pub_trait", r"pub\s+trait\s+(\w+)"),
+    builtin!(Export, "rust.pub_mod", r"pub\s+mod\s+(\w+)"),
+    builtin!(Export, "rust.pub_type", r"pub\s+type\s+(\w+)"),
+    builtin!(Export, "rust.impl", r"impl\s+([\w<>]+)\s*\{"),
+    builtin!(Export, "rust.pub_const", r"pub\s+const\s+(\w+)"),
+    builtin!(Export, "rust.pub_use", r"pub\s+use\s+[\w:]+::(\w+)\s*;"),
+];
+
+const JAVASCRIPT: &[BuiltinPattern] = &[
+    builtin!(
+        Import,
+        "js.import_from",
+        r#"import \s*[{]?\s*([\w,\s]+)\s*[}]?\s*from \s*['"]([^'"]+)['"]"#
+    ),
+    builtin!(Import, "js.require", r#"require\(['"]([^'"]+)['"]\)"#),
+    builtin!(
+        Export,
+        "js.export_const",
+        r"export \s*(default )?\s*(const|let|var) \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "js.export_function",
+        r"export \s*(default )?\s*function \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "js.export_class",
+        r"export \s*(default )?\s*class \s*(\w+)"
+    ),
+    builtin!(Export, "js.exports_property", r"exports\.([\w]+)"),
+    builtin!(Export, "js.reexport", r"export \s*\{\s*(\w+)\s*\}\s*from"),
+];
+
+const TYPESCRIPT: &[BuiltinPattern] = &[
+    builtin!(
+        Import,
+        "ts.import_from",
+        r#"import \s*[{]?\s*([\w,\s]+)\s*[}]?\s*from \s*['"]([^'"]+)['"]"#
+    ),
+    builtin!(Import, "ts.require", r#"require\(['"]([^'"]+)['"]\)"#),
+    builtin!(
+        Export,
+        "ts.export_const",
+        r"export \s*(default )?\s*(const|let|var) \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "ts.export_function",
+        r"export \s*(default )?\s*function \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "ts.export_class",
+        r"export \s*(default )?\s*class \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "ts.export_interface",
+        r"export \s*(default )?\s*interface \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "ts.export_type",
+        r"export \s*(default )?\s*type \s*(\w+)"
+    ),
+    builtin!(
+        Export,
+        "ts.export_enum",
+        r"export \s*(default )?\s*enum \s*(\w+)"
+    ),
+    builtin!(Export, "ts.reexport", r"export \s*\{\s*(\w+)\s*\}\s*from"),
+];
+
+const PYTHON: &[BuiltinPattern] = &[
+    builtin!(Import, "py.import", r"import \s*(\w+)"),
+    builtin!(Import, "py.from_import", r"from \s*(\w+) \s*import"),
+    builtin!(Export, "py.def", r"def \s*(\w+)\s*\("),
+    builtin!(Export, "py.class", r"class \s*(\w+)"),
+];
+
+const GO: &[BuiltinPattern] = &[
+    builtin!(Import, "go.import", r#"^import\s+(?:[\w.]+\s+)?"([^"]+)""#),
+    builtin!(
+        Import,
+        "go.import_group",
+        r#"^\s+(?:[\w.]+\s+)?"([\w.\-/]+)"\s*$"#
+    ),
+    builtin!(Export, "go.func", r"^func\s+(?:\([^)]*\)\s*)?([A-Z]\w*)"),
+    builtin!(Export, "go.type", r"^type\s+([A-Z]\w*)"),
+    builtin!(Export, "go.var", r"^(?:var|const)\s+([A-Z]\w*)"),
+];
+
+/// Built-in patterns for a language, by its name in the configuration
+pub fn builtin_patterns(language: &str) -> &'static [BuiltinPattern] {
+    match language {
+        "rust" => RUST,
+        "javascript" => JAVASCRIPT,
+        "typescript" => TYPESCRIPT,
+        "python" => PYTHON,
+        "go" => GO,
+        _ => &[],
+    }
+}
+
+/// The patterns of one kind a language ends up with.
+///
+/// A configured list holding any anonymous pattern is taken as the complete set, as before
+/// patterns had names. Otherwise the built-ins are used, with named entries replacing the
+/// built-in of the same name or adding a new pattern. Names in `disable_patterns` are left out
+/// either way.
+pub fn effective_patterns(
+    language: &str,
+    lang: &LanguageConfig,
+    kind: PatternKind,
+) -> Vec<EffectivePattern> {
+    let entries = match kind {
+        PatternKind::Import => &lang.import_patterns,
+        PatternKind::Export => &lang.export_patterns,
+    };
+    let disabled = |name: &str| lang.disable_patterns.iter().any(|d| d == name);
+
+    let mut patterns: Vec<EffectivePattern> = Vec::new();
+    let replaces_builtins = entries
+        .iter()
+        .any(|entry| matches!(entry, PatternEntry::Plain(_)));
+
+    if !replaces_builtins {
+        for builtin in builtin_patterns(language).iter().filter(|b| b.kind == kind) {
+            let overridden = entries.iter().find_map(|entry| match entry {
+                PatternEntry::Named { name, pattern } if name == builtin.name => Some(pattern),
+                _ => None,
+            });
+
+            patterns.push(EffectivePattern {
+                name: Some(builtin.name.to_string()),
+                pattern: overridden
+                    .cloned()
+                    .unwrap_or_else(|| builtin.pattern.to_string()),
+                source: if overridden.is_some() {
+                    PatternSource::Overridden
+                } else {
+                    PatternSource::Builtin
+                },
+            });
+        }
+    }
+
+    for entry in entries {
+        let (name, pattern) = match entry {
+            PatternEntry::Plain(pattern) => (None, pattern),
+            PatternEntry::Named { name, pattern } => (Some(name), pattern),
+        };
+        if patterns
+            .iter()
+            .any(|p| name.is_some() && p.name.as_ref() == name)
+        {
+            continue;
+        }
+
+        patterns.push(EffectivePattern {
+            name: name.cloned(),
+            pattern: pattern.clone(),
+            source: PatternSource::Config,
+        });
+    }
+
+    patterns.retain(|p| !p.name.as_deref().is_some_and(disabled));
+    patterns
+}
+
+/// Regexes of the patterns of one kind a language ends up with
+pub fn effective_regexes(language: &str, lang: &LanguageConfig, kind: PatternKind) -> Vec<String> {
+    effective_patterns(language, lang, kind)
+        .into_iter()
+        .map(|p| p.pattern)
+        .collect()
+}
+
+/// Describe the effective pattern set of every configured language (for `--check-config`).
+/// Returns the report and the number of problems found: invalid regexes and
+/// `disable_patterns` entries that match no pattern.
+pub fn render_pattern_report(config: &Config) -> (String, usize) {
+    let mut output = String::new();
+    let mut problems = 0;
+
+    let mut languages: Vec<(&String, &LanguageConfig)> = config.languages.iter().collect();
+    languages.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (language, lang) in languages {
+        output.push_str(&format!("{} ({})\n", language, lang.extensions.join(", ")));
+
+        for (title, kind) in [
+            ("import_patterns", PatternKind::Import),
+            ("export_patterns", PatternKind::Export),
+        ] {
+            let patterns = effective_patterns(language, lang, kind);
+            output.push_str(&format!("  {}:\n", title));
+            if patterns.is_empty() {
+                output.push_str("    (none)\n");
+            }
+
+            for pattern in &patterns {
+                let source = match pattern.source {
+                    PatternSource::Builtin => "built-in",
+                    PatternSource::Overridden => "overridden",
+                    PatternSource::Config => "config",
+                };
+                output.push_str(&format!(
+                    "    - {} [{}] {}\n",
+                    pattern.name.as_deref().unwrap_or("(unnamed)"),
+                    source,
+                    pattern.pattern
+                ));
+                if let Err(err) = Regex::new(&pattern.pattern) {
+                    let err = err.to_string().replace('\n', "\n      ");
+                    output.push_str(&format!("      invalid regex: {}\n", err));
+                    problems += 1;
+                }
+            }
+        }
+
+        let known = |name: &str| {
+            builtin_patterns(language).iter().any(|b| b.name == name)
+                || lang
+                    .import_patterns
+                    .iter()
+                    .chain(&lang.export_patterns)
+                    .any(|entry| matches!(entry, PatternEntry::Named { name: n, .. } if n == name))
+        };
+        for name in &lang.disable_patterns {
+            if !known(name) {
+                output.push_str(&format!(
+                    "  disable_patterns: '{}' does not name any pattern of {}\n",
+                    name, language
+                ));
+                problems += 1;
+            }
+        }
+        output.push('\n');
+    }
+
+    (output, problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LANGUAGES: &[&str] = &["rust", "javascript", "typescript", "python", "go"];
+
+    /// `(pattern, line, name it captures)`
+    const MATCHES: &[(&str, &str, &str)] = &[
+        (
+            "rust.use",
+            "use std::collections::HashMap;",
+            "std::collections::HashMap",
+        ),
+        (
+            "rust.use_group",
+            "use std::io::{Read, Write};",
+            "Read, Write",
+        ),
+        ("rust.extern_crate", "extern crate serde;", "serde"),
+        (
+            "rust.pub_fn",
+            "pub fn parse(input: &str) -> Config {",
+            "parse",
+        ),
+        ("rust.pub_struct", "pub struct Config {", "Config"),
+        ("rust.pub_enum", "pub enum Theme {", "Theme"),
+        ("rust.pub_trait", "pub trait Render {", "Render"),
+        ("rust.pub_mod", "pub mod report;", "report"),
+        (
+            "rust.pub_type",
+            "pub type Result<T> = std::result::Result<T, Error>;",
+            "Result",
+        ),
+        ("rust.impl", "impl Config {", "Config"),
+        ("rust.impl", "impl Wrapper<T> {", "Wrapper<T>"),
+        (
+            "rust.pub_const",
+            "pub const MAX_DEPTH: usize = 3;",
+            "MAX_DEPTH",
+        ),
+        ("rust.pub_use", "pub use crate::report::Theme;", "Theme"),
+        (
+            "js.import_from",
+            "import { render, VERSION } from \"./app\";",
+            "render, VERSION",
+        ),
+        ("js.import_from", "import App from './App';", "App"),
+        ("js.require", "const fs = require('fs');", "fs"),
+        ("js.export_const", "export const VERSION = 1;", "VERSION"),
+        ("js.export_const", "export let count = 0;", "count"),
+        (
+            "js.export_function",
+            "export default function render() {",
+            "render",
+        ),
+        ("js.export_class", "export class App {", "App"),
+        (
+            "js.exports_property",
+            "module.exports.render = render;",
+            "render",
+        ),
+        ("js.reexport", "export { render } from \"./app\";", "render"),
+        (
+            "ts.import_from",
+            "import { Props } from \"./types\";",
+            "Props",
+        ),
+        ("ts.require", "const path = require(\"path\");", "path"),
+        (
+            "ts.export_const",
+            "export const routes: Route[] = [];",
+            "routes",
+        ),
+        (
+            "ts.export_function",
+            "export function parse(text: string): Ast {",
+            "parse",
+        ),
+        ("ts.export_class", "export default class Parser {", "Parser"),
+        ("ts.export_interface", "export interface Props {", "Props"),
+        ("ts.export_type", "export type Id = string;", "Id"),
+        ("ts.export_enum", "export enum Color {", "Color"),
+        (
+            "ts.reexport",
+            "export { Parser } from './parser';",
+            "Parser",
+        ),
+        ("py.import", "import os", "os"),
+        (
+            "py.from_import",
+            "from collections import OrderedDict",
+            "collections",
+        ),
+        ("py.def", "def parse(text):", "parse"),
+        ("py.def", "    def render(self):", "render"),
+        ("py.class", "class Parser(Base):", "Parser"),
+        ("go.import", "import \"fmt\"", "fmt"),
+        ("go.import", "import str \"strings\"", "strings"),
+        ("go.import_group", "\t\"net/http\"", "net/http"),
+        (
+            "go.import_group",
+            "\tyaml \"gopkg.in/yaml.v3\"",
+            "gopkg.in/yaml.v3",
+        ),
+        ("go.func", "func Parse(s string) error {", "Parse"),
+        ("go.func", "func (p *Parser) Next() Token {", "Next"),
+        ("go.type", "type Parser struct {", "Parser"),
+        ("go.var", "var Version = \"1.0\"", "Version"),
+        ("go.var", "const MaxDepth = 3", "MaxDepth"),
+    ];
+
+    /// `(pattern, line it doesn't match)`
+    const MISSES: &[(&str, &str)] = &[
+        ("rust.use", "let user = current_user();"),
+        ("rust.use_group", "use std::io::Read;"),
+        ("rust.extern_crate", "extern \"C\" fn callback();"),
+        ("rust.pub_fn", "fn parse(input: &str) {"),
+        ("rust.pub_fn", "pub(crate) fn helper() {"),
+        ("rust.pub_struct", "struct Config;"),
+        ("rust.pub_enum", "enum Theme {"),
+        ("rust.pub_trait", "trait Render {"),
+        ("rust.pub_mod", "mod report;"),
+        ("rust.pub_type", "type Alias = u8;"),
+        ("rust.impl", "impl Display for Config {"),
+        ("rust.pub_const", "const MAX_DEPTH: usize = 3;"),
+        (
+            "rust.pub_use",
+            "pub use crate::report::{Theme, HtmlOptions};",
+        ),
+        ("rust.pub_use", "use crate::report::Theme;"),
+        ("js.import_from", "import \"./styles.css\";"),
+        ("js.import_from", "// imported from the app"),
+        ("js.require", "const module = require(name);"),
+        ("js.export_const", "const VERSION = 1;"),
+        ("js.export_function", "function render() {}"),
+        ("js.export_class", "class App {}"),
+        ("js.exports_property", "module.exports = render;"),
+        ("js.reexport", "export { render };"),
+        ("ts.import_from", "import type * as types from \"./types\";"),
+        ("ts.require", "const loader = require(modulePath);"),
+        ("ts.export_const", "let routes: Route[] = [];"),
+        ("ts.export_function", "function parse(text: string) {"),
+        ("ts.export_class", "abstract class Parser {"),
+        ("ts.export_interface", "interface Props {"),
+        ("ts.export_type", "type Id = string;"),
+        ("ts.export_enum", "enum Color {"),
+        ("ts.reexport", "export { Parser };"),
+        ("py.import", "# important: keep this order"),
+        ("py.from_import", "import collections"),
+        ("py.def", "default = None"),
+        ("py.class", "subclass_of = Base"),
+        ("go.import", "\t\"fmt\""),
+        ("go.import_group", "import \"fmt\""),
+        ("go.import_group", "\tfmt.Println(\"hello\")"),
+        ("go.func", "func parse(s string) error {"),
+        ("go.type", "type parser struct {"),
+        ("go.var", "var version = \"1.0\""),
+    ];
+
+    fn builtin(name: &str) -> &'static BuiltinPattern {
+        LANGUAGES
+            .iter()
+            .flat_map(|language| builtin_patterns(language))
+            .find(|builtin| builtin.name == name)
+            .unwrap_or_else(|| panic!("no built-in pattern {}", name))
+    }
+
+    /// The name a built-in pattern captures in a line, taken from the group the scan uses:
+    /// the first for imports, the last for exports
+    fn captured(name: &str, line: &str) -> Option<String> {
+        let builtin = builtin(name);
+        let captures = Regex::new(builtin.pattern).unwrap().captures(line)?;
+        let group = match builtin.kind {
+            PatternKind::Import => 1,
+            PatternKind::Export => captures.len() - 1,
+        };
+        Some(captures.get(group)?.as_str().trim().to_string())
+    }
+
+    #[test]
+    fn builtin_patterns_capture_the_imported_or_exported_name() {
+        for (name, line, expected) in MATCHES {
+            assert_eq!(
+                captured(name, line).as_deref(),
+                Some(*expected),
+                "{} on {:?}",
+                name,
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_patterns_ignore_similar_lines() {
+        for (name, line) in MISSES {
+            assert_eq!(captured(name, line), None, "{} on {:?}", name, line);
+        }
+    }
+
+    #[test]
+    fn every_builtin_pattern_has_examples() {
+        for builtin in LANGUAGES
+            .iter()
+            .flat_map(|language| builtin_patterns(language))
+        {
+            assert!(
+                MATCHES.iter().any(|(name, _, _)| *name == builtin.name),
+                "{} has no matching example",
+                builtin.name
+            );
+            assert!(
+                MISSES.iter().any(|(name, _)| *name == builtin.name),
+                "{} has no non-matching example",
+                builtin.name
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_pattern_names_are_prefixed_and_unique() {
+        let prefixes = [
+            ("rust", "rust."),
+            ("javascript", "js."),
+            ("typescript", "ts."),
+            ("python", "py."),
+            ("go", "go."),
+        ];
+        let mut names = Vec::new();
+        for (language, prefix) in prefixes {
+            for builtin in builtin_patterns(language) {
+                assert!(
+                    builtin.name.starts_with(prefix),
+                    "{} in {}",
+                    builtin.name,
+                    language
+                );
+                names.push(builtin.name);
+            }
+        }
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+}
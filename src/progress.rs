@@ -0,0 +1,69 @@
+//! Progress bars for the per-file phases, drawn on stderr between the log lines.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::{Log, Metadata, Record, SetLoggerError};
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// Bars of the run; unset when progress isn't shown
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+/// Install `logger` as the global logger, showing progress bars when `enabled` and stderr is
+/// a terminal
+pub fn init(logger: env_logger::Logger, enabled: bool) -> Result<(), SetLoggerError> {
+    if enabled && std::io::stderr().is_terminal() {
+        let _ = BARS.set(MultiProgress::with_draw_target(ProgressDrawTarget::stderr()));
+    }
+
+    let max_level = logger.filter();
+    log::set_boxed_logger(Box::new(SuspendingLogger { inner: logger }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Writes each log line with the bars cleared, then redraws them below it
+struct SuspendingLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for SuspendingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        match BARS.get() {
+            Some(bars) => bars.suspend(|| self.inner.log(record)),
+            None => self.inner.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Bar counting the files of a phase; hidden when progress isn't shown. Call
+/// [`ProgressBar::finish_and_clear`] once the phase ends.
+pub fn files(phase: &'static str, total: usize) -> ProgressBar {
+    let Some(bars) = BARS.get() else {
+        return ProgressBar::hidden();
+    };
+
+    let bar = bars.add(ProgressBar::new(total as u64));
+    let style = ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} {wide_msg}")
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ");
+    bar.set_style(style);
+    bar.set_prefix(phase);
+    bar
+}
+
+/// Advance the bar past a file, showing its path
+pub fn advance(bar: &ProgressBar, path: &str) {
+    bar.set_message(path.to_string());
+    bar.inc(1);
+}
@@ -0,0 +1,100 @@
+use crate::config::BadgesConfig;
+use crate::metrics::RepositoryMetrics;
+
+use super::html::escape;
+
+/// Directory (inside the output directory) holding the badges
+pub const BADGES_DIR: &str = "badges";
+
+const GREEN: &str = "#4c1";
+const YELLOW: &str = "#dfb317";
+const RED: &str = "#e05d44";
+const BLUE: &str = "#007ec6";
+
+/// Render every badge as `(file name, svg)` pairs
+pub fn render_badges(
+    metrics: &RepositoryMetrics,
+    config: &BadgesConfig,
+) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "maintainability.svg",
+            render_badge(
+                "maintainability",
+                &format!("{:.1}", metrics.avg_maintainability_index),
+                threshold_color(
+                    metrics.avg_maintainability_index,
+                    config.maintainability_green,
+                    config.maintainability_yellow,
+                ),
+            ),
+        ),
+        (
+            "comment-ratio.svg",
+            render_badge(
+                "comment ratio",
+                &format!("{:.1}%", metrics.avg_comment_ratio * 100.0),
+                threshold_color(
+                    metrics.avg_comment_ratio,
+                    config.comment_ratio_green,
+                    config.comment_ratio_yellow,
+                ),
+            ),
+        ),
+        (
+            "files.svg",
+            render_badge("files", &metrics.total_files.to_string(), BLUE),
+        ),
+    ]
+}
+
+/// Pick a color for a value where higher is better
+fn threshold_color(value: f64, green: f64, yellow: f64) -> &'static str {
+    if value >= green {
+        GREEN
+    } else if value >= yellow {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+/// Approximate rendered width of badge text (11px Verdana)
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+/// Render a shield-style badge with a gray label and a colored value
+fn render_badge(label: &str, value: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let value_width = text_width(value);
+    let width = label_width + value_width;
+    let (label, value) = (escape(label), escape(value));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <title>{label}: {value}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r">
+    <rect width="{width}" height="20" rx="3" fill="#fff"/>
+  </clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text>
+    <text x="{value_x}" y="14">{value}</text>
+  </g>
+</svg>
+"##,
+        label_x = label_width as f64 / 2.0,
+        value_x = label_width as f64 + value_width as f64 / 2.0,
+    )
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::html::escape;
+use super::AnalysisReport;
+use crate::metrics::FileMetrics;
+use crate::traversal::relative_path;
+
+const COBERTURA_DTD: &str = "http://cobertura.sourceforge.net/xml/coverage-04.dtd";
+
+/// Commented and countable lines of a set of files, with their summed cyclomatic complexity
+#[derive(Default)]
+struct Coverage {
+    comment_lines: usize,
+    valid_lines: usize,
+    complexity: f64,
+}
+
+impl Coverage {
+    fn add(&mut self, metrics: &FileMetrics) {
+        self.comment_lines += metrics.comment_lines;
+        self.valid_lines += metrics.code_lines + metrics.comment_lines;
+        self.complexity += metrics
+            .complexity_metrics
+            .as_ref()
+            .map(|c| c.cyclomatic_complexity)
+            .unwrap_or(0.0);
+    }
+
+    /// Comment ratio, reported as the line rate
+    fn line_rate(&self) -> f64 {
+        if self.valid_lines == 0 {
+            return 0.0;
+        }
+        self.comment_lines as f64 / self.valid_lines as f64
+    }
+}
+
+/// Render documentation coverage as Cobertura XML.
+///
+/// Each file is a class whose line rate is its comment ratio, and files are grouped into
+/// packages by directory. Lines are not itemized; `lines-covered` counts comment lines.
+pub fn render(report: &AnalysisReport) -> Result<String> {
+    let metrics = report
+        .metrics
+        .as_ref()
+        .context("Documentation coverage needs file metrics, which --skip-metrics disables")?;
+
+    let mut packages: BTreeMap<String, Vec<(String, &FileMetrics)>> = BTreeMap::new();
+    for (path, file_metrics) in &metrics.file_metrics {
+        let relative = relative_path(path, &report.repo_path).replace('\\', "/");
+        let package = Path::new(&relative)
+            .parent()
+            .map(|dir| dir.to_string_lossy().replace('/', "."))
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        packages
+            .entry(package)
+            .or_default()
+            .push((relative, file_metrics));
+    }
+
+    let mut total = Coverage::default();
+    let mut packages_xml = String::new();
+    for (package, mut files) in packages {
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut package_coverage = Coverage::default();
+        let mut classes_xml = String::new();
+        for (relative, file_metrics) in files {
+            let mut class_coverage = Coverage::default();
+            class_coverage.add(file_metrics);
+            package_coverage.add(file_metrics);
+
+            let name = Path::new(&relative)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| relative.clone());
+            classes_xml.push_str(&format!(
+                "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"{}\">\n          <methods/>\n          <lines/>\n        </class>\n",
+                escape(&name),
+                escape(&relative),
+                class_coverage.line_rate(),
+                class_coverage.complexity
+            ));
+        }
+
+        packages_xml.push_str(&format!(
+            "    <package name=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"{}\">\n      <classes>\n{}      </classes>\n    </package>\n",
+            escape(&package),
+            package_coverage.line_rate(),
+            package_coverage.complexity,
+            classes_xml
+        ));
+        total.comment_lines += package_coverage.comment_lines;
+        total.valid_lines += package_coverage.valid_lines;
+        total.complexity += package_coverage.complexity;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<!DOCTYPE coverage SYSTEM \"{}\">\n",
+        COBERTURA_DTD
+    ));
+    xml.push_str(&format!(
+        "<coverage line-rate=\"{:.4}\" branch-rate=\"0\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"0\" branches-valid=\"0\" complexity=\"{}\" version=\"{}\" timestamp=\"{}\">\n",
+        total.line_rate(),
+        total.comment_lines,
+        total.valid_lines,
+        total.complexity,
+        env!("CARGO_PKG_VERSION"),
+        timestamp
+    ));
+    xml.push_str(&format!(
+        "  <sources>\n    <source>{}</source>\n  </sources>\n",
+        escape(&report.repo_path)
+    ));
+    xml.push_str(&format!("  <packages>\n{}  </packages>\n", packages_xml));
+    xml.push_str("</coverage>\n");
+
+    Ok(xml)
+}
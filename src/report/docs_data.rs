@@ -0,0 +1,440 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::meta::RunMetadata;
+use super::{pages, AnalysisReport, TopDirectory, TopFile};
+use crate::directories::{contains, DirectoryMetrics};
+use crate::exports::ExportedEntity;
+use crate::metrics::FileMetrics;
+use crate::traversal::relative_path;
+
+/// Directory of the docs-site data under the output directory (`--docs-data`)
+pub const DOCS_DATA_DIR: &str = "docs-data";
+
+/// Version of the page and nav shapes, bumped on breaking changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Docs site generator whose naming conventions the pages follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocsSite {
+    /// `files/src_lib.rs.json` linked as `files/src_lib.rs.md`, modules as `modules/<dir>/`
+    Mkdocs,
+
+    /// Lowercase kebab-case doc ids: `files/src-lib-rs.json` linked as `/files/src-lib-rs`
+    Docusaurus,
+}
+
+impl DocsSite {
+    /// Slug of a repository path in the site's convention
+    fn slug(self, path: &str) -> String {
+        match self {
+            DocsSite::Mkdocs => pages::slugify(path),
+            DocsSite::Docusaurus => {
+                let kebab: String = path
+                    .trim_start_matches("./")
+                    .to_lowercase()
+                    .chars()
+                    .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+                    .collect();
+                let kebab = kebab
+                    .split('-')
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("-");
+                if kebab.is_empty() {
+                    "root".to_string()
+                } else {
+                    kebab
+                }
+            }
+        }
+    }
+
+    /// Data file and site link of a file page
+    fn file_page(self, path: &str) -> (String, String) {
+        let slug = self.slug(path);
+        let link = match self {
+            DocsSite::Mkdocs => format!("files/{}.md", slug),
+            DocsSite::Docusaurus => format!("/files/{}", slug),
+        };
+        (format!("files/{}.json", slug), link)
+    }
+
+    /// Data file and site link of a module page; modules are index files of their own directory
+    fn module_page(self, path: &str) -> (String, String) {
+        let slug = self.slug(path);
+        let link = match self {
+            DocsSite::Mkdocs => format!("modules/{}/", slug),
+            DocsSite::Docusaurus => format!("/modules/{}", slug),
+        };
+        (format!("modules/{}/index.json", slug), link)
+    }
+
+    /// Rewrite a link relative to the docs root for a page `depth` directories below it;
+    /// mkdocs resolves links relative to the page, docusaurus from the site root
+    fn link_from(self, link: &str, depth: usize) -> String {
+        match self {
+            DocsSite::Mkdocs => format!("{}{}", "../".repeat(depth), link),
+            DocsSite::Docusaurus => link.to_string(),
+        }
+    }
+}
+
+/// Another file, with the link to its page if it has one
+#[derive(Serialize)]
+struct PageRef {
+    path: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+}
+
+/// Data of the page of one of the top files
+#[derive(Serialize)]
+struct FilePage<'a> {
+    schema_version: u32,
+    kind: &'static str,
+    title: String,
+    path: String,
+    description: String,
+    rank: usize,
+    score: usize,
+    exports: &'a [ExportedEntity],
+    dependents: Vec<PageRef>,
+    dependencies: Vec<PageRef>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<&'a FileMetrics>,
+}
+
+/// Data of the page of one of the top directories
+#[derive(Serialize)]
+struct ModulePage<'a> {
+    schema_version: u32,
+    kind: &'static str,
+    title: String,
+    path: String,
+    description: String,
+    rank: usize,
+    score: usize,
+    files: Vec<PageRef>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<&'a DirectoryMetrics>,
+}
+
+/// An entry of `nav.json`
+#[derive(Serialize)]
+struct NavEntry {
+    title: String,
+    path: String,
+    page: String,
+    link: String,
+    score: usize,
+}
+
+/// `nav.json`: every page, files and modules each ordered by importance
+#[derive(Serialize)]
+struct Nav<'a> {
+    schema_version: u32,
+    site: DocsSite,
+    meta: &'a RunMetadata,
+    files: Vec<NavEntry>,
+    modules: Vec<NavEntry>,
+}
+
+/// Render the docs-site data as (path under `docs-data/`, JSON) pairs, `nav.json` last
+pub fn render(report: &AnalysisReport, site: DocsSite) -> Result<Vec<(String, String)>> {
+    let relative = |path: &str| relative_path(path, &report.repo_path);
+    let file_links: HashMap<&str, String> = report
+        .top_files
+        .iter()
+        .map(|file| (file.path.as_str(), site.file_page(&relative(&file.path)).1))
+        .collect();
+    let page_ref = |path: &String, depth: usize| PageRef {
+        path: relative(path),
+        link: file_links
+            .get(path.as_str())
+            .map(|link| site.link_from(link, depth)),
+    };
+
+    let mut output = Vec::new();
+    let mut nav = Nav {
+        schema_version: SCHEMA_VERSION,
+        site,
+        meta: &report.meta,
+        files: Vec::new(),
+        modules: Vec::new(),
+    };
+
+    for (idx, top_file) in report.top_files.iter().enumerate() {
+        let path = relative(&top_file.path);
+        let (page, link) = site.file_page(&path);
+        let data = FilePage {
+            schema_version: SCHEMA_VERSION,
+            kind: "file",
+            title: title(&path),
+            description: describe_file(top_file),
+            rank: idx + 1,
+            score: top_file.score,
+            exports: &top_file.exports,
+            dependents: top_file.dependents.iter().map(|p| page_ref(p, 1)).collect(),
+            dependencies: top_file
+                .dependencies
+                .iter()
+                .map(|p| page_ref(p, 1))
+                .collect(),
+            metrics: top_file.metrics.as_ref(),
+            path: path.clone(),
+        };
+        output.push((page.clone(), to_json(&data, &path)?));
+        nav.files.push(NavEntry {
+            title: data.title,
+            path,
+            page,
+            link,
+            score: top_file.score,
+        });
+    }
+
+    for (idx, top_dir) in report.top_directories.iter().enumerate() {
+        let path = relative(&top_dir.path);
+        let (page, link) = site.module_page(&path);
+        let files: Vec<PageRef> = report
+            .top_files
+            .iter()
+            .filter(|file| contains(&top_dir.path, &file.path))
+            .map(|file| page_ref(&file.path, 2))
+            .collect();
+        let data = ModulePage {
+            schema_version: SCHEMA_VERSION,
+            kind: "module",
+            title: title(&path),
+            description: describe_module(top_dir, files.len()),
+            rank: idx + 1,
+            score: top_dir.score,
+            files,
+            metrics: top_dir.metrics.as_ref(),
+            path: path.clone(),
+        };
+        output.push((page.clone(), to_json(&data, &path)?));
+        nav.modules.push(NavEntry {
+            title: data.title,
+            path,
+            page,
+            link,
+            score: top_dir.score,
+        });
+    }
+
+    let nav = serde_json::to_string_pretty(&nav).context("Failed to serialize nav.json")?;
+    output.push(("nav.json".to_string(), nav));
+    Ok(output)
+}
+
+/// Page title: the last path component
+fn title(path: &str) -> String {
+    path.rsplit('/')
+        .find(|part| !part.is_empty())
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// One-line description of a top file
+fn describe_file(top_file: &TopFile) -> String {
+    format!(
+        "Exports {} {}, used by {} {} and depends on {} {}.",
+        top_file.exports.len(),
+        plural(top_file.exports.len(), "entity", "entities"),
+        top_file.dependents.len(),
+        plural(top_file.dependents.len(), "file", "files"),
+        top_file.dependencies.len(),
+        plural(top_file.dependencies.len(), "file", "files"),
+    )
+}
+
+/// One-line description of a top directory
+fn describe_module(top_dir: &TopDirectory, top_files: usize) -> String {
+    match &top_dir.metrics {
+        Some(metrics) => format!(
+            "{} {} with {} {}, {} among the top files.",
+            metrics.file_count,
+            plural(metrics.file_count, "file", "files"),
+            metrics.line_count,
+            plural(metrics.line_count, "line", "lines"),
+            top_files
+        ),
+        None => format!(
+            "{} of the top files, importance score {}.",
+            top_files, top_dir.score
+        ),
+    }
+}
+
+fn plural(count: usize, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 {
+        one
+    } else {
+        many
+    }
+}
+
+fn to_json(data: &impl Serialize, path: &str) -> Result<String> {
+    serde_json::to_string_pretty(data).context(format!("Failed to serialize docs data of {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixture::Fixture;
+    use serde_json::Value;
+
+    /// Data files of the fixture repository's docs site, parsed, by path under `docs-data/`
+    fn pages(site: DocsSite) -> HashMap<String, Value> {
+        render(&Fixture::new().report(), site)
+            .unwrap()
+            .into_iter()
+            .map(|(page, json)| (page, serde_json::from_str(&json).unwrap()))
+            .collect()
+    }
+
+    /// Links of the `(path, link)` references in a page field; `None` for files without a page
+    fn links<'a>(page: &'a Value, field: &str) -> Vec<(&'a str, Option<&'a str>)> {
+        page[field]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| (entry["path"].as_str().unwrap(), entry["link"].as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn slugs_follow_the_site_convention() {
+        let cases = [
+            ("src/lib.rs", "src_lib.rs", "src-lib-rs"),
+            (
+                "./Src/My Utils/index.tsx",
+                "Src_My-Utils_index.tsx",
+                "src-my-utils-index-tsx",
+            ),
+            ("src/__init__.py", "src___init__.py", "src-init-py"),
+            ("", "file", "root"),
+        ];
+
+        for (path, mkdocs, docusaurus) in cases {
+            assert_eq!(DocsSite::Mkdocs.slug(path), mkdocs, "{}", path);
+            assert_eq!(DocsSite::Docusaurus.slug(path), docusaurus, "{}", path);
+        }
+    }
+
+    #[test]
+    fn pages_are_named_after_the_site_convention() {
+        assert_eq!(
+            DocsSite::Mkdocs.file_page("src/lib.rs"),
+            (
+                "files/src_lib.rs.json".to_string(),
+                "files/src_lib.rs.md".to_string()
+            )
+        );
+        assert_eq!(
+            DocsSite::Docusaurus.file_page("src/lib.rs"),
+            (
+                "files/src-lib-rs.json".to_string(),
+                "/files/src-lib-rs".to_string()
+            )
+        );
+        assert_eq!(
+            DocsSite::Mkdocs.module_page("src/core"),
+            (
+                "modules/src_core/index.json".to_string(),
+                "modules/src_core/".to_string()
+            )
+        );
+        assert_eq!(
+            DocsSite::Docusaurus.module_page("src/core"),
+            (
+                "modules/src-core/index.json".to_string(),
+                "/modules/src-core".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn links_are_rewritten_for_the_page_depth() {
+        assert_eq!(DocsSite::Mkdocs.link_from("files/a.md", 0), "files/a.md");
+        assert_eq!(
+            DocsSite::Mkdocs.link_from("files/a.md", 2),
+            "../../files/a.md"
+        );
+        assert_eq!(DocsSite::Docusaurus.link_from("/files/a", 2), "/files/a");
+
+        // File pages are one directory below the docs root, module pages two; dependents
+        // are ordered by importance
+        let mkdocs = pages(DocsSite::Mkdocs);
+        assert_eq!(
+            links(&mkdocs["files/src_lib.rs.json"], "dependents"),
+            [
+                ("src/util.rs", Some("../files/src_util.rs.md")),
+                ("src/main.rs", None),
+                ("tests/parse.rs", None),
+            ]
+        );
+        assert_eq!(
+            links(&mkdocs["modules/src/index.json"], "files"),
+            [
+                ("src/lib.rs", Some("../../files/src_lib.rs.md")),
+                ("src/util.rs", Some("../../files/src_util.rs.md")),
+            ]
+        );
+
+        let docusaurus = pages(DocsSite::Docusaurus);
+        assert_eq!(
+            links(&docusaurus["files/src-util-rs.json"], "dependencies"),
+            [("src/lib.rs", Some("/files/src-lib-rs"))]
+        );
+        assert_eq!(
+            links(&docusaurus["modules/src/index.json"], "files"),
+            [
+                ("src/lib.rs", Some("/files/src-lib-rs")),
+                ("src/util.rs", Some("/files/src-util-rs")),
+            ]
+        );
+    }
+
+    #[test]
+    fn pages_carry_their_front_matter() {
+        for (site, page, link) in [
+            (
+                DocsSite::Mkdocs,
+                "files/src_lib.rs.json",
+                "files/src_lib.rs.md",
+            ),
+            (
+                DocsSite::Docusaurus,
+                "files/src-lib-rs.json",
+                "/files/src-lib-rs",
+            ),
+        ] {
+            let pages = pages(site);
+            let file = &pages[page];
+            assert_eq!(file["kind"], "file", "{:?}", site);
+            assert_eq!(file["title"], "lib.rs", "{:?}", site);
+            assert_eq!(file["path"], "src/lib.rs", "{:?}", site);
+            assert_eq!(
+                file["description"], "Exports 3 entities, used by 3 files and depends on 0 files.",
+                "{:?}",
+                site
+            );
+            assert_eq!(file["rank"], 1, "{:?}", site);
+
+            let nav = &pages["nav.json"];
+            assert_eq!(nav["site"], format!("{:?}", site).to_lowercase());
+            assert_eq!(nav["files"][0]["page"], page, "{:?}", site);
+            assert_eq!(nav["files"][0]["link"], link, "{:?}", site);
+            assert_eq!(nav["files"][0]["title"], "lib.rs", "{:?}", site);
+        }
+    }
+}
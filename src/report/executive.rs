@@ -0,0 +1,416 @@
+//! Short prose summary opening the markdown and HTML reports, assembled from the report's
+//! structured data with the sentence templates of `executive_summary`.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use super::template::fill;
+use super::AnalysisReport;
+use crate::config::{ExecutiveSummaryConfig, LanguageConfig};
+use crate::history::PreviousRun;
+use crate::metrics::knowledge_score_breakdown;
+use crate::traversal::relative_path;
+
+/// Number of languages, top files and hotspots the summary names
+const HIGHLIGHTS: usize = 3;
+
+/// The facts of the executive summary and the sentences written from them
+#[derive(Debug, Serialize)]
+pub struct ExecutiveSummary {
+    pub files: usize,
+
+    /// Total lines, absent with --skip-metrics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+
+    /// Most common languages, with their share of the files
+    pub languages: Vec<LanguageShare>,
+
+    pub top_files: Vec<TopFileFact>,
+    pub hotspots: Vec<HotspotFact>,
+
+    /// Number of findings per severity
+    pub findings: BTreeMap<String, usize>,
+
+    /// Change since the previous run recorded in --db
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trends: Option<Trends>,
+
+    /// The facts written out with the sentence templates, in order
+    pub sentences: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LanguageShare {
+    /// Name of the configured language, or the extension no language covers
+    pub language: String,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopFileFact {
+    pub path: String,
+    pub score: usize,
+    pub dependents: usize,
+    pub exports: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HotspotFact {
+    pub path: String,
+    pub knowledge_score: f64,
+
+    /// Largest term of the knowledge score breakdown
+    pub factor: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trends {
+    pub files: Trend,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Trend>,
+
+    /// Average maintainability index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintainability: Option<Trend>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trend {
+    pub previous: f64,
+    pub current: f64,
+}
+
+impl Trend {
+    /// Arrow with the difference, e.g. "↑ 12", "↓ 0.5", or "→" when unchanged
+    fn describe(&self) -> String {
+        let delta = ((self.current - self.previous) * 10.0).round() / 10.0;
+        if delta > 0.0 {
+            format!("↑ {}", delta)
+        } else if delta < 0.0 {
+            format!("↓ {}", -delta)
+        } else {
+            "→".to_string()
+        }
+    }
+}
+
+/// Collect the facts from the report and write the sentences; extensions are counted under
+/// the name of the configured language covering them
+pub fn build(
+    report: &AnalysisReport,
+    templates: &ExecutiveSummaryConfig,
+    configured_languages: &HashMap<String, LanguageConfig>,
+    previous: Option<&PreviousRun>,
+) -> ExecutiveSummary {
+    let metrics = report.metrics.as_ref();
+    let relative = |path: &str| relative_path(path, &report.repo_path);
+
+    let mut files_per_language: BTreeMap<String, usize> = BTreeMap::new();
+    for (extension, files) in metrics
+        .iter()
+        .flat_map(|metrics| metrics.language_distribution.iter())
+    {
+        *files_per_language
+            .entry(language_name(extension, configured_languages))
+            .or_default() += files;
+    }
+    let mut languages: Vec<(String, usize)> = files_per_language.into_iter().collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let languages = languages
+        .into_iter()
+        .take(HIGHLIGHTS)
+        .map(|(language, files)| LanguageShare {
+            language,
+            percentage: metrics.map_or(0.0, |m| files as f64 / m.total_files as f64 * 100.0),
+        })
+        .collect();
+
+    let top_files = report
+        .top_files
+        .iter()
+        .take(HIGHLIGHTS)
+        .map(|file| TopFileFact {
+            path: relative(&file.path),
+            score: file.score,
+            dependents: file.dependents.len(),
+            exports: file.exports.len(),
+        })
+        .collect();
+
+    // Hotspots whose per-file metrics weren't kept have no breakdown to explain them
+    let hotspots = metrics
+        .iter()
+        .flat_map(|metrics| {
+            metrics
+                .knowledge_hotspots
+                .iter()
+                .filter_map(|(path, score)| {
+                    let file_metrics = metrics.file_metrics.get(path)?;
+                    let complexity = file_metrics.complexity_metrics.as_ref()?;
+                    let breakdown =
+                        knowledge_score_breakdown(file_metrics, complexity, &report.meta.scoring);
+                    let factor = [
+                        ("size", breakdown.size),
+                        ("complexity", breakdown.complexity),
+                        ("low maintainability", breakdown.maintainability),
+                        ("function count", breakdown.functions),
+                        ("declarations", breakdown.declarations),
+                        ("export usage", breakdown.exports),
+                        ("external signals", breakdown.extra),
+                    ]
+                    .into_iter()
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(name, _)| name)?;
+                    Some(HotspotFact {
+                        path: relative(path),
+                        knowledge_score: *score,
+                        factor,
+                    })
+                })
+        })
+        .take(HIGHLIGHTS)
+        .collect();
+
+    let mut findings = BTreeMap::new();
+    for finding in &report.findings {
+        *findings.entry(finding.severity.to_string()).or_insert(0) += 1;
+    }
+
+    let trends = previous.map(|previous| Trends {
+        files: Trend {
+            previous: previous.total_files as f64,
+            current: report.summary.total_files as f64,
+        },
+        lines: previous
+            .total_lines
+            .zip(metrics)
+            .map(|(lines, metrics)| Trend {
+                previous: lines as f64,
+                current: metrics.total_lines as f64,
+            }),
+        maintainability: previous
+            .avg_maintainability_index
+            .zip(metrics)
+            .map(|(index, metrics)| Trend {
+                previous: index,
+                current: metrics.avg_maintainability_index,
+            }),
+    });
+
+    let mut summary = ExecutiveSummary {
+        files: report.summary.total_files,
+        lines: metrics.map(|m| m.total_lines),
+        languages,
+        top_files,
+        hotspots,
+        findings,
+        trends,
+        sentences: Vec::new(),
+    };
+    summary.sentences = sentences(&summary, templates);
+    summary
+}
+
+/// Write the facts out, leaving out sentences whose facts are missing
+fn sentences(summary: &ExecutiveSummary, templates: &ExecutiveSummaryConfig) -> Vec<String> {
+    let mut sentences = Vec::new();
+
+    sentences.push(match summary.lines {
+        Some(lines) => fill(
+            &templates.size,
+            &[
+                ("files", summary.files.to_string()),
+                ("lines", lines.to_string()),
+            ],
+        ),
+        None => fill(
+            &templates.size_without_lines,
+            &[("files", summary.files.to_string())],
+        ),
+    });
+
+    if !summary.languages.is_empty() {
+        let languages = summary
+            .languages
+            .iter()
+            .map(|share| format!("{} ({:.0}%)", share.language, share.percentage))
+            .collect();
+        sentences.push(fill(
+            &templates.languages,
+            &[("languages", join_list(languages))],
+        ));
+    }
+
+    if !summary.top_files.is_empty() {
+        let files = summary
+            .top_files
+            .iter()
+            .map(|file| {
+                fill(
+                    &templates.top_file,
+                    &[
+                        ("path", file.path.clone()),
+                        ("score", file.score.to_string()),
+                        ("dependents", count(file.dependents, "file", "files")),
+                        ("exports", count(file.exports, "export", "exports")),
+                    ],
+                )
+            })
+            .collect();
+        sentences.push(fill(&templates.top_files, &[("files", join_list(files))]));
+    }
+
+    if !summary.hotspots.is_empty() {
+        let files = summary
+            .hotspots
+            .iter()
+            .map(|hotspot| {
+                fill(
+                    &templates.hotspot,
+                    &[
+                        ("path", hotspot.path.clone()),
+                        ("score", format!("{:.1}", hotspot.knowledge_score)),
+                        ("factor", hotspot.factor.to_string()),
+                    ],
+                )
+            })
+            .collect();
+        sentences.push(fill(&templates.hotspots, &[("files", join_list(files))]));
+    }
+
+    if summary.findings.is_empty() {
+        sentences.push(templates.no_findings.clone());
+    } else {
+        let findings = summary
+            .findings
+            .iter()
+            .map(|(severity, n)| count(*n, severity, &format!("{}s", severity)))
+            .collect();
+        sentences.push(fill(
+            &templates.findings,
+            &[("findings", join_list(findings))],
+        ));
+    }
+
+    if let Some(trends) = &summary.trends {
+        let describe = |trend: &Option<Trend>| {
+            trend
+                .as_ref()
+                .map_or_else(|| "n/a".to_string(), Trend::describe)
+        };
+        sentences.push(fill(
+            &templates.trend,
+            &[
+                ("files", trends.files.describe()),
+                ("lines", describe(&trends.lines)),
+                ("maintainability", describe(&trends.maintainability)),
+            ],
+        ));
+    }
+
+    sentences
+}
+
+/// Name of the configured language covering an extension, or the extension if none does;
+/// the first name in order when several languages claim it
+fn language_name(extension: &str, languages: &HashMap<String, LanguageConfig>) -> String {
+    languages
+        .iter()
+        .filter(|(_, lang)| {
+            lang.extensions
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(extension))
+        })
+        .map(|(name, _)| name.clone())
+        .min()
+        .unwrap_or_else(|| extension.to_string())
+}
+
+/// "1 file", "2 files"
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// "a", "a and b", "a, b and c"
+fn join_list(mut items: Vec<String>) -> String {
+    match items.len() {
+        0 => String::new(),
+        1 => items.remove(0),
+        _ => {
+            let last = items.pop().unwrap_or_default();
+            format!("{} and {}", items.join(", "), last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::RepositoryMetrics;
+    use crate::report::build_report;
+    use crate::report::fixture::Fixture;
+    use std::path::Path;
+
+    /// Executive summary of the fixture repository, with the files counted per extension
+    /// of `language_distribution`
+    fn summary(language_distribution: &[(&str, usize)]) -> ExecutiveSummary {
+        let fixture = Fixture::new();
+        let mut input = fixture.input();
+        let files: Vec<(&str, f64, f64)> = fixture
+            .file_paths
+            .iter()
+            .map(|path| (path.as_str(), 1.0, 100.0))
+            .collect();
+        let mut metrics = RepositoryMetrics::from_complexities(&files);
+        metrics.total_files = language_distribution.iter().map(|(_, files)| files).sum();
+        metrics.total_lines = 240;
+        metrics.language_distribution = language_distribution
+            .iter()
+            .map(|(extension, files)| (extension.to_string(), *files))
+            .collect();
+        input.metrics = Some(metrics);
+
+        build_report(input).executive_summary.unwrap()
+    }
+
+    #[test]
+    fn fixture_summary_names_languages() {
+        let fixture = Fixture::new();
+        let mut extensions: BTreeMap<&str, usize> = BTreeMap::new();
+        for path in &fixture.file_paths {
+            let extension = Path::new(path).extension().unwrap().to_str().unwrap();
+            *extensions.entry(extension).or_default() += 1;
+        }
+        let extensions: Vec<(&str, usize)> = extensions.into_iter().collect();
+
+        assert_eq!(
+            summary(&extensions).sentences.join(" "),
+            "The repository has 8 files with 240 lines. \
+             Most files are rust (100%). \
+             The most important files are src/lib.rs (used by 3 files) and src/util.rs (used by 1 file). \
+             The analysis raised no findings."
+        );
+    }
+
+    #[test]
+    fn extensions_are_counted_under_their_language() {
+        let summary = summary(&[("ts", 3), ("tsx", 1), ("rs", 2), ("toml", 2)]);
+
+        let languages: Vec<(&str, f64)> = summary
+            .languages
+            .iter()
+            .map(|share| (share.language.as_str(), share.percentage))
+            .collect();
+        // `toml` belongs to no configured language and keeps its extension
+        assert_eq!(
+            languages,
+            [("typescript", 50.0), ("rust", 25.0), ("toml", 25.0)]
+        );
+        assert_eq!(
+            summary.sentences[1],
+            "Most files are typescript (50%), rust (25%) and toml (25%)."
+        );
+    }
+}
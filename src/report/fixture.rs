@@ -0,0 +1,177 @@
+//! A small repository held in memory, to build reports from in tests
+
+use std::path::PathBuf;
+
+use crate::categories::{CategoryRules, CategorySummary};
+use crate::config::{Config, PathCase};
+use crate::dependencies::{self, DependencyGraph};
+use crate::directories::DirectoryCasing;
+use crate::exports::{self, ExportedEntity, ExportsMap, ImportReference, ImportsMap};
+use crate::import_stats::ImportStats;
+
+use super::meta::RunMetadata;
+use super::{build_report, AnalysisReport, ReportInput, TopFilesOrder};
+
+/// Root of the fixture repository; nothing is read from it
+pub const REPO: &str = "repo";
+
+/// Exports of each file, as `(file, name, export_type)`
+const EXPORTS: &[(&str, &str, &str)] = &[
+    ("repo/src/lib.rs", "parse", "function"),
+    ("repo/src/lib.rs", "Config", "struct"),
+    ("repo/src/lib.rs", "unused_helper", "function"),
+    ("repo/src/util.rs", "format_path", "function"),
+    ("repo/tests/support.rs", "sample_repo", "function"),
+];
+
+/// Imports of each file, as `(file, name)`
+const IMPORTS: &[(&str, &str)] = &[
+    ("repo/src/main.rs", "parse"),
+    ("repo/src/main.rs", "Config"),
+    ("repo/src/main.rs", "format_path"),
+    ("repo/src/util.rs", "Config"),
+    ("repo/tests/parse.rs", "parse"),
+    ("repo/tests/parse.rs", "sample_repo"),
+    ("repo/tests/render.rs", "sample_repo"),
+    ("repo/tests/cli.rs", "sample_repo"),
+    ("repo/tests/export.rs", "sample_repo"),
+];
+
+/// The analysis results of the fixture repository: `tests/support.rs` is the test helper
+/// every test imports, and more important than any production file
+pub struct Fixture {
+    pub config: Config,
+    pub file_paths: Vec<String>,
+    pub exports_map: ExportsMap,
+    pub imports_map: ImportsMap,
+    pub dependency_graph: DependencyGraph,
+    pub directory_casing: DirectoryCasing,
+    pub category_rules: CategoryRules,
+}
+
+/// An export as the scan would find it, before usages are counted
+pub fn export(file: &str, name: &str, export_type: &str) -> ExportedEntity {
+    ExportedEntity {
+        name: name.to_string(),
+        file_path: PathBuf::from(file),
+        line_number: 1,
+        export_type: export_type.to_string(),
+        visibility: "pub".to_string(),
+        id: String::new(),
+        usage_count: 0,
+        is_reexport: false,
+        reexport_source: None,
+        canonical_path: None,
+    }
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let mut exports_map = ExportsMap::new();
+        for (file, name, export_type) in EXPORTS {
+            exports_map
+                .entry(file.to_string())
+                .or_default()
+                .push(export(file, name, export_type));
+        }
+        exports::assign_export_ids(&mut exports_map, REPO);
+
+        let mut imports_map = ImportsMap::new();
+        for (line, (file, name)) in IMPORTS.iter().enumerate() {
+            imports_map
+                .entry(name.to_string())
+                .or_default()
+                .push(ImportReference {
+                    name: name.to_string(),
+                    file_path: PathBuf::from(file),
+                    line_number: line + 1,
+                    import_statement: format!("use crate::{};", name),
+                });
+        }
+
+        let dependency_graph =
+            dependencies::build_dependency_graph(&mut exports_map, &imports_map).unwrap();
+
+        let mut file_paths: Vec<String> = EXPORTS
+            .iter()
+            .map(|(file, _, _)| file)
+            .chain(IMPORTS.iter().map(|(file, _)| file))
+            .map(|file| file.to_string())
+            .collect();
+        file_paths.sort();
+        file_paths.dedup();
+
+        let directory_casing = DirectoryCasing::resolve(PathCase::Preserve, REPO, &file_paths);
+
+        let config = Config::default();
+        let category_rules = CategoryRules::new(&config.categories).unwrap();
+
+        Fixture {
+            config,
+            file_paths,
+            exports_map,
+            imports_map,
+            dependency_graph,
+            directory_casing,
+            category_rules,
+        }
+    }
+
+    /// Report inputs as a plain run would gather them, without metrics
+    pub fn input(&self) -> ReportInput<'_> {
+        let config = &self.config;
+        ReportInput {
+            meta: RunMetadata::new(REPO, "overdoc.yaml", config).unwrap(),
+            repo_path: REPO,
+            file_paths: &self.file_paths,
+            exports_map: &self.exports_map,
+            imports_map: &self.imports_map,
+            dependency_graph: &self.dependency_graph,
+            dir_importance: dependencies::calculate_directory_importance(
+                &self.dependency_graph,
+                &self.exports_map,
+                &self.directory_casing,
+            ),
+            repositories: Vec::new(),
+            test_files: Vec::new(),
+            only: None,
+            since: None,
+            partial: None,
+            output_stem: None,
+            directory_casing: &self.directory_casing,
+            metrics: None,
+            findings: Vec::new(),
+            notices: Vec::new(),
+            categories: CategorySummary::default(),
+            category_rules: &self.category_rules,
+            import_stats: ImportStats::default(),
+            code_owners: None,
+            leaf_directories: &config.architecture.leaf_directories,
+            top_n: 10,
+            top_files_order: TopFilesOrder::Importance,
+            min_importance: 0,
+            min_knowledge_score: 0.0,
+            top_dirs: 10,
+            top_exports: 0,
+            per_file_pages: false,
+            per_dir_pages: false,
+            limits: &config.report,
+            thresholds: &config.findings,
+            unused_exports: &config.unused_exports,
+            configured_languages: &config.languages,
+            languages: Vec::new(),
+            language_pages: false,
+            group_by_language: false,
+            verbose: false,
+            scope: None,
+            html: Default::default(),
+            executive_summary: &config.executive_summary,
+            previous_run: None,
+        }
+    }
+
+    /// The report of a plain run
+    pub fn report(&self) -> AnalysisReport {
+        build_report(self.input())
+    }
+}
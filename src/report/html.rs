@@ -0,0 +1,614 @@
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+
+use super::AnalysisReport;
+
+/// Directory (inside the output directory) holding the stylesheet and script of the HTML report
+pub const ASSETS_DIR: &str = "assets";
+
+/// File name of the sorting script under `assets/`
+const SCRIPT_FILE: &str = "overdoc.js";
+
+/// Look of the HTML report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    /// Light page with the report heading
+    #[default]
+    Light,
+
+    /// Dark page with the report heading
+    Dark,
+
+    /// Black on white for printing and PDF: tables wrap to the page width, headers repeat on
+    /// every page, and the sorting script is left out
+    Print,
+
+    /// Minimal styling and no page heading, for embedding in a wiki page
+    Plain,
+}
+
+impl Theme {
+    /// Name of the theme as given to --theme
+    fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Print => "print",
+            Theme::Plain => "plain",
+        }
+    }
+
+    /// Theme-specific rules, applied after `BASE_STYLE`
+    fn style(&self) -> &'static str {
+        match self {
+            Theme::Light => LIGHT_STYLE,
+            Theme::Dark => DARK_STYLE,
+            Theme::Print => PRINT_STYLE,
+            Theme::Plain => PLAIN_STYLE,
+        }
+    }
+
+    /// Whether tables can be sorted by clicking their headers
+    fn interactive(&self) -> bool {
+        *self != Theme::Print
+    }
+
+    /// Whether the page shows the report heading and repository
+    fn chrome(&self) -> bool {
+        *self != Theme::Plain
+    }
+
+    /// File name of the theme's stylesheet under `assets/`
+    fn stylesheet_file(&self) -> String {
+        format!("overdoc-{}.css", self.name())
+    }
+}
+
+/// How the HTML report is presented (`--theme`, `--title`, `--inline-assets`)
+#[derive(Debug, Clone, Default)]
+pub struct HtmlOptions {
+    pub theme: Theme,
+
+    /// Page heading and title instead of "OverDoc Analysis Results"
+    pub title: Option<String>,
+
+    /// Embed the stylesheet and script instead of linking them from `assets/`
+    pub inline_assets: bool,
+}
+
+impl HtmlOptions {
+    fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("OverDoc Analysis Results")
+    }
+}
+
+/// Layout shared by all themes
+const BASE_STYLE: &str = r#"
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; font-size: 0.9rem; }
+th, td { padding: 0.35rem 0.6rem; text-align: left; }
+th.sortable { cursor: pointer; user-select: none; }
+th.sortable::after { content: " \2195"; }
+th.asc::after { content: " \2191"; }
+th.desc::after { content: " \2193"; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+dl.summary { display: grid; grid-template-columns: max-content auto; gap: 0.25rem 1.5rem; }
+dl.summary dt { font-weight: 600; }
+dl.summary dd { margin: 0; }
+"#;
+
+const LIGHT_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem; color: #24292f; }
+h1, h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.3rem; }
+.notice { background: #fff8c5; border: 1px solid #d4a72c; border-radius: 6px; padding: 0.5rem 1rem; }
+th, td { border: 1px solid #d0d7de; }
+th { background: #f6f8fa; }
+th.sortable::after { color: #8c959f; }
+th.asc::after, th.desc::after { color: #24292f; }
+tr:nth-child(even) td { background: #fafbfc; }
+"#;
+
+const DARK_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem; color: #e6edf3; background: #0d1117; }
+h1, h2 { border-bottom: 1px solid #30363d; padding-bottom: 0.3rem; }
+code { color: #e6edf3; }
+.notice { background: #2e2a14; border: 1px solid #9e6a03; border-radius: 6px; padding: 0.5rem 1rem; }
+th, td { border: 1px solid #30363d; }
+th { background: #161b22; }
+th.sortable::after { color: #7d8590; }
+th.asc::after, th.desc::after { color: #e6edf3; }
+tr:nth-child(even) td { background: #11161d; }
+"#;
+
+const PRINT_STYLE: &str = r#"
+@page { size: A4; margin: 15mm; }
+body { font-family: Georgia, "Times New Roman", serif; font-size: 10pt; color: #000; background: #fff; }
+h1, h2 { border-bottom: 1px solid #000; padding-bottom: 0.2rem; break-after: avoid; }
+.notice { border: 1px solid #000; padding: 0.3rem 0.6rem; }
+table { table-layout: fixed; font-size: 8pt; }
+th, td { border: 1px solid #000; overflow-wrap: anywhere; word-break: break-word; }
+td code { white-space: normal; }
+thead { display: table-header-group; }
+tr { break-inside: avoid; page-break-inside: avoid; }
+th.sortable { cursor: auto; }
+th.sortable::after, th.asc::after, th.desc::after { content: none; }
+"#;
+
+const PLAIN_STYLE: &str = r#"
+th, td { border: 1px solid #ccc; }
+"#;
+
+/// Inline script that makes every table with the `sortable` class sortable by column
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  table.querySelectorAll("th.sortable").forEach(function (th, column) {
+    th.addEventListener("click", function () {
+      var ascending = !th.classList.contains("asc");
+      table.querySelectorAll("th").forEach(function (h) { h.classList.remove("asc", "desc"); });
+      th.classList.add(ascending ? "asc" : "desc");
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[column].getAttribute("data-value");
+        var y = b.cells[column].getAttribute("data-value");
+        var nx = parseFloat(x), ny = parseFloat(y);
+        var cmp = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+        return ascending ? cmp : -cmp;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+"#;
+
+/// Stylesheet of a theme
+fn stylesheet(theme: Theme) -> String {
+    format!("{}{}", BASE_STYLE, theme.style())
+}
+
+/// Files the HTML report links to when its assets aren't inlined, relative to `assets/`
+pub fn assets(options: &HtmlOptions) -> Vec<(String, String)> {
+    let mut files = vec![(options.theme.stylesheet_file(), stylesheet(options.theme))];
+    if options.theme.interactive() {
+        files.push((SCRIPT_FILE.to_string(), SCRIPT.to_string()));
+    }
+    files
+}
+
+/// Render the report as an HTML page in the theme of `report.html`, with its stylesheet and
+/// script inlined or linked from `assets/`
+pub fn render(report: &AnalysisReport) -> String {
+    let options = &report.html;
+    let theme = options.theme;
+
+    let mut content = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    content.push_str("<meta charset=\"utf-8\">\n");
+    content.push_str(&format!(
+        "<meta name=\"generator\" content=\"{}\">\n",
+        escape(&report.meta.describe())
+    ));
+    content.push_str(&format!(
+        "<title>{} - {}</title>\n",
+        escape(options.title()),
+        escape(&report.repo_path)
+    ));
+    if options.inline_assets {
+        content.push_str(&format!("<style>{}</style>\n", stylesheet(theme)));
+    } else {
+        content.push_str(&format!(
+            "<link rel=\"stylesheet\" href=\"{}/{}\">\n",
+            ASSETS_DIR,
+            theme.stylesheet_file()
+        ));
+    }
+    content.push_str("</head>\n<body>\n");
+    if theme.chrome() {
+        content.push_str(&format!("<h1>{}</h1>\n", escape(options.title())));
+        content.push_str(&format!(
+            "<p>Repository: <code>{}</code></p>\n",
+            escape(&report.repo_path)
+        ));
+        content.push_str(&format!(
+            "<p><small>{}</small></p>\n",
+            escape(&report.meta.describe())
+        ));
+    }
+    for notice in &report.notices {
+        content.push_str(&format!(
+            "<p class=\"notice\"><strong>Warning:</strong> {}</p>\n",
+            escape(notice)
+        ));
+    }
+
+    if let Some(summary) = &report.executive_summary {
+        content.push_str(&format!(
+            "<h2>Executive Summary</h2>\n<p class=\"executive-summary\">{}</p>\n",
+            escape(&summary.sentences.join(" "))
+        ));
+    }
+
+    render_summary(report, &mut content);
+    render_repositories(report, &mut content);
+    render_files(report, &mut content);
+    render_directories(report, &mut content);
+
+    if theme.interactive() {
+        if options.inline_assets {
+            content.push_str(&format!("<script>{}</script>\n", SCRIPT));
+        } else {
+            content.push_str(&format!(
+                "<script src=\"{}/{}\"></script>\n",
+                ASSETS_DIR, SCRIPT_FILE
+            ));
+        }
+    }
+    content.push_str("</body>\n</html>\n");
+    content
+}
+
+/// Render the summary statistics as a definition list
+fn render_summary(report: &AnalysisReport, content: &mut String) {
+    let mut items = vec![
+        (
+            "Total files analyzed",
+            report.summary.total_files.to_string(),
+        ),
+        (
+            "Total exported entities",
+            report.summary.total_exports.to_string(),
+        ),
+        (
+            "Files with exports",
+            report.summary.files_with_exports.to_string(),
+        ),
+    ];
+
+    if let Some(metrics) = &report.metrics {
+        items.push(("Total lines of code", metrics.total_lines.to_string()));
+        items.push(("Code lines", metrics.total_code_lines.to_string()));
+        items.push(("Comment lines", metrics.total_comment_lines.to_string()));
+        items.push(("Blank lines", metrics.total_blank_lines.to_string()));
+        items.push((
+            "Comment ratio",
+            format!("{:.2}%", metrics.avg_comment_ratio * 100.0),
+        ));
+        items.push((
+            "Average cyclomatic complexity",
+            format!("{:.2}", metrics.avg_cyclomatic_complexity),
+        ));
+        items.push((
+            "Average cognitive complexity",
+            format!("{:.2}", metrics.avg_cognitive_complexity),
+        ));
+        items.push((
+            "Average maintainability index",
+            format!("{:.2}", metrics.avg_maintainability_index),
+        ));
+        if !metrics.complexity_levels.is_empty() {
+            items.push(("Files by complexity", metrics.describe_complexity_levels()));
+        }
+    }
+
+    content.push_str("<h2>Summary</h2>\n<dl class=\"summary\">\n");
+    for (label, value) in items {
+        content.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", label, value));
+    }
+    content.push_str("</dl>\n");
+}
+
+/// Render a sortable table with every analyzed file
+fn render_files(report: &AnalysisReport, content: &mut String) {
+    content.push_str("<h2>Files</h2>\n");
+    if report.html.theme.interactive() {
+        content.push_str("<p>Click a column header to sort.</p>\n");
+    }
+    if let Some(suppressed) = &report.suppressed {
+        content.push_str(&format!(
+            "<p>Files with an importance score below {} or a knowledge score below {} are not listed.</p>\n",
+            suppressed.min_importance, suppressed.min_knowledge_score
+        ));
+    }
+    content.push_str("<table class=\"sortable\">\n<thead><tr>");
+
+    let headers: &[&str] = if report.metrics.is_some() {
+        &[
+            "File",
+            "Importance",
+            "Knowledge Score",
+            "Cyclomatic",
+            "Cognitive",
+            "Maintainability",
+            "Lines",
+            "Code Lines",
+            "Comment Ratio",
+            "Functions",
+        ]
+    } else {
+        &["File", "Importance"]
+    };
+    for header in headers {
+        content.push_str(&format!("<th class=\"sortable\">{}</th>", header));
+    }
+    content.push_str("</tr></thead>\n<tbody>\n");
+
+    match &report.metrics {
+        Some(metrics) => {
+            let paths: BTreeSet<&String> = metrics
+                .file_metrics
+                .keys()
+                .filter(|path| report.meets_thresholds(path))
+                .collect();
+            for path in paths {
+                let file_metrics = &metrics.file_metrics[path];
+                let importance = report.file_importance.get(path).copied().unwrap_or(0);
+
+                content.push_str("<tr>");
+                content.push_str(&text_cell(path));
+                content.push_str(&num_cell(importance as f64, 0));
+                content.push_str(&num_cell(file_metrics.knowledge_score(), 1));
+
+                match &file_metrics.complexity_metrics {
+                    Some(complexity) => {
+                        content.push_str(&num_cell(complexity.cyclomatic_complexity, 1));
+                        content.push_str(&num_cell(complexity.cognitive_complexity, 1));
+                        content.push_str(&num_cell(complexity.maintainability_index, 1));
+                    }
+                    // Skipped deep analysis is marked so it isn't mistaken for simplicity
+                    None if !file_metrics.deep_analysis => content.push_str(
+                        &"<td class=\"num\" data-value=\"\" title=\"below the deep_analysis thresholds\">skipped</td>".repeat(3),
+                    ),
+                    None => content.push_str(&"<td class=\"num\" data-value=\"\"></td>".repeat(3)),
+                }
+
+                content.push_str(&num_cell(file_metrics.line_count as f64, 0));
+                content.push_str(&num_cell(file_metrics.code_lines as f64, 0));
+                content.push_str(&format!(
+                    "<td class=\"num\" data-value=\"{:.4}\">{:.1}%</td>",
+                    file_metrics.comment_ratio(),
+                    file_metrics.comment_ratio() * 100.0
+                ));
+                content.push_str(&num_cell(file_metrics.function_count as f64, 0));
+                content.push_str("</tr>\n");
+            }
+        }
+        None => {
+            let paths: BTreeSet<&String> = report
+                .file_importance
+                .keys()
+                .filter(|path| report.meets_thresholds(path))
+                .collect();
+            for path in paths {
+                content.push_str("<tr>");
+                content.push_str(&text_cell(path));
+                content.push_str(&num_cell(report.file_importance[path] as f64, 0));
+                content.push_str("</tr>\n");
+            }
+        }
+    }
+
+    content.push_str("</tbody>\n</table>\n");
+}
+
+/// Render the totals of each repository when several are analyzed together
+fn render_repositories(report: &AnalysisReport, content: &mut String) {
+    if report.repositories.is_empty() {
+        return;
+    }
+
+    content.push_str("<h2>Repositories</h2>\n");
+    content.push_str("<table class=\"sortable\">\n<thead><tr>");
+    content.push_str("<th class=\"sortable\">Repository</th><th class=\"sortable\">Files</th><th class=\"sortable\">Exports</th><th class=\"sortable\">Lines</th><th>Depends on</th>");
+    content.push_str("</tr></thead>\n<tbody>\n");
+
+    for repository in &report.repositories {
+        content.push_str("<tr>");
+        content.push_str(&text_cell(&repository.path));
+        content.push_str(&num_cell(repository.files as f64, 0));
+        content.push_str(&num_cell(repository.exports as f64, 0));
+        match repository.lines {
+            Some(lines) => content.push_str(&num_cell(lines as f64, 0)),
+            None => content.push_str("<td class=\"num\" data-value=\"\"></td>"),
+        }
+        let depends_on: Vec<String> = repository
+            .depends_on
+            .iter()
+            .map(|(other, edges)| format!("{} ({})", escape(other), edges))
+            .collect();
+        content.push_str(&format!("<td>{}</td>", depends_on.join(", ")));
+        content.push_str("</tr>\n");
+    }
+
+    content.push_str("</tbody>\n</table>\n");
+}
+
+/// Render the top directories table
+fn render_directories(report: &AnalysisReport, content: &mut String) {
+    content.push_str("<h2>Top Important Directories</h2>\n");
+    content.push_str("<table class=\"sortable\">\n<thead><tr>");
+    content.push_str("<th class=\"sortable\">Directory</th><th class=\"sortable\">Score</th>");
+    if report.metrics.is_some() {
+        content.push_str("<th class=\"sortable\">Files</th><th class=\"sortable\">Total Lines</th><th class=\"sortable\">Functions</th>");
+    }
+    content.push_str("</tr></thead>\n<tbody>\n");
+
+    for dir in &report.top_directories {
+        content.push_str("<tr>");
+        content.push_str(&text_cell(&dir.path));
+        content.push_str(&num_cell(dir.score as f64, 0));
+        if let Some(dir_metrics) = &dir.metrics {
+            content.push_str(&num_cell(dir_metrics.file_count as f64, 0));
+            content.push_str(&num_cell(dir_metrics.line_count as f64, 0));
+            content.push_str(&num_cell(dir_metrics.function_count as f64, 0));
+        }
+        content.push_str("</tr>\n");
+    }
+
+    content.push_str("</tbody>\n</table>\n");
+}
+
+/// A table cell holding text, sorted alphabetically
+fn text_cell(text: &str) -> String {
+    let escaped = escape(text);
+    format!(
+        "<td data-value=\"{}\"><code>{}</code></td>",
+        escaped, escaped
+    )
+}
+
+/// A table cell holding a number, sorted numerically
+fn num_cell(value: f64, precision: usize) -> String {
+    format!(
+        "<td class=\"num\" data-value=\"{}\">{:.*}</td>",
+        value, precision, value
+    )
+}
+
+/// Escape text for inclusion in HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixture::Fixture;
+    use regex::Regex;
+
+    /// Outline of a page: its elements down to the tables, with the attributes that load
+    /// assets, one per line
+    fn structure(html: &str) -> String {
+        let tag =
+            Regex::new(r#"<([a-z0-9]+)((?:\s+(?:href|src|rel|class)="[^"]*")*)[^>]*>"#).unwrap();
+        let attribute = Regex::new(r#"(href|src|class)="([^"]*)""#).unwrap();
+        let mut lines: Vec<String> = Vec::new();
+        for captures in tag.captures_iter(html) {
+            let name = &captures[1];
+            if [
+                "tr", "td", "th", "code", "small", "strong", "thead", "tbody", "dt", "dd", "li",
+                "a",
+            ]
+            .contains(&name)
+            {
+                continue;
+            }
+            let mut line = name.to_string();
+            for attribute in attribute.captures_iter(&captures[2]) {
+                line.push_str(&format!(" {}={}", &attribute[1], &attribute[2]));
+            }
+            if lines.last() != Some(&line) {
+                lines.push(line);
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn render_with(theme: Theme, inline_assets: bool) -> String {
+        let mut report = Fixture::new().report();
+        report.html = HtmlOptions {
+            theme,
+            title: None,
+            inline_assets,
+        };
+        render(&report)
+    }
+
+    /// Outline of a page with the full chrome, linking the stylesheet of `theme`; interactive
+    /// themes add the sort hint above the first table and the script
+    fn full_page(theme: &str, interactive: bool) -> String {
+        let stylesheet = format!("link href=assets/overdoc-{}.css", theme);
+        let mut lines = vec![
+            "html",
+            "head",
+            "meta",
+            "title",
+            &stylesheet,
+            "body",
+            "h1",
+            "p",
+            "h2",
+            "p class=executive-summary",
+            "h2",
+            "dl class=summary",
+            "h2",
+        ];
+        if interactive {
+            lines.push("p");
+        }
+        lines.extend(["table class=sortable", "h2", "table class=sortable"]);
+        if interactive {
+            lines.push("script src=assets/overdoc.js");
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn light_theme_links_its_stylesheet_and_the_script() {
+        let html = render_with(Theme::Light, false);
+        assert_eq!(structure(&html), full_page("light", true));
+    }
+
+    #[test]
+    fn dark_theme_links_its_stylesheet_and_the_script() {
+        let html = render_with(Theme::Dark, false);
+        assert_eq!(structure(&html), full_page("dark", true));
+    }
+
+    #[test]
+    fn print_theme_has_no_script_or_sort_hint() {
+        let html = render_with(Theme::Print, false);
+        assert_eq!(structure(&html), full_page("print", false));
+        assert!(!html.contains("<script"), "{}", html);
+    }
+
+    #[test]
+    fn plain_theme_drops_the_page_chrome() {
+        let html = render_with(Theme::Plain, false);
+        let outline = structure(&html);
+        assert!(
+            outline
+                .starts_with("html\nhead\nmeta\ntitle\nlink href=assets/overdoc-plain.css\nbody\n"),
+            "{}",
+            outline
+        );
+        assert!(!outline.contains("\nh1\n"), "{}", outline);
+        assert!(outline.contains("table class=sortable"), "{}", outline);
+        assert!(
+            outline.ends_with("script src=assets/overdoc.js"),
+            "{}",
+            outline
+        );
+    }
+
+    #[test]
+    fn inline_assets_leave_no_external_references() {
+        for theme in [Theme::Light, Theme::Dark, Theme::Print, Theme::Plain] {
+            let html = render_with(theme, true);
+            assert!(html.contains("<style>"), "{:?}: {}", theme, html);
+            assert!(!html.contains("<link"), "{:?}: {}", theme, html);
+            assert!(!html.contains("src="), "{:?}: {}", theme, html);
+            assert!(
+                !html.contains(&format!("{}/", ASSETS_DIR)),
+                "{:?}: {}",
+                theme,
+                html
+            );
+            assert_eq!(
+                html.contains("<script>"),
+                theme != Theme::Print,
+                "{:?}: {}",
+                theme,
+                html
+            );
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use anyhow::{Context, Result};
+
+use super::AnalysisReport;
+
+/// Render the report as pretty-printed JSON
+pub fn render(report: &AnalysisReport) -> Result<String> {
+    serde_json::to_string_pretty(report).context("Failed to serialize report to JSON")
+}
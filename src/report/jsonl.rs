@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::meta::RunMetadata;
+use super::{AnalysisReport, ReportSummary};
+use crate::metrics::FileMetrics;
+
+/// A line describing one analyzed file
+#[derive(Serialize)]
+struct FileLine<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+
+    /// Importance score from the dependency graph
+    importance: usize,
+
+    #[serde(flatten)]
+    metrics: &'a FileMetrics,
+}
+
+/// The final line, with the repository totals
+#[derive(Serialize)]
+struct SummaryLine<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    meta: &'a RunMetadata,
+    repo_path: &'a str,
+
+    #[serde(flatten)]
+    summary: &'a ReportSummary,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    totals: Option<Totals<'a>>,
+    findings: usize,
+}
+
+/// Repository-wide metrics without the per-file details
+#[derive(Serialize)]
+struct Totals<'a> {
+    total_lines: usize,
+    total_code_lines: usize,
+    total_comment_lines: usize,
+    total_blank_lines: usize,
+    total_size_bytes: u64,
+    language_distribution: &'a HashMap<String, usize>,
+    avg_comment_ratio: f64,
+    avg_cyclomatic_complexity: f64,
+    avg_cognitive_complexity: f64,
+    avg_maintainability_index: f64,
+}
+
+/// Render the line for one analyzed file
+pub fn render_file(metrics: &FileMetrics, importance: usize) -> Result<String> {
+    serde_json::to_string(&FileLine {
+        kind: "file",
+        importance,
+        metrics,
+    })
+    .context(format!("Failed to serialize metrics of {}", metrics.path))
+}
+
+/// Render the summary line that closes the stream
+pub fn render_summary(report: &AnalysisReport) -> Result<String> {
+    serde_json::to_string(&SummaryLine {
+        kind: "summary",
+        meta: &report.meta,
+        repo_path: &report.repo_path,
+        summary: &report.summary,
+        totals: report.metrics.as_ref().map(|m| Totals {
+            total_lines: m.total_lines,
+            total_code_lines: m.total_code_lines,
+            total_comment_lines: m.total_comment_lines,
+            total_blank_lines: m.total_blank_lines,
+            total_size_bytes: m.total_size_bytes,
+            language_distribution: &m.language_distribution,
+            avg_comment_ratio: m.avg_comment_ratio,
+            avg_cyclomatic_complexity: m.avg_cyclomatic_complexity,
+            avg_cognitive_complexity: m.avg_cognitive_complexity,
+            avg_maintainability_index: m.avg_maintainability_index,
+        }),
+        findings: report.findings.len(),
+    })
+    .context("Failed to serialize report summary")
+}
+
+/// Render a complete report held in memory: a line per file, ordered by path, then the summary
+pub fn render(report: &AnalysisReport) -> Result<String> {
+    let mut content = String::new();
+
+    if let Some(metrics) = &report.metrics {
+        let mut files: Vec<&FileMetrics> = metrics.file_metrics.values().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for file in files {
+            let importance = report.file_importance.get(&file.path).copied().unwrap_or(0);
+            content.push_str(&render_file(file, importance)?);
+            content.push('\n');
+        }
+    }
+
+    content.push_str(&render_summary(report)?);
+    content.push('\n');
+    Ok(content)
+}
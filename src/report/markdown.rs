@@ -0,0 +1,1139 @@
+use std::collections::{HashMap, HashSet};
+
+use super::scope::ScopeSummary;
+use super::{
+    pages, AnalysisReport, ComplexityOffender, LanguageReport, OutputFormat, Suppressed, TopFile,
+    TopFilesOrder,
+};
+use crate::categories;
+use crate::exports;
+use crate::findings::CONCENTRATED_COMPLEXITY;
+use crate::traversal::relative_path;
+
+/// Directory (inside the output directory) holding the per-language sub-reports
+pub const LANGUAGES_DIR: &str = "by-language";
+
+/// Version, time and configuration of the run, so reports of different runs can be compared
+fn render_metadata(report: &AnalysisReport, content: &mut String) {
+    let meta = &report.meta;
+    content.push_str(&format!("- OverDoc version: {}\n", meta.version));
+    content.push_str(&format!("- Generated: {}\n", meta.timestamp));
+    content.push_str(&format!("- Repository path: `{}`\n", meta.repo_path));
+    content.push_str(&format!("- Configuration: `{}`\n", meta.config_path));
+    content.push_str(&format!("- Configuration hash: `{}`\n", meta.config_hash));
+    content.push_str(&format!(
+        "- Knowledge score weights: {}\n\n",
+        meta.describe_scoring()
+    ));
+}
+
+/// Render the report as markdown
+pub fn render(report: &AnalysisReport) -> String {
+    let mut content = String::from("# OverDoc Analysis Results\n\n");
+    content.push_str("## Repository: ");
+    content.push_str(&report.repo_path);
+    content.push_str("\n\n");
+    render_metadata(report, &mut content);
+
+    for notice in &report.notices {
+        content.push_str(&format!("> **Warning:** {}\n\n", notice));
+    }
+
+    if let Some(summary) = &report.executive_summary {
+        content.push_str("## Executive Summary\n\n");
+        content.push_str(&summary.sentences.join(" "));
+        content.push_str("\n\n");
+    }
+
+    render_contents(report, &mut content);
+    let anchors = if report.group_by_language {
+        file_anchors(
+            report.languages.iter().flat_map(|lang| &lang.top_files),
+            &report.repo_path,
+        )
+    } else {
+        file_anchors(&report.top_files, &report.repo_path)
+    };
+
+    // Add summary statistics
+    content.push_str("## Summary\n\n");
+    content.push_str(&format!(
+        "- Total files analyzed: {}\n",
+        report.summary.total_files
+    ));
+    content.push_str(&format!(
+        "- Total exported entities: {}\n",
+        report.summary.total_exports
+    ));
+    content.push_str(&format!(
+        "- Files with exports: {}\n",
+        report.summary.files_with_exports
+    ));
+    if let Some(only) = &report.only {
+        content.push_str(&format!(
+            "- Limited to: `{}` (usage counts include importers across the repository)\n",
+            only
+        ));
+    }
+    if let Some(since) = &report.since {
+        content.push_str(&format!(
+            "- Partial analysis: only the files changed since `{}` (usage counts include importers across the repository)\n",
+            since
+        ));
+    }
+    if let Some(split) = &report.test_split {
+        content.push_str(&format!(
+            "- Production code: {}\n",
+            split.production.describe()
+        ));
+        content.push_str(&format!("- Test code: {}\n", split.tests.describe()));
+    }
+    if let Some(scope) = &report.scope {
+        content.push_str(&format!(
+            "- Scope: {} ({} of {} files{})\n",
+            scope.description(),
+            scope.files,
+            report.summary.total_files,
+            scope_shares(scope)
+        ));
+    }
+
+    // Add metrics summary if available
+    if let Some(metrics) = &report.metrics {
+        content.push_str(&format!("- Total lines of code: {}\n", metrics.total_lines));
+        content.push_str(&format!("- Code lines: {}\n", metrics.total_code_lines));
+        content.push_str(&format!(
+            "- Comment lines: {}\n",
+            metrics.total_comment_lines
+        ));
+        content.push_str(&format!("- Blank lines: {}\n", metrics.total_blank_lines));
+        content.push_str(&format!(
+            "- Comment ratio: {:.2}%\n",
+            metrics.avg_comment_ratio * 100.0
+        ));
+        content.push_str(&format!(
+            "- Average lines per file: {}\n",
+            metrics.avg_lines_per_file
+        ));
+
+        // Add complexity metrics summary
+        content.push_str(&format!(
+            "- Average cyclomatic complexity: {:.2}\n",
+            metrics.avg_cyclomatic_complexity
+        ));
+        content.push_str(&format!(
+            "- Average cognitive complexity: {:.2}\n",
+            metrics.avg_cognitive_complexity
+        ));
+        content.push_str(&format!(
+            "- Average maintainability index: {:.2}\n",
+            metrics.avg_maintainability_index
+        ));
+        if !metrics.complexity_levels.is_empty() {
+            content.push_str(&format!(
+                "- Files by complexity: {}\n",
+                metrics.describe_complexity_levels()
+            ));
+        }
+        if metrics.partial_complexity_files > 0 {
+            content.push_str(&format!(
+                "- Files with partial complexity (time budget exceeded, not averaged): {}\n",
+                metrics.partial_complexity_files
+            ));
+        }
+        if metrics.shallow_files > 0 {
+            content.push_str(&format!(
+                "- Files without deep analysis (below the `deep_analysis` thresholds, no complexity or per-function data): {}\n",
+                metrics.shallow_files
+            ));
+        }
+
+        // Add language distribution
+        content.push_str("\n### Language Distribution\n\n");
+        let mut lang_dist: Vec<(String, usize)> = metrics
+            .language_distribution
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        lang_dist.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        for (lang, count) in lang_dist {
+            let percentage = (count as f64 / metrics.total_files as f64) * 100.0;
+            let sub_report = report
+                .language_for_extension(&lang)
+                .filter(|_| report.language_pages)
+                .map(|sub| format!(" - see [{}]({}/{})", sub.language, LANGUAGES_DIR, sub.page))
+                .unwrap_or_default();
+            content.push_str(&format!(
+                "- {}: {} files ({:.1}%){}\n",
+                lang, count, percentage, sub_report
+            ));
+        }
+
+        // Add knowledge hotspots section
+        let knowledge_note = report
+            .suppressed
+            .as_ref()
+            .and_then(Suppressed::knowledge_note);
+        if !metrics.knowledge_hotspots.is_empty() || knowledge_note.is_some() {
+            content.push_str("\n### Knowledge Hotspots\n\n");
+            content.push_str("Files with highest knowledge scores (combining complexity, size, and importance):\n\n");
+
+            for (idx, (file, score)) in metrics.knowledge_hotspots.iter().take(5).enumerate() {
+                // Link to the file's entry when it is one of the top files
+                let title = match anchors.get(file) {
+                    Some(anchor) => format!("[{}](#{})", file, anchor),
+                    None => file.clone(),
+                };
+                content.push_str(&format!(
+                    "{}. **{}** (Knowledge Score: {:.1})\n",
+                    idx + 1,
+                    title,
+                    score
+                ));
+            }
+            if let Some(note) = knowledge_note {
+                content.push_str(&format!("\n_{}._\n", note));
+            }
+        }
+    }
+
+    render_repositories(report, &mut content);
+    render_categories(report, &mut content);
+    render_concentrated_complexity(report, &mut content);
+
+    content.push('\n');
+
+    // Add top important files
+    content.push_str("## Top Important Files\n\n");
+    render_order_note(report, &mut content);
+    if report.group_by_language {
+        render_language_groups(report, &anchors, &mut content);
+    } else {
+        for (idx, top_file) in report.top_files.iter().enumerate() {
+            render_top_file(report, top_file, idx + 1, "###", &anchors, &mut content);
+        }
+    }
+    if let Some(note) = report
+        .suppressed
+        .as_ref()
+        .and_then(Suppressed::importance_note)
+    {
+        content.push_str(&format!("_{}._\n\n", note));
+    }
+    render_test_utilities(report, &mut content);
+    render_top_exports(report, &anchors, &mut content);
+
+    // Add top important directories
+    content.push_str("## Top Important Directories\n\n");
+
+    // Entries with metrics are separated by blank lines; without metrics they form a compact list
+    let with_metrics = report
+        .top_directories
+        .iter()
+        .any(|dir| dir.metrics.is_some());
+    for (idx, dir) in report.top_directories.iter().enumerate() {
+        let title = match report.dir_pages.get(&dir.path) {
+            Some(page) => format!("[{}]({}/{})", dir.path, pages::DIRS_DIR, page),
+            None => dir.path.clone(),
+        };
+        content.push_str(&format!(
+            "{}. **{}** (Score: {})\n",
+            idx + 1,
+            title,
+            dir.score
+        ));
+
+        // If we have metrics, add directory metrics summary
+        if let Some(dir_metrics) = &dir.metrics {
+            content.push_str(&format!(
+                "   - Files: {}, Total lines: {}, Functions: {}\n",
+                dir_metrics.file_count, dir_metrics.line_count, dir_metrics.function_count
+            ));
+        }
+
+        if with_metrics {
+            content.push('\n');
+        }
+    }
+    if !with_metrics {
+        content.push('\n');
+    }
+
+    render_complexity_offenders(report, &mut content);
+    render_unused_exports(report, &mut content);
+    render_directory_coupling(report, &mut content);
+    render_imports(report, &mut content);
+    render_owners(report, &mut content);
+
+    content
+}
+
+/// Render the top files of each language under its own heading, with the language's totals
+fn render_language_groups(
+    report: &AnalysisReport,
+    anchors: &HashMap<String, String>,
+    content: &mut String,
+) {
+    content.push_str("Files are ranked per language; the summary above covers all of them.\n\n");
+    for language in &report.languages {
+        content.push_str(&format!("### {}\n\n", language.language));
+        render_language_totals(language, content);
+        for (idx, top_file) in language.top_files.iter().enumerate() {
+            render_top_file(report, top_file, idx + 1, "####", anchors, content);
+        }
+    }
+}
+
+/// Render the file, line and export totals of a language
+fn render_language_totals(language: &LanguageReport, content: &mut String) {
+    content.push_str(&format!(
+        "- Extensions: {}\n",
+        language.extensions.join(", ")
+    ));
+    content.push_str(&format!("- Files analyzed: {}\n", language.file_count));
+    if let Some(line_count) = language.line_count {
+        content.push_str(&format!("- Total lines of code: {}\n", line_count));
+    }
+    content.push_str(&format!(
+        "- Exported entities: {}\n",
+        language.exports.total_exports
+    ));
+    content.push_str(&format!(
+        "- Files with exports: {}\n\n",
+        language.exports.files_with_exports
+    ));
+}
+
+/// Render the entry of a top file under a `heading` of the given level, e.g. `###`
+fn render_top_file(
+    report: &AnalysisReport,
+    top_file: &TopFile,
+    number: usize,
+    heading: &str,
+    anchors: &HashMap<String, String>,
+    content: &mut String,
+) {
+    let title = match report.file_pages.get(&top_file.path) {
+        Some(page) => format!("[{}]({}/{})", top_file.path, pages::PAGES_DIR, page),
+        None => top_file.path.clone(),
+    };
+    content.push_str(&format!(
+        "<a id=\"{}\"></a>\n\n{} {}. {} (Score: {})\n\n",
+        anchors[&top_file.path], heading, number, title, top_file.score
+    ));
+
+    // If verbose, show the exports and their usage counts
+    if let Some(detail) = &top_file.export_detail {
+        for export in &detail.listed {
+            if export.is_reexport {
+                let defined_in = export
+                    .canonical_path
+                    .as_ref()
+                    .map(|path| format!("defined in {}", path.display()))
+                    .unwrap_or_else(|| "definition unresolved".to_string());
+                content.push_str(&format!(
+                    "   - re-export `{}` ({})\n",
+                    export.name, defined_in
+                ));
+                continue;
+            }
+
+            let sites = exports::reexport_sites(&report.exports, export);
+            let also_exported = if sites.is_empty() {
+                String::new()
+            } else {
+                let sites: Vec<String> = sites.iter().map(|p| p.display().to_string()).collect();
+                format!("; also exported from: {}", sites.join(", "))
+            };
+            content.push_str(&format!(
+                "   - {} `{}` (used {} times{})\n",
+                export.export_type, export.name, export.usage_count, also_exported
+            ));
+        }
+        if let Some(more) = detail.more_line(&report.output_name(OutputFormat::Json)) {
+            content.push_str(&format!("   - {}\n", more));
+        }
+    }
+
+    // Add metrics for this file if available
+    if let Some(file_metrics) = &top_file.metrics {
+        content.push_str(&format!(
+            "   - Lines: {} (Code: {}, Comments: {}, Blank: {})\n",
+            file_metrics.line_count,
+            file_metrics.code_lines,
+            file_metrics.comment_lines,
+            file_metrics.blank_lines
+        ));
+
+        content.push_str(&format!(
+            "   - Functions: {}, Comment ratio: {:.1}%\n",
+            file_metrics.function_count,
+            file_metrics.comment_ratio() * 100.0
+        ));
+
+        if !file_metrics.declaration_count.is_empty() {
+            let decl_str = file_metrics
+                .declaration_count
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            content.push_str(&format!("   - Declarations: {}\n", decl_str));
+        }
+
+        // Add complexity metrics if available
+        if let Some(complexity) = &file_metrics.complexity_metrics {
+            match &file_metrics.complexity_level {
+                Some(level) => content.push_str(&format!(
+                    "   - Complexity: {} ({})\n",
+                    level,
+                    complexity.description()
+                )),
+                None => {
+                    content.push_str(&format!("   - Complexity: {}\n", complexity.description()))
+                }
+            }
+
+            content.push_str(&format!(
+                "   - Maintainability Index: {:.1} (Higher is better)\n",
+                complexity.maintainability_index
+            ));
+
+            content.push_str(&format!(
+                "   - Knowledge Score: {:.1}\n",
+                file_metrics.knowledge_score()
+            ));
+        }
+        if !file_metrics.deep_analysis {
+            content
+                .push_str("   - Deep analysis: skipped (below the `deep_analysis` thresholds)\n");
+        }
+    }
+
+    render_related_files("Used by", &top_file.dependents, report, content);
+    render_related_files("Depends on", &top_file.dependencies, report, content);
+
+    content.push('\n');
+}
+
+/// Say what the top files are ordered by when --sort-by changed it from importance
+fn render_order_note(report: &AnalysisReport, content: &mut String) {
+    if report.top_files_order != TopFilesOrder::Importance {
+        content.push_str(&format!(
+            "_Ordered by {}._\n\n",
+            report.top_files_order.describe()
+        ));
+    }
+}
+
+/// Render the test files left out of the top files, so heavily used helpers stay visible
+fn render_test_utilities(report: &AnalysisReport, content: &mut String) {
+    if report.test_utilities.is_empty() {
+        return;
+    }
+
+    content.push_str(
+        "### Most Depended-Upon Test Utilities
+
+",
+    );
+    content.push_str(
+        "Files of the `report.test_categories` categories, ranked apart from production code:\n\n",
+    );
+    for (idx, file) in report.test_utilities.iter().enumerate() {
+        content.push_str(&format!(
+            "{}. **{}** (Score: {}), used by {} files\n",
+            idx + 1,
+            file.path,
+            file.score,
+            file.dependents.len()
+        ));
+    }
+    content.push('\n');
+}
+
+/// Render the most used exported entities, linking each to its file's entry when it is one of
+/// the top files
+fn render_top_exports(
+    report: &AnalysisReport,
+    anchors: &HashMap<String, String>,
+    content: &mut String,
+) {
+    if report.top_exports.is_empty() {
+        return;
+    }
+
+    content.push_str("## Most Used Exports\n\n");
+    content.push_str("| # | Export | Type | Defined in | Uses |\n");
+    content.push_str("|---|--------|------|------------|------|\n");
+    for (idx, export) in report.top_exports.iter().enumerate() {
+        let location = format!(
+            "{}:{}",
+            relative_path(&export.path, &report.repo_path),
+            export.line
+        );
+        let defined_in = match anchors.get(&export.path) {
+            Some(anchor) => format!("[{}](#{})", location, anchor),
+            None => format!("`{}`", location),
+        };
+        content.push_str(&format!(
+            "| {} | `{}` | {} | {} | {} |\n",
+            idx + 1,
+            export.name,
+            export.export_type,
+            defined_in,
+            export.usage_count
+        ));
+    }
+    content.push('\n');
+}
+
+/// Describe the scope's share of lines and complexity, e.g. ", 12.5% of lines, 20.0% of complexity"
+fn scope_shares(scope: &ScopeSummary) -> String {
+    match (scope.line_share, scope.complexity_share) {
+        (Some(lines), Some(complexity)) => format!(
+            ", {:.1}% of lines, {:.1}% of cyclomatic complexity",
+            lines * 100.0,
+            complexity * 100.0
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Render links to the main sections, leaving out sections the report doesn't have
+fn render_contents(report: &AnalysisReport, content: &mut String) {
+    let mut entries = vec![(0, "Summary")];
+    if let Some(metrics) = &report.metrics {
+        entries.push((1, "Language Distribution"));
+        let suppressed_hotspots = report
+            .suppressed
+            .as_ref()
+            .is_some_and(|suppressed| suppressed.below_knowledge_score > 0);
+        if !metrics.knowledge_hotspots.is_empty() || suppressed_hotspots {
+            entries.push((1, "Knowledge Hotspots"));
+        }
+    }
+    if !report.repositories.is_empty() {
+        entries.push((1, "Repositories"));
+    }
+    if !report.categories.counts.is_empty() {
+        entries.push((1, "File Categories"));
+    }
+    if report
+        .findings
+        .iter()
+        .any(|finding| finding.rule_id == CONCENTRATED_COMPLEXITY)
+    {
+        entries.push((1, "Concentrated Complexity"));
+    }
+    entries.push((0, "Top Important Files"));
+    if report.group_by_language {
+        for language in &report.languages {
+            entries.push((1, language.language.as_str()));
+        }
+    }
+    if !report.test_utilities.is_empty() {
+        entries.push((1, "Most Depended-Upon Test Utilities"));
+    }
+    if !report.top_exports.is_empty() {
+        entries.push((0, "Most Used Exports"));
+    }
+    entries.push((0, "Top Important Directories"));
+    if report
+        .complexity_offenders
+        .as_ref()
+        .is_some_and(|offenders| !offenders.by_cyclomatic.is_empty())
+    {
+        entries.push((0, "Top Complexity Offenders"));
+    }
+    if !report.unused_exports.is_empty() {
+        entries.push((0, "Unused Exports"));
+    }
+    if !report.directory_coupling.is_empty() {
+        entries.push((0, "Directory Dependencies"));
+    }
+    if report.imports.statements > 0 {
+        entries.push((0, "Import Statements"));
+    }
+    if report.owners.is_some() {
+        entries.push((0, "Code Owners"));
+    }
+
+    content.push_str("## Contents\n\n");
+    for (level, heading) in entries {
+        content.push_str(&format!(
+            "{}- [{}](#{})\n",
+            "  ".repeat(level),
+            heading,
+            anchor_slug(heading)
+        ));
+    }
+    content.push('\n');
+}
+
+/// Assign each top file a unique anchor for its heading, e.g. `file-src-lib-rs`.
+///
+/// Anchors are derived from the repository-relative path, so they stay the same across
+/// runs; paths that slugify alike (in sorted order) get a numeric suffix.
+pub fn file_anchors<'a>(
+    top_files: impl IntoIterator<Item = &'a TopFile>,
+    repo_path: &str,
+) -> HashMap<String, String> {
+    let mut paths: Vec<&String> = top_files.into_iter().map(|file| &file.path).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut anchors = HashMap::new();
+
+    for path in paths {
+        let slug = format!("file-{}", anchor_slug(&relative_path(path, repo_path)));
+
+        let mut anchor = slug.clone();
+        let mut counter = 2;
+        while !used.insert(anchor.clone()) {
+            anchor = format!("{}-{}", slug, counter);
+            counter += 1;
+        }
+
+        anchors.insert(path.clone(), anchor);
+    }
+
+    anchors
+}
+
+/// Lowercase the text and join its alphanumeric runs with dashes, as heading anchors are
+fn anchor_slug(text: &str) -> String {
+    text.to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Render a collapsible list of the files related to a top file, most important first
+fn render_related_files(
+    title: &str,
+    files: &[String],
+    report: &AnalysisReport,
+    content: &mut String,
+) {
+    if files.is_empty() {
+        return;
+    }
+
+    content.push_str(&format!(
+        "\n   <details><summary>{} ({})</summary>\n\n",
+        title,
+        files.len()
+    ));
+    for file in files.iter().take(report.dependency_list_limit) {
+        content.push_str(&format!(
+            "   - {}\n",
+            relative_path(file, &report.repo_path)
+        ));
+    }
+    if files.len() > report.dependency_list_limit {
+        content.push_str(&format!(
+            "   - … and {} more\n",
+            files.len() - report.dependency_list_limit
+        ));
+    }
+    content.push_str("\n   </details>\n");
+}
+
+/// Render incoming and outgoing dependency edges per top-level directory
+fn render_directory_coupling(report: &AnalysisReport, content: &mut String) {
+    if report.directory_coupling.is_empty() {
+        return;
+    }
+
+    content.push_str("## Directory Dependencies\n\n");
+    content.push_str("Dependency edges crossing each top-level directory. Instability is the share of outgoing edges: directories near 0 are depended upon, directories near 1 depend on others.\n\n");
+    content.push_str("| Directory | Incoming | Outgoing | Instability |\n");
+    content.push_str("|-----------|----------|----------|-------------|\n");
+    for dir in &report.directory_coupling {
+        let name = if dir.leaf {
+            format!("{} (leaf)", dir.directory)
+        } else {
+            dir.directory.clone()
+        };
+        content.push_str(&format!(
+            "| {} | {} | {} | {:.2} |\n",
+            name, dir.afferent, dir.efferent, dir.instability
+        ));
+    }
+    content.push('\n');
+
+    let violations: Vec<_> = report
+        .directory_coupling
+        .iter()
+        .filter(|dir| !dir.unexpected_dependencies.is_empty())
+        .collect();
+    if violations.is_empty() {
+        return;
+    }
+
+    content.push_str("### Unexpected Dependencies of Leaf Directories\n\n");
+    for dir in violations {
+        let targets: Vec<String> = dir
+            .unexpected_dependencies
+            .iter()
+            .map(|(target, count)| {
+                let unit = if *count == 1 { "edge" } else { "edges" };
+                format!("{} ({} {})", target, count, unit)
+            })
+            .collect();
+        content.push_str(&format!(
+            "- **{}** depends on {}\n",
+            dir.directory,
+            targets.join(", ")
+        ));
+    }
+    content.push('\n');
+}
+
+/// Render the aggregated import statements
+fn render_imports(report: &AnalysisReport, content: &mut String) {
+    let imports = &report.imports;
+    if imports.statements == 0 {
+        return;
+    }
+
+    content.push_str("## Import Statements\n\n");
+    content.push_str(&format!(
+        "- Import statements: {} ({} of internal modules)\n",
+        imports.statements, imports.internal_statements
+    ));
+    content.push_str(&format!(
+        "- Wildcard imports: {}\n\n",
+        imports.wildcard_imports
+    ));
+
+    if !imports.most_imported_modules.is_empty() {
+        content.push_str("### Most Imported Internal Modules\n\n");
+        content.push_str("| Module | Import statements |\n");
+        content.push_str("|--------|-------------------|\n");
+        for module in &imports.most_imported_modules {
+            content.push_str(&format!("| `{}` | {} |\n", module.module, module.imports));
+        }
+        content.push('\n');
+    }
+
+    if !imports.deepest_relative_imports.is_empty() {
+        content.push_str("### Deepest Relative Imports\n\n");
+        content.push_str("| File | Line | Module | Depth |\n");
+        content.push_str("|------|------|--------|-------|\n");
+        for import in &imports.deepest_relative_imports {
+            content.push_str(&format!(
+                "| {} | {} | `{}` | {} |\n",
+                import.path, import.line, import.module, import.depth
+            ));
+        }
+        content.push('\n');
+    }
+
+    if !imports.wildcard_files.is_empty() {
+        content.push_str("### Files with Wildcard Imports\n\n");
+        for file in &imports.wildcard_files {
+            content.push_str(&format!("- {}: {}\n", file.path, file.count));
+        }
+        content.push('\n');
+    }
+
+    if !imports.files_by_distinct_imports.is_empty() {
+        content.push_str("### Files Importing the Most Modules\n\n");
+        for file in &imports.files_by_distinct_imports {
+            content.push_str(&format!("- {}: {} modules\n", file.path, file.count));
+        }
+        content.push('\n');
+    }
+}
+
+/// Render the totals of each repository and the dependencies between them, when several
+/// repositories are analyzed together
+fn render_repositories(report: &AnalysisReport, content: &mut String) {
+    if report.repositories.is_empty() {
+        return;
+    }
+
+    content.push_str("\n### Repositories\n\n");
+    content.push_str("| Repository | Files | Exports | Lines | Depends on |\n");
+    content.push_str("|------------|-------|---------|-------|------------|\n");
+    for repository in &report.repositories {
+        let lines = repository
+            .lines
+            .map(|lines| lines.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let depends_on = if repository.depends_on.is_empty() {
+            "-".to_string()
+        } else {
+            repository
+                .depends_on
+                .iter()
+                .map(|(other, edges)| format!("{} ({})", other, edges))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        content.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            repository.path, repository.files, repository.exports, lines, depends_on
+        ));
+    }
+}
+
+/// Render the per-owner rollups and the files without an owner
+fn render_owners(report: &AnalysisReport, content: &mut String) {
+    let Some(owners) = &report.owners else {
+        return;
+    };
+
+    content.push_str(
+        "## Code Owners
+
+",
+    );
+    content.push_str(&format!("Based on `{}`.\n\n", owners.source));
+    content.push_str("| Owner | Files | Lines | Avg. Maintainability | Hotspots |\n");
+    content.push_str("|-------|-------|-------|----------------------|----------|\n");
+
+    for owner in &owners.owners {
+        let maintainability = owner
+            .avg_maintainability
+            .map(|mi| format!("{:.1}", mi))
+            .unwrap_or_else(|| "-".to_string());
+        content.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            owner.owner, owner.file_count, owner.line_count, maintainability, owner.hotspot_count
+        ));
+    }
+
+    content.push_str(&format!(
+        "\n### Unowned Files ({})\n\n",
+        owners.unowned_files.len()
+    ));
+    for file in &owners.unowned_files {
+        content.push_str(&format!("- {}\n", file));
+    }
+    content.push('\n');
+}
+
+/// Render the number of files per category and, in verbose mode, the files several categories claim
+fn render_categories(report: &AnalysisReport, content: &mut String) {
+    if report.categories.counts.is_empty() {
+        return;
+    }
+
+    content.push_str("\n### File Categories\n\n");
+    for (category, count) in &report.categories.counts {
+        content.push_str(&format!("- {}: {} files\n", category, count));
+    }
+
+    if report.categories.ambiguous.is_empty() {
+        return;
+    }
+
+    content.push_str("\n### Files Matching Several Categories\n\n");
+    content.push_str("The first matching rule decides the category; reorder the `categories` rules or give them a `priority` to change it:\n\n");
+    for file in &report.categories.ambiguous {
+        content.push_str(&format!(
+            "- {}: {}\n",
+            file.path,
+            categories::describe_ambiguity(&file.classification)
+        ));
+    }
+}
+
+/// Render the most complex files by cyclomatic and by cognitive complexity
+fn render_complexity_offenders(report: &AnalysisReport, content: &mut String) {
+    let Some(offenders) = &report.complexity_offenders else {
+        return;
+    };
+    if offenders.by_cyclomatic.is_empty() {
+        return;
+    }
+
+    content.push_str("## Top Complexity Offenders\n\n");
+    content.push_str(
+        "Files marked ⚠️ exceed a complexity threshold of the `findings` configuration.\n\n",
+    );
+
+    for (heading, list) in [
+        ("By Cyclomatic Complexity", &offenders.by_cyclomatic),
+        ("By Cognitive Complexity", &offenders.by_cognitive),
+    ] {
+        content.push_str(&format!("### {}\n\n", heading));
+        content.push_str("| # | File | Cyclomatic | Cognitive | Maintainability | Functions |\n");
+        content.push_str("|---|------|------------|-----------|-----------------|-----------|\n");
+        for (idx, offender) in list.iter().enumerate() {
+            content.push_str(&offender_row(idx + 1, offender));
+        }
+        content.push('\n');
+    }
+}
+
+/// Render the exports no analyzed file imports, grouped by file
+fn render_unused_exports(report: &AnalysisReport, content: &mut String) {
+    if report.unused_exports.is_empty() {
+        return;
+    }
+
+    content.push_str("## Unused Exports\n\n");
+    content.push_str("Exports no analyzed file imports: dead code, or entry points of the public API worth documenting. Names in `unused_exports.ignore_names` are left out.\n\n");
+
+    for file in &report.unused_exports {
+        content.push_str(&format!("- **{}** ({})\n", file.path, file.exports.len()));
+        for export in &file.exports {
+            content.push_str(&format!(
+                "  - line {}: {} `{}`\n",
+                export.line_number, export.export_type, export.name
+            ));
+        }
+    }
+    content.push('\n');
+}
+
+/// Render one row of a complexity offender table
+fn offender_row(rank: usize, offender: &ComplexityOffender) -> String {
+    let marker = if offender.over_threshold {
+        " ⚠️"
+    } else {
+        ""
+    };
+    format!(
+        "| {} | {}{} | {:.1} | {:.1} | {:.1} | {} |\n",
+        rank,
+        offender.path,
+        marker,
+        offender.cyclomatic_complexity,
+        offender.cognitive_complexity,
+        offender.maintainability_index,
+        offender.function_count
+    )
+}
+
+/// Render the files whose complexity is concentrated in a single function
+fn render_concentrated_complexity(report: &AnalysisReport, content: &mut String) {
+    let findings: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|finding| finding.rule_id == CONCENTRATED_COMPLEXITY)
+        .collect();
+
+    if findings.is_empty() {
+        return;
+    }
+
+    content.push_str("\n### Concentrated Complexity\n\n");
+    content.push_str("Files where a single function holds most of the cyclomatic complexity, which the file-level averages hide:\n\n");
+
+    for (idx, finding) in findings.iter().enumerate() {
+        let location = finding
+            .line
+            .map(|line| format!(" (line {})", line))
+            .unwrap_or_default();
+        content.push_str(&format!(
+            "{}. **{}**{}: {}\n",
+            idx + 1,
+            finding.path,
+            location,
+            finding.message
+        ));
+    }
+}
+
+/// Render the sub-report for one language; links are relative to `by-language/`
+pub fn render_language(report: &AnalysisReport, language: &LanguageReport) -> String {
+    let mut content = format!("# OverDoc Analysis Results: {}\n\n", language.language);
+    content.push_str(&format!(
+        "[Back to analysis results](../{})\n\n",
+        report.output_name(OutputFormat::Markdown)
+    ));
+
+    content.push_str("## Summary\n\n");
+    render_language_totals(language, &mut content);
+
+    content.push_str("## Top Important Files\n\n");
+    render_order_note(report, &mut content);
+    for (idx, top_file) in language.top_files.iter().enumerate() {
+        let title = match report.file_pages.get(&top_file.path) {
+            Some(page) => format!("[{}](../{}/{})", top_file.path, pages::PAGES_DIR, page),
+            None => top_file.path.clone(),
+        };
+        content.push_str(&format!(
+            "{}. **{}** (Score: {})\n",
+            idx + 1,
+            title,
+            top_file.score
+        ));
+        if let Some(metrics) = &top_file.metrics {
+            content.push_str(&format!(
+                "   - Lines: {}, Functions: {}, Knowledge Score: {:.1}\n",
+                metrics.line_count,
+                metrics.function_count,
+                metrics.knowledge_score()
+            ));
+        }
+    }
+    content.push('\n');
+
+    if !language.hotspots.is_empty() {
+        content.push_str("## Knowledge Hotspots\n\n");
+        for (idx, (file, score)) in language.hotspots.iter().enumerate() {
+            content.push_str(&format!(
+                "{}. **{}** (Knowledge Score: {:.1})\n",
+                idx + 1,
+                file,
+                score
+            ));
+        }
+        content.push('\n');
+    }
+
+    if !language.findings.is_empty() {
+        content.push_str("## Findings\n\n");
+        for finding in &language.findings {
+            content.push_str(&format!("- {}\n", finding));
+        }
+        content.push('\n');
+    }
+
+    content.push_str("## Export Statistics\n\n");
+    if !language.exports.by_type.is_empty() {
+        content.push_str("| Export type | Count |\n");
+        content.push_str("|-------------|-------|\n");
+        for (export_type, count) in &language.exports.by_type {
+            content.push_str(&format!("| {} | {} |\n", export_type, count));
+        }
+        content.push('\n');
+    }
+    if !language.exports.most_used.is_empty() {
+        content.push_str("Most used exports:\n\n");
+        for (idx, (name, path, usage)) in language.exports.most_used.iter().enumerate() {
+            content.push_str(&format!(
+                "{}. `{}` in {} (used {} times)\n",
+                idx + 1,
+                name,
+                path,
+                usage
+            ));
+        }
+    }
+
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::categories::summarize;
+    use crate::findings::{Finding, Phase, Severity};
+    use crate::owners::{rollup, CodeOwners};
+    use crate::report::fixture::{Fixture, REPO};
+
+    /// Anchors the Contents section links to
+    fn contents_links(markdown: &str) -> Vec<String> {
+        markdown
+            .split("## Contents\n\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .map(|line| line.rsplit_once("](#").unwrap().1.trim_end_matches(')').to_string())
+            .collect()
+    }
+
+    /// Anchors of the `##` headings after the Contents, and of the `###` ones in `sections`
+    fn heading_anchors(markdown: &str, sections: &[&str]) -> Vec<String> {
+        let body = markdown.split("## Contents\n\n").nth(1).unwrap();
+        body.lines()
+            .filter_map(|line| {
+                line.strip_prefix("## ").or_else(|| {
+                    line.strip_prefix("### ")
+                        .filter(|heading| sections.contains(heading))
+                })
+            })
+            .map(anchor_slug)
+            .collect()
+    }
+
+    #[test]
+    fn contents_link_every_section() {
+        let fixture = Fixture::new();
+        let mut report = fixture.report();
+        report.categories = summarize(&fixture.file_paths, REPO, &fixture.category_rules, false);
+        report.findings.push(Finding {
+            rule_id: CONCENTRATED_COMPLEXITY.to_string(),
+            phase: Phase::Metrics,
+            severity: Severity::Warning,
+            path: "repo/src/lib.rs".to_string(),
+            line: Some(12),
+            message: "Function `parse` holds 90% of the file's cyclomatic complexity".to_string(),
+        });
+        report.imports.statements = 8;
+        let code_owners = CodeOwners::parse("src/ @core\n", "CODEOWNERS".into());
+        report.owners = Some(rollup(&code_owners, REPO, &fixture.file_paths, None, 5));
+        assert!(!report.directory_coupling.is_empty());
+
+        let markdown = render(&report);
+        let links = contents_links(&markdown);
+        let headings = heading_anchors(
+            &markdown,
+            &["File Categories", "Concentrated Complexity", "Most Depended-Upon Test Utilities"],
+        );
+
+        assert_eq!(links, headings);
+        for section in [
+            "file-categories",
+            "concentrated-complexity",
+            "directory-dependencies",
+            "import-statements",
+            "code-owners",
+        ] {
+            assert!(links.iter().any(|link| link == section), "{} missing from {:?}", section, links);
+        }
+    }
+
+    #[test]
+    fn contents_leave_out_empty_sections() {
+        let mut report = Fixture::new().report();
+        report.directory_coupling.clear();
+
+        let links = contents_links(&render(&report));
+        assert_eq!(
+            links,
+            [
+                "summary",
+                "top-important-files",
+                "most-depended-upon-test-utilities",
+                "top-important-directories",
+                "unused-exports",
+            ]
+        );
+    }
+
+    #[test]
+    fn file_anchors_are_deduplicated() {
+        let fixture = Fixture::new();
+        let mut report = fixture.report();
+        let mut clash = fixture.report().top_files.remove(0);
+        clash.path = "repo/src/lib_rs".to_string();
+        report.top_files.push(clash);
+        let mut dashed = fixture.report().top_files.remove(0);
+        dashed.path = "repo/src/lib-rs".to_string();
+        report.top_files.push(dashed);
+
+        let anchors = file_anchors(&report.top_files, REPO);
+        assert_eq!(anchors["repo/src/lib-rs"], "file-src-lib-rs");
+        assert_eq!(anchors["repo/src/lib.rs"], "file-src-lib-rs-2");
+        assert_eq!(anchors["repo/src/lib_rs"], "file-src-lib-rs-3");
+        assert_eq!(anchors["repo/src/util.rs"], "file-src-util-rs");
+
+        let markdown = render(&report);
+        for anchor in anchors.values() {
+            assert_eq!(markdown.matches(&format!("<a id=\"{}\">", anchor)).count(), 1, "{}", anchor);
+        }
+    }
+}
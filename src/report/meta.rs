@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, ScoringConfig};
+
+/// Which OverDoc and configuration produced a report, so runs can be compared
+#[derive(Debug, Clone, Serialize)]
+pub struct RunMetadata {
+    /// Crate version, as printed by --version
+    pub version: &'static str,
+
+    /// Start of the run, ISO 8601 in UTC
+    pub timestamp: String,
+
+    /// Absolute path of the analyzed repository
+    pub repo_path: String,
+
+    /// Configuration file that was loaded, or "default" when none was found
+    pub config_path: String,
+
+    /// Short hash of the effective configuration, including --set overrides
+    pub config_hash: String,
+
+    /// Weights and caps the knowledge scores were computed with; scores computed with
+    /// different ones aren't comparable
+    pub scoring: ScoringConfig,
+}
+
+impl RunMetadata {
+    /// Describe a run of `repo_path` with the configuration loaded from `config_path`
+    pub fn new(repo_path: &str, config_path: &str, config: &Config) -> Result<Self> {
+        let repo_path = fs::canonicalize(repo_path)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_else(|_| repo_path.to_string());
+        let config_path = if Path::new(config_path).exists() {
+            config_path.to_string()
+        } else {
+            "default".to_string()
+        };
+        let effective = serde_yaml::to_string(config)
+            .context("Failed to serialize the effective configuration")?;
+
+        Ok(RunMetadata {
+            version: env!("CARGO_PKG_VERSION"),
+            timestamp: iso_timestamp(SystemTime::now()),
+            repo_path,
+            config_path,
+            config_hash: format!("{:016x}", fnv1a(effective.as_bytes()))[..12].to_string(),
+            scoring: config.scoring.clone(),
+        })
+    }
+
+    /// One-line summary for the text and HTML reports
+    pub fn describe(&self) -> String {
+        format!(
+            "OverDoc {} at {}, config {} ({})",
+            self.version, self.timestamp, self.config_path, self.config_hash
+        )
+    }
+
+    /// The knowledge score weights, with the cap of each capped factor after a slash
+    pub fn describe_scoring(&self) -> String {
+        let (weights, caps) = (&self.scoring.weights, &self.scoring.caps);
+        format!(
+            "size {}, cyclomatic {}/{}, cognitive {}/{}, maintainability {}, functions {}/{}, declarations {}/{}, exports {}, scale {}",
+            weights.size,
+            weights.cyclomatic,
+            caps.cyclomatic,
+            weights.cognitive,
+            caps.cognitive,
+            weights.maintainability,
+            weights.functions,
+            caps.functions,
+            weights.declarations,
+            caps.declarations,
+            weights.exports,
+            self.scoring.scale
+        )
+    }
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Format a time as `YYYY-MM-DDTHH:MM:SSZ`
+fn iso_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's days_from_civil, inverted)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,1460 @@
+use clap::ValueEnum;
+use globset::{Glob, GlobSetBuilder};
+use log::info;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use crate::categories::{CategoryRules, CategorySummary};
+use crate::config::{
+    serialize_sorted, ExecutiveSummaryConfig, FindingsConfig, LanguageConfig,
+    ReportConfig, UnusedExportsConfig,
+};
+use crate::dependencies::{DependencyGraph, DirectoryCoupling};
+use crate::directories::{self, DirectoryCasing, DirectoryMetrics, DirectorySummary};
+use crate::exports::{ExportedEntity, ExportsMap, ImportsMap};
+use crate::filter::Truncation;
+use crate::findings::{sort_findings, Finding};
+use crate::history::PreviousRun;
+use crate::import_stats::ImportStats;
+use crate::metrics::{FileMetrics, RepositoryMetrics};
+use crate::owners::{self, CodeOwners, OwnersReport};
+use crate::traversal::relative_path;
+use executive::ExecutiveSummary;
+use html::HtmlOptions;
+use meta::RunMetadata;
+use scope::{Scope, ScopeSummary};
+
+pub mod badges;
+pub mod doc_coverage;
+pub mod docs_data;
+pub mod executive;
+#[cfg(test)]
+pub mod fixture;
+pub mod html;
+pub mod json;
+pub mod jsonl;
+pub mod markdown;
+pub mod meta;
+pub mod pages;
+pub mod sarif;
+pub mod scope;
+pub mod symbols;
+pub mod table;
+pub mod template;
+pub mod text;
+pub mod treemap;
+pub mod yaml;
+
+/// Output formats the analysis can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Markdown report (`analysis_results.md`)
+    #[value(name = "md")]
+    Markdown,
+
+    /// Machine-readable JSON report (`analysis_results.json`)
+    Json,
+
+    /// Per-file metrics as JSON Lines, streamed while files are analyzed,
+    /// followed by a summary line (`files.jsonl`)
+    Jsonl,
+
+    /// Machine-readable YAML report with the same structure as JSON (`analysis_results.yaml`)
+    Yaml,
+
+    /// Self-contained HTML report with sortable tables (`index.html`)
+    Html,
+
+    /// Findings as a SARIF 2.1.0 log for code scanning tools (`analysis_results.sarif`)
+    Sarif,
+
+    /// Compact plain-text summary for the terminal (`analysis_results.txt`)
+    Text,
+
+    /// Comment ratio per file as Cobertura coverage XML, for CI dashboards (`doc_coverage.xml`)
+    DocCoverageXml,
+
+    /// Per-file metrics as comma-separated values with a header row (`files.csv`)
+    Csv,
+
+    /// Per-file metrics as tab-separated values with a header row, unquoted, floats with
+    /// two decimals (`files.tsv`)
+    Tsv,
+}
+
+impl OutputFormat {
+    /// File name of the artifact written for this format
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "analysis_results.md",
+            OutputFormat::Json => "analysis_results.json",
+            OutputFormat::Jsonl => "files.jsonl",
+            OutputFormat::Yaml => "analysis_results.yaml",
+            OutputFormat::Html => "index.html",
+            OutputFormat::Sarif => "analysis_results.sarif",
+            OutputFormat::Text => "analysis_results.txt",
+            OutputFormat::DocCoverageXml => "doc_coverage.xml",
+            OutputFormat::Csv => "files.csv",
+            OutputFormat::Tsv => "files.tsv",
+        }
+    }
+
+    /// File name of the artifact with the stem given by --output-file, or the default name
+    pub fn output_name(&self, stem: Option<&str>) -> String {
+        match stem {
+            Some(stem) => {
+                let extension = Path::new(self.file_name())
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                format!("{}.{}", stem, extension)
+            }
+            None => self.file_name().to_string(),
+        }
+    }
+
+    /// Render the report in this format
+    pub fn render(&self, report: &AnalysisReport) -> anyhow::Result<String> {
+        match self {
+            OutputFormat::Markdown => Ok(markdown::render(report)),
+            OutputFormat::Json => json::render(report),
+            OutputFormat::Jsonl => jsonl::render(report),
+            OutputFormat::Yaml => yaml::render(report),
+            OutputFormat::Html => Ok(html::render(report)),
+            OutputFormat::Sarif => sarif::render(report),
+            OutputFormat::Text => Ok(text::render(report)),
+            OutputFormat::DocCoverageXml => doc_coverage::render(report),
+            OutputFormat::Csv => table::render_csv(report),
+            OutputFormat::Tsv => table::render_tsv(report),
+        }
+    }
+}
+
+/// Ordering of the "Top Important Files" list (--sort-by)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TopFilesOrder {
+    /// Importance score from the dependency graph
+    #[default]
+    Importance,
+
+    /// Knowledge score
+    Knowledge,
+
+    /// Cyclomatic complexity
+    Cyclomatic,
+
+    /// Cognitive complexity
+    Cognitive,
+
+    /// Maintainability index, lowest first
+    Maintainability,
+
+    /// Line count
+    Lines,
+
+    /// File size in bytes
+    Size,
+}
+
+impl TopFilesOrder {
+    /// Whether the ordering needs the metrics analysis
+    pub fn needs_metrics(&self) -> bool {
+        *self != TopFilesOrder::Importance
+    }
+
+    /// What the list is ordered by, e.g. "knowledge score"
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TopFilesOrder::Importance => "importance score",
+            TopFilesOrder::Knowledge => "knowledge score",
+            TopFilesOrder::Cyclomatic => "cyclomatic complexity",
+            TopFilesOrder::Cognitive => "cognitive complexity",
+            TopFilesOrder::Maintainability => "maintainability index, lowest first",
+            TopFilesOrder::Lines => "line count",
+            TopFilesOrder::Size => "file size",
+        }
+    }
+
+    /// Value of a file to order by, higher first; `None` for files without the metric
+    fn key(&self, path: &str, score: usize, metrics: Option<&RepositoryMetrics>) -> Option<f64> {
+        let file_metrics = metrics.and_then(|metrics| metrics.file_metrics.get(path));
+        let complexity = file_metrics.and_then(|m| m.complexity_metrics.as_ref());
+        match self {
+            TopFilesOrder::Importance => Some(score as f64),
+            TopFilesOrder::Knowledge => file_metrics?.knowledge_score,
+            TopFilesOrder::Cyclomatic => complexity.map(|c| c.cyclomatic_complexity),
+            TopFilesOrder::Cognitive => complexity.map(|c| c.cognitive_complexity),
+            // A partial analysis may stop before the maintainability index is computed
+            TopFilesOrder::Maintainability => complexity
+                .filter(|c| !c.partial)
+                .map(|c| -c.maintainability_index),
+            TopFilesOrder::Lines => file_metrics.map(|m| m.line_count as f64),
+            TopFilesOrder::Size => file_metrics.map(|m| m.file_size_bytes as f64),
+        }
+    }
+
+    /// Sort ranked files in this order, ties by path; files without the metric go last
+    fn sort(&self, files: &mut [(String, usize)], metrics: Option<&RepositoryMetrics>) {
+        files.sort_by(|a, b| {
+            let key_a = self.key(&a.0, a.1, metrics);
+            let key_b = self.key(&b.0, b.1, metrics);
+            match (key_a, key_b) {
+                (Some(x), Some(y)) => y.total_cmp(&x),
+                (a_key, b_key) => b_key.is_some().cmp(&a_key.is_some()),
+            }
+            .then_with(|| a.0.cmp(&b.0))
+        });
+    }
+}
+
+/// Summary totals shown at the top of the report
+#[derive(Debug, Serialize)]
+pub struct ReportSummary {
+    /// Number of files that survived filtering
+    pub total_files: usize,
+
+    /// Number of exported entities found
+    pub total_exports: usize,
+
+    /// Number of files with at least one export
+    pub files_with_exports: usize,
+}
+
+/// An entry in the "Top Important Files" list
+#[derive(Debug, Serialize)]
+pub struct TopFile {
+    /// Path of the file
+    pub path: String,
+
+    /// Importance score from the dependency graph
+    pub score: usize,
+
+    /// Entities exported from the file, with their usage counts
+    pub exports: Vec<ExportedEntity>,
+
+    /// Detailed metrics, if metrics analysis was run
+    pub metrics: Option<FileMetrics>,
+
+    /// Files this file depends on, most important first
+    pub dependencies: Vec<String>,
+
+    /// Files that depend on this file, most important first
+    pub dependents: Vec<String>,
+
+    /// Exports listed in verbose output, for the first `report.files_with_export_detail` files
+    #[serde(skip)]
+    pub export_detail: Option<ExportDetail>,
+}
+
+/// An entry in the "Most Used Exports" list
+#[derive(Debug, Serialize)]
+pub struct TopExport {
+    pub name: String,
+    pub export_type: String,
+
+    /// File defining the entity, and the line of the definition
+    pub path: String,
+    pub line: usize,
+    pub usage_count: usize,
+}
+
+/// The capped list of a file's exports shown in verbose output
+#[derive(Debug)]
+pub struct ExportDetail {
+    /// Most used exports first, ties broken by name, at most `report.exports_per_file`
+    pub listed: Vec<ExportedEntity>,
+
+    /// Number of exports left out of `listed`
+    pub more: usize,
+}
+
+impl ExportDetail {
+    /// Order exports by usage and keep the first `limit`
+    pub fn new(exports: &[ExportedEntity], limit: usize) -> Self {
+        let mut listed = exports.to_vec();
+        listed.sort_by(|a, b| {
+            b.usage_count
+                .cmp(&a.usage_count)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let more = listed.len().saturating_sub(limit);
+        listed.truncate(limit);
+        ExportDetail { listed, more }
+    }
+
+    /// Line noting the exports left out, if any, pointing at the JSON report `json_file`
+    pub fn more_line(&self, json_file: &str) -> Option<String> {
+        (self.more > 0).then(|| format!("… and {} more (see {})", self.more, json_file))
+    }
+}
+
+/// An entry in the "Top Important Directories" list
+#[derive(Debug, Serialize)]
+pub struct TopDirectory {
+    /// Path of the directory
+    pub path: String,
+
+    /// Sum of the importance scores of the files inside it
+    pub score: usize,
+
+    /// Aggregated metrics, if metrics analysis was run
+    pub metrics: Option<DirectoryMetrics>,
+}
+
+/// A file in the "Top Complexity Offenders" lists
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplexityOffender {
+    pub path: String,
+    pub cyclomatic_complexity: f64,
+    pub cognitive_complexity: f64,
+    pub maintainability_index: f64,
+    pub function_count: usize,
+
+    /// Whether the file exceeds a complexity threshold of the `findings` section
+    pub over_threshold: bool,
+}
+
+/// Most complex files by cyclomatic and by cognitive complexity, most complex first
+#[derive(Debug, Serialize)]
+pub struct ComplexityOffenders {
+    pub by_cyclomatic: Vec<ComplexityOffender>,
+    pub by_cognitive: Vec<ComplexityOffender>,
+}
+
+/// An export nothing imports
+#[derive(Debug, Serialize)]
+pub struct UnusedExport {
+    pub name: String,
+    pub export_type: String,
+    pub line_number: usize,
+}
+
+/// The unused exports of one file
+#[derive(Debug, Serialize)]
+pub struct UnusedExportFile {
+    pub path: String,
+    pub exports: Vec<UnusedExport>,
+}
+
+/// Everything known about an analysis run, shared by all renderers
+#[derive(Debug, Serialize)]
+pub struct AnalysisReport {
+    /// Version, time and configuration of the run
+    pub meta: RunMetadata,
+
+    /// Repository path as given on the command line
+    pub repo_path: String,
+
+    pub summary: ReportSummary,
+
+    /// Totals of each repository, when several are analyzed together
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repositories: Vec<RepositorySummary>,
+
+    /// Totals of the production and the test files, when both are analyzed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_split: Option<TestSplit>,
+
+    /// Path the whole report is limited to (--only); usage counts still include importers
+    /// outside it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only: Option<String>,
+
+    /// Git ref the report is limited to the changes since (--since); usage counts still
+    /// include importers of unchanged files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+
+    /// The part of the repository the top files, hotspots and findings are limited to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<ScopeSummary>,
+
+    /// Warnings about the analysis itself, e.g. why every importance score is 0
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notices: Vec<String>,
+
+    /// Key facts in a few sentences, unless `executive_summary.enabled` is off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executive_summary: Option<ExecutiveSummary>,
+
+    /// How `--max-files` truncated the analyzed files, if it did; the report is then partial
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial: Option<Truncation>,
+
+    /// Files left out by --min-importance and --min-knowledge-score, if either is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppressed: Option<Suppressed>,
+
+    /// Number of files per category, and files claimed by several categories
+    pub categories: CategorySummary,
+
+    /// Repository-wide metrics, if metrics analysis was run
+    pub metrics: Option<RepositoryMetrics>,
+
+    /// Most important files, ordered by `top_files_order`, leaving out the files of
+    /// `report.test_categories`
+    pub top_files: Vec<TopFile>,
+
+    /// Ordering of `top_files` (--sort-by)
+    pub top_files_order: TopFilesOrder,
+
+    /// Most important files of `report.test_categories` that other files import, ordered by
+    /// importance score
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub test_utilities: Vec<TopFile>,
+
+    /// Most used exported entities across the repository (--top-exports), each definition
+    /// listed on its own even when several files export the same name
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_exports: Vec<TopExport>,
+
+    /// Maximum number of files listed under "Used by" and "Depends on" for a top file
+    #[serde(skip)]
+    pub dependency_list_limit: usize,
+
+    /// Most important directories, ordered by importance score
+    pub top_directories: Vec<TopDirectory>,
+
+    /// Most complex files, if metrics analysis was run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity_offenders: Option<ComplexityOffenders>,
+
+    /// Exports with a usage count of 0, by file in path order, leaving out re-exports and
+    /// the names in `unused_exports.ignore_names`
+    pub unused_exports: Vec<UnusedExportFile>,
+
+    /// Dependency edges crossing each top-level directory, ordered by directory
+    pub directory_coupling: Vec<DirectoryCoupling>,
+
+    /// Aggregated import statements: most imported modules, deep relative and wildcard imports
+    pub imports: ImportStats,
+
+    /// Importance score of every file with exports
+    #[serde(serialize_with = "serialize_sorted")]
+    pub file_importance: HashMap<String, usize>,
+
+    /// Problems detected by the finding rules
+    pub findings: Vec<Finding>,
+
+    /// All exported entities with their usage counts, keyed by file path
+    #[serde(serialize_with = "serialize_sorted")]
+    pub exports: ExportsMap,
+
+    /// Page name under `files/` for each top file, when per-file pages are written
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub file_pages: HashMap<String, String>,
+
+    /// Page name under `dirs/` for each top directory, when per-directory pages are written
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub dir_pages: HashMap<String, String>,
+
+    /// What the per-directory pages show about each top directory, in list order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub directories: Vec<DirectorySummary>,
+
+    /// Per-owner rollups, if the repository has a CODEOWNERS file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owners: Option<OwnersReport>,
+
+    /// Per-language views, when per-language sub-reports are written or the report is grouped by language
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<LanguageReport>,
+
+    /// Whether the per-language views have sub-reports in `by-language/` to link to
+    #[serde(skip)]
+    pub language_pages: bool,
+
+    /// Whether the top files are ranked per language instead of across the repository
+    #[serde(skip)]
+    pub group_by_language: bool,
+
+    /// Theme, title and asset handling of the HTML report
+    #[serde(skip)]
+    pub html: HtmlOptions,
+
+    /// Stem of the report file names (--output-file), for links between the outputs
+    #[serde(skip)]
+    pub output_stem: Option<String>,
+}
+
+impl AnalysisReport {
+    /// File name the report is written to in the given format
+    pub fn output_name(&self, format: OutputFormat) -> String {
+        format.output_name(self.output_stem.as_deref())
+    }
+
+    /// Sub-report covering files with the given extension, if there is one
+    pub fn language_for_extension(&self, extension: &str) -> Option<&LanguageReport> {
+        self.languages
+            .iter()
+            .find(|lang| lang.extensions.iter().any(|ext| ext == extension))
+    }
+
+    /// Whether a file clears --min-importance and --min-knowledge-score, for the per-file tables
+    pub fn meets_thresholds(&self, path: &str) -> bool {
+        let Some(suppressed) = &self.suppressed else {
+            return true;
+        };
+        let file_metrics = self
+            .metrics
+            .as_ref()
+            .and_then(|metrics| metrics.file_metrics.get(path));
+        self.file_importance.get(path).copied().unwrap_or(0) >= suppressed.min_importance
+            && file_metrics.is_none_or(|m| m.knowledge_score() >= suppressed.min_knowledge_score)
+    }
+}
+
+/// Totals of one of several repositories analyzed together
+#[derive(Debug, Serialize)]
+pub struct RepositorySummary {
+    /// Root relative to the common directory of the repositories
+    pub path: String,
+
+    pub files: usize,
+    pub exports: usize,
+    pub files_with_exports: usize,
+
+    /// Total lines, absent with --skip-metrics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+
+    /// Dependency edges from this repository's files to the files of each other repository
+    pub depends_on: BTreeMap<String, usize>,
+}
+
+/// Totals of the production and the test files analyzed together
+#[derive(Debug, Serialize)]
+pub struct TestSplit {
+    pub production: CodeTotals,
+    pub tests: CodeTotals,
+}
+
+/// Totals of part of the analyzed files
+#[derive(Debug, Serialize)]
+pub struct CodeTotals {
+    pub files: usize,
+
+    /// Total lines, code lines and functions, absent with --skip-metrics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub functions: Option<usize>,
+}
+
+impl CodeTotals {
+    /// "12 files, 1400 lines (1100 code), 85 functions"
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} {}",
+            self.files,
+            if self.files == 1 { "file" } else { "files" }
+        );
+        if let (Some(lines), Some(code_lines)) = (self.lines, self.code_lines) {
+            text.push_str(&format!(", {} lines ({} code)", lines, code_lines));
+        }
+        if let Some(functions) = self.functions {
+            text.push_str(&format!(
+                ", {} {}",
+                functions,
+                if functions == 1 {
+                    "function"
+                } else {
+                    "functions"
+                }
+            ));
+        }
+        text
+    }
+}
+
+/// Files left out of the report by the score thresholds
+#[derive(Debug, Serialize)]
+pub struct Suppressed {
+    pub min_importance: usize,
+
+    /// Files ranked below `min_importance`, left out of "Top Important Files"
+    pub below_importance: usize,
+
+    pub min_knowledge_score: f64,
+
+    /// Files scoring below `min_knowledge_score`, left out of "Knowledge Hotspots"
+    pub below_knowledge_score: usize,
+}
+
+impl Suppressed {
+    /// Line telling how many files the top files leave out, if any
+    pub fn importance_note(&self) -> Option<String> {
+        (self.below_importance > 0).then(|| {
+            format!(
+                "{} files with an importance score below {} are not listed (--min-importance)",
+                self.below_importance, self.min_importance
+            )
+        })
+    }
+
+    /// Line telling how many files the knowledge hotspots leave out, if any
+    pub fn knowledge_note(&self) -> Option<String> {
+        (self.below_knowledge_score > 0).then(|| {
+            format!(
+                "{} files with a knowledge score below {} are not listed (--min-knowledge-score)",
+                self.below_knowledge_score, self.min_knowledge_score
+            )
+        })
+    }
+}
+
+/// Export statistics for a set of files
+#[derive(Debug, Serialize)]
+pub struct ExportStats {
+    pub total_exports: usize,
+    pub files_with_exports: usize,
+
+    /// Number of exports per export type
+    pub by_type: BTreeMap<String, usize>,
+
+    /// Most used exports as (name, file, usage count)
+    pub most_used: Vec<(String, String, usize)>,
+}
+
+/// The report restricted to the files of one configured language
+#[derive(Debug, Serialize)]
+pub struct LanguageReport {
+    /// Language name from the configuration
+    pub language: String,
+    pub extensions: Vec<String>,
+
+    /// Page name under `by-language/`
+    pub page: String,
+
+    pub file_count: usize,
+
+    /// Total lines, if metrics analysis was run
+    pub line_count: Option<usize>,
+
+    /// Most important files of the language, ordered like the repository's top files
+    pub top_files: Vec<TopFile>,
+
+    /// Files of the language with the highest knowledge scores
+    pub hotspots: Vec<(String, f64)>,
+
+    pub findings: Vec<Finding>,
+    pub exports: ExportStats,
+}
+
+/// Inputs gathered by the analysis phases that make up a report
+pub struct ReportInput<'a> {
+    pub meta: RunMetadata,
+    pub repo_path: &'a str,
+    pub file_paths: &'a [String],
+    pub exports_map: &'a ExportsMap,
+    pub imports_map: &'a ImportsMap,
+    pub dependency_graph: &'a DependencyGraph,
+    pub dir_importance: HashMap<String, usize>,
+
+    /// Each repository root, relative to the common directory, with the analyzed files
+    /// attributed to it; empty when a single repository is analyzed
+    pub repositories: Vec<(String, Vec<String>)>,
+
+    /// Analyzed files the `tests` configuration marks as tests
+    pub test_files: Vec<String>,
+
+    /// Path `file_paths` and `exports_map` are limited to (--only), while the dependency graph
+    /// covers the whole repository
+    pub only: Option<&'a str>,
+
+    /// Git ref `file_paths` and `exports_map` are limited to the changes since (--since)
+    pub since: Option<&'a str>,
+
+    /// How `--max-files` truncated the analyzed files, if it did
+    pub partial: Option<Truncation>,
+
+    /// Stem of the report file names (--output-file)
+    pub output_stem: Option<&'a str>,
+
+    /// Normalized directory keys, matching those of `dir_importance`
+    pub directory_casing: &'a DirectoryCasing,
+    pub metrics: Option<RepositoryMetrics>,
+    pub findings: Vec<Finding>,
+    pub notices: Vec<String>,
+    pub categories: CategorySummary,
+
+    /// Rules deciding which files are ranked as test utilities
+    pub category_rules: &'a CategoryRules,
+    pub import_stats: ImportStats,
+    pub code_owners: Option<&'a CodeOwners>,
+
+    /// Top-level directories expected to only depend on each other
+    pub leaf_directories: &'a [String],
+
+    pub top_n: usize,
+
+    /// Ordering of the top files (--sort-by)
+    pub top_files_order: TopFilesOrder,
+
+    /// Lowest importance score of the top files, from --min-importance or `report.min_importance`
+    pub min_importance: usize,
+
+    /// Lowest knowledge score of the hotspots, from --min-knowledge-score or
+    /// `report.min_knowledge_score`
+    pub min_knowledge_score: f64,
+
+    /// Number of directories in the "Top Important Directories" list
+    pub top_dirs: usize,
+
+    /// Number of entities in the "Most Used Exports" list (0 for none)
+    pub top_exports: usize,
+    pub per_file_pages: bool,
+    pub per_dir_pages: bool,
+
+    /// Caps on the verbose export listings and the complexity offender lists
+    pub limits: &'a ReportConfig,
+
+    /// Thresholds above which complexity offenders are flagged
+    pub thresholds: &'a FindingsConfig,
+
+    /// Export names left out of the unused exports
+    pub unused_exports: &'a UnusedExportsConfig,
+
+    /// Every configured language, to name the extensions of the executive summary
+    pub configured_languages: &'a HashMap<String, LanguageConfig>,
+
+    /// Configured languages to build per-language views for (empty for none)
+    pub languages: Vec<(&'a String, &'a LanguageConfig)>,
+
+    /// Whether per-language sub-reports are written for `languages`
+    pub language_pages: bool,
+
+    /// Rank the top files per language, grouping files no language covers by extension
+    pub group_by_language: bool,
+
+    /// Whether to list the exports of the top files
+    pub verbose: bool,
+
+    /// Restrict the top files, hotspots and findings to part of the repository
+    pub scope: Option<&'a Scope<'a>>,
+
+    /// Theme, title and asset handling of the HTML report
+    pub html: HtmlOptions,
+
+    /// Sentence templates of the executive summary
+    pub executive_summary: &'a ExecutiveSummaryConfig,
+
+    /// Latest run recorded in --db before this one, for the trends of the executive summary
+    pub previous_run: Option<&'a PreviousRun>,
+}
+
+impl ReportInput<'_> {
+    /// Whether a file is shown in the scoped sections; always true without a scope
+    fn in_scope(&self, path: &str) -> bool {
+        self.scope
+            .is_none_or(|scope| scope.contains(path, self.repo_path))
+    }
+
+    /// Whether a file belongs to one of `report.test_categories`
+    fn is_test_file(&self, path: &str) -> bool {
+        if self.limits.test_categories.is_empty() {
+            return false;
+        }
+        let classification = self
+            .category_rules
+            .classify(&relative_path(path, self.repo_path));
+        self.limits
+            .test_categories
+            .contains(&classification.category)
+    }
+}
+
+/// Assemble the report from the results of the analysis phases
+pub fn build_report(mut input: ReportInput) -> AnalysisReport {
+    let mut findings = std::mem::take(&mut input.findings);
+    findings.retain(|finding| input.in_scope(&finding.path));
+    sort_findings(&mut findings);
+    let mut notices = std::mem::take(&mut input.notices);
+    if let Some(partial) = &input.partial {
+        notices.insert(0, partial.notice());
+    }
+    let categories = std::mem::take(&mut input.categories);
+    let imports = std::mem::take(&mut input.import_stats);
+
+    let total_exports = input.exports_map.values().map(|v| v.len()).sum::<usize>();
+
+    // Test helpers are often imported everywhere, so they are ranked on their own. With --only
+    // the graph also holds files outside the analyzed ones.
+    let analyzed: HashSet<&String> = input.file_paths.iter().collect();
+    let (test_ranked, mut ranked): (Vec<_>, Vec<_>) = input
+        .dependency_graph
+        .get_files_by_importance()
+        .into_iter()
+        .filter(|(path, _)| analyzed.contains(path) && input.in_scope(path))
+        .partition(|(path, _)| input.is_test_file(path));
+    input
+        .top_files_order
+        .sort(&mut ranked, input.metrics.as_ref());
+
+    let below_importance = ranked
+        .iter()
+        .filter(|(_, score)| *score < input.min_importance)
+        .count();
+    let mut top_files: Vec<TopFile> = ranked
+        .into_iter()
+        .filter(|(_, score)| *score >= input.min_importance)
+        .take(input.top_n)
+        .map(|(path, score)| top_file(path, score, &input))
+        .collect();
+    let test_utilities: Vec<TopFile> = test_ranked
+        .into_iter()
+        .filter(|(_, score)| *score > 0)
+        .take(input.limits.test_utilities)
+        .map(|(path, score)| top_file(path, score, &input))
+        .collect();
+
+    if input.verbose {
+        for file in top_files
+            .iter_mut()
+            .take(input.limits.files_with_export_detail)
+        {
+            file.export_detail = Some(ExportDetail::new(
+                &file.exports,
+                input.limits.exports_per_file,
+            ));
+        }
+    }
+
+    let file_pages = if input.per_file_pages {
+        let paths: Vec<&String> = top_files.iter().map(|f| &f.path).collect();
+        pages::page_names(&paths, input.repo_path)
+    } else {
+        HashMap::new()
+    };
+
+    let mut dir_scores: Vec<(String, usize)> = std::mem::take(&mut input.dir_importance)
+        .into_iter()
+        .collect();
+    dir_scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let top_directories: Vec<TopDirectory> = dir_scores
+        .into_iter()
+        .take(input.top_dirs)
+        .map(|(path, score)| TopDirectory {
+            metrics: input.metrics.as_ref().map(|m| {
+                directories::directory_metrics(&path, input.file_paths, m, input.directory_casing)
+            }),
+            path,
+            score,
+        })
+        .collect();
+
+    let (dir_pages, directories) = if input.per_dir_pages {
+        let paths: Vec<&String> = top_directories.iter().map(|dir| &dir.path).collect();
+        let summaries = top_directories
+            .iter()
+            .map(|dir| {
+                directories::summarize(
+                    &dir.path,
+                    &directories::DirectoryInput {
+                        file_paths: input.file_paths,
+                        exports_map: input.exports_map,
+                        imports_map: input.imports_map,
+                        graph: input.dependency_graph,
+                        metrics: input.metrics.as_ref(),
+                        casing: input.directory_casing,
+                    },
+                    input.top_n,
+                )
+            })
+            .collect();
+        (pages::page_names(&paths, input.repo_path), summaries)
+    } else {
+        (HashMap::new(), Vec::new())
+    };
+
+    // Owners and the scope share are repository-wide context, computed before hotspots are scoped
+    let owners = input.code_owners.map(|code_owners| {
+        owners::rollup(
+            code_owners,
+            input.repo_path,
+            input.file_paths,
+            input.metrics.as_ref(),
+            input.top_n,
+        )
+    });
+
+    let scope = input
+        .scope
+        .map(|scope| scope.summarize(input.file_paths, input.repo_path, input.metrics.as_ref()));
+    if let Some(scope) = input.scope {
+        if let Some(metrics) = input.metrics.as_mut() {
+            metrics
+                .knowledge_hotspots
+                .retain(|(path, _)| scope.contains(path, input.repo_path));
+        }
+    }
+
+    let suppressed = (input.min_importance > 0 || input.min_knowledge_score > 0.0).then(|| {
+        let mut below_knowledge_score = 0;
+        if let Some(metrics) = input.metrics.as_mut() {
+            let hotspots = metrics.knowledge_hotspots.len();
+            metrics
+                .knowledge_hotspots
+                .retain(|(_, score)| *score >= input.min_knowledge_score);
+            below_knowledge_score = hotspots - metrics.knowledge_hotspots.len();
+        }
+        let suppressed = Suppressed {
+            min_importance: input.min_importance,
+            below_importance,
+            min_knowledge_score: input.min_knowledge_score,
+            below_knowledge_score,
+        };
+        for note in [suppressed.importance_note(), suppressed.knowledge_note()]
+            .into_iter()
+            .flatten()
+        {
+            info!("{}", note);
+        }
+        suppressed
+    });
+
+    let complexity_offenders = complexity_offenders(&input);
+    let unused_exports = unused_exports(&input);
+    let repositories = repository_summaries(&input);
+    let test_split = test_split(&input);
+    let top_exports = top_exports(&input);
+
+    let fallback_languages = if input.group_by_language {
+        unconfigured_extensions(&input)
+    } else {
+        Vec::new()
+    };
+    let mut languages: Vec<LanguageReport> = input
+        .languages
+        .iter()
+        .map(|(name, lang)| (name.as_str(), *lang))
+        .chain(
+            fallback_languages
+                .iter()
+                .map(|(ext, lang)| (ext.as_str(), lang)),
+        )
+        .filter_map(|(name, lang)| language_report(name, lang, &input, &findings))
+        .collect();
+    languages.sort_by(|a, b| a.language.cmp(&b.language));
+
+    let mut report = AnalysisReport {
+        meta: input.meta,
+        repo_path: input.repo_path.to_string(),
+        summary: ReportSummary {
+            total_files: input.file_paths.len(),
+            total_exports,
+            files_with_exports: input.exports_map.len(),
+        },
+        repositories,
+        test_split,
+        only: input.only.map(str::to_string),
+        since: input.since.map(str::to_string),
+        scope,
+        notices,
+        executive_summary: None,
+        partial: input.partial.take(),
+        suppressed,
+        categories,
+        metrics: input.metrics,
+        top_files,
+        top_files_order: input.top_files_order,
+        test_utilities,
+        top_exports,
+        dependency_list_limit: input.limits.dependency_list_limit,
+        top_directories,
+        complexity_offenders,
+        unused_exports,
+        directory_coupling: input
+            .dependency_graph
+            .directory_coupling(input.repo_path, input.leaf_directories),
+        imports,
+        file_importance: input
+            .dependency_graph
+            .get_files_by_importance()
+            .into_iter()
+            .collect(),
+        findings,
+        exports: input.exports_map.clone(),
+        file_pages,
+        dir_pages,
+        directories,
+        owners,
+        languages,
+        language_pages: input.language_pages,
+        group_by_language: input.group_by_language,
+        html: input.html,
+        output_stem: input.output_stem.map(str::to_string),
+    };
+
+    if input.executive_summary.enabled {
+        report.executive_summary = Some(executive::build(
+            &report,
+            input.executive_summary,
+            input.configured_languages,
+            input.previous_run,
+        ));
+    }
+    report
+}
+
+/// One stand-in language per extension of the files no configured language covers
+fn unconfigured_extensions(input: &ReportInput) -> Vec<(String, LanguageConfig)> {
+    let extensions: BTreeSet<String> = input
+        .file_paths
+        .iter()
+        .filter_map(|path| Path::new(path).extension().and_then(|ext| ext.to_str()))
+        .map(|ext| ext.to_lowercase())
+        .filter(|ext| {
+            !input
+                .languages
+                .iter()
+                .any(|(_, lang)| lang.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        })
+        .collect();
+
+    extensions
+        .into_iter()
+        .map(|ext| {
+            let lang = LanguageConfig {
+                extensions: vec![ext.clone()],
+                ..Default::default()
+            };
+            (ext, lang)
+        })
+        .collect()
+}
+
+/// Collect what the report shows about one of the top files
+fn top_file(path: String, score: usize, input: &ReportInput) -> TopFile {
+    let mut dependencies = input.dependency_graph.get_dependencies(&path);
+    sort_by_importance(&mut dependencies, input.dependency_graph);
+    let mut dependents = input.dependency_graph.get_dependent_files(&path);
+    sort_by_importance(&mut dependents, input.dependency_graph);
+
+    TopFile {
+        exports: input.exports_map.get(&path).cloned().unwrap_or_default(),
+        metrics: input
+            .metrics
+            .as_ref()
+            .and_then(|m| m.file_metrics.get(&path).cloned()),
+        path,
+        score,
+        dependencies,
+        dependents,
+        export_detail: None,
+    }
+}
+
+/// List the files with the highest cyclomatic and cognitive complexity, flagging those
+/// over the `findings` thresholds; `None` without metrics
+fn complexity_offenders(input: &ReportInput) -> Option<ComplexityOffenders> {
+    let metrics = input.metrics.as_ref()?;
+    let thresholds = input.thresholds;
+
+    let offenders: Vec<ComplexityOffender> = metrics
+        .file_metrics
+        .iter()
+        .filter(|(path, _)| input.in_scope(path))
+        .filter_map(|(path, file_metrics)| {
+            let complexity = file_metrics.complexity_metrics.as_ref()?;
+            Some(ComplexityOffender {
+                path: path.clone(),
+                cyclomatic_complexity: complexity.cyclomatic_complexity,
+                cognitive_complexity: complexity.cognitive_complexity,
+                maintainability_index: complexity.maintainability_index,
+                function_count: file_metrics.function_count,
+                // A partial analysis may stop before the maintainability index is computed
+                over_threshold: complexity.cyclomatic_complexity > thresholds.max_cyclomatic
+                    || complexity.cognitive_complexity > thresholds.max_cognitive
+                    || (!complexity.partial
+                        && complexity.maintainability_index < thresholds.min_maintainability),
+            })
+        })
+        .collect();
+
+    let top_by = |key: fn(&ComplexityOffender) -> f64| {
+        let mut ranked: Vec<&ComplexityOffender> = offenders.iter().collect();
+        ranked.sort_by(|a, b| key(b).total_cmp(&key(a)).then_with(|| a.path.cmp(&b.path)));
+        ranked
+            .into_iter()
+            .take(input.limits.complexity_offenders)
+            .cloned()
+            .collect()
+    };
+
+    Some(ComplexityOffenders {
+        by_cyclomatic: top_by(|offender| offender.cyclomatic_complexity),
+        by_cognitive: top_by(|offender| offender.cognitive_complexity),
+    })
+}
+
+/// Sum up each repository's files, and count the dependency edges crossing into the others
+fn repository_summaries(input: &ReportInput) -> Vec<RepositorySummary> {
+    let repository_of: HashMap<&str, &str> = input
+        .repositories
+        .iter()
+        .flat_map(|(name, paths)| paths.iter().map(move |path| (path.as_str(), name.as_str())))
+        .collect();
+
+    input
+        .repositories
+        .iter()
+        .map(|(name, paths)| {
+            let mut depends_on = BTreeMap::new();
+            for path in paths {
+                for dependency in input.dependency_graph.get_dependencies(path) {
+                    match repository_of.get(dependency.as_str()) {
+                        Some(other) if other != name => {
+                            *depends_on.entry(other.to_string()).or_insert(0) += 1
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let exports: Vec<usize> = paths
+                .iter()
+                .filter_map(|path| input.exports_map.get(path).map(|e| e.len()))
+                .collect();
+            RepositorySummary {
+                path: name.clone(),
+                files: paths.len(),
+                exports: exports.iter().sum(),
+                files_with_exports: exports.len(),
+                lines: input.metrics.as_ref().map(|m| {
+                    paths
+                        .iter()
+                        .filter_map(|path| m.file_metrics.get(path))
+                        .map(|fm| fm.line_count)
+                        .sum()
+                }),
+                depends_on,
+            }
+        })
+        .collect()
+}
+
+/// Split the totals between production and test files, unless all files are one or the other
+fn test_split(input: &ReportInput) -> Option<TestSplit> {
+    if input.test_files.is_empty() || input.test_files.len() == input.file_paths.len() {
+        return None;
+    }
+
+    let tests: HashSet<&String> = input.test_files.iter().collect();
+    let (test_paths, production_paths): (Vec<&String>, Vec<&String>) = input
+        .file_paths
+        .iter()
+        .partition(|path| tests.contains(path));
+    let totals = |paths: &[&String]| {
+        let file_metrics = input.metrics.as_ref().map(|m| {
+            paths
+                .iter()
+                .filter_map(|path| m.file_metrics.get(*path))
+                .collect::<Vec<_>>()
+        });
+        let sum = |value: fn(&FileMetrics) -> usize| {
+            file_metrics
+                .as_ref()
+                .map(|files| files.iter().map(|fm| value(fm)).sum())
+        };
+        CodeTotals {
+            files: paths.len(),
+            lines: sum(|fm| fm.line_count),
+            code_lines: sum(|fm| fm.code_lines),
+            functions: sum(|fm| fm.function_count),
+        }
+    };
+
+    Some(TestSplit {
+        production: totals(&production_paths),
+        tests: totals(&test_paths),
+    })
+}
+
+/// The `input.top_exports` most used definitions, ties broken by path and line. Re-exports are
+/// left out, as their uses count towards the original definition.
+fn top_exports(input: &ReportInput) -> Vec<TopExport> {
+    if input.top_exports == 0 {
+        return Vec::new();
+    }
+
+    let mut exports: Vec<(&String, &ExportedEntity)> = input
+        .exports_map
+        .iter()
+        .flat_map(|(path, exports)| exports.iter().map(move |export| (path, export)))
+        .filter(|(_, export)| export.usage_count > 0 && !export.is_reexport)
+        .collect();
+    exports.sort_by(|a, b| {
+        b.1.usage_count
+            .cmp(&a.1.usage_count)
+            .then_with(|| a.0.cmp(b.0))
+            .then_with(|| a.1.line_number.cmp(&b.1.line_number))
+    });
+
+    exports
+        .into_iter()
+        .take(input.top_exports)
+        .map(|(path, export)| TopExport {
+            name: export.name.clone(),
+            export_type: export.export_type.clone(),
+            path: path.clone(),
+            line: export.line_number,
+            usage_count: export.usage_count,
+        })
+        .collect()
+}
+
+/// Collect the exports no import refers to, grouped by file in path order
+fn unused_exports(input: &ReportInput) -> Vec<UnusedExportFile> {
+    let mut ignored = GlobSetBuilder::new();
+    for pattern in &input.unused_exports.ignore_names {
+        // Invalid globs were reported when the configuration was loaded
+        if let Ok(glob) = Glob::new(pattern) {
+            ignored.add(glob);
+        }
+    }
+    let ignored = ignored.build().unwrap_or_default();
+
+    let mut files: Vec<UnusedExportFile> = input
+        .exports_map
+        .iter()
+        .filter(|(path, _)| input.in_scope(path))
+        .filter_map(|(path, entities)| {
+            let mut exports: Vec<UnusedExport> = entities
+                .iter()
+                .filter(|export| export.usage_count == 0 && !export.is_reexport)
+                .filter(|export| !ignored.is_match(&export.name))
+                .map(|export| UnusedExport {
+                    name: export.name.clone(),
+                    export_type: export.export_type.clone(),
+                    line_number: export.line_number,
+                })
+                .collect();
+            if exports.is_empty() {
+                return None;
+            }
+            exports.sort_by(|a, b| a.line_number.cmp(&b.line_number).then(a.name.cmp(&b.name)));
+            Some(UnusedExportFile {
+                path: path.clone(),
+                exports,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Order files by their own importance, most important first, ties broken by path
+fn sort_by_importance(files: &mut [String], graph: &DependencyGraph) {
+    files.sort_by(|a, b| {
+        graph
+            .get_file_importance(b)
+            .cmp(&graph.get_file_importance(a))
+            .then_with(|| a.cmp(b))
+    });
+}
+
+/// Build the view of the report restricted to one language; `None` if it has no files
+fn language_report(
+    name: &str,
+    lang: &LanguageConfig,
+    input: &ReportInput,
+    findings: &[Finding],
+) -> Option<LanguageReport> {
+    let in_language = |path: &str| {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| lang.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    };
+
+    let files: Vec<&String> = input.file_paths.iter().filter(|p| in_language(p)).collect();
+    if files.is_empty() {
+        return None;
+    }
+
+    let mut ranked: Vec<(String, usize)> = input
+        .dependency_graph
+        .get_files_by_importance()
+        .into_iter()
+        .filter(|(path, score)| {
+            in_language(path)
+                && input.in_scope(path)
+                && !input.is_test_file(path)
+                && *score >= input.min_importance
+        })
+        .collect();
+    input
+        .top_files_order
+        .sort(&mut ranked, input.metrics.as_ref());
+    let top_files = ranked
+        .into_iter()
+        .take(input.top_n)
+        .map(|(path, score)| top_file(path, score, input))
+        .collect();
+
+    let hotspots = input
+        .metrics
+        .as_ref()
+        .map(|m| {
+            m.knowledge_hotspots
+                .iter()
+                .filter(|(path, _)| in_language(path))
+                .take(input.top_n)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+    let mut most_used = Vec::new();
+    let mut files_with_exports = 0;
+    for (path, exports) in input.exports_map.iter().filter(|(p, _)| in_language(p)) {
+        files_with_exports += 1;
+        for export in exports {
+            *by_type.entry(export.export_type.clone()).or_default() += 1;
+            most_used.push((export.name.clone(), path.clone(), export.usage_count));
+        }
+    }
+    let total_exports = most_used.len();
+    most_used.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+    most_used.truncate(input.top_n);
+
+    Some(LanguageReport {
+        language: name.to_string(),
+        extensions: lang.extensions.clone(),
+        page: format!("{}.md", pages::slugify(&name.to_lowercase())),
+        file_count: files.len(),
+        line_count: input.metrics.as_ref().map(|m| {
+            files
+                .iter()
+                .filter_map(|path| m.file_metrics.get(*path))
+                .map(|fm| fm.line_count)
+                .sum()
+        }),
+        top_files,
+        hotspots,
+        findings: findings
+            .iter()
+            .filter(|finding| in_language(&finding.path))
+            .cloned()
+            .collect(),
+        exports: ExportStats {
+            total_exports,
+            files_with_exports,
+            by_type,
+            most_used,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::{export, Fixture};
+    use super::*;
+
+    fn paths(files: &[TopFile]) -> Vec<&str> {
+        files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    fn used(name: &str, usage_count: usize) -> ExportedEntity {
+        let mut export = export("repo/src/lib.rs", name, "function");
+        export.usage_count = usage_count;
+        export
+    }
+
+    fn names(exports: &[ExportedEntity]) -> Vec<&str> {
+        exports.iter().map(|export| export.name.as_str()).collect()
+    }
+
+    #[test]
+    fn export_detail_lists_the_most_used_first() {
+        let exports = [used("b", 3), used("d", 1), used("a", 3), used("c", 7), used("e", 0)];
+        let detail = ExportDetail::new(&exports, 10);
+
+        assert_eq!(names(&detail.listed), ["c", "a", "b", "d", "e"]);
+        assert_eq!(detail.more, 0);
+        assert_eq!(detail.more_line("exports.json"), None);
+    }
+
+    #[test]
+    fn export_detail_truncates_at_the_limit() {
+        let exports = [used("b", 3), used("d", 1), used("a", 3), used("c", 7), used("e", 0)];
+
+        // The limit falls between two exports used equally often: the name decides
+        let detail = ExportDetail::new(&exports, 2);
+        assert_eq!(names(&detail.listed), ["c", "a"]);
+        assert_eq!(detail.more, 3);
+        assert_eq!(
+            detail.more_line("exports.json").as_deref(),
+            Some("… and 3 more (see exports.json)")
+        );
+
+        let exact = ExportDetail::new(&exports, 5);
+        assert_eq!(exact.listed.len(), 5);
+        assert_eq!(exact.more, 0);
+
+        let none = ExportDetail::new(&exports, 0);
+        assert!(none.listed.is_empty());
+        assert_eq!(none.more, 5);
+    }
+
+    #[test]
+    fn verbose_reports_cap_the_files_with_export_detail() {
+        let mut fixture = Fixture::new();
+        fixture.config.report.files_with_export_detail = 1;
+        fixture.config.report.exports_per_file = 2;
+        let mut input = fixture.input();
+        input.verbose = true;
+        let report = build_report(input);
+
+        let detail = report.top_files[0].export_detail.as_ref().unwrap();
+        assert_eq!(names(&detail.listed), ["Config", "parse"]);
+        assert_eq!(detail.more, 1);
+        assert!(report.top_files[1].export_detail.is_none());
+        assert_eq!(report.top_files[0].exports.len(), 3, "the JSON report keeps every export");
+    }
+
+    #[test]
+    fn test_helpers_are_ranked_apart_from_top_files() {
+        let fixture = Fixture::new();
+        let report = fixture.report();
+
+        // The helper outranks every production file in the graph
+        let ranked = fixture.dependency_graph.get_files_by_importance();
+        assert_eq!(ranked[0].0, "repo/tests/support.rs");
+
+        assert_eq!(paths(&report.top_files), ["repo/src/lib.rs", "repo/src/util.rs"]);
+        assert_eq!(paths(&report.test_utilities), ["repo/tests/support.rs"]);
+        assert_eq!(report.test_utilities[0].score, ranked[0].1);
+        assert_eq!(
+            report.test_utilities[0].dependents.len(),
+            4,
+            "the graph keeps the edges of test files"
+        );
+    }
+
+    #[test]
+    fn test_helpers_stay_in_top_files_without_test_categories() {
+        let mut fixture = Fixture::new();
+        fixture.config.report.test_categories.clear();
+        let report = fixture.report();
+
+        assert_eq!(
+            paths(&report.top_files),
+            ["repo/tests/support.rs", "repo/src/lib.rs", "repo/src/util.rs"]
+        );
+        assert!(report.test_utilities.is_empty());
+    }
+}
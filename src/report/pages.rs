@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{AnalysisReport, OutputFormat, TopDirectory, TopFile};
+use crate::directories::DirectorySummary;
+use crate::traversal::relative_path;
+
+/// Directory (inside the output directory) holding the per-file pages
+pub const PAGES_DIR: &str = "files";
+
+/// Directory (inside the output directory) holding the per-directory pages
+pub const DIRS_DIR: &str = "dirs";
+
+/// Assign each file a unique page name under `files/`.
+///
+/// Names are derived from the repository-relative path with separators flattened,
+/// so `src/lib.rs` and `src_lib.rs` would collide; later paths (in sorted order)
+/// get a numeric suffix. Collisions are checked case-insensitively so pages
+/// don't overwrite each other on case-insensitive file systems.
+pub fn page_names(paths: &[&String], repo_path: &str) -> HashMap<String, String> {
+    let mut sorted: Vec<&String> = paths.to_vec();
+    sorted.sort();
+    sorted.dedup();
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut names = HashMap::new();
+
+    for path in sorted {
+        let slug = slugify(&relative_path(path, repo_path));
+
+        let mut name = format!("{}.md", slug);
+        let mut counter = 2;
+        while !used.insert(name.to_lowercase()) {
+            name = format!("{}-{}.md", slug, counter);
+            counter += 1;
+        }
+
+        names.insert(path.clone(), name);
+    }
+
+    names
+}
+
+/// Flatten a path into a single file name made of link-safe characters
+pub fn slugify(path: &str) -> String {
+    let slug: String = path
+        .trim_start_matches("./")
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' => '_',
+            ch if ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' || ch == '_' => ch,
+            _ => '-',
+        })
+        .collect();
+
+    if slug.is_empty() {
+        "file".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Render the page for one of the top files
+pub fn render_file_page(top_file: &TopFile, report: &AnalysisReport) -> String {
+    let mut content = String::new();
+
+    content.push_str(&format!("# {}\n\n", top_file.path));
+    content.push_str(&format!(
+        "[Back to analysis results](../{})\n\n",
+        report.output_name(OutputFormat::Markdown)
+    ));
+    content.push_str(&format!("Importance score: {}\n\n", top_file.score));
+
+    if let Some(metrics) = &top_file.metrics {
+        content.push_str("## File Metrics\n\n");
+        content.push_str("| Metric | Value |\n");
+        content.push_str("|--------|-------|\n");
+        content.push_str(&format!("| Total lines | {} |\n", metrics.line_count));
+        content.push_str(&format!("| Code lines | {} |\n", metrics.code_lines));
+        content.push_str(&format!("| Comment lines | {} |\n", metrics.comment_lines));
+        content.push_str(&format!("| Blank lines | {} |\n", metrics.blank_lines));
+        content.push_str(&format!("| Size (bytes) | {} |\n", metrics.file_size_bytes));
+        content.push_str(&format!("| Functions | {} |\n", metrics.function_count));
+
+        let mut declarations: Vec<(&String, &usize)> = metrics.declaration_count.iter().collect();
+        declarations.sort();
+        for (kind, count) in declarations {
+            content.push_str(&format!("| Declarations ({}) | {} |\n", kind, count));
+        }
+        content.push_str(&format!(
+            "| Knowledge score | {:.1} |\n\n",
+            metrics.knowledge_score()
+        ));
+
+        if let Some(complexity) = &metrics.complexity_metrics {
+            content.push_str("## Complexity Metrics\n\n");
+            content.push_str("| Metric | Value |\n");
+            content.push_str("|--------|-------|\n");
+            for (name, value) in [
+                ("Cyclomatic complexity", complexity.cyclomatic_complexity),
+                ("Cognitive complexity", complexity.cognitive_complexity),
+                ("Max nesting depth", complexity.max_nesting_depth),
+                ("Halstead volume", complexity.halstead_volume),
+                ("Halstead difficulty", complexity.halstead_difficulty),
+                ("Halstead effort", complexity.halstead_effort),
+                ("Halstead time", complexity.halstead_time),
+                ("Maintainability index", complexity.maintainability_index),
+            ] {
+                content.push_str(&format!("| {} | {:.1} |\n", name, value));
+            }
+            content.push('\n');
+        }
+    }
+
+    content.push_str(&format!("## Exports ({})\n\n", top_file.exports.len()));
+    if !top_file.exports.is_empty() {
+        content.push_str("| Name | Type | Line | Usage count |\n");
+        content.push_str("|------|------|------|-------------|\n");
+
+        let mut exports: Vec<_> = top_file.exports.iter().collect();
+        exports.sort_by_key(|export| export.line_number);
+        for export in exports {
+            content.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                export.name, export.export_type, export.line_number, export.usage_count
+            ));
+        }
+        content.push('\n');
+    }
+
+    for (title, files) in [
+        ("Dependencies", &top_file.dependencies),
+        ("Dependents", &top_file.dependents),
+    ] {
+        content.push_str(&format!("## {} ({})\n\n", title, files.len()));
+        for file in files {
+            // Link to the other file's page when it has one (pages share a directory)
+            match report.file_pages.get(file) {
+                Some(page) => content.push_str(&format!("- [{}]({})\n", file, page)),
+                None => content.push_str(&format!("- {}\n", file)),
+            }
+        }
+        content.push('\n');
+    }
+
+    content
+}
+
+/// Render the page for one of the top directories
+pub fn render_directory_page(
+    dir: &TopDirectory,
+    summary: &DirectorySummary,
+    report: &AnalysisReport,
+) -> String {
+    let rel = |path: &str| relative_path(path, &report.repo_path);
+    let mut content = String::new();
+
+    content.push_str(&format!("# {}\n\n", rel(&dir.path)));
+    content.push_str(&format!(
+        "[Back to analysis results](../{})\n\n",
+        report.output_name(OutputFormat::Markdown)
+    ));
+    content.push_str(&format!("Importance score: {}\n\n", dir.score));
+
+    content.push_str("## Directory Metrics\n\n");
+    content.push_str("| Metric | Value |\n");
+    content.push_str("|--------|-------|\n");
+    content.push_str(&format!("| Files | {} |\n", summary.file_count));
+    if let Some(metrics) = &summary.metrics {
+        content.push_str(&format!("| Total lines | {} |\n", metrics.line_count));
+        content.push_str(&format!("| Functions | {} |\n", metrics.function_count));
+    }
+    content.push('\n');
+
+    content.push_str(&format!(
+        "## Most Important Files ({})\n\n",
+        summary.top_files.len()
+    ));
+    for (idx, (file, score)) in summary.top_files.iter().enumerate() {
+        let title = match report.file_pages.get(file) {
+            Some(page) => format!("[{}](../{}/{})", rel(file), PAGES_DIR, page),
+            None => rel(file),
+        };
+        content.push_str(&format!("{}. {} (Score: {})\n", idx + 1, title, score));
+    }
+    content.push('\n');
+
+    content.push_str(&format!("## Exports ({})\n\n", summary.exports.len()));
+    if !summary.exports.is_empty() {
+        content.push_str("| Name | Type | File | Used from outside | Total usage |\n");
+        content.push_str("|------|------|------|-------------------|-------------|\n");
+        for export in &summary.exports {
+            content.push_str(&format!(
+                "| `{}` | {} | {} | {} | {} |\n",
+                export.name,
+                export.export_type,
+                rel(&export.file),
+                export.outside_usage,
+                export.usage_count
+            ));
+        }
+        content.push('\n');
+    }
+
+    content.push_str(&format!(
+        "## Dependent Directories ({})\n\n",
+        summary.dependent_directories.len()
+    ));
+    for (directory, count) in &summary.dependent_directories {
+        let unit = if *count == 1 {
+            "file dependency"
+        } else {
+            "file dependencies"
+        };
+        content.push_str(&format!("- {}: {} {}\n", rel(directory), count, unit));
+    }
+    content.push('\n');
+
+    content
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use super::meta::RunMetadata;
+use super::AnalysisReport;
+use crate::findings::{Finding, Severity, RULES};
+use crate::traversal::relative_path;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Top-level SARIF log
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+
+    /// Property bag with the run metadata
+    properties: RunProperties,
+}
+
+#[derive(Serialize)]
+struct RunProperties {
+    meta: RunMetadata,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    uri_base_id: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+}
+
+/// Render the report's findings as a SARIF 2.1.0 log
+pub fn render(report: &AnalysisReport) -> Result<String> {
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "OverDoc",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: "https://github.com/FKnorring/overdoc",
+                    rules: RULES
+                        .iter()
+                        .map(|(id, description)| Rule {
+                            id,
+                            short_description: Message {
+                                text: description.to_string(),
+                            },
+                        })
+                        .collect(),
+                },
+            },
+            results: report
+                .findings
+                .iter()
+                .map(|finding| to_result(finding, &report.repo_path))
+                .collect(),
+            properties: RunProperties {
+                meta: report.meta.clone(),
+            },
+        }],
+    };
+
+    serde_json::to_string_pretty(&log).context("Failed to serialize report to SARIF")
+}
+
+/// Convert a finding into a SARIF result located relative to the repository root
+fn to_result(finding: &Finding, repo_path: &str) -> SarifResult {
+    let level = match finding.severity {
+        Severity::Warning => "warning",
+    };
+
+    SarifResult {
+        rule_id: finding.rule_id.clone(),
+        level,
+        message: Message {
+            text: finding.message.clone(),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: to_uri(&relative_path(&finding.path, repo_path)),
+                    uri_base_id: "%SRCROOT%",
+                },
+                // SARIF lines are 1-based; file-level findings carry no region
+                region: finding
+                    .line
+                    .filter(|line| *line > 0)
+                    .map(|start_line| Region { start_line }),
+            },
+        }],
+    }
+}
+
+/// Turn a relative file path into a relative URI reference
+fn to_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./");
+
+    let mut uri = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
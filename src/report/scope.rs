@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::metrics::{FileMetrics, RepositoryMetrics};
+use crate::owners::{self, CodeOwners};
+use crate::traversal::relative_path;
+
+/// The part of the repository a team cares about (`--scope`, `--scope-owner`).
+///
+/// Globs follow CODEOWNERS syntax and are matched against repository-relative paths;
+/// a file is in scope when any glob matches it or CODEOWNERS assigns it to the owner.
+pub struct Scope<'a> {
+    globs: Vec<(String, Regex)>,
+    owner: Option<(String, &'a CodeOwners)>,
+}
+
+/// How much of the repository a scope covers
+#[derive(Debug, Serialize)]
+pub struct ScopeSummary {
+    /// Globs given with --scope
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub globs: Vec<String>,
+
+    /// Owner given with --scope-owner, and the CODEOWNERS patterns assigning files to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owner_patterns: Vec<String>,
+
+    /// Analyzed files in scope
+    pub files: usize,
+
+    /// Lines of the files in scope, and their share of all lines, if metrics were collected
+    pub lines: Option<usize>,
+    pub line_share: Option<f64>,
+
+    /// Share of the cyclomatic complexity of all files that lies in scope
+    pub complexity_share: Option<f64>,
+}
+
+impl ScopeSummary {
+    /// Describe the scope, e.g. "`src/payments/**`, files owned by @payments-team"
+    pub fn description(&self) -> String {
+        let mut parts: Vec<String> = self
+            .globs
+            .iter()
+            .map(|glob| format!("`{}`", glob))
+            .collect();
+        if let Some(owner) = &self.owner {
+            parts.push(format!("files owned by {}", owner));
+        }
+        parts.join(", ")
+    }
+}
+
+impl<'a> Scope<'a> {
+    /// Build a scope from globs and an owner; the owner must have rules in CODEOWNERS
+    pub fn new(
+        globs: &[String],
+        owner: Option<&str>,
+        code_owners: Option<&'a CodeOwners>,
+    ) -> Result<Self> {
+        let globs = globs
+            .iter()
+            .map(|glob| {
+                owners::pattern_to_regex(glob)
+                    .map(|regex| (glob.clone(), regex))
+                    .context(format!("Invalid --scope glob '{}'", glob))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let owner = match owner {
+            Some(owner) => {
+                let code_owners = code_owners.context(format!(
+                    "--scope-owner {} needs a CODEOWNERS file, but the repository has none",
+                    owner
+                ))?;
+                if code_owners.patterns_of(owner).is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "No CODEOWNERS rule in {} assigns files to {}",
+                        code_owners.source.display(),
+                        owner
+                    ));
+                }
+                Some((owner.to_string(), code_owners))
+            }
+            None => None,
+        };
+
+        Ok(Scope { globs, owner })
+    }
+
+    /// Whether a path (as analyzed, not relative) is in scope
+    pub fn contains(&self, path: &str, repo_path: &str) -> bool {
+        let relative = relative_path(path, repo_path).replace('\\', "/");
+
+        self.globs
+            .iter()
+            .any(|(_, regex)| regex.is_match(&relative))
+            || self.owner.as_ref().is_some_and(|(owner, code_owners)| {
+                code_owners
+                    .owners_of(&relative)
+                    .is_some_and(|owners| owners.contains(owner))
+            })
+    }
+
+    /// Count the files in scope and their share of the repository's lines and complexity
+    pub fn summarize(
+        &self,
+        file_paths: &[String],
+        repo_path: &str,
+        metrics: Option<&RepositoryMetrics>,
+    ) -> ScopeSummary {
+        let in_scope: Vec<&String> = file_paths
+            .iter()
+            .filter(|path| self.contains(path, repo_path))
+            .collect();
+
+        let share = |part: f64, total: f64| if total > 0.0 { part / total } else { 0.0 };
+        let lines = metrics.map(|m| {
+            in_scope
+                .iter()
+                .filter_map(|path| m.file_metrics.get(*path))
+                .map(|fm| fm.line_count)
+                .sum::<usize>()
+        });
+        let complexity_share = metrics.map(|m| {
+            let cyclomatic = |fm: &FileMetrics| {
+                fm.complexity_metrics
+                    .as_ref()
+                    .map(|c| c.cyclomatic_complexity)
+                    .unwrap_or(0.0)
+            };
+            let total: f64 = m.file_metrics.values().map(cyclomatic).sum();
+            let scoped: f64 = in_scope
+                .iter()
+                .filter_map(|path| m.file_metrics.get(*path))
+                .map(cyclomatic)
+                .sum();
+            share(scoped, total)
+        });
+
+        ScopeSummary {
+            globs: self.globs.iter().map(|(glob, _)| glob.clone()).collect(),
+            owner: self.owner.as_ref().map(|(owner, _)| owner.clone()),
+            owner_patterns: self
+                .owner
+                .as_ref()
+                .map(|(owner, code_owners)| code_owners.patterns_of(owner))
+                .unwrap_or_default(),
+            files: in_scope.len(),
+            line_share: metrics
+                .zip(lines)
+                .map(|(m, lines)| share(lines as f64, m.total_lines as f64)),
+            lines,
+            complexity_share,
+        }
+    }
+}
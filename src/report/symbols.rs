@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::exports::{ExportedEntity, ExportsMap, ImportReference, ImportsMap};
+
+/// File name of the raw symbols artifact (`--emit-symbols`)
+pub const FILE_NAME: &str = "symbols.json";
+
+/// Everything the scanner extracted, for tuning `export_patterns` and `import_patterns`
+#[derive(Serialize)]
+struct Symbols<'a> {
+    /// Exported entities by file path, in source order
+    exports: BTreeMap<&'a str, Vec<&'a ExportedEntity>>,
+
+    /// Import references by imported name, ordered by file and line
+    imports: BTreeMap<&'a str, Vec<&'a ImportReference>>,
+}
+
+/// Render the exports and imports maps as JSON, with keys and entries in a stable order
+pub fn render(exports_map: &ExportsMap, imports_map: &ImportsMap) -> Result<String> {
+    let exports = exports_map
+        .iter()
+        .map(|(path, entities)| {
+            let mut entities: Vec<&ExportedEntity> = entities.iter().collect();
+            entities.sort_by(|a, b| a.line_number.cmp(&b.line_number).then(a.name.cmp(&b.name)));
+            (path.as_str(), entities)
+        })
+        .collect();
+
+    let imports = imports_map
+        .iter()
+        .map(|(name, references)| {
+            let mut references: Vec<&ImportReference> = references.iter().collect();
+            references.sort_by(|a, b| {
+                a.file_path
+                    .cmp(&b.file_path)
+                    .then(a.line_number.cmp(&b.line_number))
+            });
+            (name.as_str(), references)
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&Symbols { exports, imports })
+        .context("Failed to serialize symbols to JSON")
+}
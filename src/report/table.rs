@@ -0,0 +1,122 @@
+//! Per-file metrics as one row per file, for spreadsheets (`--format csv` and `--format tsv`).
+//!
+//! Both formats share the rows and the column order below; they only differ in how cells
+//! are separated, escaped and how floats are printed.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::AnalysisReport;
+use crate::traversal::relative_path;
+
+/// Column order of both formats; new columns are only ever appended
+pub const COLUMNS: [&str; 14] = [
+    "path",
+    "extension",
+    "importance",
+    "lines",
+    "code_lines",
+    "comment_lines",
+    "blank_lines",
+    "size_bytes",
+    "functions",
+    "exports",
+    "cyclomatic_complexity",
+    "cognitive_complexity",
+    "maintainability_index",
+    "knowledge_score",
+];
+
+/// A cell before formatting; `Empty` for metrics that weren't computed
+enum Cell {
+    Text(String),
+    Int(u64),
+    Float(f64),
+    Empty,
+}
+
+/// One row per file in path order: every analyzed file, or the files with exports
+/// when metrics were skipped, leaving out files below the score thresholds
+fn rows(report: &AnalysisReport) -> Vec<Vec<Cell>> {
+    let mut paths: Vec<&String> = match &report.metrics {
+        Some(metrics) => metrics.file_metrics.keys().collect(),
+        None => report.exports.keys().collect(),
+    };
+    paths.retain(|path| report.meets_thresholds(path));
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let file_metrics = report
+                .metrics
+                .as_ref()
+                .and_then(|metrics| metrics.file_metrics.get(path));
+            let complexity = file_metrics.and_then(|m| m.complexity_metrics.as_ref());
+            let int = |value: Option<u64>| value.map_or(Cell::Empty, Cell::Int);
+            let float = |value: Option<f64>| value.map_or(Cell::Empty, Cell::Float);
+
+            vec![
+                Cell::Text(relative_path(path, &report.repo_path)),
+                Cell::Text(
+                    Path::new(path)
+                        .extension()
+                        .map(|ext| ext.to_string_lossy().to_lowercase())
+                        .unwrap_or_default(),
+                ),
+                Cell::Int(report.file_importance.get(path).copied().unwrap_or(0) as u64),
+                int(file_metrics.map(|m| m.line_count as u64)),
+                int(file_metrics.map(|m| m.code_lines as u64)),
+                int(file_metrics.map(|m| m.comment_lines as u64)),
+                int(file_metrics.map(|m| m.blank_lines as u64)),
+                int(file_metrics.map(|m| m.file_size_bytes)),
+                int(file_metrics.map(|m| m.function_count as u64)),
+                Cell::Int(report.exports.get(path).map_or(0, |e| e.len()) as u64),
+                float(complexity.map(|c| c.cyclomatic_complexity)),
+                float(complexity.map(|c| c.cognitive_complexity)),
+                float(complexity.map(|c| c.maintainability_index)),
+                float(file_metrics.and_then(|m| m.knowledge_score)),
+            ]
+        })
+        .collect()
+}
+
+/// Render the rows with a header, formatting each cell with `cell` and joining with `separator`
+fn render(report: &AnalysisReport, separator: &str, cell: impl Fn(&Cell) -> String) -> String {
+    let mut content = COLUMNS.join(separator);
+    content.push('\n');
+    for row in rows(report) {
+        let cells: Vec<String> = row.iter().map(&cell).collect();
+        content.push_str(&cells.join(separator));
+        content.push('\n');
+    }
+    content
+}
+
+/// Render the per-file metrics as CSV, quoting cells as RFC 4180 requires
+pub fn render_csv(report: &AnalysisReport) -> Result<String> {
+    Ok(render(report, ",", |cell| match cell {
+        Cell::Text(text) if text.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        }
+        Cell::Text(text) => text.clone(),
+        Cell::Int(value) => value.to_string(),
+        Cell::Float(value) => value.to_string(),
+        Cell::Empty => String::new(),
+    }))
+}
+
+/// Render the per-file metrics as TSV: nothing is quoted, tabs, newlines and backslashes in
+/// text are written as `\t`, `\n`, `\r` and `\\`, and floats have two decimals
+pub fn render_tsv(report: &AnalysisReport) -> Result<String> {
+    Ok(render(report, "\t", |cell| match cell {
+        Cell::Text(text) => text
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+        Cell::Int(value) => value.to_string(),
+        Cell::Float(value) => format!("{:.2}", value),
+        Cell::Empty => String::new(),
+    }))
+}
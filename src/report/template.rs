@@ -0,0 +1,231 @@
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use std::fs;
+use tera::{Context, Tera};
+
+use super::executive::ExecutiveSummary;
+use super::meta::RunMetadata;
+use super::{
+    markdown, AnalysisReport, ComplexityOffenders, ReportSummary, TopDirectory, TopFile,
+    UnusedExportFile,
+};
+use crate::dependencies::DirectoryCoupling;
+use crate::exports::ExportedEntity;
+use crate::findings::Finding;
+use crate::metrics::{FileMetrics, RepositoryMetrics};
+use crate::owners::OwnersReport;
+
+/// A user-provided Tera template replacing the built-in markdown layout (`--template`)
+pub struct ReportTemplate {
+    tera: Tera,
+    name: String,
+}
+
+/// Values available to a template
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    /// Version, time and configuration of the run
+    meta: &'a RunMetadata,
+    repo_path: &'a str,
+    summary: &'a ReportSummary,
+    notices: &'a [String],
+
+    /// Facts and sentences of the executive summary, absent when disabled
+    executive_summary: Option<&'a ExecutiveSummary>,
+
+    /// Repository-wide metrics, absent with --skip-metrics
+    metrics: Option<&'a RepositoryMetrics>,
+
+    /// File count per extension, most common first
+    language_distribution: Vec<LanguageShare<'a>>,
+
+    /// Files with the highest knowledge scores, highest first
+    knowledge_hotspots: Vec<Hotspot<'a>>,
+
+    top_files: Vec<TemplateFile<'a>>,
+
+    /// What the top files are ordered by, e.g. "importance score"
+    top_files_order: &'static str,
+
+    /// Files of the test categories, ranked apart from the top files
+    test_utilities: &'a [TopFile],
+    top_directories: &'a [TopDirectory],
+
+    /// Most complex files, absent with --skip-metrics
+    complexity_offenders: Option<&'a ComplexityOffenders>,
+
+    /// Exports nothing imports, by file
+    unused_exports: &'a [UnusedExportFile],
+    directory_coupling: &'a [DirectoryCoupling],
+    findings: &'a [Finding],
+    owners: Option<&'a OwnersReport>,
+}
+
+#[derive(Serialize)]
+struct LanguageShare<'a> {
+    language: &'a str,
+    files: usize,
+    percentage: f64,
+}
+
+#[derive(Serialize)]
+struct Hotspot<'a> {
+    path: &'a str,
+    score: f64,
+
+    /// Anchor of the file's entry, if it is one of the top files
+    anchor: Option<String>,
+}
+
+/// A top file with the values the markdown report derives from its metrics
+#[derive(Serialize)]
+struct TemplateFile<'a> {
+    path: &'a str,
+
+    /// Unique anchor for the file's heading
+    anchor: String,
+    score: usize,
+    exports: &'a [ExportedEntity],
+    dependencies: &'a [String],
+    dependents: &'a [String],
+    metrics: Option<&'a FileMetrics>,
+    comment_ratio: Option<f64>,
+    knowledge_score: Option<f64>,
+
+    /// One-line summary of the complexity metrics, if complexity was measured
+    complexity: Option<String>,
+}
+
+impl ReportTemplate {
+    /// Read and parse a template, so syntax errors surface before the analysis runs
+    pub fn load(path: &str) -> Result<Self> {
+        let source =
+            fs::read_to_string(path).context(format!("Failed to read template {}", path))?;
+
+        let mut tera = Tera::default();
+        tera.add_raw_template(path, &source)
+            .context(format!("Failed to parse template {}", path))?;
+
+        Ok(ReportTemplate {
+            tera,
+            name: path.to_string(),
+        })
+    }
+
+    /// Render the report through the template
+    pub fn render(&self, report: &AnalysisReport) -> Result<String> {
+        let context = Context::from_serialize(template_context(report))
+            .context("Failed to build template context")?;
+
+        self.tera.render(&self.name, &context).context(format!(
+            "Failed to render template {}; check that every field it uses exists in the template context (see README)",
+            self.name
+        ))
+    }
+}
+
+fn template_context(report: &AnalysisReport) -> TemplateContext<'_> {
+    let anchors = markdown::file_anchors(&report.top_files, &report.repo_path);
+
+    let mut language_distribution: Vec<LanguageShare> = report
+        .metrics
+        .iter()
+        .flat_map(|metrics| {
+            metrics
+                .language_distribution
+                .iter()
+                .map(|(language, files)| LanguageShare {
+                    language,
+                    files: *files,
+                    percentage: (*files as f64 / metrics.total_files as f64) * 100.0,
+                })
+        })
+        .collect();
+    language_distribution.sort_by(|a, b| b.files.cmp(&a.files).then(a.language.cmp(b.language)));
+
+    let knowledge_hotspots = report
+        .metrics
+        .iter()
+        .flat_map(|metrics| metrics.knowledge_hotspots.iter())
+        .map(|(path, score)| Hotspot {
+            path,
+            score: *score,
+            anchor: anchors.get(path).cloned(),
+        })
+        .collect();
+
+    let top_files = report
+        .top_files
+        .iter()
+        .map(|file| TemplateFile {
+            path: &file.path,
+            anchor: anchors[&file.path].clone(),
+            score: file.score,
+            exports: &file.exports,
+            dependencies: &file.dependencies,
+            dependents: &file.dependents,
+            metrics: file.metrics.as_ref(),
+            comment_ratio: file.metrics.as_ref().map(|m| m.comment_ratio()),
+            knowledge_score: file
+                .metrics
+                .as_ref()
+                .filter(|m| m.complexity_metrics.is_some())
+                .map(|m| m.knowledge_score()),
+            complexity: file
+                .metrics
+                .as_ref()
+                .and_then(|m| m.complexity_metrics.as_ref())
+                .map(|c| c.description()),
+        })
+        .collect();
+
+    TemplateContext {
+        meta: &report.meta,
+        repo_path: &report.repo_path,
+        summary: &report.summary,
+        notices: &report.notices,
+        executive_summary: report.executive_summary.as_ref(),
+        metrics: report.metrics.as_ref(),
+        language_distribution,
+        knowledge_hotspots,
+        top_files,
+        top_files_order: report.top_files_order.describe(),
+        test_utilities: &report.test_utilities,
+        top_directories: &report.top_directories,
+        complexity_offenders: report.complexity_offenders.as_ref(),
+        unused_exports: &report.unused_exports,
+        directory_coupling: &report.directory_coupling,
+        findings: &report.findings,
+        owners: report.owners.as_ref(),
+    }
+}
+
+/// Fill the `{name}` placeholders of a sentence template; unknown placeholders are kept as
+/// written so mistakes show in the report
+pub fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut sentence = String::new();
+    let mut rest = template;
+    // A single pass, so values containing braces are never filled themselves
+    while let Some(start) = rest.find('{') {
+        sentence.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                sentence.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                sentence.push('{');
+                rest = after;
+            }
+        }
+    }
+    sentence.push_str(rest);
+    sentence
+}
@@ -0,0 +1,194 @@
+use super::{AnalysisReport, Suppressed, TopFilesOrder};
+use crate::traversal::relative_path;
+
+/// Number of hotspots and findings listed in the compact report
+const SHORT_LIST: usize = 5;
+
+/// Render a compact plain-text report for the terminal
+pub fn render(report: &AnalysisReport) -> String {
+    let rel = |path: &str| relative_path(path, &report.repo_path);
+    let mut content = format!("OverDoc analysis of {}\n", report.repo_path);
+    content.push_str(&format!("{}\n\n", report.meta.describe()));
+    for notice in &report.notices {
+        content.push_str(&format!("WARNING: {}\n\n", notice));
+    }
+
+    content.push_str(&format!(
+        "Files: {}  Exports: {}  Files with exports: {}\n",
+        report.summary.total_files, report.summary.total_exports, report.summary.files_with_exports
+    ));
+    if let Some(only) = &report.only {
+        content.push_str(&format!(
+            "  Limited to {} (usage counts include importers across the repository)\n",
+            only
+        ));
+    }
+    if let Some(since) = &report.since {
+        content.push_str(&format!(
+            "  Partial analysis of the files changed since {} (usage counts include importers across the repository)\n",
+            since
+        ));
+    }
+    if let Some(split) = &report.test_split {
+        content.push_str(&format!(
+            "  Production: {}\n  Tests: {}\n",
+            split.production.describe(),
+            split.tests.describe()
+        ));
+    }
+    for repository in &report.repositories {
+        content.push_str(&format!(
+            "  {}: {} files, {} exports",
+            repository.path, repository.files, repository.exports
+        ));
+        if let Some(lines) = repository.lines {
+            content.push_str(&format!(", {} lines", lines));
+        }
+        for (other, edges) in &repository.depends_on {
+            content.push_str(&format!(", {} edges to {}", edges, other));
+        }
+        content.push('\n');
+    }
+    if let Some(scope) = &report.scope {
+        content.push_str(&format!(
+            "Scope: {} ({} files",
+            scope.description(),
+            scope.files
+        ));
+        if let (Some(lines), Some(complexity)) = (scope.line_share, scope.complexity_share) {
+            content.push_str(&format!(
+                ", {:.1}% of lines, {:.1}% of complexity",
+                lines * 100.0,
+                complexity * 100.0
+            ));
+        }
+        content.push_str(")\n");
+    }
+    if let Some(metrics) = &report.metrics {
+        content.push_str(&format!(
+            "Lines: {} (code {}, comments {}, blank {})  Comment ratio: {:.1}%\n",
+            metrics.total_lines,
+            metrics.total_code_lines,
+            metrics.total_comment_lines,
+            metrics.total_blank_lines,
+            metrics.avg_comment_ratio * 100.0
+        ));
+        content.push_str(&format!(
+            "Average complexity: cyclomatic {:.1}, cognitive {:.1}, maintainability {:.1}\n",
+            metrics.avg_cyclomatic_complexity,
+            metrics.avg_cognitive_complexity,
+            metrics.avg_maintainability_index
+        ));
+        if !metrics.complexity_levels.is_empty() {
+            content.push_str(&format!(
+                "Files by complexity: {}\n",
+                metrics.describe_complexity_levels()
+            ));
+        }
+    }
+
+    content.push_str("\nTop files:\n");
+    if report.top_files_order != TopFilesOrder::Importance {
+        content.push_str(&format!(
+            "     ordered by {}\n",
+            report.top_files_order.describe()
+        ));
+    }
+    let width = report
+        .top_files
+        .iter()
+        .map(|file| rel(&file.path).len())
+        .max()
+        .unwrap_or(0);
+    for (idx, file) in report.top_files.iter().enumerate() {
+        content.push_str(&format!(
+            "{:>3}. {:<width$}  score {:>4}",
+            idx + 1,
+            rel(&file.path),
+            file.score,
+            width = width
+        ));
+        if let Some(metrics) = &file.metrics {
+            content.push_str(&format!(
+                "  {:>5} lines  knowledge {:>5.1}",
+                metrics.line_count,
+                metrics.knowledge_score()
+            ));
+        }
+        content.push('\n');
+    }
+    let suppressed = report.suppressed.as_ref();
+    if let Some(note) = suppressed.and_then(Suppressed::importance_note) {
+        content.push_str(&format!("     {}\n", note));
+    }
+
+    if !report.test_utilities.is_empty() {
+        content.push_str("\nTop test utilities:\n");
+        for (idx, file) in report.test_utilities.iter().enumerate() {
+            content.push_str(&format!(
+                "{:>3}. {}  score {}\n",
+                idx + 1,
+                rel(&file.path),
+                file.score
+            ));
+        }
+    }
+
+    if !report.top_exports.is_empty() {
+        content.push_str("\nMost used exports:\n");
+        for (idx, export) in report.top_exports.iter().enumerate() {
+            content.push_str(&format!(
+                "{:>3}. {} {}  {}:{}  used {}\n",
+                idx + 1,
+                export.export_type,
+                export.name,
+                rel(&export.path),
+                export.line,
+                export.usage_count
+            ));
+        }
+    }
+
+    content.push_str("\nTop directories:\n");
+    for (idx, dir) in report.top_directories.iter().enumerate() {
+        content.push_str(&format!(
+            "{:>3}. {}  score {}\n",
+            idx + 1,
+            rel(&dir.path),
+            dir.score
+        ));
+    }
+
+    if let Some(metrics) = &report.metrics {
+        let knowledge_note = suppressed.and_then(Suppressed::knowledge_note);
+        if !metrics.knowledge_hotspots.is_empty() || knowledge_note.is_some() {
+            content.push_str("\nKnowledge hotspots:\n");
+            for (idx, (file, score)) in metrics
+                .knowledge_hotspots
+                .iter()
+                .take(SHORT_LIST)
+                .enumerate()
+            {
+                content.push_str(&format!("{:>3}. {}  {:.1}\n", idx + 1, rel(file), score));
+            }
+            if let Some(note) = knowledge_note {
+                content.push_str(&format!("     {}\n", note));
+            }
+        }
+    }
+
+    if !report.findings.is_empty() {
+        content.push_str(&format!("\nFindings ({}):\n", report.findings.len()));
+        for finding in report.findings.iter().take(SHORT_LIST) {
+            content.push_str(&format!("  - {}\n", finding));
+        }
+        if report.findings.len() > SHORT_LIST {
+            content.push_str(&format!(
+                "  ... and {} more\n",
+                report.findings.len() - SHORT_LIST
+            ));
+        }
+    }
+
+    content
+}
@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use super::AnalysisReport;
+use crate::traversal::relative_path;
+
+/// Metric used as the size of the treemap rectangles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TreemapValue {
+    /// Lines of code (excluding comments and blank lines)
+    Lines,
+
+    /// Cyclomatic complexity
+    Complexity,
+
+    /// Importance score from the dependency graph
+    Importance,
+}
+
+/// A node in the d3 "flare" hierarchy: directories have children, files have a value
+#[derive(Debug, Serialize)]
+pub struct TreemapNode {
+    /// Last path component
+    pub name: String,
+
+    /// Path relative to the repository root
+    pub path: String,
+
+    /// Size of the rectangle (files only; directories are the sum of their children)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+
+    /// Knowledge score for files, used for coloring; importance score for directories
+    /// (the sum of the importance of the files inside them)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreemapNode>,
+}
+
+/// Intermediate tree keyed by path component so children come out sorted
+#[derive(Default)]
+struct DirectoryTree {
+    files: BTreeMap<String, (f64, f64)>,
+    directories: BTreeMap<String, DirectoryTree>,
+
+    /// Sum of the importance scores of the files below
+    importance: usize,
+}
+
+/// Build the treemap hierarchy from the analyzed files
+pub fn build_treemap(report: &AnalysisReport, value: TreemapValue) -> Result<TreemapNode> {
+    let mut root = DirectoryTree::default();
+
+    let paths: Vec<&String> = match &report.metrics {
+        Some(metrics) => metrics.file_metrics.keys().collect(),
+        None if value == TreemapValue::Importance => report.file_importance.keys().collect(),
+        None => {
+            return Err(anyhow::anyhow!(
+                "The treemap needs metrics for --treemap-value {:?}; run without --skip-metrics or use --treemap-value importance",
+                value
+            ))
+        }
+    };
+
+    for path in paths {
+        let file_metrics = report
+            .metrics
+            .as_ref()
+            .and_then(|metrics| metrics.file_metrics.get(path));
+
+        let size = match value {
+            TreemapValue::Lines => file_metrics.map(|m| m.code_lines as f64).unwrap_or(0.0),
+            TreemapValue::Complexity => file_metrics
+                .and_then(|m| m.complexity_metrics.as_ref())
+                .map(|c| c.cyclomatic_complexity)
+                .unwrap_or(0.0),
+            TreemapValue::Importance => {
+                report.file_importance.get(path).copied().unwrap_or(0) as f64
+            }
+        };
+        let score = file_metrics.map(|m| m.knowledge_score()).unwrap_or(0.0);
+        let importance = report.file_importance.get(path).copied().unwrap_or(0);
+
+        let relative = relative_path(path, &report.repo_path);
+        let components: Vec<String> = Path::new(&relative)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let Some((file_name, dirs)) = components.split_last() else {
+            continue;
+        };
+
+        let mut node = &mut root;
+        node.importance += importance;
+        for dir in dirs {
+            node = node.directories.entry(dir.clone()).or_default();
+            node.importance += importance;
+        }
+        node.files.insert(file_name.clone(), (size, score));
+    }
+
+    Ok(into_node(".".to_string(), String::new(), root))
+}
+
+/// Convert the intermediate tree into serializable nodes
+fn into_node(name: String, path: String, tree: DirectoryTree) -> TreemapNode {
+    let join = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", path, name)
+        }
+    };
+
+    let mut children: Vec<TreemapNode> = tree
+        .directories
+        .into_iter()
+        .map(|(dir_name, subtree)| {
+            let dir_path = join(&dir_name);
+            into_node(dir_name, dir_path, subtree)
+        })
+        .collect();
+
+    children.extend(
+        tree.files
+            .into_iter()
+            .map(|(file_name, (value, score))| TreemapNode {
+                path: join(&file_name),
+                name: file_name,
+                value: Some(value),
+                score: Some(score),
+                children: Vec::new(),
+            }),
+    );
+
+    TreemapNode {
+        name,
+        path,
+        value: None,
+        score: Some(tree.importance as f64),
+        children,
+    }
+}
+
+/// Serialize the treemap hierarchy to JSON
+pub fn render_json(tree: &TreemapNode) -> Result<String> {
+    serde_json::to_string_pretty(tree).context("Failed to serialize treemap to JSON")
+}
+
+/// Render a self-contained HTML page that draws the treemap (no external scripts)
+pub fn render_html(tree: &TreemapNode, value: TreemapValue) -> Result<String> {
+    // Keep the embedded JSON from closing the script element early
+    let data = serde_json::to_string(tree)
+        .context("Failed to serialize treemap to JSON")?
+        .replace("</", "<\\/");
+
+    Ok(TREEMAP_HTML
+        .replace("{{VALUE}}", &format!("{:?}", value).to_lowercase())
+        .replace("{{DATA}}", &data))
+}
+
+const TREEMAP_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>OverDoc Treemap</title>
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; margin: 1rem; }
+#treemap { position: relative; width: 100%; height: 85vh; }
+.node { position: absolute; box-sizing: border-box; overflow: hidden; border: 1px solid #fff; font-size: 11px; padding: 2px; }
+.dir { background: #eaeef2; color: #57606a; }
+.file { color: #1f2328; }
+</style>
+</head>
+<body>
+<h1>OverDoc Treemap</h1>
+<p>Size: {{VALUE}}. Color: knowledge score (green is low, red is high). Hover a rectangle for details.</p>
+<div id="treemap"></div>
+<script id="treemap-data" type="application/json">{{DATA}}</script>
+<script>
+(function () {
+  var data = JSON.parse(document.getElementById("treemap-data").textContent);
+  var container = document.getElementById("treemap");
+
+  function total(node) {
+    if (!node.children) { node.total = node.value || 0; return node.total; }
+    node.total = node.children.reduce(function (sum, child) { return sum + total(child); }, 0);
+    return node.total;
+  }
+
+  function worst(row, side, scale) {
+    var sum = 0, max = 0, min = Infinity;
+    row.forEach(function (c) { var a = c.total * scale; sum += a; max = Math.max(max, a); min = Math.min(min, a); });
+    return Math.max(side * side * max / (sum * sum), (sum * sum) / (side * side * min));
+  }
+
+  function layoutRow(row, rect, scale) {
+    var area = row.reduce(function (sum, c) { return sum + c.total * scale; }, 0);
+    if (rect.w >= rect.h) {
+      var width = area / rect.h, y = rect.y;
+      row.forEach(function (c) { var h = c.total * scale / width; c.rect = { x: rect.x, y: y, w: width, h: h }; y += h; });
+      return { x: rect.x + width, y: rect.y, w: rect.w - width, h: rect.h };
+    }
+    var height = area / rect.w, x = rect.x;
+    row.forEach(function (c) { var w = c.total * scale / height; c.rect = { x: x, y: rect.y, w: w, h: height }; x += w; });
+    return { x: rect.x, y: rect.y + height, w: rect.w, h: rect.h - height };
+  }
+
+  // Squarified treemap layout
+  function squarify(children, rect) {
+    var items = children.filter(function (c) { return c.total > 0; }).sort(function (a, b) { return b.total - a.total; });
+    var sum = items.reduce(function (s, c) { return s + c.total; }, 0);
+    if (sum <= 0 || rect.w <= 0 || rect.h <= 0) { return items; }
+    var scale = rect.w * rect.h / sum, row = [], laid = items.slice();
+    while (items.length) {
+      var side = Math.min(rect.w, rect.h), next = items[0];
+      if (row.length === 0 || worst(row.concat([next]), side, scale) <= worst(row, side, scale)) {
+        row.push(items.shift());
+      } else {
+        rect = layoutRow(row, rect, scale);
+        row = [];
+      }
+    }
+    if (row.length) { layoutRow(row, rect, scale); }
+    return laid;
+  }
+
+  function color(score) {
+    var s = Math.max(0, Math.min(100, score || 0));
+    return "hsl(" + ((1 - s / 100) * 120).toFixed(0) + ", 65%, 62%)";
+  }
+
+  function draw(node, rect, depth) {
+    var el = document.createElement("div");
+    el.className = "node " + (node.children ? "dir" : "file");
+    el.style.left = rect.x + "px"; el.style.top = rect.y + "px";
+    el.style.width = rect.w + "px"; el.style.height = rect.h + "px";
+    el.title = (node.path || node.name) + "\n" + "{{VALUE}}: " + node.total +
+      (node.children ? "\nimportance: " + node.score :
+        node.score !== undefined ? "\nknowledge score: " + node.score.toFixed(1) : "");
+    if (!node.children) { el.style.background = color(node.score); }
+    if (rect.w > 30 && rect.h > 14) { el.textContent = node.name; }
+    container.appendChild(el);
+
+    if (node.children) {
+      var header = depth > 0 ? 14 : 0, pad = depth > 0 ? 2 : 0;
+      var inner = { x: rect.x + pad, y: rect.y + header, w: rect.w - 2 * pad, h: rect.h - header - pad };
+      squarify(node.children, inner).forEach(function (child) {
+        if (child.rect) { draw(child, child.rect, depth + 1); }
+      });
+    }
+  }
+
+  total(data);
+  draw(data, { x: 0, y: 0, w: container.clientWidth, h: container.clientHeight }, 0);
+})();
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,8 @@
+use anyhow::{Context, Result};
+
+use super::AnalysisReport;
+
+/// Render the report as YAML
+pub fn render(report: &AnalysisReport) -> Result<String> {
+    serde_yaml::to_string(report).context("Failed to serialize report to YAML")
+}
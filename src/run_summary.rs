@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Number of files listed by --timings
+const SLOWEST_FILES: usize = 10;
+
+/// Wall-clock time of one phase of the run
+#[derive(Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub elapsed_ms: u64,
+}
+
+/// Time the metrics analysis spent on one file (--timings)
+#[derive(Debug, Serialize)]
+pub struct FileTiming {
+    pub path: String,
+    pub elapsed_ms: f64,
+}
+
+/// What `--summary-json` prints to stdout at the end of a run, for CI wrappers to parse
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    /// "ok", or "failed" when the run ends with an error and a non-zero exit status
+    status: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+
+    pub files_traversed: usize,
+    pub files_filtered_out: usize,
+
+    /// Files left after filtering but dropped by `--max-files`
+    pub files_truncated: usize,
+
+    /// Files left after filtering, minus those that could not be read
+    files_analyzed: usize,
+
+    pub total_exports: usize,
+
+    /// Import references whose name matches no export in the repository
+    pub unresolved_imports: usize,
+
+    /// Files skipped because the export scan or the metrics analysis could not read them
+    read_errors: usize,
+
+    /// Phases in the order they ran
+    phases: Vec<PhaseTiming>,
+    total_ms: u64,
+
+    /// Files the metrics analysis spent the most time on, slowest first (--timings)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slowest_files: Vec<FileTiming>,
+
+    /// Every file the run wrote, in the order they were written
+    artifacts: Vec<String>,
+
+    #[serde(skip)]
+    files_kept: usize,
+
+    #[serde(skip)]
+    unreadable: BTreeSet<String>,
+
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl RunSummary {
+    /// Start timing a run
+    pub fn new() -> Self {
+        RunSummary {
+            status: "ok",
+            error: None,
+            files_traversed: 0,
+            files_filtered_out: 0,
+            files_truncated: 0,
+            files_analyzed: 0,
+            total_exports: 0,
+            unresolved_imports: 0,
+            read_errors: 0,
+            phases: Vec::new(),
+            total_ms: 0,
+            slowest_files: Vec::new(),
+            artifacts: Vec::new(),
+            files_kept: 0,
+            unreadable: BTreeSet::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the traversal counts: files found and files left after filtering
+    pub fn files(&mut self, traversed: usize, kept: usize) {
+        self.files_traversed = traversed;
+        self.files_filtered_out = traversed.saturating_sub(kept);
+        self.files_kept = kept;
+    }
+
+    /// Record files a phase could not read; a file failing in several phases counts once
+    pub fn unreadable<'a>(&mut self, paths: impl IntoIterator<Item = &'a String>) {
+        self.unreadable.extend(paths.into_iter().cloned());
+    }
+
+    /// Record a phase that started at `started` and just ended
+    pub fn phase(&mut self, phase: &'static str, started: Instant) {
+        self.phases.push(PhaseTiming {
+            phase,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    /// Keep the files that took longest to analyze
+    pub fn file_timings(&mut self, timings: &[(String, Duration)]) {
+        let mut timings: Vec<&(String, Duration)> = timings.iter().collect();
+        timings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.slowest_files = timings
+            .into_iter()
+            .take(SLOWEST_FILES)
+            .map(|(path, elapsed)| FileTiming {
+                path: path.clone(),
+                elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            })
+            .collect();
+    }
+
+    /// Log the time of each phase and the slowest files (--timings)
+    pub fn log_timings(&self) {
+        info!("Timings:");
+        for phase in &self.phases {
+            info!("  {:<10} {:>8} ms", phase.phase, phase.elapsed_ms);
+        }
+        info!(
+            "  {:<10} {:>8} ms",
+            "total",
+            self.started.elapsed().as_millis()
+        );
+        if !self.slowest_files.is_empty() {
+            info!("Slowest files in the metrics analysis:");
+            for file in &self.slowest_files {
+                info!("  {:>10.2} ms  {}", file.elapsed_ms, file.path);
+            }
+        }
+    }
+
+    /// Record a file the run wrote
+    pub fn artifact(&mut self, path: &Path) {
+        self.artifacts.push(path.display().to_string());
+    }
+
+    /// Files the run wrote so far
+    pub fn artifacts(&self) -> &[String] {
+        &self.artifacts
+    }
+
+    /// Render the summary as a single line of JSON, with the outcome of the run
+    pub fn render(&mut self, result: &Result<()>) -> Result<String> {
+        if let Err(err) = result {
+            self.status = "failed";
+            self.error = Some(format!("{:#}", err));
+        }
+        self.read_errors = self.unreadable.len();
+        self.files_analyzed = self
+            .files_kept
+            .saturating_sub(self.files_truncated + self.read_errors);
+        self.total_ms = self.started.elapsed().as_millis() as u64;
+
+        serde_json::to_string(self).context("Failed to serialize the run summary")
+    }
+}
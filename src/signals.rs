@@ -0,0 +1,64 @@
+//! Per-file signals from external tools (coverage, incident counts, ...) loaded with
+//! `--signals` and weighted into the knowledge score by `scoring.extra`.
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use crate::traversal::relative_path;
+
+/// Named numeric signals of one file
+pub type Signals = BTreeMap<String, f64>;
+
+/// Load a signals file: a JSON object mapping repository-relative paths to named numeric
+/// signals, e.g. `{"src/payments.rs": {"coverage": 0.42, "incidents": 3}}`.
+///
+/// Returns the signals keyed by the analyzed file path. Paths that don't match an analyzed
+/// file are skipped with a warning.
+pub fn load(
+    path: &str,
+    repo_root: &str,
+    file_paths: &[String],
+) -> Result<HashMap<String, Signals>> {
+    let content =
+        fs::read_to_string(path).context(format!("Failed to read signals file {}", path))?;
+    let entries: BTreeMap<String, Signals> = serde_json::from_str(&content).context(format!(
+        "Failed to parse signals file {}; expected an object of paths to objects of numbers",
+        path
+    ))?;
+
+    let analyzed: HashMap<String, &String> = file_paths
+        .iter()
+        .map(|file| (normalize(&relative_path(file, repo_root)), file))
+        .collect();
+
+    let mut signals = HashMap::new();
+    for (key, values) in entries {
+        match analyzed.get(&normalize(&relative_path(&key, repo_root))) {
+            Some(file) => {
+                signals.insert((*file).clone(), values);
+            }
+            None => warn!(
+                "Signals file {} lists {}, which is not an analyzed file of the repository",
+                path, key
+            ),
+        }
+    }
+
+    info!("Loaded signals for {} files from {}", signals.len(), path);
+    Ok(signals)
+}
+
+/// Weighted sum of a file's signals; signals without a weight count for nothing
+pub fn weighted_score(signals: &Signals, weights: &BTreeMap<String, f64>) -> f64 {
+    signals
+        .iter()
+        .filter_map(|(name, value)| weights.get(name).map(|weight| weight * value))
+        .fold(0.0, |total, term| total + term)
+}
+
+/// Repository-relative path in the form used for matching: `/` separators, no leading `./`
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_string()
+}
@@ -0,0 +1,246 @@
+//! Aligned, colored tables of the top files and directories for a terminal on stdout.
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+
+use crate::report::{AnalysisReport, OutputFormat};
+use crate::traversal::relative_path;
+
+/// When to color terminal output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output written to a stream that is (or isn't) a terminal
+    pub fn enabled(&self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        }
+    }
+}
+
+/// Whether the tables replace the top files and directories log lines: only for a terminal,
+/// unless colors are forced
+pub fn wanted(color: ColorChoice) -> bool {
+    color == ColorChoice::Always || std::io::stdout().is_terminal()
+}
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Column alignment
+#[derive(Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+}
+
+/// A table cell: its text and the color it is printed in, if any
+struct Cell {
+    text: String,
+    color: Option<&'static str>,
+}
+
+impl Cell {
+    fn plain(text: impl ToString) -> Self {
+        Cell {
+            text: text.to_string(),
+            color: None,
+        }
+    }
+}
+
+/// Knowledge scores of 70 and up are red, 40 to 70 yellow, lower ones green
+fn knowledge_color(score: f64) -> &'static str {
+    if score >= 70.0 {
+        RED
+    } else if score >= 40.0 {
+        YELLOW
+    } else {
+        GREEN
+    }
+}
+
+/// The other way round for the maintainability index, where higher is better
+fn maintainability_color(index: f64) -> &'static str {
+    if index < 40.0 {
+        RED
+    } else if index < 70.0 {
+        YELLOW
+    } else {
+        GREEN
+    }
+}
+
+/// Render the top files and top directories of the report as tables
+pub fn render(report: &AnalysisReport, color: bool) -> String {
+    let rel = |path: &str| relative_path(path, &report.repo_path);
+    let mut out = String::new();
+
+    let files: Vec<Vec<Cell>> = report
+        .top_files
+        .iter()
+        .enumerate()
+        .map(|(idx, top_file)| {
+            let metrics = top_file.metrics.as_ref();
+            let complexity = metrics.and_then(|metrics| metrics.complexity_metrics.as_ref());
+            vec![
+                Cell::plain(idx + 1),
+                Cell::plain(rel(&top_file.path)),
+                Cell::plain(top_file.score),
+                metrics.map_or(Cell::plain("-"), |metrics| {
+                    let score = metrics.knowledge_score();
+                    Cell {
+                        text: format!("{:.1}", score),
+                        color: Some(knowledge_color(score)),
+                    }
+                }),
+                complexity.map_or(Cell::plain("-"), |complexity| Cell {
+                    text: format!("{:.1}", complexity.maintainability_index),
+                    color: Some(maintainability_color(complexity.maintainability_index)),
+                }),
+                metrics.map_or(Cell::plain("-"), |metrics| Cell::plain(metrics.line_count)),
+            ]
+        })
+        .collect();
+    out.push_str(&heading(
+        &format!("Top {} important files", report.top_files.len()),
+        color,
+    ));
+    out.push_str(&table(
+        &[
+            "#",
+            "File",
+            "Score",
+            "Knowledge",
+            "Maintainability",
+            "Lines",
+        ],
+        &[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ],
+        &files,
+        color,
+    ));
+
+    // Verbose runs list the exports of the first files
+    for (idx, top_file) in report.top_files.iter().enumerate() {
+        let Some(detail) = &top_file.export_detail else {
+            continue;
+        };
+        out.push_str(&format!("\n  {}. {}\n", idx + 1, rel(&top_file.path)));
+        for export in &detail.listed {
+            out.push_str(&format!(
+                "     - {} {} (used {} times)\n",
+                export.export_type, export.name, export.usage_count
+            ));
+        }
+        if let Some(more) = detail.more_line(&report.output_name(OutputFormat::Json)) {
+            out.push_str(&format!("     {}\n", more));
+        }
+    }
+
+    let directories: Vec<Vec<Cell>> = report
+        .top_directories
+        .iter()
+        .enumerate()
+        .map(|(idx, dir)| {
+            let metrics = dir.metrics.as_ref();
+            vec![
+                Cell::plain(idx + 1),
+                Cell::plain(rel(&dir.path)),
+                Cell::plain(dir.score),
+                metrics.map_or(Cell::plain("-"), |metrics| Cell::plain(metrics.file_count)),
+                metrics.map_or(Cell::plain("-"), |metrics| Cell::plain(metrics.line_count)),
+            ]
+        })
+        .collect();
+    out.push('\n');
+    out.push_str(&heading(
+        &format!("Top {} important directories", report.top_directories.len()),
+        color,
+    ));
+    out.push_str(&table(
+        &["#", "Directory", "Score", "Files", "Lines"],
+        &[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ],
+        &directories,
+        color,
+    ));
+
+    out
+}
+
+fn heading(title: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}\n", BOLD, title, RESET)
+    } else {
+        format!("{}\n", title)
+    }
+}
+
+/// Lay out the rows under the header with every column as wide as its widest cell
+fn table(header: &[&str], align: &[Align], rows: &[Vec<Cell>], color: bool) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.text.chars().count());
+        }
+    }
+
+    let pad = |text: &str, width: usize, align: Align| {
+        let fill = " ".repeat(width - text.chars().count());
+        match align {
+            Align::Left => format!("{}{}", text, fill),
+            Align::Right => format!("{}{}", fill, text),
+        }
+    };
+
+    let mut out = String::new();
+    let titles: Vec<String> = header
+        .iter()
+        .zip(&widths)
+        .zip(align)
+        .map(|((title, width), align)| pad(title, *width, *align))
+        .collect();
+    out.push_str(&format!("  {}\n", titles.join("  ").trim_end()));
+
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .zip(align)
+            .map(|((cell, width), align)| {
+                let text = pad(&cell.text, *width, *align);
+                match cell.color.filter(|_| color) {
+                    Some(code) => format!("{}{}{}", code, text, RESET),
+                    None => text,
+                }
+            })
+            .collect();
+        out.push_str(&format!("  {}\n", cells.join("  ").trim_end()));
+    }
+    out
+}
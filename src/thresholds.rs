@@ -0,0 +1,243 @@
+//! Quality gates failing the run when metrics cross the `thresholds` (`--fail-on-*`).
+
+use log::info;
+use std::fmt;
+use std::path::Path;
+
+use crate::baseline::{Baseline, BaselineEntry, Worse};
+use crate::config::ThresholdsConfig;
+use crate::filter::PathOverrides;
+use crate::metrics::{ComplexityMetrics, RepositoryMetrics};
+use crate::traversal::relative_path;
+
+/// Number of files named as the main contributors to a violated average
+const CONTRIBUTORS: usize = 5;
+
+/// A crossed limit, with the files that triggered it
+#[derive(Debug)]
+pub struct Violation {
+    /// What was measured, e.g. "average maintainability index"
+    pub metric: String,
+    pub value: f64,
+
+    /// Description of the limit, e.g. "below 50"
+    pub limit: String,
+
+    /// Files triggering the violation, with their value, worst first
+    pub files: Vec<(String, f64)>,
+}
+
+/// Every crossed limit of a run; the process exits with code 2 on this error
+#[derive(Debug)]
+pub struct Violations(pub Vec<Violation>);
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Quality thresholds violated:")?;
+        for violation in &self.0 {
+            write!(
+                f,
+                "\n  - {} {:.1} is {}",
+                violation.metric, violation.value, violation.limit
+            )?;
+            for (path, value) in &violation.files {
+                write!(f, "\n      {} ({:.1})", path, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Violations {}
+
+/// Check the metrics against the configured limits; with a baseline, files it records are
+/// only counted once their value got worse than recorded by more than the tolerance. The
+/// per-file limit of the most specific matching override replaces `file_cyclomatic_above`.
+pub fn check(
+    metrics: &RepositoryMetrics,
+    thresholds: &ThresholdsConfig,
+    overrides: &PathOverrides,
+    repo_root: &str,
+    baseline: Option<&Baseline>,
+) -> Result<(), Violations> {
+    let mut files: Vec<(String, &ComplexityMetrics)> = metrics
+        .file_metrics
+        .iter()
+        .filter_map(|(path, file_metrics)| {
+            let complexity = file_metrics.complexity_metrics.as_ref()?;
+            Some((relative_path(path, repo_root), complexity))
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // A partial analysis stops before the maintainability index, so only its complexity counts
+    let cyclomatic = |c: &ComplexityMetrics| Some(c.cyclomatic_complexity);
+    let maintainability = |c: &ComplexityMetrics| (!c.partial).then_some(c.maintainability_index);
+
+    // Files the baseline accepts no longer count towards a violation
+    let mut known = 0;
+    let mut unknown = |offending: Vec<(String, f64)>,
+                       recorded: fn(&BaselineEntry) -> Option<f64>,
+                       worse: Worse| {
+        let Some(baseline) = baseline else {
+            return offending;
+        };
+        let (accepted, offending): (Vec<_>, Vec<_>) =
+            offending.into_iter().partition(|(path, value)| {
+                baseline.accepts(
+                    &path.replace('\\', "/"),
+                    recorded,
+                    *value,
+                    worse,
+                    thresholds.baseline_tolerance,
+                )
+            });
+        known += accepted.len();
+        offending
+    };
+
+    // With a baseline, an average over the limit only counts when a file crossing the limit
+    // is new or got worse
+    let counts = |offending: &[(String, f64)]| baseline.is_none() || !offending.is_empty();
+
+    let mut violations = Vec::new();
+    if let Some(limit) = thresholds.maintainability_below {
+        if metrics.avg_maintainability_index < limit {
+            let mut offending = unknown(
+                crossing(&files, maintainability, |mi| mi < limit),
+                |entry| entry.maintainability_index,
+                Worse::Lower,
+            );
+            offending.sort_by(|a, b| a.1.total_cmp(&b.1));
+            offending.truncate(CONTRIBUTORS);
+            if counts(&offending) {
+                violations.push(Violation {
+                    metric: "average maintainability index".to_string(),
+                    value: metrics.avg_maintainability_index,
+                    limit: format!("below {}", limit),
+                    files: offending,
+                });
+            }
+        }
+    }
+    if let Some(limit) = thresholds.avg_cyclomatic_above {
+        if metrics.avg_cyclomatic_complexity > limit {
+            let mut offending = unknown(
+                crossing(&files, cyclomatic, |cc| cc > limit),
+                |entry| Some(entry.cyclomatic_complexity),
+                Worse::Higher,
+            );
+            offending.truncate(CONTRIBUTORS);
+            if counts(&offending) {
+                violations.push(Violation {
+                    metric: "average cyclomatic complexity".to_string(),
+                    value: metrics.avg_cyclomatic_complexity,
+                    limit: format!("above {}", limit),
+                    files: offending,
+                });
+            }
+        }
+    }
+    let file_limit = |path: &str| {
+        overrides
+            .value(Path::new(path), |o| o.thresholds.file_cyclomatic_above)
+            .map(|(limit, _)| limit)
+            .or(thresholds.file_cyclomatic_above)
+    };
+    let mut offending: Vec<(String, f64)> = files
+        .iter()
+        .filter_map(|(path, complexity)| {
+            let limit = file_limit(path)?;
+            (complexity.cyclomatic_complexity > limit)
+                .then(|| (path.clone(), complexity.cyclomatic_complexity))
+        })
+        .collect();
+    offending.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let offending = unknown(
+        offending,
+        |entry| Some(entry.cyclomatic_complexity),
+        Worse::Higher,
+    );
+    if let Some((path, highest)) = offending.first() {
+        let limit = file_limit(path).unwrap_or_default();
+        let limit = if offending
+            .iter()
+            .all(|(path, _)| file_limit(path) == Some(limit))
+        {
+            format!("above {}", limit)
+        } else {
+            "above the limits set for their paths".to_string()
+        };
+        violations.push(Violation {
+            metric: "highest file cyclomatic complexity".to_string(),
+            value: *highest,
+            limit,
+            files: offending,
+        });
+    }
+    if known > 0 {
+        info!(
+            "Ignored {} threshold crossings of files the baseline records at least as bad",
+            known
+        );
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Violations(violations))
+    }
+}
+
+/// Files whose value crosses the limit, highest value first
+fn crossing(
+    files: &[(String, &ComplexityMetrics)],
+    value: impl Fn(&ComplexityMetrics) -> Option<f64>,
+    crosses: impl Fn(f64) -> bool,
+) -> Vec<(String, f64)> {
+    let mut offending: Vec<(String, f64)> = files
+        .iter()
+        .filter_map(|(path, complexity)| Some((path.clone(), value(complexity)?)))
+        .filter(|(_, value)| crosses(*value))
+        .collect();
+    offending.sort_by(|a, b| b.1.total_cmp(&a.1));
+    offending
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PathOverride;
+
+    #[test]
+    fn overrides_relax_and_tighten_the_file_limit() {
+        let thresholds = ThresholdsConfig {
+            file_cyclomatic_above: Some(10.0),
+            ..ThresholdsConfig::default()
+        };
+        let overrides: Vec<PathOverride> = [("services/legacy/**", 30.0), ("src/core/**", 5.0)]
+            .iter()
+            .map(|(path, limit)| {
+                let mut path_override = PathOverride {
+                    path: path.to_string(),
+                    ..PathOverride::default()
+                };
+                path_override.thresholds.file_cyclomatic_above = Some(*limit);
+                path_override
+            })
+            .collect();
+        let overrides = PathOverrides::new(&overrides, "").unwrap();
+        let metrics = RepositoryMetrics::from_complexities(&[
+            ("services/legacy/billing.rs", 25.0, 60.0),
+            ("services/api/handler.rs", 12.0, 60.0),
+            ("src/core/graph.rs", 7.0, 60.0),
+            ("src/util.rs", 7.0, 60.0),
+        ]);
+
+        let err = check(&metrics, &thresholds, &overrides, "", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Quality thresholds violated:\n  - highest file cyclomatic complexity 12.0 is above the limits set for their paths\n      services/api/handler.rs (12.0)\n      src/core/graph.rs (7.0)"
+        );
+    }
+}
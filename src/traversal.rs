@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
 use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use walkdir::{DirEntry, WalkDir};
 
@@ -8,7 +11,7 @@ use crate::config::Config;
 /// Represents a file found during repository traversal
 #[derive(Debug, Clone)]
 pub struct RepoFile {
-    /// Full path to the file
+    /// Path relative to the repository root, or absolute with --absolute-paths
     pub path: PathBuf,
     
     /// File extension (if any)
@@ -19,16 +22,25 @@ pub struct RepoFile {
     
     /// Whether the file is in a directory that starts with a dot
     pub in_dot_directory: bool,
+    
+    /// Index in `RepoRoots::roots` of the most specific root containing the file
+    pub root: usize,
+    
+    /// Whether the file is a test, set by filtering from the `tests` configuration
+    pub is_test: bool,
 }
 
 impl RepoFile {
-    /// Creates a new RepoFile from a DirEntry
-    fn from_entry(entry: &DirEntry) -> Result<Self> {
+    /// Creates a new RepoFile from a DirEntry below the canonical root `root`, with a path
+    /// relative to `base`
+    fn from_entry(entry: &DirEntry, root: &Path, base: &Path, absolute_paths: bool) -> Result<Self> {
         let metadata = entry.metadata()
             .context("Failed to read file metadata")?;
+        let relative = entry.path().strip_prefix(base).unwrap_or(entry.path());
         
-        // Check if the file is in a dot directory
-        let in_dot_directory = entry.path().components().any(|c| {
+        // Check if the file is in a dot directory (inside the repository)
+        let in_root = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let in_dot_directory = in_root.components().any(|c| {
             if let std::path::Component::Normal(name) = c {
                 if let Some(name_str) = name.to_str() {
                     return name_str.starts_with('.');
@@ -44,16 +56,23 @@ impl RepoFile {
             .map(|ext| ext.to_lowercase());
         
         Ok(RepoFile {
-            path: entry.path().to_path_buf(),
+            path: if absolute_paths {
+                entry.path().to_path_buf()
+            } else {
+                relative.to_path_buf()
+            },
             extension: extension.map(String::from),
             size: metadata.len(),
             in_dot_directory,
+            root: 0,
+            is_test: false,
         })
     }
 }
 
-/// Traverse a repository and collect all files
-pub fn traverse_repository(repo_path: &str, config: &Config) -> Result<Vec<RepoFile>> {
+/// Resolve the repository path to the canonical root all file paths are relative to,
+/// following symlinks and dropping `.` components and trailing slashes
+pub fn resolve_root(repo_path: &str) -> Result<String> {
     let path = Path::new(repo_path);
     
     if !path.exists() {
@@ -64,28 +83,367 @@ pub fn traverse_repository(repo_path: &str, config: &Config) -> Result<Vec<RepoF
         return Err(anyhow::anyhow!("Repository path is not a directory: {}", repo_path));
     }
     
-    info!("Starting repository traversal at: {}", repo_path);
+    let root = path.canonicalize()
+        .context(format!("Failed to resolve repository path {}", repo_path))?;
+    Ok(root.to_string_lossy().to_string())
+}
+
+/// Canonical roots of the repository directories, and the directory file paths are
+/// relative to
+#[derive(Debug, Clone)]
+pub struct RepoRoots {
+    /// Deepest directory containing every root; the root itself when there is only one
+    pub base: String,
     
-    let walker = WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|e| !is_ignored_by_default(e, config));
+    /// Canonical roots in the order given, without duplicates
+    pub roots: Vec<String>,
+}
+
+impl RepoRoots {
+    /// Each root relative to the base, `.` for the base itself
+    pub fn names(&self) -> Vec<String> {
+        self.roots.iter().map(|root| {
+            if *root == self.base {
+                ".".to_string()
+            } else {
+                relative_path(root, &self.base)
+            }
+        }).collect()
+    }
+}
+
+/// Resolve every repository path to its canonical root, warning about roots nested in others
+pub fn resolve_roots(repo_paths: &[String]) -> Result<RepoRoots> {
+    let mut roots: Vec<String> = Vec::new();
+    for repo_path in repo_paths {
+        let root = resolve_root(repo_path)?;
+        if roots.contains(&root) {
+            warn!("Repository path {} is given more than once; analyzing it once", root);
+            continue;
+        }
+        roots.push(root);
+    }
+    
+    for inner in &roots {
+        if let Some(outer) = roots.iter().find(|outer| *outer != inner && Path::new(inner).starts_with(outer)) {
+            warn!(
+                "Repository path {} lies inside {}; its files are analyzed once and attributed to {}",
+                inner, outer, inner
+            );
+        }
+    }
+    
+    // The deepest common ancestor, component by component
+    let mut base = PathBuf::from(&roots[0]);
+    for root in &roots[1..] {
+        while !Path::new(root).starts_with(&base) {
+            if !base.pop() {
+                break;
+            }
+        }
+    }
+    
+    Ok(RepoRoots {
+        base: base.to_string_lossy().to_string(),
+        roots,
+    })
+}
+
+/// Location on disk of a traversed file, whether its path is relative or absolute
+pub fn on_disk(repo_root: &str, path: impl AsRef<Path>) -> PathBuf {
+    Path::new(repo_root).join(path)
+}
+
+/// Name of the gitignore-style files listing paths the traversal skips
+pub const IGNORE_FILE: &str = ".overdocignore";
+
+/// Ignore files of the traversed directories, each applying below its own directory
+#[derive(Default)]
+struct IgnoreFiles {
+    /// Whether `.gitignore` files and the git excludes apply too
+    respect_gitignore: bool,
+    
+    /// Matcher of every directory looked at, empty when it holds no ignore file
+    matchers: HashMap<PathBuf, Gitignore>,
+    
+    /// Git work tree of every root, if it lies in one, with its `.git/info/exclude` and
+    /// global excludes
+    work_trees: HashMap<PathBuf, Option<(PathBuf, Gitignore)>>,
+}
+
+impl IgnoreFiles {
+    /// Whether the ignore files of the directories from `root` down to the entry's parent
+    /// exclude it. As in git, the deepest file with a matching pattern decides, and a
+    /// `.overdocignore` decides over the `.gitignore` of its directory. `.gitignore` files
+    /// above `root` up to the top of its work tree apply too.
+    fn excludes(&mut self, path: &Path, is_dir: bool, root: &Path) -> bool {
+        let work_tree = if self.respect_gitignore {
+            self.work_tree(root).map(|(top, _)| top.clone())
+        } else {
+            None
+        };
+        let top = work_tree.as_deref().unwrap_or(root);
+        
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(top) {
+                break;
+            }
+            match self.matcher(dir, dir.starts_with(root)).matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    debug!("Ignoring {} (pattern {} in {})", path.display(), glob.original(), glob.from().unwrap_or(dir).display());
+                    return true;
+                },
+                Match::Whitelist(_) => return false,
+                Match::None => {},
+            }
+        }
+        
+        if let Some((_, excludes)) = work_tree.and_then(|_| self.work_tree(root)) {
+            if let Match::Ignore(glob) = excludes.matched(path, is_dir) {
+                debug!("Ignoring {} (pattern {} in the git excludes)", path.display(), glob.original());
+                return true;
+            }
+        }
+        false
+    }
+    
+    /// Matcher of the ignore files in `dir`, read the first time the directory is looked at;
+    /// `.overdocignore` is only read in directories inside the root
+    fn matcher(&mut self, dir: &Path, in_root: bool) -> &Gitignore {
+        let respect_gitignore = self.respect_gitignore;
+        self.matchers.entry(dir.to_path_buf()).or_insert_with(|| {
+            let mut files = Vec::new();
+            if respect_gitignore {
+                files.push(dir.join(".gitignore"));
+            }
+            if in_root {
+                files.push(dir.join(IGNORE_FILE));
+            }
+            build_matcher(dir, files)
+        })
+    }
+    
+    /// The git work tree containing `root` and its repository-wide excludes, found the first
+    /// time the root is looked at
+    fn work_tree(&mut self, root: &Path) -> Option<&(PathBuf, Gitignore)> {
+        self.work_trees.entry(root.to_path_buf()).or_insert_with(|| {
+            let top = root.ancestors().find(|dir| dir.join(".git").exists())?;
+            let mut files = vec![top.join(".git").join("info").join("exclude")];
+            files.extend(ignore::gitignore::gitconfig_excludes_path());
+            debug!("{} lies in the git work tree {}", root.display(), top.display());
+            Some((top.to_path_buf(), build_matcher(top, files)))
+        }).as_ref()
+    }
+}
+
+/// Matcher of the patterns of the existing `files`, relative to `dir`; invalid patterns are
+/// warned about and skipped
+fn build_matcher(dir: &Path, files: Vec<PathBuf>) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file in files.iter().filter(|file| file.is_file()) {
+        found = true;
+        if let Some(err) = builder.add(file) {
+            warn!("Skipping invalid patterns in {}: {}", file.display(), err);
+        }
+    }
+    if !found {
+        return Gitignore::empty();
+    }
+    builder.build().unwrap_or_else(|err| {
+        warn!("Ignoring the ignore files of {}: {}", dir.display(), err);
+        Gitignore::empty()
+    })
+}
+
+/// Files found by a traversal
+#[derive(Debug)]
+pub struct Traversal {
+    pub files: Vec<RepoFile>,
+    
+    /// Directories at the depth limit whose contents weren't walked
+    pub cut_directories: usize,
+}
+
+/// Files collected by the walks over the roots
+#[derive(Default)]
+struct Walked {
+    /// Canonical paths of the collected files, so overlapping roots and symlinks collect
+    /// each file once
+    seen: HashSet<PathBuf>,
+    
+    /// Canonical paths of the directories entered while following symlinks, so a link back
+    /// up the tree or a second link to a directory doesn't walk it again
+    visited_directories: HashSet<PathBuf>,
+    
+    /// `.overdocignore` and `.gitignore` files read so far
+    ignore_files: IgnoreFiles,
+    
+    files: Vec<RepoFile>,
+    cut_directories: usize,
+}
+
+impl Walked {
+    fn new(config: &Config) -> Self {
+        Walked {
+            ignore_files: IgnoreFiles {
+                respect_gitignore: config.default_settings.respect_gitignore,
+                ..IgnoreFiles::default()
+            },
+            ..Walked::default()
+        }
+    }
+}
+
+/// Traverse every root and collect all files, with paths relative to the base of the roots
+/// (absolute with `absolute_paths`). Files reached through several overlapping roots are
+/// collected once and attributed to the most specific root containing them.
+///
+/// With `max_depth` only files at most that many levels below their root are collected,
+/// 1 being the files directly in the root.
+pub fn traverse_repository(roots: &RepoRoots, config: &Config, absolute_paths: bool, max_depth: Option<usize>) -> Result<Traversal> {
+    let mut walked = Walked::new(config);
+    
+    for root in &roots.roots {
+        let root = Path::new(root);
+        info!("Starting repository traversal at: {}", root.display());
+        walk(root, root, roots, config, absolute_paths, max_depth, &mut walked)?;
+    }
+    
+    let Walked { files, cut_directories, .. } = walked;
+    if roots.roots.len() > 1 {
+        for (idx, root) in roots.roots.iter().enumerate() {
+            let count = files.iter().filter(|file| file.root == idx).count();
+            info!("{} files attributed to {}", count, root);
+        }
+    }
+    
+    info!("Repository traversal complete. Found {} files", files.len());
+    if cut_directories > 0 {
+        warn!(
+            "Stopped at --max-depth {}; the contents of {} directories were not traversed",
+            max_depth.unwrap_or_default(),
+            cut_directories
+        );
+    }
+    
+    Ok(Traversal { files, cut_directories })
+}
+
+/// Collect the files of an explicit list instead of traversing the roots. Relative paths are
+/// resolved against the base of the roots, directories are expanded, and paths that don't
+/// exist or lie outside every root are warned about and skipped.
+pub fn files_from_list(paths: &[String], roots: &RepoRoots, config: &Config, absolute_paths: bool) -> Result<Vec<RepoFile>> {
+    let mut walked = Walked::new(config);
+    
+    for listed in paths {
+        let path = Path::new(&roots.base).join(listed);
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(err) => {
+                warn!("Skipping listed file {}: {}", listed, err);
+                continue;
+            }
+        };
+        let Some(root) = roots.roots.get(most_specific_root(&roots.roots, &canonical))
+            .map(Path::new)
+            .filter(|root| canonical.starts_with(root)) else {
+            warn!("Skipping listed file {}: outside the repository {}", listed, roots.base);
+            continue;
+        };
+        walk(&canonical, root, roots, config, absolute_paths, None, &mut walked)?;
+    }
     
-    let mut files = Vec::new();
+    info!("Read {} files from the file list of {} entries", walked.files.len(), paths.len());
+    
+    Ok(walked.files)
+}
+
+/// Read newline-separated paths from a file, or from stdin for `-`, skipping blank lines
+pub fn read_file_list(source: &str) -> Result<Vec<String>> {
+    let content = if source == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .context("Failed to read the file list from stdin")?
+    } else {
+        std::fs::read_to_string(source)
+            .context(format!("Failed to read the file list {}", source))?
+    };
+    
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Walk `start`, a file or a directory below the canonical `root`, collecting files not
+/// already collected, down to `max_depth` levels below `start`. Symlinks are followed with
+/// `default_settings.follow_symlinks`. Paths matched by a `.overdocignore` in their
+/// directory or one above it, up to `root`, are skipped, and with
+/// `default_settings.respect_gitignore` so are paths git ignores.
+fn walk(
+    start: &Path,
+    root: &Path,
+    roots: &RepoRoots,
+    config: &Config,
+    absolute_paths: bool,
+    max_depth: Option<usize>,
+    walked: &mut Walked,
+) -> Result<()> {
+    let base = Path::new(&roots.base);
+    let follow_links = config.default_settings.follow_symlinks;
+    let mut walker = WalkDir::new(start).follow_links(follow_links);
+    if let Some(max_depth) = max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+    let visited_directories = &mut walked.visited_directories;
+    let ignore_files = &mut walked.ignore_files;
+    let walker = walker
+        .into_iter()
+        .filter_entry(|e| {
+            !is_ignored_by_default(e, config)
+                && !ignore_files.excludes(e.path(), e.file_type().is_dir(), root)
+                && (!follow_links || !is_visited_directory(e, visited_directories))
+        });
     
     for entry in walker {
-        let entry = entry.context("Error accessing directory entry")?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) if follow_links && is_unfollowable_link(&err) => {
+                debug!("Skipping symlink: {}", err);
+                continue;
+            },
+            Err(err) => return Err(err).context("Error accessing directory entry"),
+        };
         
-        // Skip directories
+        // Skip directories, counting those whose contents are below the depth limit
         if entry.file_type().is_dir() {
+            if max_depth == Some(entry.depth()) {
+                debug!("Not descending below --max-depth into: {}", entry.path().display());
+                walked.cut_directories += 1;
+            }
+            continue;
+        }
+        
+        // Roots are canonical, so without following links the walked path is the file's
+        // canonical path
+        let canonical = if follow_links {
+            entry.path().canonicalize().unwrap_or_else(|_| entry.path().to_path_buf())
+        } else {
+            entry.path().to_path_buf()
+        };
+        if !walked.seen.insert(canonical) {
+            debug!("Already traversed through another root or symlink: {}", entry.path().display());
             continue;
         }
         
         // Process files
-        match RepoFile::from_entry(&entry) {
-            Ok(file) => {
+        match RepoFile::from_entry(&entry, root, base, absolute_paths) {
+            Ok(mut file) => {
+                file.root = most_specific_root(&roots.roots, entry.path());
                 debug!("Found file: {:?}", file.path);
-                files.push(file);
+                walked.files.push(file);
             },
             Err(err) => {
                 warn!("Error processing file {}: {}", entry.path().display(), err);
@@ -93,9 +451,37 @@ pub fn traverse_repository(repo_path: &str, config: &Config) -> Result<Vec<RepoF
         }
     }
     
-    info!("Repository traversal complete. Found {} files", files.len());
-    
-    Ok(files)
+    Ok(())
+}
+
+/// Check if a directory was already entered, recording it otherwise
+fn is_visited_directory(entry: &DirEntry, visited: &mut HashSet<PathBuf>) -> bool {
+    if !entry.file_type().is_dir() {
+        return false;
+    }
+    let canonical = entry.path().canonicalize().unwrap_or_else(|_| entry.path().to_path_buf());
+    if visited.insert(canonical) {
+        return false;
+    }
+    debug!("Already traversed, not following: {}", entry.path().display());
+    true
+}
+
+/// Whether a walk error comes from a broken symlink or a link back to one of its ancestors
+fn is_unfollowable_link(err: &walkdir::Error) -> bool {
+    err.loop_ancestor().is_some()
+        || err.io_error().is_some_and(|err| err.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Index of the deepest root containing the path
+fn most_specific_root(roots: &[String], path: &Path) -> usize {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| path.starts_with(root))
+        .max_by_key(|(_, root)| Path::new(root).components().count())
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
 }
 
 /// Check if a directory entry should be ignored by default rules
@@ -116,4 +502,187 @@ fn is_ignored_by_default(entry: &DirEntry, config: &Config) -> bool {
     
     // Don't ignore by default
     false
-} 
\ No newline at end of file
+} 
+
+/// Check if a path below `root` lies in a directory the traversal skips
+pub fn in_ignored_directory(path: &Path, root: &Path, config: &Config) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    
+    // The entry itself counts too, so creating an ignored directory is skipped as well
+    relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        config.ignore_directories.iter().any(|dir| name == dir.as_str())
+    })
+}
+
+/// Express a path relative to the repository root for display purposes.
+/// Paths outside the root are returned unchanged.
+pub fn relative_path(path: &str, repo_root: &str) -> String {
+    match Path::new(path).strip_prefix(repo_root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository holding `files`, each with some content
+    fn repo(files: &[&str]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "content").unwrap();
+        }
+        dir
+    }
+
+    fn canonical(path: &Path) -> String {
+        path.canonicalize().unwrap().to_string_lossy().to_string()
+    }
+
+    /// Paths of the files traversed from `repo_paths`, sorted
+    fn traversed(repo_paths: &[&str]) -> Vec<String> {
+        let repo_paths: Vec<String> = repo_paths.iter().map(|path| path.to_string()).collect();
+        let roots = resolve_roots(&repo_paths).unwrap();
+        let traversal = traverse_repository(&roots, &Config::default(), false, None).unwrap();
+        let mut paths: Vec<String> = traversal.files.iter()
+            .map(|file| file.path.to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn dot_resolves_to_the_working_directory() {
+        let working_directory = std::env::current_dir().unwrap();
+        assert_eq!(resolve_root(".").unwrap(), canonical(&working_directory));
+    }
+
+    #[test]
+    fn dot_components_and_trailing_slashes_resolve_to_the_same_root() {
+        let dir = repo(&["src/lib.rs", "README.md"]);
+        let plain = dir.path().to_string_lossy().to_string();
+        let expected = canonical(dir.path());
+
+        for repo_path in [
+            plain.clone(),
+            format!("{}/", plain),
+            format!("{}/.", plain),
+            format!("{}/./", plain),
+            format!("{}/src/..", plain),
+        ] {
+            assert_eq!(resolve_root(&repo_path).unwrap(), expected, "{:?}", repo_path);
+            assert_eq!(
+                traversed(&[&repo_path]),
+                vec!["README.md", "src/lib.rs"],
+                "{:?}",
+                repo_path
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_root_resolves_to_its_target() {
+        let dir = repo(&["src/lib.rs"]);
+        let links = tempfile::tempdir().unwrap();
+        let link = links.path().join("checkout");
+        std::os::unix::fs::symlink(dir.path(), &link).unwrap();
+        let link = link.to_string_lossy().to_string();
+
+        assert_eq!(resolve_root(&link).unwrap(), canonical(dir.path()));
+        assert_eq!(resolve_root(&format!("{}/", link)).unwrap(), canonical(dir.path()));
+        assert_eq!(traversed(&[&link]), vec!["src/lib.rs"]);
+    }
+
+    #[test]
+    fn missing_or_file_roots_are_rejected() {
+        let dir = repo(&["src/lib.rs"]);
+        let missing = dir.path().join("missing").to_string_lossy().to_string();
+        let file = dir.path().join("src/lib.rs").to_string_lossy().to_string();
+
+        assert!(resolve_root(&missing).unwrap_err().to_string().contains("does not exist"));
+        assert!(resolve_root(&file).unwrap_err().to_string().contains("not a directory"));
+    }
+
+    /// Paths of the files traversed from `repo_paths`, with the root each is attributed to
+    fn attributed(repo_paths: &[String]) -> (RepoRoots, Vec<(String, String)>) {
+        let roots = resolve_roots(repo_paths).unwrap();
+        let traversal = traverse_repository(&roots, &Config::default(), false, None).unwrap();
+        let names = roots.names();
+        let mut files: Vec<(String, String)> = traversal.files.iter()
+            .map(|file| (file.path.to_string_lossy().to_string(), names[file.root].clone()))
+            .collect();
+        files.sort();
+        (roots, files)
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|(path, root)| (path.to_string(), root.to_string())).collect()
+    }
+
+    #[test]
+    fn nested_roots_collect_each_file_once_under_the_deepest_root() {
+        let dir = repo(&["README.md", "packages/core/src/lib.rs", "packages/web/app.ts"]);
+        let outer = dir.path().to_string_lossy().to_string();
+        let inner = dir.path().join("packages/core").to_string_lossy().to_string();
+
+        // Either order, and the same root given twice in another form
+        for repo_paths in [
+            vec![outer.clone(), inner.clone()],
+            vec![inner.clone(), outer.clone()],
+            vec![outer.clone(), inner.clone(), format!("{}/", inner)],
+        ] {
+            let (roots, files) = attributed(&repo_paths);
+            assert_eq!(roots.base, canonical(dir.path()), "{:?}", repo_paths);
+            assert_eq!(roots.roots.len(), 2, "{:?}", repo_paths);
+            assert_eq!(
+                files,
+                pairs(&[
+                    ("README.md", "."),
+                    ("packages/core/src/lib.rs", "packages/core"),
+                    ("packages/web/app.ts", "."),
+                ]),
+                "{:?}",
+                repo_paths
+            );
+        }
+    }
+
+    #[test]
+    fn disjoint_roots_keep_their_files_apart_below_the_common_base() {
+        let dir = repo(&["api/src/lib.rs", "web/src/lib.rs", "docs/README.md"]);
+        let api = dir.path().join("api").to_string_lossy().to_string();
+        let web = dir.path().join("web").to_string_lossy().to_string();
+
+        let (roots, files) = attributed(&[api, web]);
+
+        assert_eq!(roots.base, canonical(dir.path()));
+        assert_eq!(roots.names(), vec!["api", "web"]);
+        // Files of the same name in both roots stay distinct, and the base isn't walked
+        assert_eq!(
+            files,
+            pairs(&[("api/src/lib.rs", "api"), ("web/src/lib.rs", "web")])
+        );
+    }
+
+    #[test]
+    fn most_specific_root_is_the_deepest_containing_the_path() {
+        let roots: Vec<String> = ["/repo", "/repo/packages/core", "/other"]
+            .iter()
+            .map(|root| root.to_string())
+            .collect();
+
+        assert_eq!(most_specific_root(&roots, Path::new("/repo/packages/core/src/lib.rs")), 1);
+        assert_eq!(most_specific_root(&roots, Path::new("/repo/packages/web/app.ts")), 0);
+        assert_eq!(most_specific_root(&roots, Path::new("/other/lib.rs")), 2);
+        // A sibling sharing the prefix as text lies outside the nested root
+        assert_eq!(most_specific_root(&roots, Path::new("/repo/packages/core-utils/lib.rs")), 0);
+    }
+}
@@ -0,0 +1,257 @@
+//! Watch mode re-running the analysis when files of the repository change (--watch).
+
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::metrics::MetricsCache;
+use crate::report::AnalysisReport;
+use crate::traversal::{in_ignored_directory, relative_path};
+
+/// Quiet period after the last change before the analysis re-runs
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// Number of moved scores listed after each run
+const MOVED_SCORES: usize = 10;
+
+/// State carried from one run of a watch session to the next
+#[derive(Default)]
+pub struct Session {
+    /// Metrics of the files unchanged since an earlier run
+    pub metrics_cache: MetricsCache,
+
+    /// Files changed since the previous run
+    changed: usize,
+
+    /// Scores of the previous run, by path relative to the repository
+    previous: Option<BTreeMap<String, Scores>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scores {
+    importance: usize,
+    knowledge: Option<f64>,
+}
+
+impl Session {
+    /// Print how the run's scores moved since the previous run, and keep them for the next
+    pub fn record(&mut self, report: &AnalysisReport) {
+        let knowledge: BTreeMap<&String, f64> = report
+            .metrics
+            .iter()
+            .flat_map(|metrics| metrics.file_metrics.iter())
+            .filter_map(|(path, file_metrics)| Some((path, file_metrics.knowledge_score?)))
+            .collect();
+        let scores: BTreeMap<String, Scores> = report
+            .file_importance
+            .iter()
+            .map(|(path, importance)| {
+                let scores = Scores {
+                    importance: *importance,
+                    knowledge: knowledge.get(path).copied(),
+                };
+                (relative_path(path, &report.repo_path), scores)
+            })
+            .collect();
+
+        if let Some(previous) = &self.previous {
+            print!(
+                "{}",
+                render_delta(previous, &scores, self.changed, self.metrics_cache.hits)
+            );
+        }
+        self.previous = Some(scores);
+    }
+}
+
+/// What watching listens for
+enum Message {
+    Changed(notify::Result<notify::Event>),
+    Interrupted,
+}
+
+/// Paths whose changes don't re-run the analysis
+struct Ignored<'a> {
+    roots: &'a [String],
+    config: &'a Config,
+
+    /// Where the outputs are written, so writing them doesn't trigger another run
+    output_dir: PathBuf,
+}
+
+impl Ignored<'_> {
+    fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.output_dir)
+            || self
+                .roots
+                .iter()
+                .any(|root| in_ignored_directory(path, Path::new(root), self.config))
+    }
+}
+
+/// Run `analyze`, then again each time files under the roots change, until Ctrl-C. Changes
+/// in the directories the traversal skips and in `output_dir` are left out; a change to the
+/// configuration file drops the cached metrics. A failed run is logged and watching goes on.
+pub fn watch(
+    roots: &[String],
+    config: &Config,
+    config_path: &str,
+    output_dir: &Path,
+    mut analyze: impl FnMut(&mut Session) -> Result<()>,
+) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let events = sender.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = events.send(Message::Changed(event));
+    })
+    .context("Failed to start watching for file changes")?;
+    for root in roots {
+        watcher
+            .watch(Path::new(root), RecursiveMode::Recursive)
+            .context(format!("Failed to watch {}", root))?;
+    }
+    ctrlc::set_handler(move || {
+        let _ = sender.send(Message::Interrupted);
+    })
+    .context("Failed to set up the Ctrl-C handler")?;
+
+    let mut session = Session::default();
+    run_once(&mut session, &mut analyze);
+
+    // The output directory exists once the first run wrote to it
+    let ignored = Ignored {
+        roots,
+        config,
+        output_dir: absolute(output_dir),
+    };
+    let config_file = absolute(Path::new(config_path));
+
+    loop {
+        info!("Watching for changes; press Ctrl-C to stop");
+        let Some(changed) = next_changes(&receiver, &ignored) else {
+            break;
+        };
+        if changed.contains(&config_file) {
+            info!("Configuration changed; analyzing every file again");
+            session.metrics_cache.clear();
+        }
+        session.changed = changed.len();
+        run_once(&mut session, &mut analyze);
+    }
+
+    info!("Stopped watching");
+    Ok(())
+}
+
+/// Run the analysis, logging instead of returning a failure so watching continues
+fn run_once(session: &mut Session, analyze: &mut impl FnMut(&mut Session) -> Result<()>) {
+    if let Err(err) = analyze(session) {
+        error!("Analysis failed: {:#}", err);
+    }
+}
+
+/// Wait for changes to settle, returning the changed paths, or `None` on Ctrl-C
+fn next_changes(receiver: &Receiver<Message>, ignored: &Ignored) -> Option<BTreeSet<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    loop {
+        // Block until the first change, then until none came for the debounce period
+        let message = if changed.is_empty() {
+            receiver.recv().ok()?
+        } else {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => return Some(changed),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        };
+
+        match message {
+            Message::Interrupted => return None,
+            Message::Changed(Err(err)) => warn!("Error watching for changes: {}", err),
+            Message::Changed(Ok(event)) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                changed.extend(
+                    event
+                        .paths
+                        .into_iter()
+                        .filter(|path| !ignored.contains(path)),
+                );
+            }
+        }
+    }
+}
+
+/// Canonical form of a path, or the path joined to the working directory when it doesn't exist
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    })
+}
+
+/// Files changed and reused, then the scores that moved, largest move first
+fn render_delta(
+    previous: &BTreeMap<String, Scores>,
+    current: &BTreeMap<String, Scores>,
+    changed: usize,
+    reused: usize,
+) -> String {
+    let mut output = format!(
+        "Re-analyzed after {} changed {}, reusing metrics of {} unchanged {}\n",
+        changed,
+        if changed == 1 { "file" } else { "files" },
+        reused,
+        if reused == 1 { "file" } else { "files" }
+    );
+
+    let mut moved: Vec<(f64, String)> = Vec::new();
+    for (path, scores) in current {
+        let Some(before) = previous.get(path) else {
+            moved.push((f64::INFINITY, format!("  + {}", path)));
+            continue;
+        };
+        if scores.importance != before.importance {
+            moved.push((
+                (scores.importance as f64 - before.importance as f64).abs(),
+                format!(
+                    "  {} importance {} -> {}",
+                    path, before.importance, scores.importance
+                ),
+            ));
+        }
+        if let (Some(old), Some(new)) = (before.knowledge, scores.knowledge) {
+            if (new - old).abs() >= 0.1 {
+                moved.push((
+                    (new - old).abs(),
+                    format!("  {} knowledge {:.1} -> {:.1}", path, old, new),
+                ));
+            }
+        }
+    }
+    for path in previous.keys().filter(|path| !current.contains_key(*path)) {
+        moved.push((f64::INFINITY, format!("  - {}", path)));
+    }
+
+    if moved.is_empty() {
+        output.push_str("No scores moved\n");
+        return output;
+    }
+    moved.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    for (_, line) in moved.iter().take(MOVED_SCORES) {
+        output.push_str(line);
+        output.push('\n');
+    }
+    if moved.len() > MOVED_SCORES {
+        output.push_str(&format!("  ... and {} more\n", moved.len() - MOVED_SCORES));
+    }
+    output
+}
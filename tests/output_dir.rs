@@ -0,0 +1,135 @@
+//! Checks that an unusable output directory stops a run before the analysis starts, and that
+//! `--ephemeral` leaves the output directory alone.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Logged once the output checks pass and the repository is scanned
+const ANALYSIS_STARTED: &str = "Starting repository analysis";
+
+fn write_fixture(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn parse() {}\n").unwrap();
+}
+
+/// Analyze the fixture repository at `repo` into `output`, with the default log level
+fn analyze(repo: &Path, output: &Path, extra_args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_overdoc"))
+        .args([
+            "analyze",
+            "--repo-path",
+            repo.to_str().unwrap(),
+            "--config-path",
+            repo.join("overdoc.yaml").to_str().unwrap(),
+            "--output-dir",
+            output.to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .args(extra_args)
+        .output()
+        .unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_output_dir_fails_before_the_analysis() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+    let output = tempfile::tempdir().unwrap();
+    fs::set_permissions(output.path(), fs::Permissions::from_mode(0o555)).unwrap();
+
+    // Permissions don't bind root, so there is nothing to check
+    let probe = output.path().join("probe");
+    if fs::write(&probe, b"").is_ok() {
+        fs::remove_file(&probe).unwrap();
+        eprintln!("output directory is writable despite its mode; skipping");
+        return;
+    }
+
+    let result = analyze(repo.path(), output.path(), &[]);
+    fs::set_permissions(output.path(), fs::Permissions::from_mode(0o755)).unwrap();
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains(&format!(
+            "Output directory {} is not writable",
+            output.path().display()
+        )),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--ephemeral"), "{}", stderr);
+    assert!(!stderr.contains(ANALYSIS_STARTED), "{}", stderr);
+    assert_eq!(fs::read_dir(output.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn uncreatable_output_dir_fails_before_the_analysis() {
+    let repo = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+    let scratch = tempfile::tempdir().unwrap();
+    fs::write(scratch.path().join("report"), "").unwrap();
+    let output = scratch.path().join("report/out");
+
+    let result = analyze(repo.path(), &output, &[]);
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains(&format!(
+            "Failed to create output directory {}",
+            output.display()
+        )),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains(ANALYSIS_STARTED), "{}", stderr);
+}
+
+#[test]
+fn ephemeral_runs_write_nothing_to_the_output_dir() {
+    let repo = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+    let scratch = tempfile::tempdir().unwrap();
+
+    // A missing output directory isn't created
+    let missing = scratch.path().join("out");
+    let result = analyze(repo.path(), &missing, &["--ephemeral"]);
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert!(!missing.exists());
+
+    // An existing one isn't probed either, or the probe would have to be written
+    let existing = scratch.path().join("existing");
+    fs::create_dir(&existing).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o555)).unwrap();
+    }
+    let result = analyze(repo.path(), &existing, &["--ephemeral"]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    let left: Vec<_> = fs::read_dir(&existing)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert!(left.is_empty(), "{:?}", left);
+    assert!(!existing.join(".overdoc-write-test").exists());
+}
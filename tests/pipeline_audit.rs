@@ -0,0 +1,133 @@
+//! Runs overdoc with --audit-pipeline on a small fixture repository and checks that every
+//! file and export reaches the saved report.
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Files of the fixture repository: Rust and TypeScript sources with four exports between
+/// them, and a markdown file no language covers
+const FIXTURE: &[(&str, &str)] = &[
+    ("src/lib.rs", "pub fn parse() {}\npub struct Config;\n"),
+    ("src/main.rs", "use crate::parse;\nuse crate::Config;\n\nfn main() {\n    parse();\n}\n"),
+    ("web/app.ts", "export function render() {}\nexport const VERSION = 1;\n"),
+    ("web/index.ts", "import { render } from \"./app\";\nrender();\n"),
+    ("docs/README.md", "# Docs\n"),
+];
+
+fn write_fixture(root: &Path) {
+    for (path, content) in FIXTURE {
+        let file = root.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+    }
+}
+
+fn overdoc(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_overdoc"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "overdoc {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Write the JSON report and the pipeline audit of the fixture repository to `output`
+fn analyze(repo: &Path, output: &Path) -> (Value, Value) {
+    overdoc(&[
+        "analyze",
+        "--repo-path",
+        repo.to_str().unwrap(),
+        "--config-path",
+        repo.join("overdoc.yaml").to_str().unwrap(),
+        "--output-dir",
+        output.to_str().unwrap(),
+        "--format",
+        "json",
+        "--audit-pipeline",
+        "--quiet",
+    ]);
+    (
+        read_json(&output.join("analysis_results.json")),
+        read_json(&output.join("pipeline_audit.json")),
+    )
+}
+
+#[test]
+fn audit_reports_no_losses_and_the_report_keeps_every_file() {
+    let repo = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+
+    let (report, audit) = analyze(repo.path(), output.path());
+
+    assert_eq!(audit["losses"], Value::Array(Vec::new()), "{:#}", audit);
+    assert_eq!(audit["unexpected"], serde_json::json!({}), "{:#}", audit);
+    for phase in audit["phases"].as_array().unwrap() {
+        assert_eq!(phase["lost"], 0, "{:#}", phase);
+        assert_eq!(
+            phase["received"].as_u64().unwrap(),
+            phase["emitted"].as_u64().unwrap() + phase["excluded"].as_u64().unwrap(),
+            "{:#}",
+            phase
+        );
+    }
+    assert_eq!(
+        audit["excluded"]["exports"]["docs/README.md"],
+        "no language is configured for its extension"
+    );
+
+    // Every traversed file is counted, and keeps its metrics, in the saved report
+    let phases = audit["phases"].as_array().unwrap();
+    let filtered = &phases[0];
+    assert_eq!(filtered["phase"], "filter");
+    assert_eq!(report["summary"]["total_files"], FIXTURE.len());
+    assert_eq!(filtered["emitted"], FIXTURE.len());
+    assert_eq!(report["metrics"]["total_files"], FIXTURE.len());
+    assert_eq!(report["metrics"]["file_metrics"].as_object().unwrap().len(), FIXTURE.len());
+    let reported = phases.iter().find(|phase| phase["phase"] == "report").unwrap();
+    assert_eq!(reported["emitted"], FIXTURE.len());
+
+    // Every export is counted, and listed, in the saved report
+    let exports = report["exports"].as_object().unwrap();
+    let listed: usize = exports.values().map(|file| file.as_array().unwrap().len()).sum();
+    assert_eq!(report["summary"]["total_exports"], 4);
+    assert_eq!(listed, 4);
+    assert_eq!(report["summary"]["files_with_exports"], exports.len());
+}
+
+#[test]
+fn saved_report_reads_back_without_differences() {
+    let repo = tempfile::tempdir().unwrap();
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+
+    let (before, _) = analyze(repo.path(), first.path());
+    let (after, _) = analyze(repo.path(), second.path());
+    assert_eq!(before["summary"], after["summary"]);
+
+    // `diff` loads both saved reports back; no file or export goes missing on the way
+    let diff = overdoc(&[
+        "diff",
+        first.path().join("analysis_results.json").to_str().unwrap(),
+        second.path().join("analysis_results.json").to_str().unwrap(),
+        "--format",
+        "json",
+    ]);
+    let diff: Value = serde_json::from_str(&diff).unwrap();
+    assert_eq!(
+        diff,
+        serde_json::json!({ "added": [], "removed": [], "changed": [] })
+    );
+}
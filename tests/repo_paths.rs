@@ -0,0 +1,90 @@
+//! Runs overdoc on a repository given as `.`, with a trailing slash and through a symlink,
+//! and checks that every form reports the same repository-relative paths.
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn write_fixture(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn parse() {}\n").unwrap();
+    fs::write(root.join("src/main.rs"), "use crate::parse;\n\nfn main() {\n    parse();\n}\n").unwrap();
+}
+
+/// Analyze `repo_path` from the directory `cwd` and read back the JSON report
+fn analyze(cwd: &Path, repo_path: &str, output: &Path) -> Value {
+    let result = Command::new(env!("CARGO_BIN_EXE_overdoc"))
+        .current_dir(cwd)
+        .args([
+            "analyze",
+            "--repo-path",
+            repo_path,
+            "--config-path",
+            output.join("overdoc.yaml").to_str().unwrap(),
+            "--output-dir",
+            output.to_str().unwrap(),
+            "--format",
+            "json",
+            "--quiet",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        result.status.success(),
+        "overdoc failed for {:?}: {}",
+        repo_path,
+        String::from_utf8_lossy(&result.stderr)
+    );
+    serde_json::from_str(&fs::read_to_string(output.join("analysis_results.json")).unwrap()).unwrap()
+}
+
+fn keys(value: &Value) -> Vec<String> {
+    value.as_object().unwrap().keys().cloned().collect()
+}
+
+/// Files keyed in the exports and the metrics of a report
+fn reported_files(report: &Value) -> (Vec<String>, Vec<String>) {
+    (keys(&report["exports"]), keys(&report["metrics"]["file_metrics"]))
+}
+
+#[test]
+fn every_form_of_the_repo_path_reports_relative_paths() {
+    let repo = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+    let expected = (
+        vec!["src/lib.rs".to_string()],
+        vec!["src/lib.rs".to_string(), "src/main.rs".to_string()],
+    );
+
+    for repo_path in [".", "./", "src/.."] {
+        let output = tempfile::tempdir().unwrap();
+        let report = analyze(repo.path(), repo_path, output.path());
+        assert_eq!(reported_files(&report), expected, "{:?}", repo_path);
+    }
+
+    let parent = repo.path().parent().unwrap();
+    let name = repo.path().file_name().unwrap().to_str().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let report = analyze(parent, &format!("{}/", name), output.path());
+    assert_eq!(reported_files(&report), expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_repo_path_reports_relative_paths() {
+    let repo = tempfile::tempdir().unwrap();
+    write_fixture(repo.path());
+    let links = tempfile::tempdir().unwrap();
+    std::os::unix::fs::symlink(repo.path(), links.path().join("checkout")).unwrap();
+
+    let output = tempfile::tempdir().unwrap();
+    let report = analyze(links.path(), "checkout", output.path());
+    assert_eq!(
+        reported_files(&report),
+        (
+            vec!["src/lib.rs".to_string()],
+            vec!["src/lib.rs".to_string(), "src/main.rs".to_string()],
+        )
+    );
+}